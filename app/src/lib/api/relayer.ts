@@ -90,6 +90,12 @@ export interface PendingWithdrawalInfo {
 // Increased to 120s to handle slow devnet RPC and tree sync operations
 const REQUEST_TIMEOUT = 120000;
 
+// Deposit payload key derivation (MUST match crates/privacy-proxy-sdk/src/crypto.rs)
+// v2: AES key = HKDF-SHA256(X25519 shared secret, salt, info)
+const PAYLOAD_VERSION = 2;
+const PAYLOAD_HKDF_SALT = new TextEncoder().encode("tracezero-ecdh-payload");
+const PAYLOAD_HKDF_INFO = new TextEncoder().encode("aes-256-gcm-key-v2");
+
 // ECDH key pair for request encryption
 let clientKeyPair: CryptoKeyPair | null = null;
 let relayerPublicKey: CryptoKey | null = null;
@@ -140,7 +146,26 @@ async function initializeKeyExchange(
     clientKeyPair.privateKey,
     256,
   );
-  sharedSecret = new Uint8Array(sharedSecretBits);
+
+  // Never use the raw X25519 output as the AES key, derive it with HKDF
+  const hkdfKey = await crypto.subtle.importKey(
+    "raw",
+    sharedSecretBits,
+    "HKDF",
+    false,
+    ["deriveBits"],
+  );
+  const payloadKeyBits = await crypto.subtle.deriveBits(
+    {
+      name: "HKDF",
+      hash: "SHA-256",
+      salt: toArrayBuffer(PAYLOAD_HKDF_SALT),
+      info: toArrayBuffer(PAYLOAD_HKDF_INFO),
+    },
+    hkdfKey,
+    256,
+  );
+  sharedSecret = new Uint8Array(payloadKeyBits);
 
   return sharedSecret;
 }
@@ -417,6 +442,7 @@ class RelayerClient {
     );
     const encrypted = await encryptPayload(plaintext, secret);
     const body = JSON.stringify({
+      version: PAYLOAD_VERSION,
       encrypted: true,
       ciphertext: Array.from(encrypted.ciphertext),
       nonce: Array.from(encrypted.nonce),
@@ -16,9 +16,9 @@ export const SOLANA_RPC_URL = "https://api.devnet.solana.com";
 
 // Domain tags for Poseidon hashes (MUST match circuits/*.circom and SDK)
 export const DOMAIN_NULLIFIER = 1853189228n; // "null" as u32
-export const DOMAIN_COMMIT = 1668246637n; // "comm" as u32
-export const DOMAIN_BIND = 1651076196n; // "bind" as u32
-export const DOMAIN_OWNER_BIND = 1869771618n; // "ownb" as u32
+export const DOMAIN_COMMIT = 1668246637n; // "colm" as u32
+export const DOMAIN_BIND = 1651076196n; // "bild" as u32
+export const DOMAIN_OWNER_BIND = 1869771618n; // "orsb" as u32
 
 // Merkle tree depth
 export const MERKLE_TREE_DEPTH = 20;
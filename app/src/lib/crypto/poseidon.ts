@@ -6,9 +6,9 @@ let poseidonInstance: Poseidon | null = null;
 
 // Domain tags (MUST match circuits/*.circom and SDK)
 export const DOMAIN_NULLIFIER = 1853189228n; // "null" as u32
-export const DOMAIN_COMMIT = 1668246637n; // "comm" as u32
-export const DOMAIN_BIND = 1651076196n; // "bind" as u32
-export const DOMAIN_OWNER_BIND = 1869771618n; // "ownb" as u32
+export const DOMAIN_COMMIT = 1668246637n; // "colm" as u32
+export const DOMAIN_BIND = 1651076196n; // "bild" as u32
+export const DOMAIN_OWNER_BIND = 1869771618n; // "orsb" as u32
 
 /**
  * Initialize Poseidon hasher (lazy loaded)
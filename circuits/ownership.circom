@@ -50,7 +50,7 @@ template Ownership() {
     // Smart contract MUST verify: bindingHash == Poseidon(DOMAIN_OWNER_BIND, nullifier, pendingWithdrawalId)
     // Since nullifier is private, contract computes expected binding from nullifierHash.
     signal domainOwnerBind;
-    domainOwnerBind <== 1869771618;  // "ownb" as u32
+    domainOwnerBind <== 1869771618;  // "orsb" as u32
     
     component bindingHasher = Poseidon(3);
     bindingHasher.inputs[0] <== domainOwnerBind;
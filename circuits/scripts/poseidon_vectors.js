@@ -0,0 +1,57 @@
+#!/usr/bin/env node
+/**
+ * Print the Poseidon test vectors checked by
+ * crates/privacy-proxy-sdk/src/crypto/test_vectors.rs
+ *
+ * Inputs and domain tags MUST match the Rust module and circuits/*.circom
+ *
+ * Run: node scripts/poseidon_vectors.js
+ */
+
+const { buildPoseidon } = require("circomlibjs");
+
+const DOMAIN_NULLIFIER = 1853189228n;
+const DOMAIN_COMMIT = 1668246637n;
+const DOMAIN_BIND = 1651076196n;
+const DOMAIN_OWNER_BIND = 1869771618n;
+
+const NULLIFIER = 12345n;
+const SECRET = 67890n;
+const AMOUNT = 100000000n;
+const RECIPIENT = BigInt("0x" + "01".repeat(32));
+const RELAYER = BigInt("0x" + "02".repeat(32));
+const FEE = 500000n;
+const PENDING_WITHDRAWAL_ID = 7n;
+
+async function main() {
+  const poseidon = await buildPoseidon();
+  const F = poseidon.F;
+  const hash = (inputs) => F.toObject(poseidon(inputs));
+  const hex = (value) => value.toString(16).padStart(64, "0");
+
+  const nullifierHash = hash([DOMAIN_NULLIFIER, NULLIFIER]);
+  const vectors = [
+    ["poseidon(1)", hash([1n])],
+    ["poseidon(1, 2)", hash([1n, 2n])],
+    ["poseidon(1, 2, 3, 4)", hash([1n, 2n, 3n, 4n])],
+    ["commitment", hash([DOMAIN_COMMIT, NULLIFIER, SECRET, AMOUNT])],
+    ["nullifier_hash", nullifierHash],
+    [
+      "withdrawal_binding_hash",
+      hash([DOMAIN_BIND, nullifierHash, RECIPIENT, RELAYER, FEE]),
+    ],
+    [
+      "ownership_binding_hash",
+      hash([DOMAIN_OWNER_BIND, NULLIFIER, PENDING_WITHDRAWAL_ID]),
+    ],
+  ];
+
+  for (const [name, value] of vectors) {
+    console.log(`    ("${name}", "${hex(value)}"),`);
+  }
+}
+
+main().catch((err) => {
+  console.error(err);
+  process.exit(1);
+});
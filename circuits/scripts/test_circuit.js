@@ -22,9 +22,9 @@ let F; // Field
 
 // Domain tags - MUST match circuit constants
 const DOMAIN_NULLIFIER = 1853189228n; // "null" as u32
-const DOMAIN_COMMIT = 1668246637n; // "comm" as u32
-const DOMAIN_BIND = 1651076196n; // "bind" as u32
-const DOMAIN_OWNER_BIND = 1869771618n; // "ownb" as u32
+const DOMAIN_COMMIT = 1668246637n; // "colm" as u32
+const DOMAIN_BIND = 1651076196n; // "bild" as u32
+const DOMAIN_OWNER_BIND = 1869771618n; // "orsb" as u32
 
 async function initPoseidon() {
   const { buildPoseidon } = await import("circomlibjs");
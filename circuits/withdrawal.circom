@@ -68,7 +68,7 @@ template Withdrawal(levels) {
     // 2. Compute commitment with domain separation
     // commitment = Poseidon(DOMAIN_COMMIT, nullifier, secret, amount)
     signal domainCommit;
-    domainCommit <== 1668246637;  // "comm" as u32
+    domainCommit <== 1668246637;  // "colm" as u32
     
     component commitmentHasher = Poseidon(4);
     commitmentHasher.inputs[0] <== domainCommit;
@@ -102,7 +102,7 @@ template Withdrawal(levels) {
     // This cryptographically binds the proof to specific values
     // Smart contract MUST verify: bindingHash == Poseidon(DOMAIN_BIND, nullifierHash, recipient, relayer, fee)
     signal domainBind;
-    domainBind <== 1651076196;  // "bind" as u32
+    domainBind <== 1651076196;  // "bild" as u32
     
     component bindingHasher = Poseidon(5);
     bindingHasher.inputs[0] <== domainBind;
@@ -1,21 +1,70 @@
 pub const DEFAULT_TOR_SOCKS_ADDR: &str = "127.0.0.1:9050";
 pub const DEFAULT_HTTP_GATEWAY_ADDR: &str = "127.0.0.1:3080";
+/// reqwest's own pool defaults
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = usize::MAX;
+pub const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Config {
     pub socks_addr: String,
+    /// Username/password for a SOCKS port that requires authentication
+    /// Tor also isolates streams by these, distinct credentials never share a circuit
+    pub socks_username: Option<String>,
+    pub socks_password: Option<String>,
     pub http_gateway_addr: String,
     pub timeout_secs: u64,
     pub verify_tls: bool,
+    /// Hosts whose certificates are accepted unchecked, e.g. a local relayer with a
+    /// self-signed cert. Matched case-insensitively against the URL host, every other host
+    /// is still validated. Redirects from these hosts are only followed to other listed hosts
+    pub insecure_hosts: Vec<String>,
+    /// DER certificate that is the only one trusted, e.g. the relayer's own
+    /// Built-in roots are dropped when set, so a MITM can't present any other cert
+    pub pinned_cert_der: Option<Vec<u8>>,
+    /// Idle keep-alive connections kept per host, 0 opens a new connection for every request
+    /// Reuse is faster, but everything sent over one connection shares a Tor circuit and is
+    /// linkable by the server. A fresh connection is not a fresh circuit though, Tor only
+    /// isolates streams by SOCKS credentials, so 0 reduces linkability without removing it
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle connection is kept before closing, None keeps it until the server
+    /// closes it
+    pub pool_idle_timeout_secs: Option<u64>,
+}
+
+/// Written out by hand so logging a config never prints the SOCKS password
+impl std::fmt::Debug for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Config")
+            .field("socks_addr", &self.socks_addr)
+            .field("socks_username", &self.socks_username)
+            .field(
+                "socks_password",
+                &self.socks_password.as_ref().map(|_| "<redacted>"),
+            )
+            .field("http_gateway_addr", &self.http_gateway_addr)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("verify_tls", &self.verify_tls)
+            .field("insecure_hosts", &self.insecure_hosts)
+            .field("pinned_cert_der", &self.pinned_cert_der)
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("pool_idle_timeout_secs", &self.pool_idle_timeout_secs)
+            .finish()
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             socks_addr: DEFAULT_TOR_SOCKS_ADDR.to_string(),
+            socks_username: None,
+            socks_password: None,
             http_gateway_addr: DEFAULT_HTTP_GATEWAY_ADDR.to_string(),
             timeout_secs: 60,
             verify_tls: true,
+            insecure_hosts: Vec::new(),
+            pinned_cert_der: None,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout_secs: Some(DEFAULT_POOL_IDLE_TIMEOUT_SECS),
         }
     }
 }
@@ -26,6 +75,18 @@ impl Config {
         self
     }
 
+    pub fn with_socks_auth(mut self, username: &str, password: &str) -> Self {
+        self.socks_username = Some(username.to_string());
+        self.socks_password = Some(password.to_string());
+        self
+    }
+
+    /// SOCKS credentials if a username is set, a missing password is sent empty
+    pub(crate) fn socks_auth(&self) -> Option<(&str, &str)> {
+        let username = self.socks_username.as_deref()?;
+        Some((username, self.socks_password.as_deref().unwrap_or("")))
+    }
+
     pub fn with_http_gateway_addr(mut self, addr: &str) -> Self {
         self.http_gateway_addr = addr.to_string();
         self
@@ -40,4 +101,34 @@ impl Config {
         self.verify_tls = false;
         self
     }
+
+    /// Skip certificate validation for `host` only
+    pub fn with_insecure_host(mut self, host: &str) -> Self {
+        self.insecure_hosts.push(host.to_string());
+        self
+    }
+
+    /// Whether requests to `host` validate its certificate
+    pub fn verifies_tls_for(&self, host: &str) -> bool {
+        self.verify_tls
+            && !self
+                .insecure_hosts
+                .iter()
+                .any(|insecure| insecure.eq_ignore_ascii_case(host))
+    }
+
+    pub fn with_pinned_cert(mut self, der: Vec<u8>) -> Self {
+        self.pinned_cert_der = Some(der);
+        self
+    }
+
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = max;
+        self
+    }
+
+    pub fn with_pool_idle_timeout(mut self, secs: Option<u64>) -> Self {
+        self.pool_idle_timeout_secs = secs;
+        self
+    }
 }
@@ -1,35 +1,161 @@
-use reqwest::{Client, Proxy, Response};
+use reqwest::{redirect, Certificate, Client, Method, Proxy, RequestBuilder, Response};
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 use crate::config::Config;
 use crate::error::{Result, TraceZeroError};
+use crate::socks_client::REMOTE_DNS_PROXY_SCHEME;
 
 pub struct TorHttpClient {
     client: Client,
+    /// Same proxy and pool settings without certificate validation, only for
+    /// `Config::insecure_hosts`
+    insecure_client: Option<Client>,
     config: Config,
 }
 
+/// Connection pool limits a client was built with
+/// reqwest doesn't expose live pool counters, so these are the configured bounds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolSettings {
+    pub max_idle_per_host: usize,
+    pub idle_timeout: Option<Duration>,
+}
+
+/// Where traffic leaves the network, as check.torproject.org saw it
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ExitInfo {
+    #[serde(rename = "IsTor", default)]
+    pub is_tor: bool,
+    /// Source address of the check request, the user's own IP when Tor isn't in the path
+    #[serde(rename = "IP", default)]
+    pub ip: Option<String>,
+}
+
+/// A request through the proxy, from `TorHttpClient::request`
+/// For what the convenience methods don't cover, e.g. headers an onion relayer requires
+pub struct TorRequest {
+    method: Method,
+    builder: RequestBuilder,
+}
+
+impl TorRequest {
+    /// Add a header, an invalid name or value fails the request at `send`
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.builder = self.builder.header(name, value);
+        self
+    }
+
+    /// JSON request body, also sets `Content-Type: application/json`
+    pub fn json<T: Serialize + ?Sized>(mut self, body: &T) -> Self {
+        self.builder = self.builder.json(body);
+        self
+    }
+
+    /// Raw request body, sent byte for byte
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.builder = self.builder.body(body);
+        self
+    }
+
+    pub async fn send(self) -> Result<Response> {
+        self.builder
+            .send()
+            .await
+            .map_err(|e| TraceZeroError::Http(format!("{} request failed: {}", self.method, e)))
+    }
+}
+
+/// Redirects followed by a client with reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+/// Follow redirects only between `insecure_hosts`
+/// The insecure client skips certificate checks, so a redirect elsewhere would reach a
+/// verified host without verification. It fails instead of silently switching clients
+fn insecure_redirect_policy(insecure_hosts: Vec<String>) -> redirect::Policy {
+    redirect::Policy::custom(move |attempt| {
+        let allowed = attempt.url().host_str().is_some_and(|host| {
+            insecure_hosts
+                .iter()
+                .any(|insecure| insecure.eq_ignore_ascii_case(host))
+        });
+        if !allowed {
+            let error = format!("redirect to {} leaves the insecure hosts", attempt.url());
+            attempt.error(error)
+        } else if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
 impl TorHttpClient {
     pub fn new(config: Config) -> Result<Self> {
-        let proxy_url = format!("socks5h://{}", config.socks_addr);
-        let proxy = Proxy::all(&proxy_url)
+        // Remote DNS through the proxy, see socks_client for the privacy property
+        let mut proxy_url = reqwest::Url::parse(&format!(
+            "{}://{}",
+            REMOTE_DNS_PROXY_SCHEME, config.socks_addr
+        ))
+        .map_err(|e| TraceZeroError::Config(format!("Invalid proxy URL: {}", e)))?;
+        if let Some((username, password)) = config.socks_auth() {
+            // Percent-encoded in the URL, reqwest decodes them for the SOCKS handshake
+            proxy_url
+                .set_username(username)
+                .and_then(|()| proxy_url.set_password(Some(password)))
+                .map_err(|()| TraceZeroError::Config("Invalid proxy URL for credentials".into()))?;
+        }
+        let proxy = Proxy::all(proxy_url)
             .map_err(|e| TraceZeroError::Config(format!("Invalid proxy URL: {}", e)))?;
 
-        let mut builder = Client::builder()
-            .proxy(proxy)
-            .timeout(Duration::from_secs(config.timeout_secs));
+        let base_builder = || {
+            Client::builder()
+                .proxy(proxy.clone())
+                .timeout(Duration::from_secs(config.timeout_secs))
+                .pool_max_idle_per_host(config.pool_max_idle_per_host)
+                .pool_idle_timeout(config.pool_idle_timeout_secs.map(Duration::from_secs))
+        };
+        let mut builder = base_builder();
 
         if !config.verify_tls {
             builder = builder.danger_accept_invalid_certs(true);
         }
 
+        if let Some(der) = &config.pinned_cert_der {
+            if !config.verify_tls {
+                return Err(TraceZeroError::Config(
+                    "Certificate pinning requires TLS verification".into(),
+                ));
+            }
+            let cert = Certificate::from_der(der)
+                .map_err(|e| TraceZeroError::Config(format!("Invalid pinned cert: {}", e)))?;
+            builder = builder
+                .tls_built_in_root_certs(false)
+                .add_root_certificate(cert);
+        }
+
         let client = builder
             .build()
             .map_err(|e| TraceZeroError::Config(format!("Failed to build client: {}", e)))?;
 
-        Ok(Self { client, config })
+        // Listed hosts skip the pin too, it's a certificate check like any other
+        let insecure_client = if config.verify_tls && !config.insecure_hosts.is_empty() {
+            let client = base_builder()
+                .danger_accept_invalid_certs(true)
+                .redirect(insecure_redirect_policy(config.insecure_hosts.clone()))
+                .build()
+                .map_err(|e| TraceZeroError::Config(format!("Failed to build client: {}", e)))?;
+            Some(client)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            client,
+            insecure_client,
+            config,
+        })
     }
 
     #[cfg(any(test, feature = "test-utils"))]
@@ -40,16 +166,43 @@ impl TorHttpClient {
 
         Ok(Self {
             client,
+            insecure_client: None,
             config: Config::default(),
         })
     }
 
+    pub fn pool_settings(&self) -> PoolSettings {
+        PoolSettings {
+            max_idle_per_host: self.config.pool_max_idle_per_host,
+            idle_timeout: self.config.pool_idle_timeout_secs.map(Duration::from_secs),
+        }
+    }
+
+    /// Build a request with any method and headers, sent through the proxy like the rest
+    pub fn request(&self, method: Method, url: &str) -> TorRequest {
+        TorRequest {
+            builder: self.client_for(url).request(method.clone(), url),
+            method,
+        }
+    }
+
+    /// Client whose TLS policy applies to `url`'s host. An unparseable URL gets the strict
+    /// one, the request fails at `send` anyway
+    fn client_for(&self, url: &str) -> &Client {
+        let insecure = self.insecure_client.as_ref().filter(|_| {
+            reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| {
+                    url.host_str()
+                        .map(|host| !self.config.verifies_tls_for(host))
+                })
+                .unwrap_or(false)
+        });
+        insecure.unwrap_or(&self.client)
+    }
+
     pub async fn get(&self, url: &str) -> Result<Response> {
-        self.client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| TraceZeroError::Http(format!("GET request failed: {}", e)))
+        self.request(Method::GET, url).send().await
     }
 
     pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
@@ -61,12 +214,7 @@ impl TorHttpClient {
     }
 
     pub async fn post<T: Serialize>(&self, url: &str, body: &T) -> Result<Response> {
-        self.client
-            .post(url)
-            .json(body)
-            .send()
-            .await
-            .map_err(|e| TraceZeroError::Http(format!("POST request failed: {}", e)))
+        self.request(Method::POST, url).json(body).send().await
     }
 
     pub async fn post_json<T: Serialize, R: DeserializeOwned>(
@@ -89,14 +237,34 @@ impl TorHttpClient {
             .map_err(|e| TraceZeroError::Http(format!("Failed to get IP: {}", e)))
     }
 
+    /// Ask check.torproject.org whether requests exit through Tor, and from which address
+    pub async fn get_exit_info(&self) -> Result<ExitInfo> {
+        self.get_json("https://check.torproject.org/api/ip").await
+    }
+
     pub async fn verify_tor_connection(&self) -> Result<bool> {
-        let response = self.get("https://check.torproject.org/api/ip").await?;
-        let json: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| TraceZeroError::Http(format!("JSON parse failed: {}", e)))?;
+        Ok(self.get_exit_info().await?.is_tor)
+    }
 
-        Ok(json.get("IsTor").and_then(|v| v.as_bool()).unwrap_or(false))
+    /// Force Tor to build a circuit now with a cheap check request, so the first real
+    /// request doesn't pay for it. Fails with `Connection` if no circuit exits through Tor
+    /// within the configured timeout
+    pub async fn warm_up(&self) -> Result<()> {
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        match tokio::time::timeout(timeout, self.verify_tor_connection()).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false)) => Err(TraceZeroError::Connection(
+                "Circuit built but traffic is not exiting through Tor".into(),
+            )),
+            Ok(Err(e)) => Err(TraceZeroError::Connection(format!(
+                "Tor bootstrap failed: {}",
+                e
+            ))),
+            Err(_) => Err(TraceZeroError::Connection(format!(
+                "Tor circuit not ready within {:?}",
+                timeout
+            ))),
+        }
     }
 
     pub fn config(&self) -> &Config {
@@ -21,10 +21,14 @@ pub mod error;
 pub mod http_client;
 pub mod socks_client;
 
-pub use config::{Config, DEFAULT_HTTP_GATEWAY_ADDR, DEFAULT_TOR_SOCKS_ADDR};
+pub use config::{
+    Config, DEFAULT_HTTP_GATEWAY_ADDR, DEFAULT_POOL_IDLE_TIMEOUT_SECS,
+    DEFAULT_POOL_MAX_IDLE_PER_HOST, DEFAULT_TOR_SOCKS_ADDR,
+};
 pub use error::{Result, TraceZeroError};
-pub use http_client::TorHttpClient;
-pub use socks_client::SocksClient;
+pub use http_client::{ExitInfo, PoolSettings, TorHttpClient, TorRequest};
+pub use reqwest::Method;
+pub use socks_client::{remote_target, SocksClient, DEFAULT_MAX_RESPONSE_BYTES, DNS_CHECK_HOST};
 
 pub fn tor_client() -> Result<TorHttpClient> {
     TorHttpClient::new(Config::default())
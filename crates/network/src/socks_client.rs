@@ -1,11 +1,36 @@
-use std::net::SocketAddr;
+/// Privacy property: target hostnames are never resolved locally. They are handed to the
+/// proxy as SOCKS5 DOMAINNAME addresses and Tor resolves them at the exit, so no DNS query
+/// for the destination ever leaves this machine
+use std::borrow::Cow;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio_socks::tcp::Socks5Stream;
+use tokio_socks::TargetAddr;
 
 use crate::config::Config;
 use crate::error::{Result, TraceZeroError};
 
+/// Proxy scheme for HTTP clients, `socks5h` delegates hostname resolution to the proxy
+/// (plain `socks5` would resolve locally and leak the destination over DNS)
+pub(crate) const REMOTE_DNS_PROXY_SCHEME: &str = "socks5h";
+
+/// Response cap for `send_receive`
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Hostname `check_dns_leak` has the proxy resolve, public and answered by Tor exits
+pub const DNS_CHECK_HOST: &str = "check.torproject.org";
+
+/// SOCKS5 target for `host:port` without any local DNS lookup
+/// IP literals are sent as-is, everything else goes to the proxy as a domain name
+pub fn remote_target(host: &str, port: u16) -> TargetAddr<'_> {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => TargetAddr::Ip(SocketAddr::new(ip, port)),
+        Err(_) => TargetAddr::Domain(Cow::Borrowed(host), port),
+    }
+}
+
 pub struct SocksClient {
     config: Config,
 }
@@ -20,24 +45,60 @@ impl SocksClient {
         target_host: &str,
         target_port: u16,
     ) -> Result<Socks5Stream<TcpStream>> {
-        let proxy_addr: SocketAddr = self
-            .config
+        let proxy_addr = self.proxy_addr()?;
+        self.open(proxy_addr, remote_target(target_host, target_port))
+            .await
+            .map_err(|e| TraceZeroError::Connection(format!("SOCKS5 connection failed: {}", e)))
+    }
+
+    fn proxy_addr(&self) -> Result<SocketAddr> {
+        self.config
             .socks_addr
             .parse()
-            .map_err(|e| TraceZeroError::Config(format!("Invalid SOCKS address: {}", e)))?;
-
-        let stream = Socks5Stream::connect(proxy_addr, (target_host, target_port))
-            .await
-            .map_err(|e| TraceZeroError::Connection(format!("SOCKS5 connection failed: {}", e)))?;
+            .map_err(|e| TraceZeroError::Config(format!("Invalid SOCKS address: {}", e)))
+    }
 
-        Ok(stream)
+    /// SOCKS5 handshake and CONNECT, with credentials if configured
+    async fn open(
+        &self,
+        proxy_addr: SocketAddr,
+        target: TargetAddr<'_>,
+    ) -> std::result::Result<Socks5Stream<TcpStream>, tokio_socks::Error> {
+        match self.config.socks_auth() {
+            Some((username, password)) => {
+                Socks5Stream::connect_with_password(proxy_addr, target, username, password).await
+            }
+            None => Socks5Stream::connect(proxy_addr, target).await,
+        }
     }
 
+    /// `send_receive_bounded` with a 16 MiB cap and the configured timeout per read
     pub async fn send_receive(
         &self,
         target_host: &str,
         target_port: u16,
         data: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.send_receive_bounded(
+            target_host,
+            target_port,
+            data,
+            DEFAULT_MAX_RESPONSE_BYTES,
+            Duration::from_secs(self.config.timeout_secs),
+        )
+        .await
+    }
+
+    /// Send `data` and read until the peer closes the connection
+    /// Fails once the response grows past `max_bytes` or a single read waits longer
+    /// than `read_timeout`, so a slow or hostile server can't hang the caller or fill memory
+    pub async fn send_receive_bounded(
+        &self,
+        target_host: &str,
+        target_port: u16,
+        data: &[u8],
+        max_bytes: usize,
+        read_timeout: Duration,
     ) -> Result<Vec<u8>> {
         let mut stream = self.connect(target_host, target_port).await?;
         stream
@@ -46,21 +107,59 @@ impl SocksClient {
             .map_err(|e| TraceZeroError::Io(e.to_string()))?;
 
         let mut response = Vec::new();
-        stream
-            .read_to_end(&mut response)
-            .await
-            .map_err(|e| TraceZeroError::Io(e.to_string()))?;
+        let mut buf = [0u8; 8192];
+        loop {
+            let read = tokio::time::timeout(read_timeout, stream.read(&mut buf))
+                .await
+                .map_err(|_| {
+                    TraceZeroError::Io(format!("No data from peer within {:?}", read_timeout))
+                })?
+                .map_err(|e| TraceZeroError::Io(e.to_string()))?;
+            if read == 0 {
+                return Ok(response);
+            }
+            if response.len() + read > max_bytes {
+                return Err(TraceZeroError::Io(format!(
+                    "Response exceeds {} bytes",
+                    max_bytes
+                )));
+            }
+            response.extend_from_slice(&buf[..read]);
+        }
+    }
 
-        Ok(response)
+    /// `check_dns_leak_for` against `DNS_CHECK_HOST:443`
+    pub async fn check_dns_leak(&self) -> Result<bool> {
+        self.check_dns_leak_for(DNS_CHECK_HOST, 443).await
     }
 
-    pub async fn check_connection(&self) -> Result<bool> {
-        let proxy_addr: SocketAddr = self
-            .config
-            .socks_addr
-            .parse()
-            .map_err(|e| TraceZeroError::Config(format!("Invalid SOCKS address: {}", e)))?;
+    /// Privacy self-test for name resolution. Clients in this crate already resolve
+    /// remotely (`socks5h` for HTTP, `remote_target` here), this checks the proxy plays
+    /// along: `host` is sent to it as a SOCKS5 domain name and nothing is looked up locally.
+    /// Returns false if the proxy resolved and reached it, true if the proxy refuses domain
+    /// names, so reaching `host` would take a local lookup that leaks it to the DNS server.
+    /// Any other failure is a `Connection` error
+    pub async fn check_dns_leak_for(&self, host: &str, port: u16) -> Result<bool> {
+        let target = remote_target(host, port);
+        if !matches!(target, TargetAddr::Domain(..)) {
+            return Err(TraceZeroError::Config(format!(
+                "{} is an IP address, there is nothing to resolve",
+                host
+            )));
+        }
 
+        match self.open(self.proxy_addr()?, target).await {
+            Ok(_) => Ok(false),
+            Err(tokio_socks::Error::AddressTypeNotSupported) => Ok(true),
+            Err(e) => Err(TraceZeroError::Connection(format!(
+                "Proxy could not resolve {}: {}",
+                host, e
+            ))),
+        }
+    }
+
+    pub async fn check_connection(&self) -> Result<bool> {
+        let proxy_addr = self.proxy_addr()?;
         match TcpStream::connect(proxy_addr).await {
             Ok(_) => Ok(true),
             Err(_) => Ok(false),
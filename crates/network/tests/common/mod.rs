@@ -0,0 +1,97 @@
+//! Fake SOCKS5 proxy pieces shared by the network tests
+// Each test binary compiles this module on its own and uses only part of it
+#![allow(dead_code)]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// CONNECT succeeded, with an unspecified bound address
+pub const SOCKS5_SUCCESS: [u8; 10] = [0x05, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0];
+
+/// SOCKS5 CONNECT target as received by the proxy
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProxiedTarget {
+    Domain(String, u16),
+    Ip(Vec<u8>, u16),
+}
+
+impl ProxiedTarget {
+    pub fn host(&self) -> Option<&str> {
+        match self {
+            ProxiedTarget::Domain(host, _) => Some(host),
+            ProxiedTarget::Ip(..) => None,
+        }
+    }
+}
+
+/// Bind a listener on a free local port, returning it with its address
+pub async fn bind() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    (listener, addr)
+}
+
+/// Greeting without authentication, then the CONNECT
+pub async fn handshake(stream: &mut TcpStream) -> ProxiedTarget {
+    // Greeting: VER, NMETHODS, METHODS
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await.unwrap();
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await.unwrap();
+    stream.write_all(&[0x05, 0x00]).await.unwrap();
+
+    accept_connect(stream).await
+}
+
+/// Read the CONNECT request and report success, returning its target
+pub async fn accept_connect(stream: &mut TcpStream) -> ProxiedTarget {
+    let target = read_connect(stream).await;
+    stream.write_all(&SOCKS5_SUCCESS).await.unwrap();
+    target
+}
+
+/// Read the CONNECT request without replying to it
+pub async fn read_connect(stream: &mut TcpStream) -> ProxiedTarget {
+    // Request: VER, CMD, RSV, ATYP, DST.ADDR, DST.PORT
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await.unwrap();
+    match request[3] {
+        0x03 => {
+            let len = stream.read_u8().await.unwrap();
+            let mut name = vec![0u8; len as usize];
+            stream.read_exact(&mut name).await.unwrap();
+            let port = stream.read_u16().await.unwrap();
+            ProxiedTarget::Domain(String::from_utf8(name).unwrap(), port)
+        }
+        atyp => {
+            let mut ip = vec![0u8; if atyp == 0x01 { 4 } else { 16 }];
+            stream.read_exact(&mut ip).await.unwrap();
+            let port = stream.read_u16().await.unwrap();
+            ProxiedTarget::Ip(ip, port)
+        }
+    }
+}
+
+/// Read one HTTP/1.1 request, head and the body announced by Content-Length
+/// `buf` carries bytes past the request over to the next call on a keep-alive connection.
+/// None once the client hangs up or the connection fails
+pub async fn read_http_request(stream: &mut TcpStream, buf: &mut Vec<u8>) -> Option<String> {
+    let mut chunk = [0u8; 1024];
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buf[..end]).to_string();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+                .map_or(0, |len| len.parse::<usize>().unwrap());
+            if buf.len() >= end + 4 + length {
+                let request = buf.drain(..end + 4 + length).collect::<Vec<_>>();
+                return Some(String::from_utf8(request).unwrap());
+            }
+        }
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+}
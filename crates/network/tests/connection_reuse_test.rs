@@ -0,0 +1,88 @@
+//! Keep-alive connections through the SOCKS proxy are reused only as configured
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tracezero::{Config, PoolSettings, TorHttpClient};
+
+/// SOCKS5 proxy that answers every HTTP request itself, counting proxied connections
+async fn spawn_counting_proxy() -> (String, Arc<AtomicUsize>) {
+    let (listener, addr) = common::bind().await;
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(serve(stream));
+        }
+    });
+
+    (addr, connections)
+}
+
+async fn serve(mut stream: TcpStream) {
+    common::handshake(&mut stream).await;
+
+    // Keep-alive HTTP/1.1 until the client hangs up
+    let mut buf = Vec::new();
+    while common::read_http_request(&mut stream, &mut buf)
+        .await
+        .is_some()
+    {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        if stream.write_all(response).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn connections_for(config: Config) -> usize {
+    let (proxy_addr, connections) = spawn_counting_proxy().await;
+    let client = TorHttpClient::new(config.with_socks_addr(&proxy_addr)).unwrap();
+
+    for _ in 0..3 {
+        let body = client
+            .get("http://relayer.invalid/health")
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+    }
+    connections.load(Ordering::SeqCst)
+}
+
+#[tokio::test]
+async fn test_pooled_client_reuses_connection() {
+    assert_eq!(connections_for(Config::default()).await, 1);
+}
+
+#[tokio::test]
+async fn test_unpooled_client_opens_connection_per_request() {
+    let config = Config::default().with_pool_max_idle_per_host(0);
+    assert_eq!(connections_for(config).await, 3);
+}
+
+#[test]
+fn test_pool_settings_reported() {
+    let client = TorHttpClient::new(
+        Config::default()
+            .with_pool_max_idle_per_host(4)
+            .with_pool_idle_timeout(Some(30)),
+    )
+    .unwrap();
+    assert_eq!(
+        client.pool_settings(),
+        PoolSettings {
+            max_idle_per_host: 4,
+            idle_timeout: Some(Duration::from_secs(30)),
+        }
+    );
+}
@@ -0,0 +1,126 @@
+//! Verifies that target hostnames reach the SOCKS proxy unresolved (no local DNS lookup)
+mod common;
+
+use common::ProxiedTarget;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio_socks::TargetAddr;
+use tracezero::{remote_target, Config, SocksClient, TorHttpClient, TraceZeroError};
+
+/// `.invalid` never resolves, so a local lookup would fail before reaching the proxy
+const TARGET_HOST: &str = "tracezero-dns-leak.invalid";
+
+/// Minimal SOCKS5 server that records the first CONNECT target, then closes
+async fn spawn_fake_socks5() -> (String, oneshot::Receiver<ProxiedTarget>) {
+    let (listener, addr) = common::bind().await;
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let _ = tx.send(common::handshake(&mut stream).await);
+    });
+
+    (addr, rx)
+}
+
+#[test]
+fn test_remote_target_keeps_hostnames_unresolved() {
+    match remote_target(TARGET_HOST, 443) {
+        TargetAddr::Domain(host, port) => {
+            assert_eq!(host, TARGET_HOST);
+            assert_eq!(port, 443);
+        }
+        other => panic!("hostname was resolved locally: {:?}", other),
+    }
+
+    // IP literals need no resolution and are passed as addresses
+    assert!(matches!(remote_target("10.0.0.1", 80), TargetAddr::Ip(_)));
+}
+
+#[tokio::test]
+async fn test_socks_client_sends_hostname_to_proxy() {
+    let (proxy_addr, target) = spawn_fake_socks5().await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+
+    client.connect(TARGET_HOST, 443).await.unwrap();
+
+    assert_eq!(
+        target.await.unwrap(),
+        ProxiedTarget::Domain(TARGET_HOST.to_string(), 443)
+    );
+}
+
+#[tokio::test]
+async fn test_http_client_sends_hostname_to_proxy() {
+    let (proxy_addr, target) = spawn_fake_socks5().await;
+    let client = TorHttpClient::new(Config::default().with_socks_addr(&proxy_addr)).unwrap();
+
+    // The fake proxy hangs up after CONNECT, so the request itself fails
+    let _ = client.get(&format!("http://{}:8080/", TARGET_HOST)).await;
+
+    assert_eq!(
+        target.await.unwrap(),
+        ProxiedTarget::Domain(TARGET_HOST.to_string(), 8080)
+    );
+}
+
+/// SOCKS5 server that only takes IP targets, like a proxy without remote resolution
+async fn spawn_ip_only_socks5() -> String {
+    let (listener, addr) = common::bind().await;
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut greeting = [0u8; 3];
+        stream.read_exact(&mut greeting).await.unwrap();
+        stream.write_all(&[0x05, 0x00]).await.unwrap();
+
+        // Address type not supported
+        common::read_connect(&mut stream).await;
+        stream
+            .write_all(&[0x05, 0x08, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+    });
+
+    addr
+}
+
+#[tokio::test]
+async fn test_dns_leak_check() {
+    // The proxy resolves the name, no local lookup needed: no leak
+    let (proxy_addr, target) = spawn_fake_socks5().await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+    assert!(!client.check_dns_leak_for(TARGET_HOST, 443).await.unwrap());
+    assert_eq!(
+        target.await.unwrap(),
+        ProxiedTarget::Domain(TARGET_HOST.to_string(), 443)
+    );
+
+    // A proxy refusing names leaves only local resolution, which would leak
+    let proxy_addr = spawn_ip_only_socks5().await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+    assert!(client.check_dns_leak_for(TARGET_HOST, 443).await.unwrap());
+
+    // IP literals never involve DNS
+    assert!(matches!(
+        client.check_dns_leak_for("127.0.0.1", 443).await,
+        Err(TraceZeroError::Config(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_dns_leak_check_needs_working_proxy() {
+    // Bind then drop to get a port nothing listens on
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let client = SocksClient::new(Config::default().with_socks_addr(&addr));
+    assert!(matches!(
+        client.check_dns_leak_for(TARGET_HOST, 443).await,
+        Err(TraceZeroError::Connection(_))
+    ));
+}
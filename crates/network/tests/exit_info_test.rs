@@ -0,0 +1,19 @@
+//! Decoding of the check.torproject.org answer behind `get_exit_info`
+use tracezero::ExitInfo;
+
+#[test]
+fn test_exit_info_from_check_response() {
+    let info: ExitInfo = serde_json::from_str(r#"{"IsTor":true,"IP":"185.220.101.4"}"#).unwrap();
+    assert_eq!(
+        info,
+        ExitInfo {
+            is_tor: true,
+            ip: Some("185.220.101.4".into()),
+        }
+    );
+
+    // Missing fields read as "not Tor, address unknown"
+    let info: ExitInfo = serde_json::from_str("{}").unwrap();
+    assert!(!info.is_tor);
+    assert_eq!(info.ip, None);
+}
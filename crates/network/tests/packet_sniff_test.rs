@@ -143,6 +143,7 @@ async fn test_tor_hides_destination() {
 
 /// docker-compose up -d && cargo test --test packet_sniff_test test_live_tor -- --nocapture --ignored
 #[tokio::test]
+#[ignore]
 async fn test_live_tor_connection() {
     use tracezero::{Config, TorHttpClient};
 
@@ -0,0 +1,62 @@
+//! Requests built with custom methods and headers still go through the proxy intact
+mod common;
+
+use tokio::io::AsyncWriteExt;
+use tokio::sync::oneshot;
+use tracezero::{Config, Method, TorHttpClient, TraceZeroError};
+
+/// SOCKS5 proxy that answers one HTTP request itself and hands back its raw head and body
+async fn spawn_recording_proxy() -> (String, oneshot::Receiver<String>) {
+    let (listener, addr) = common::bind().await;
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        common::handshake(&mut stream).await;
+
+        let Some(request) = common::read_http_request(&mut stream, &mut Vec::new()).await else {
+            return;
+        };
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+            .await
+            .unwrap();
+        let _ = tx.send(request);
+    });
+
+    (addr, rx)
+}
+
+#[tokio::test]
+async fn test_request_with_headers_and_body() {
+    let (proxy_addr, received) = spawn_recording_proxy().await;
+    let client = TorHttpClient::new(Config::default().with_socks_addr(&proxy_addr)).unwrap();
+
+    let response = client
+        .request(Method::PUT, "http://relayer.onion/deposit")
+        .header("Authorization", "Bearer onion-token")
+        .header("Host", "relayer.internal")
+        .json(&serde_json::json!({ "bucket": 2 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "ok");
+
+    let request = received.await.unwrap();
+    assert!(request.starts_with("PUT /deposit HTTP/1.1\r\n"));
+    assert!(request.contains("authorization: Bearer onion-token\r\n"));
+    assert!(request.contains("host: relayer.internal\r\n"));
+    assert!(request.contains("content-type: application/json\r\n"));
+    assert!(request.ends_with(r#"{"bucket":2}"#));
+}
+
+#[tokio::test]
+async fn test_invalid_header_fails_at_send() {
+    let client = TorHttpClient::new(Config::default()).unwrap();
+    let result = client
+        .request(Method::GET, "http://relayer.onion/health")
+        .header("Bad Header", "value")
+        .send()
+        .await;
+    assert!(matches!(result, Err(TraceZeroError::Http(_))));
+}
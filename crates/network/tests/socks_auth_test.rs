@@ -0,0 +1,103 @@
+//! Verifies SOCKS5 username/password credentials from `Config` reach the proxy
+mod common;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::oneshot;
+use tracezero::{Config, SocksClient, TorHttpClient};
+
+/// Characters that must survive being percent-encoded into the proxy URL
+const USERNAME: &str = "tracezero user";
+const PASSWORD: &str = "p@ss:w/rd%";
+
+/// Minimal SOCKS5 server that only accepts username/password auth (RFC 1929), records the
+/// credentials of the first client, accepts its CONNECT, then closes
+async fn spawn_auth_socks5() -> (String, oneshot::Receiver<(String, String)>) {
+    let (listener, addr) = common::bind().await;
+    let (tx, rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        // Greeting: VER, NMETHODS, METHODS, pick username/password
+        let mut header = [0u8; 2];
+        stream.read_exact(&mut header).await.unwrap();
+        let mut methods = vec![0u8; header[1] as usize];
+        stream.read_exact(&mut methods).await.unwrap();
+        if !methods.contains(&0x02) {
+            stream.write_all(&[0x05, 0xff]).await.unwrap();
+            return;
+        }
+        stream.write_all(&[0x05, 0x02]).await.unwrap();
+
+        // Auth: VER, ULEN, UNAME, PLEN, PASSWD
+        let mut ver_len = [0u8; 2];
+        stream.read_exact(&mut ver_len).await.unwrap();
+        let mut username = vec![0u8; ver_len[1] as usize];
+        stream.read_exact(&mut username).await.unwrap();
+        let password_len = stream.read_u8().await.unwrap();
+        let mut password = vec![0u8; password_len as usize];
+        stream.read_exact(&mut password).await.unwrap();
+        stream.write_all(&[0x01, 0x00]).await.unwrap();
+
+        common::accept_connect(&mut stream).await;
+        let _ = tx.send((
+            String::from_utf8(username).unwrap(),
+            String::from_utf8(password).unwrap(),
+        ));
+    });
+
+    (addr, rx)
+}
+
+#[tokio::test]
+async fn test_socks_client_authenticates() {
+    let (proxy_addr, credentials) = spawn_auth_socks5().await;
+    let config = Config::default()
+        .with_socks_addr(&proxy_addr)
+        .with_socks_auth(USERNAME, PASSWORD);
+
+    SocksClient::new(config)
+        .connect("relayer.example", 443)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        credentials.await.unwrap(),
+        (USERNAME.to_string(), PASSWORD.to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_http_client_authenticates() {
+    let (proxy_addr, credentials) = spawn_auth_socks5().await;
+    let config = Config::default()
+        .with_socks_addr(&proxy_addr)
+        .with_socks_auth(USERNAME, PASSWORD);
+    let client = TorHttpClient::new(config).unwrap();
+
+    // The fake proxy hangs up after CONNECT, so the request itself fails
+    let _ = client.get("http://relayer.example:8080/").await;
+
+    assert_eq!(
+        credentials.await.unwrap(),
+        (USERNAME.to_string(), PASSWORD.to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_missing_credentials_are_refused() {
+    let (proxy_addr, credentials) = spawn_auth_socks5().await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+
+    assert!(client.connect("relayer.example", 443).await.is_err());
+    assert!(credentials.await.is_err());
+}
+
+#[test]
+fn test_debug_redacts_password() {
+    let config = Config::default().with_socks_auth(USERNAME, PASSWORD);
+    let debug = format!("{:?}", config);
+    assert!(debug.contains(USERNAME));
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains(PASSWORD));
+}
@@ -0,0 +1,67 @@
+//! `send_receive_bounded` gives up on oversized or stalled responses
+mod common;
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracezero::{Config, SocksClient, TraceZeroError};
+
+const TARGET_HOST: &str = "tracezero-bounds.invalid";
+
+/// SOCKS5 proxy that accepts one CONNECT, then answers any request with `reply`
+/// It hangs up afterwards, or keeps the connection open without sending more if `stall`
+async fn spawn_fake_peer(reply: Vec<u8>, stall: bool) -> String {
+    let (listener, addr) = common::bind().await;
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        common::handshake(&mut stream).await;
+
+        let mut payload = [0u8; 4];
+        stream.read_exact(&mut payload).await.unwrap();
+        // The client may give up mid-write, that's the point of the cap
+        let _ = stream.write_all(&reply).await;
+        if stall {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        }
+    });
+
+    addr
+}
+
+#[tokio::test]
+async fn test_response_within_bounds() {
+    let proxy_addr = spawn_fake_peer(b"pong".to_vec(), false).await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+
+    let response = client
+        .send_receive_bounded(TARGET_HOST, 80, b"ping", 4, Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(response, b"pong");
+}
+
+#[tokio::test]
+async fn test_oversized_response_rejected() {
+    let proxy_addr = spawn_fake_peer(vec![7u8; 64 * 1024], false).await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+
+    let result = client
+        .send_receive_bounded(TARGET_HOST, 80, b"ping", 1024, Duration::from_secs(5))
+        .await;
+    assert!(matches!(result, Err(TraceZeroError::Io(_))));
+}
+
+#[tokio::test]
+async fn test_stalled_peer_times_out() {
+    let proxy_addr = spawn_fake_peer(b"partial".to_vec(), true).await;
+    let client = SocksClient::new(Config::default().with_socks_addr(&proxy_addr));
+
+    let started = std::time::Instant::now();
+    let result = client
+        .send_receive_bounded(TARGET_HOST, 80, b"ping", 1024, Duration::from_millis(200))
+        .await;
+    assert!(matches!(result, Err(TraceZeroError::Io(_))));
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
@@ -0,0 +1,148 @@
+//! Certificate pinning is validated when the client is built, hosts exempt from
+//! verification don't leak the exemption to other hosts
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::AsyncWriteExt;
+use tracezero::{Config, TorHttpClient, TraceZeroError};
+
+/// Self-signed P-256 certificate for CN=relayer.onion
+const PINNED_CERT: &[u8] = include_bytes!("fixtures/relayer_cert.der");
+
+#[test]
+fn test_pinned_cert_accepted() {
+    let config = Config::default().with_pinned_cert(PINNED_CERT.to_vec());
+    assert!(TorHttpClient::new(config).is_ok());
+}
+
+#[test]
+fn test_invalid_pinned_cert_rejected() {
+    let config = Config::default().with_pinned_cert(b"not a certificate".to_vec());
+    assert!(matches!(
+        TorHttpClient::new(config),
+        Err(TraceZeroError::Config(_))
+    ));
+
+    // Truncated DER is as bad as garbage
+    let config = Config::default().with_pinned_cert(PINNED_CERT[..100].to_vec());
+    assert!(matches!(
+        TorHttpClient::new(config),
+        Err(TraceZeroError::Config(_))
+    ));
+}
+
+#[test]
+fn test_pinning_without_verification_rejected() {
+    // Accepting any cert would make the pin meaningless
+    let config = Config::default()
+        .with_pinned_cert(PINNED_CERT.to_vec())
+        .without_tls_verification();
+    assert!(matches!(
+        TorHttpClient::new(config),
+        Err(TraceZeroError::Config(_))
+    ));
+}
+
+#[test]
+fn test_insecure_hosts_are_per_host() {
+    let config = Config::default().with_insecure_host("relayer.local");
+    assert!(!config.verifies_tls_for("relayer.local"));
+    assert!(!config.verifies_tls_for("Relayer.LOCAL"));
+    assert!(config.verifies_tls_for("check.torproject.org"));
+    assert!(TorHttpClient::new(config).is_ok());
+
+    // The global switch still covers every host
+    let config = Config::default().without_tls_verification();
+    assert!(!config.verifies_tls_for("check.torproject.org"));
+}
+
+#[test]
+fn test_insecure_host_alongside_pin() {
+    // The pin keeps guarding every other host
+    let config = Config::default()
+        .with_pinned_cert(PINNED_CERT.to_vec())
+        .with_insecure_host("127.0.0.1");
+    assert!(TorHttpClient::new(config).is_ok());
+}
+
+/// SOCKS5 proxy that answers HTTP itself: hosts in `redirects` send a 302 to the paired URL,
+/// any other host gets "ok". Records the host of every CONNECT
+async fn spawn_redirecting_proxy(redirects: &[(&str, &str)]) -> (String, Arc<Mutex<Vec<String>>>) {
+    let (listener, addr) = common::bind().await;
+    let redirects: Vec<(String, String)> = redirects
+        .iter()
+        .map(|(host, location)| (host.to_string(), location.to_string()))
+        .collect();
+    let hosts = Arc::new(Mutex::new(Vec::new()));
+
+    let seen = hosts.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let redirects = redirects.clone();
+            let seen = seen.clone();
+            tokio::spawn(async move {
+                let target = common::handshake(&mut stream).await;
+                let host = target.host().unwrap().to_string();
+                seen.lock().unwrap().push(host.clone());
+
+                let mut buf = Vec::new();
+                while common::read_http_request(&mut stream, &mut buf)
+                    .await
+                    .is_some()
+                {
+                    let response = match redirects.iter().find(|(from, _)| *from == host) {
+                        Some((_, location)) => format!(
+                            "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\n\r\n",
+                            location
+                        ),
+                        None => "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok".to_string(),
+                    };
+                    if stream.write_all(response.as_bytes()).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    (addr, hosts)
+}
+
+#[tokio::test]
+async fn test_insecure_host_redirects_stay_insecure() {
+    let (proxy_addr, hosts) = spawn_redirecting_proxy(&[
+        ("relayer.local", "http://mirror.local/health"),
+        ("mirror.local", "http://relayer.example/health"),
+    ])
+    .await;
+    let config = Config::default()
+        .with_socks_addr(&proxy_addr)
+        .with_insecure_host("relayer.local")
+        .with_insecure_host("mirror.local");
+    let client = TorHttpClient::new(config).unwrap();
+
+    // Between exempt hosts the redirect is followed, off them it's refused
+    let result = client.get("http://relayer.local/health").await;
+    assert!(matches!(result, Err(TraceZeroError::Http(_))));
+    assert_eq!(*hosts.lock().unwrap(), ["relayer.local", "mirror.local"]);
+}
+
+#[tokio::test]
+async fn test_verified_host_redirects_are_followed() {
+    let (proxy_addr, hosts) =
+        spawn_redirecting_proxy(&[("relayer.example", "http://mirror.example/health")]).await;
+    let config = Config::default()
+        .with_socks_addr(&proxy_addr)
+        .with_insecure_host("relayer.local");
+    let client = TorHttpClient::new(config).unwrap();
+
+    // Hosts that verify TLS keep the default client and its redirect policy
+    let response = client.get("http://relayer.example/health").await.unwrap();
+    assert_eq!(response.text().await.unwrap(), "ok");
+    assert_eq!(
+        *hosts.lock().unwrap(),
+        ["relayer.example", "mirror.example"]
+    );
+}
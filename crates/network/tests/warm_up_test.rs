@@ -0,0 +1,38 @@
+//! `warm_up` fails fast with a connection error when no Tor circuit comes up
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpListener;
+use tracezero::{Config, TorHttpClient, TraceZeroError};
+
+#[tokio::test]
+async fn test_warm_up_without_proxy_fails() {
+    // Bind then drop to get a port nothing listens on
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .to_string();
+    let client = TorHttpClient::new(Config::default().with_socks_addr(&addr)).unwrap();
+
+    let result = client.warm_up().await;
+    assert!(matches!(result, Err(TraceZeroError::Connection(_))));
+}
+
+#[tokio::test]
+async fn test_warm_up_times_out_on_stalled_proxy() {
+    // Accepts the connection but never answers the SOCKS handshake
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        let (_stream, _) = listener.accept().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(30)).await;
+    });
+    let client =
+        TorHttpClient::new(Config::default().with_socks_addr(&addr).with_timeout(1)).unwrap();
+
+    let started = Instant::now();
+    let result = client.warm_up().await;
+    assert!(matches!(result, Err(TraceZeroError::Connection(_))));
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
@@ -76,7 +76,7 @@ pub fn verify_signature(message: &[u8], signature: &[u8], pubkey: &RsaPublicKey)
 }
 
 fn generate_blinding_factor(n: &BigUint) -> Result<BigUint> {
-    let n_bytes = (n.bits() as usize + 7) / 8;
+    let n_bytes = n.bits().div_ceil(8);
     let mut bytes = vec![0u8; n_bytes];
 
     for _ in 0..100 {
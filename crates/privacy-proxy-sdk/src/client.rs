@@ -1,15 +1,32 @@
 /// Orchestrates the flow: credit purchase → deposit → withdrawal
 use rsa::RsaPublicKey;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
 use tracezero::{Config as TorConfig, TorHttpClient};
 
-use crate::credits::{BlindedCredit, SignedCredit};
-use crate::crypto::encrypt_payload;
+use crate::credits::{BlindedCredit, CreditSignRequest, CreditSignResponse, SignedCredit};
+use crate::crypto::{encrypt_payload_ecdh, generate_nullifier_hash, NotePadding};
 use crate::deposit::{DepositNote, DepositRequest, DepositResponse};
 use crate::error::{Result, SdkError};
-use crate::merkle::MerkleProof;
+#[cfg(feature = "prover")]
+use crate::flow::{persist_note, send_payment, FlowPhase, FlowResult, FlowSetup};
+use crate::merkle::{MerkleProof, MerkleProofResponse};
+use crate::params::RelayerParams;
+use crate::pool::{fetch_pool_stats, PoolStats};
+#[cfg(feature = "prover")]
+use crate::prover::OwnershipProver;
 use crate::stealth::{StealthAddress, StealthMaster};
-use crate::withdrawal::{WithdrawalRequest, WithdrawalResponse};
+use crate::withdrawal::{
+    AnonymityGuard, NullifierStatusResponse, WithdrawalOptions, WithdrawalRequest,
+    WithdrawalResponse, WithdrawalState, WithdrawalStatusResponse, WithdrawalSubmission,
+    MAX_DELAY_HOURS, MIN_DELAY_HOURS,
+};
 
 pub struct ClientConfig {
     /// Relayer URL (accessed via Tor)
@@ -18,59 +35,111 @@ pub struct ClientConfig {
     pub relayer_pubkey: RsaPublicKey,
     /// Tor SOCKS5 proxy address
     pub tor_socks_addr: String,
-    /// Shared secret for payload encryption (derived from relayer pubkey)
-    pub encryption_secret: [u8; 32],
+    /// Relayer's ed25519 pubkey: checks the `/params` bundle and is bound into withdrawal proofs
+    pub relayer_signer: Pubkey,
+    /// Permit a non-.onion relayer URL (the relayer can then log our Tor exit node)
+    pub allow_clearnet: bool,
 }
 
+/// Cheap to clone, clones share the Tor connection pool, the Tor check and the cached params
+#[derive(Clone)]
 pub struct PrivacyClient {
-    config: ClientConfig,
-    tor_client: TorHttpClient,
-    stealth_master: StealthMaster,
-    tor_verified: bool,
+    config: Arc<ClientConfig>,
+    tor_client: Arc<TorHttpClient>,
+    stealth_master: Arc<StealthMaster>,
+    tor_verified: Arc<AtomicBool>,
+    /// Verified `/params` bundle, fetched on first use
+    params: Arc<OnceCell<RelayerParams>>,
+    /// No Tor at all, only `new_direct` sets this
+    direct: bool,
+    /// Layout of the notes stored on-chain with each deposit
+    note_padding: NotePadding,
 }
 
 impl PrivacyClient {
     pub fn new(config: ClientConfig) -> Result<Self> {
+        validate_relayer_url(&config.relayer_url, config.allow_clearnet)?;
         let tor_config = TorConfig::default().with_socks_addr(&config.tor_socks_addr);
         let tor_client = TorHttpClient::new(tor_config)?;
 
         Ok(Self {
-            config,
-            tor_client,
-            stealth_master: StealthMaster::new(),
-            tor_verified: false,
+            config: Arc::new(config),
+            tor_client: Arc::new(tor_client),
+            stealth_master: Arc::new(StealthMaster::new()),
+            tor_verified: Default::default(),
+            params: Default::default(),
+            direct: false,
+            note_padding: NotePadding::None,
+        })
+    }
+
+    /// `new`, then `warm_up`, so the first deposit doesn't wait for a Tor circuit
+    pub async fn connect(config: ClientConfig) -> Result<Self> {
+        let client = Self::new(config)?;
+        client.warm_up().await?;
+        Ok(client)
+    }
+
+    /// INSECURE, for tests only: connect to the relayer directly instead of through Tor
+    /// The Tor check is skipped and clearnet URLs are accepted, so the relayer and anyone
+    /// on the path see the caller's IP. Only built with the `test-utils` feature
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn new_direct(config: ClientConfig) -> Result<Self> {
+        Ok(Self {
+            config: Arc::new(config),
+            tor_client: Arc::new(TorHttpClient::new_direct()?),
+            stealth_master: Arc::new(StealthMaster::new()),
+            tor_verified: Default::default(),
+            params: Default::default(),
+            direct: true,
+            note_padding: NotePadding::None,
         })
     }
 
     pub fn with_stealth_master(config: ClientConfig, stealth_secret: [u8; 32]) -> Result<Self> {
+        validate_relayer_url(&config.relayer_url, config.allow_clearnet)?;
         let tor_config = TorConfig::default().with_socks_addr(&config.tor_socks_addr);
         let tor_client = TorHttpClient::new(tor_config)?;
 
         Ok(Self {
-            config,
-            tor_client,
-            stealth_master: StealthMaster::from_secret(stealth_secret),
-            tor_verified: false,
+            config: Arc::new(config),
+            tor_client: Arc::new(tor_client),
+            stealth_master: Arc::new(StealthMaster::from_secret(stealth_secret)),
+            tor_verified: Default::default(),
+            params: Default::default(),
+            direct: false,
+            note_padding: NotePadding::None,
         })
     }
 
-    async fn ensure_tor(&mut self) -> Result<()> {
-        if self.tor_verified {
+    /// Pad the encrypted note of every deposit, e.g. `NotePadding::fixed()` so all notes
+    /// are the same size on-chain. Unpadded by default
+    pub fn with_note_padding(mut self, padding: NotePadding) -> Self {
+        self.note_padding = padding;
+        self
+    }
+
+    /// Build the Tor circuit ahead of the first request, e.g. at app start, by running the
+    /// Tor check now. Later requests don't repeat it, and a non-Tor exit fails with
+    /// `TorNotDetected` as it would on the first request
+    pub async fn warm_up(&self) -> Result<()> {
+        self.ensure_tor().await
+    }
+
+    async fn ensure_tor(&self) -> Result<()> {
+        if self.is_tor_verified() || self.direct {
             return Ok(());
         }
 
-        let is_tor = self
-            .tor_client
-            .verify_tor_connection()
-            .await
-            .map_err(|e| SdkError::Network(e))?;
-        if !is_tor {
-            return Err(SdkError::TorRequired(
-                "Tor connection required but not detected. Refusing to send sensitive data.".into(),
-            ));
+        // Refuse to send anything sensitive outside Tor
+        let exit = self.get_exit_info().await?;
+        if !exit.is_tor {
+            return Err(SdkError::TorNotDetected {
+                checked_exit_ip: exit.ip,
+            });
         }
 
-        self.tor_verified = true;
+        self.tor_verified.store(true, Ordering::Release);
         Ok(())
     }
 
@@ -86,29 +155,115 @@ impl PrivacyClient {
         credit.unblind(blinded_signature, &self.config.relayer_pubkey)
     }
 
+    /// Have the relayer sign `credit` against `payment_tx`, the transfer from `payer` that
+    /// paid for it, and unblind the result
+    pub async fn request_credit_signature(
+        &self,
+        credit: BlindedCredit,
+        payment_tx: &Signature,
+        payer: &Pubkey,
+    ) -> Result<SignedCredit> {
+        self.ensure_tor().await?;
+
+        let request = CreditSignRequest {
+            blinded_token: hex::encode(credit.blinded_token()),
+            amount: credit.amount,
+            payment_tx: payment_tx.to_string(),
+            payer: payer.to_string(),
+        };
+        let url = format!("{}/sign", self.config.relayer_url);
+        let response: CreditSignResponse = self.post_to_relayer(&url, &request).await?;
+        let blinded_signature = match (response.success, response.signature) {
+            (true, Some(signature)) => hex::decode(signature)
+                .map_err(|e| SdkError::Relayer(format!("malformed blinded signature: {}", e)))?,
+            _ => {
+                return Err(SdkError::Relayer(
+                    response.error.unwrap_or_else(|| "credit not signed".into()),
+                ))
+            }
+        };
+        self.unblind_credit(credit, &blinded_signature)
+    }
+
     pub fn create_deposit_note(&self, amount: u64) -> DepositNote {
         DepositNote::new(amount)
     }
 
+    /// Encrypted to the relayer's ECDH key from `/params`, fetched first if it hasn't been.
+    /// On success `note` gets the confirmed leaf index, persist it after
     pub async fn submit_deposit(
-        &mut self,
+        &self,
         credit: SignedCredit,
-        note: &DepositNote,
+        note: &mut DepositNote,
     ) -> Result<DepositResponse> {
+        credit.verify(&self.config.relayer_pubkey)?;
         self.ensure_tor().await?;
 
-        let request = DepositRequest::new(credit, note)?;
+        let params = self.relayer_params().await?;
+        let ecdh_pubkey: [u8; 32] = hex::decode(&params.ecdh_pubkey)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| SdkError::ParamsRejected("malformed ECDH pubkey".into()))?;
+
+        // Encrypted to our viewing key so the note can be recovered from chain
+        let sealed = note.seal_with(&self.stealth_master.viewing_pubkey(), self.note_padding)?;
+        let request = DepositRequest::new(credit, note)?.with_sealed_note(sealed);
         let plaintext =
             serde_json::to_vec(&request).map_err(|e| SdkError::Serialization(e.to_string()))?;
-        let encrypted = encrypt_payload(&plaintext, &self.config.encryption_secret);
+        let encrypted = encrypt_payload_ecdh(&plaintext, &ecdh_pubkey)?;
         let url = format!("{}/deposit", self.config.relayer_url);
-        let response = self
-            .tor_client
-            .post_json(&url, &encrypted)
+        let response: DepositResponse = self.post_to_relayer(&url, &encrypted).await?;
+        if let (true, Some(leaf_index)) = (response.success, response.leaf_index) {
+            note.set_leaf_index(leaf_index);
+        }
+        Ok(response)
+    }
+
+    /// Relayer parameters, fetched and checked against `ClientConfig::relayer_signer` the
+    /// first time
+    pub async fn relayer_params(&self) -> Result<&RelayerParams> {
+        self.params
+            .get_or_try_init(|| {
+                RelayerParams::fetch_and_verify(
+                    &self.tor_client,
+                    &self.config.relayer_url,
+                    &self.config.relayer_signer,
+                )
+            })
             .await
-            .map_err(|e| SdkError::Relayer(e.to_string()))?;
+    }
 
-        Ok(response)
+    /// Stats of a bucket's pool, read from `rpc_url` over Tor
+    /// The program id comes from the relayer params, fetched first if they haven't been
+    pub async fn pool_stats(&self, rpc_url: &str, bucket_id: u8) -> Result<PoolStats> {
+        self.ensure_tor().await?;
+
+        let program_id = self
+            .relayer_params()
+            .await?
+            .program_id
+            .parse::<Pubkey>()
+            .map_err(|e| SdkError::ParamsRejected(format!("malformed program id: {}", e)))?;
+        fetch_pool_stats(&self.tor_client, rpc_url, &program_id, bucket_id).await
+    }
+
+    /// Merkle path of leaf `leaf_index` in bucket `bucket_id`'s tree, as the relayer sees it now
+    pub async fn fetch_merkle_proof(&self, bucket_id: u8, leaf_index: u64) -> Result<MerkleProof> {
+        self.ensure_tor().await?;
+
+        let url = format!(
+            "{}/proof/{}/{}",
+            self.config.relayer_url, bucket_id, leaf_index
+        );
+        let response: MerkleProofResponse = self.get_from_relayer(&url).await?;
+        let proof = response.into_proof()?;
+        if proof.leaf_index != leaf_index {
+            return Err(SdkError::MerkleTree(format!(
+                "asked for leaf {}, got {}",
+                leaf_index, proof.leaf_index
+            )));
+        }
+        Ok(proof)
     }
 
     pub fn derive_stealth_address(&self, index: u64) -> StealthAddress {
@@ -116,57 +271,792 @@ impl PrivacyClient {
     }
 
     pub async fn submit_withdrawal(
-        &mut self,
+        &self,
         note: &DepositNote,
         merkle_proof: &MerkleProof,
         root: [u8; 32],
         recipient: &StealthAddress,
         relayer: Pubkey,
-        fee: u64,
+        fee_bps: u16,
     ) -> Result<WithdrawalResponse> {
+        let request =
+            WithdrawalRequest::new(note, merkle_proof, root, recipient, relayer, fee_bps)?;
+        self.submit_withdrawal_with_options(request, WithdrawalOptions::default())
+            .await
+    }
+
+    /// Submit a built withdrawal request. The delay is checked locally, or drawn at random
+    /// if unset, within the relayer's bounds when `relayer_params` has been called, the
+    /// program's otherwise
+    pub async fn submit_withdrawal_with_options(
+        &self,
+        request: WithdrawalRequest,
+        options: WithdrawalOptions,
+    ) -> Result<WithdrawalResponse> {
+        let params = self.params.get();
+        let (min, max) = params.map_or((MIN_DELAY_HOURS, MAX_DELAY_HOURS), |params| {
+            (params.min_delay_hours, params.max_delay_hours)
+        });
+        let delay_hours = options.resolve_delay(min, max)?;
         self.ensure_tor().await?;
 
-        let request = WithdrawalRequest::new(note, merkle_proof, root, recipient, relayer, fee)?;
-        let plaintext =
-            serde_json::to_vec(&request).map_err(|e| SdkError::Serialization(e.to_string()))?;
-        let encrypted = encrypt_payload(&plaintext, &self.config.encryption_secret);
+        let bucket_id = params.and_then(|params| {
+            params
+                .bucket_amounts
+                .iter()
+                .position(|&amount| amount == request.public_inputs.amount)
+                .map(|bucket| bucket as u8)
+        });
+        let submission = WithdrawalSubmission {
+            request,
+            delay_hours,
+            bucket_id,
+        };
         let url = format!("{}/withdraw", self.config.relayer_url);
+        self.post_to_relayer(&url, &submission).await
+    }
+
+    /// `submit_withdrawal_with_options`, refused with `WeakAnonymity` while the pool holds
+    /// fewer unspent deposits than `guard` requires. The set size is read on-chain from
+    /// `rpc_url` rather than taken from the relayer. The pool's bucket comes from the relayer
+    /// params, fetched first if they haven't been
+    pub async fn submit_withdrawal_guarded(
+        &self,
+        request: WithdrawalRequest,
+        options: WithdrawalOptions,
+        rpc_url: &str,
+        guard: AnonymityGuard,
+    ) -> Result<WithdrawalResponse> {
+        if !guard.allow_weak {
+            let amount = request.public_inputs.amount;
+            let bucket_id = self
+                .relayer_params()
+                .await?
+                .bucket_amounts
+                .iter()
+                .position(|&bucket_amount| bucket_amount == amount)
+                .ok_or_else(|| {
+                    SdkError::InvalidInput(format!("No pool for {} lamports", amount))
+                })?;
+            let stats = self.pool_stats(rpc_url, bucket_id as u8).await?;
+            guard.check(stats.anonymity_set_size)?;
+        }
+        self.submit_withdrawal_with_options(request, options).await
+    }
+
+    /// Cancel the pending withdrawal `pending_withdrawal_id` (its on-chain `tx_id`) with a
+    /// locally generated ownership proof of `nullifier`, funds stay in the pool
+    #[cfg(feature = "prover")]
+    pub async fn cancel_withdrawal(
+        &self,
+        prover: &OwnershipProver,
+        nullifier: &[u8; 32],
+        pending_withdrawal_id: u64,
+    ) -> Result<WithdrawalResponse> {
+        self.ensure_tor().await?;
+
+        let request = prover.prove(nullifier, pending_withdrawal_id).await?;
+        let url = format!("{}/withdraw/cancel", self.config.relayer_url);
+        self.post_to_relayer(&url, &request).await
+    }
+
+    /// Whether `note` was already withdrawn on-chain, e.g. after a failed or interrupted
+    /// attempt. Pending withdrawals don't count as spent until they execute
+    pub async fn is_note_spent(&self, note: &DepositNote) -> Result<bool> {
+        self.ensure_tor().await?;
+
+        let nullifier_hash = generate_nullifier_hash(&note.nullifier)?;
+        let url = format!(
+            "{}/nullifier/{}",
+            self.config.relayer_url,
+            hex::encode(nullifier_hash)
+        );
+        let status: NullifierStatusResponse = self.get_from_relayer(&url).await?;
+        Ok(status.spent)
+    }
+
+    /// Block until the relayer executes the withdrawal of `nullifier_hash`, returning the
+    /// execution tx signature. Polls every `poll_interval`, but never before the timelock
+    /// (`execute_after`) ends. Fails with `SdkError::Timeout` once `timeout` has passed
+    pub async fn await_withdrawal(
+        &self,
+        nullifier_hash: &[u8; 32],
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<String> {
+        self.ensure_tor().await?;
+
+        let url = format!(
+            "{}/withdraw/status/{}",
+            self.config.relayer_url,
+            hex::encode(nullifier_hash)
+        );
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status: WithdrawalStatusResponse = self.get_from_relayer(&url).await?;
+            match (status.status, status.tx_signature) {
+                (WithdrawalState::Executed, Some(tx_signature)) => return Ok(tx_signature),
+                (WithdrawalState::Cancelled, _) => {
+                    return Err(SdkError::Relayer("Withdrawal was cancelled".into()))
+                }
+                _ => {}
+            }
+
+            let delay = next_poll_delay(status.execute_after, unix_now(), poll_interval);
+            if Instant::now() + delay > deadline {
+                return Err(SdkError::Timeout(format!(
+                    "withdrawal not executed within {:?}",
+                    timeout
+                )));
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Buy an `amount` credit, deposit it and request its withdrawal to the stealth address
+    /// `recipient_index`, after `delay_hours` (random within the relayer's bounds if `None`)
+    /// The note is written to `setup.note_path` before the deposit and again once its leaf is
+    /// known. Failures come back as `SdkError::FlowFailed` naming the phase that stopped
+    #[cfg(feature = "prover")]
+    pub async fn run_full_flow(
+        &self,
+        setup: &FlowSetup,
+        amount: u64,
+        recipient_index: u64,
+        delay_hours: Option<u8>,
+    ) -> Result<FlowResult> {
+        use solana_sdk::signer::Signer;
+
+        // Everything that can be checked up front is, before any lamports move
+        let params = self
+            .relayer_params()
+            .await
+            .map_err(|e| e.in_phase(FlowPhase::Params))?;
+        let (bucket_id, treasury, delay_hours) = (|| {
+            let bucket_id = params
+                .bucket_amounts
+                .iter()
+                .position(|&bucket| bucket == amount)
+                .ok_or_else(|| {
+                    SdkError::InvalidInput(format!("{} lamports is not a bucket amount", amount))
+                })? as u8;
+            let treasury = params
+                .treasury
+                .parse::<Pubkey>()
+                .map_err(|e| SdkError::ParamsRejected(format!("malformed treasury: {}", e)))?;
+            let options = delay_hours.map_or_else(
+                WithdrawalOptions::default,
+                WithdrawalOptions::with_delay_hours,
+            );
+            let delay_hours =
+                options.resolve_delay(params.min_delay_hours, params.max_delay_hours)?;
+            Ok((bucket_id, treasury, delay_hours))
+        })()
+        .map_err(|e: SdkError| e.in_phase(FlowPhase::Params))?;
+        self.ensure_tor()
+            .await
+            .map_err(|e| e.in_phase(FlowPhase::Params))?;
+
+        let payment_tx = send_payment(
+            &self.tor_client,
+            &setup.rpc_url,
+            &setup.payer,
+            &treasury,
+            amount,
+            params.fee_bps,
+        )
+        .await
+        .map_err(|e| e.in_phase(FlowPhase::Payment))?;
+
+        let credit = self
+            .create_blinded_credit(amount)
+            .map_err(|e| e.in_phase(FlowPhase::Signing))?;
+        let credit = self
+            .request_credit_signature(credit, &payment_tx, &setup.payer.pubkey())
+            .await
+            .map_err(|e| e.in_phase(FlowPhase::Signing))?;
+
+        let mut note = self.create_deposit_note(amount);
+        persist_note(&setup.note_path, &note).map_err(|e| e.in_phase(FlowPhase::PersistNote))?;
+        let deposit = self
+            .submit_deposit(credit, &mut note)
+            .await
+            .and_then(|response| match response {
+                DepositResponse {
+                    success: true,
+                    tx_signature: Some(tx_signature),
+                    leaf_index: Some(leaf_index),
+                    ..
+                } => Ok((tx_signature, leaf_index)),
+                response => Err(SdkError::Relayer(
+                    response
+                        .error
+                        .unwrap_or_else(|| "deposit not confirmed".into()),
+                )),
+            });
+        let (deposit_signature, leaf_index) =
+            deposit.map_err(|e| e.in_phase(FlowPhase::Deposit))?;
+        persist_note(&setup.note_path, &note).map_err(|e| e.in_phase(FlowPhase::PersistNote))?;
+
+        let (merkle_proof, root) = async {
+            let merkle_proof = self.fetch_merkle_proof(bucket_id, leaf_index).await?;
+            let root = merkle_proof.root(&note.commitment()?)?;
+            Ok((merkle_proof, root))
+        }
+        .await
+        .map_err(|e: SdkError| e.in_phase(FlowPhase::MerkleProof))?;
+
+        let recipient = self.derive_stealth_address(recipient_index);
+        let request = setup
+            .prover
+            .prove(
+                &note,
+                &merkle_proof,
+                root,
+                &recipient,
+                self.config.relayer_signer,
+                params.fee_bps,
+            )
+            .await
+            .map_err(|e| e.in_phase(FlowPhase::Proving))?;
+        let nullifier_hash = request.public_inputs.nullifier_hash;
+
+        let withdrawal_signature = self
+            .submit_withdrawal_with_options(
+                request,
+                WithdrawalOptions::with_delay_hours(delay_hours),
+            )
+            .await
+            .and_then(|response| match response {
+                WithdrawalResponse {
+                    success: true,
+                    tx_signature: Some(tx_signature),
+                    ..
+                } => Ok(tx_signature),
+                response => Err(SdkError::Relayer(
+                    response
+                        .error
+                        .unwrap_or_else(|| "withdrawal not requested".into()),
+                )),
+            })
+            .map_err(|e| e.in_phase(FlowPhase::Withdrawal))?;
+
+        Ok(FlowResult {
+            deposit_signature,
+            withdrawal_signature,
+            leaf_index,
+            nullifier_hash,
+            recipient: recipient.address,
+        })
+    }
+
+    /// POST to the relayer, error responses become `SdkError::RelayerRejected` with their code
+    async fn post_to_relayer<T: Serialize, R: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &T,
+    ) -> Result<R> {
         let response = self
             .tor_client
-            .post_json(&url, &encrypted)
+            .post(url, body)
             .await
             .map_err(|e| SdkError::Relayer(e.to_string()))?;
+        let status = response.status();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| SdkError::Relayer(e.to_string()))?;
+        decode_relayer_response(status.as_u16(), &bytes)
+    }
 
-        Ok(response)
+    /// GET from the relayer, errors handled like `post_to_relayer`
+    async fn get_from_relayer<R: DeserializeOwned>(&self, url: &str) -> Result<R> {
+        let response = self
+            .tor_client
+            .get(url)
+            .await
+            .map_err(|e| SdkError::Relayer(e.to_string()))?;
+        let status = response.status();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| SdkError::Relayer(e.to_string()))?;
+        decode_relayer_response(status.as_u16(), &bytes)
     }
 
-    pub async fn verify_tor(&mut self) -> Result<bool> {
+    pub async fn verify_tor(&self) -> Result<bool> {
         let result = self
             .tor_client
             .verify_tor_connection()
             .await
-            .map_err(|e| SdkError::Network(e))?;
+            .map_err(SdkError::Network)?;
 
-        self.tor_verified = result;
+        self.tor_verified.store(result, Ordering::Release);
         Ok(result)
     }
 
+    /// Exit address and whether it's a Tor exit, e.g. to show the user's real IP when Tor is off
+    pub async fn get_exit_info(&self) -> Result<tracezero::ExitInfo> {
+        self.tor_client
+            .get_exit_info()
+            .await
+            .map_err(SdkError::Network)
+    }
+
     pub async fn get_exit_ip(&self) -> Result<String> {
         self.tor_client
             .get_exit_ip()
             .await
-            .map_err(|e| SdkError::Network(e))
+            .map_err(SdkError::Network)
     }
 
     pub fn export_stealth_secret(&self) -> [u8; 32] {
         self.stealth_master.export_secret()
     }
 
+    /// Whether the configured relayer is a Tor hidden service
+    pub fn is_onion_relayer(&self) -> bool {
+        is_onion_url(&self.config.relayer_url)
+    }
+
     pub fn is_tor_verified(&self) -> bool {
-        self.tor_verified
+        self.tor_verified.load(Ordering::Acquire)
+    }
+
+    /// Forces the next request to re-check Tor, for this client and all its clones
+    pub fn invalidate_tor_verification(&self) {
+        self.tor_verified.store(false, Ordering::Release);
+    }
+}
+
+/// Error body the relayer sends with every non-2xx response
+#[derive(Deserialize)]
+struct RelayerErrorBody {
+    error: String,
+    code: Option<String>,
+}
+
+/// Body of a relayer response, non-2xx statuses become errors via `relayer_error`
+fn decode_relayer_response<R: DeserializeOwned>(status: u16, body: &[u8]) -> Result<R> {
+    if !(200..300).contains(&status) {
+        return Err(relayer_error(status, body));
     }
+    serde_json::from_slice(body).map_err(|e| SdkError::Serialization(e.to_string()))
+}
+
+/// How long to wait before the next status poll: at least `poll_interval`, and never
+/// less than what remains of the timelock, since the relayer can't execute before then
+fn next_poll_delay(execute_after: i64, now: i64, poll_interval: Duration) -> Duration {
+    let timelock = Duration::from_secs(execute_after.saturating_sub(now).max(0) as u64);
+    poll_interval.max(timelock)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Relayers predating error codes only send a message, those stay `SdkError::Relayer`
+fn relayer_error(status: u16, body: &[u8]) -> SdkError {
+    match serde_json::from_slice::<RelayerErrorBody>(body) {
+        Ok(RelayerErrorBody {
+            error,
+            code: Some(code),
+        }) => SdkError::RelayerRejected {
+            code,
+            message: error,
+        },
+        Ok(RelayerErrorBody { error, code: None }) => {
+            SdkError::Relayer(format!("HTTP {}: {}", status, error))
+        }
+        Err(_) => SdkError::Relayer(format!(
+            "HTTP {}: {}",
+            status,
+            String::from_utf8_lossy(body)
+        )),
+    }
+}
+
+/// Extract the host of a URL and check it's a `.onion` address
+fn is_onion_url(url: &str) -> bool {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    let host = host_port.split(':').next().unwrap_or_default();
+    host.to_ascii_lowercase().ends_with(".onion")
+}
+
+/// Refuse clearnet relayers unless explicitly allowed
+/// Even over Tor, a clearnet relayer sees and can log the exit node for every request
+fn validate_relayer_url(url: &str, allow_clearnet: bool) -> Result<()> {
+    if is_onion_url(url) || allow_clearnet {
+        return Ok(());
+    }
+    Err(SdkError::ClearnetRelayer(format!(
+        "{} is not a .onion address; a clearnet relayer can log your Tor exit node. \
+         Set allow_clearnet to proceed anyway.",
+        url
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{serve_each, serve_once, serve_once_with_status};
+    use rsa::RsaPrivateKey;
+
+    const ONION_URL: &str =
+        "http://2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid.onion:8080";
+
+    fn test_config(relayer_url: &str, allow_clearnet: bool) -> ClientConfig {
+        let mut rng = rand::thread_rng();
+        let private_key = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        ClientConfig {
+            relayer_url: relayer_url.to_string(),
+            relayer_pubkey: private_key.to_public_key(),
+            tor_socks_addr: "127.0.0.1:9050".to_string(),
+            relayer_signer: Pubkey::new_unique(),
+            allow_clearnet,
+        }
+    }
+
+    /// Params as if `relayer_params` had been called
+    fn test_params() -> RelayerParams {
+        RelayerParams {
+            rsa_keys: Vec::new(),
+            ecdh_pubkey: hex::encode([9u8; 32]),
+            treasury: Pubkey::new_unique().to_string(),
+            program_id: Pubkey::new_unique().to_string(),
+            fee_bps: 50,
+            bucket_amounts: crate::deposit::BUCKET_AMOUNTS.to_vec(),
+            min_delay_hours: 1,
+            max_delay_hours: 24,
+            circuit_version: String::new(),
+            withdrawal_vk: None,
+            issued_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_onion_url_detection() {
+        assert!(is_onion_url(ONION_URL));
+        assert!(is_onion_url("http://user:pw@abc.ONION/deposit"));
+        assert!(!is_onion_url("https://relayer.example.com"));
+        assert!(!is_onion_url("http://evil.onion.example.com:8080"));
+        assert!(!is_onion_url("http://127.0.0.1:8080/x.onion"));
+    }
+
+    #[test]
+    fn test_relayer_url_validation() {
+        // Onion relayer accepted
+        let client = PrivacyClient::new(test_config(ONION_URL, false)).unwrap();
+        assert!(client.is_onion_relayer());
+
+        // Clearnet rejected by default
+        let result = PrivacyClient::new(test_config("https://relayer.example.com", false));
+        assert!(matches!(result, Err(SdkError::ClearnetRelayer(_))));
+
+        // Clearnet allowed when opted in
+        let client = PrivacyClient::new(test_config("https://relayer.example.com", true)).unwrap();
+        assert!(!client.is_onion_relayer());
+    }
+
+    #[tokio::test]
+    async fn test_direct_client_skips_tor() {
+        // A localhost relayer is accepted and no Tor check is attempted
+        let client =
+            PrivacyClient::new_direct(test_config("http://127.0.0.1:8080", false)).unwrap();
+        assert!(client.ensure_tor().await.is_ok());
+        assert!(!client.is_tor_verified());
+
+        // Tor-backed clients never skip the check
+        let client = PrivacyClient::new(test_config(ONION_URL, false)).unwrap();
+        assert!(!client.direct);
+    }
+
+    #[test]
+    fn test_tor_not_detected_shows_exit_ip() {
+        let err = SdkError::TorNotDetected {
+            checked_exit_ip: Some("203.0.113.7".into()),
+        };
+        assert_eq!(err.to_string(), "Tor not detected (exit IP: 203.0.113.7)");
+        let err = SdkError::TorNotDetected {
+            checked_exit_ip: None,
+        };
+        assert_eq!(err.to_string(), "Tor not detected (exit IP: unknown)");
+    }
+
+    #[test]
+    fn test_relayer_error_codes() {
+        let err = relayer_error(
+            409,
+            br#"{"success":false,"error":"Token already redeemed","code":"token_already_redeemed"}"#,
+        );
+        assert_eq!(err.relayer_code(), Some("token_already_redeemed"));
+
+        // Older relayers send no code, and proxies may send no JSON at all
+        let err = relayer_error(
+            400,
+            br#"{"success":false,"error":"Invalid bucket amount: 5"}"#,
+        );
+        assert!(matches!(err, SdkError::Relayer(ref m) if m.contains("Invalid bucket amount")));
+        let err = relayer_error(502, b"Bad Gateway");
+        assert!(matches!(err, SdkError::Relayer(ref m) if m.contains("502")));
+        assert_eq!(err.relayer_code(), None);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_withdrawal_surfaces_code() {
+        use crate::merkle::MerkleTree;
+
+        let url = serve_once_with_status(
+            "409 Conflict",
+            r#"{"success":false,"error":"A withdrawal for this nullifier is already pending","code":"withdrawal_already_pending"}"#,
+        )
+        .await;
+
+        let client = PrivacyClient::new_direct(test_config(&url, false)).unwrap();
+        let note = client.create_deposit_note(1_000_000_000);
+        let mut tree = MerkleTree::new(4).unwrap();
+        tree.insert(note.commitment().unwrap()).unwrap();
+        let result = client
+            .submit_withdrawal(
+                &note,
+                &tree.proof(0).unwrap(),
+                tree.root().unwrap(),
+                &client.derive_stealth_address(0),
+                Pubkey::new_unique(),
+                50,
+            )
+            .await;
+        let err = result.err().unwrap();
+        assert_eq!(err.relayer_code(), Some("withdrawal_already_pending"));
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_delay_fails_before_network() {
+        use crate::merkle::MerkleTree;
+
+        // Nothing listens here, any request would surface as a relayer/network error
+        let client = PrivacyClient::new_direct(test_config("http://127.0.0.1:9", false)).unwrap();
+        let note = client.create_deposit_note(1_000_000_000);
+        let mut tree = MerkleTree::new(4).unwrap();
+        tree.insert(note.commitment().unwrap()).unwrap();
+        let request = WithdrawalRequest::new(
+            &note,
+            &tree.proof(0).unwrap(),
+            tree.root().unwrap(),
+            &client.derive_stealth_address(0),
+            Pubkey::new_unique(),
+            50,
+        )
+        .unwrap();
+        let result = client
+            .submit_withdrawal_with_options(
+                request,
+                WithdrawalOptions::with_delay_hours(MAX_DELAY_HOURS + 1),
+            )
+            .await;
+        assert!(matches!(result, Err(SdkError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bad_credit_fails_before_network() {
+        // Nothing listens here, any request would surface as a relayer/network error
+        let client = PrivacyClient::new_direct(test_config("http://127.0.0.1:9", false)).unwrap();
+        let credit = SignedCredit {
+            token_id: [1u8; 32],
+            signature: vec![2u8; 256],
+            amount: 1_000_000_000,
+        };
+        let mut note = client.create_deposit_note(credit.amount);
+        let result = client.submit_deposit(credit, &mut note).await;
+        assert!(matches!(result, Err(SdkError::Crypto(_))));
+    }
+
+    #[tokio::test]
+    async fn test_deposit_records_leaf_index() {
+        use crate::blind_sig::sign_blinded;
+        use crate::params::tests::signed_bundle;
+        use solana_sdk::signature::Keypair;
+        use solana_sdk::signer::Signer;
+
+        // Params haven't been fetched, so the deposit fetches them first
+        let relayer = Keypair::new();
+        let (url, mut requests) = serve_each(vec![
+            signed_bundle(&relayer).to_string(),
+            r#"{"success":true,"tx_signature":"sig","leaf_index":5,"merkle_root":null,"error":null}"#
+                .to_string(),
+        ])
+        .await;
+
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+        let mut config = test_config(&url, false);
+        config.relayer_pubkey = private_key.to_public_key();
+        config.relayer_signer = relayer.pubkey();
+        let client = PrivacyClient::new_direct(config).unwrap();
+
+        let credit = client.create_blinded_credit(1_000_000_000).unwrap();
+        let blinded_signature = sign_blinded(&credit.blinded_token, &private_key).unwrap();
+        let credit = client.unblind_credit(credit, &blinded_signature).unwrap();
+        let mut note = client.create_deposit_note(credit.amount);
+        assert_eq!(note.leaf_index, None);
+
+        let response = client.submit_deposit(credit, &mut note).await.unwrap();
+        assert!(response.success);
+        assert_eq!(note.leaf_index, Some(5));
+        assert!(requests.recv().await.unwrap().starts_with("GET /params "));
+        assert!(requests.recv().await.unwrap().starts_with("POST /deposit "));
+    }
+
+    #[tokio::test]
+    async fn test_guarded_withdrawal_refuses_small_pool() {
+        use crate::merkle::MerkleTree;
+        use base64::Engine;
+
+        // JSON-RPC endpoint serving a 5 SOL pool with 40 unspent deposits
+        let rpc_url = serve_once(
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": {
+                        "data": [
+                            base64::engine::general_purpose::STANDARD
+                                .encode(crate::pool::tests::pool_account()),
+                            "base64",
+                        ],
+                    },
+                },
+            })
+            .to_string(),
+        )
+        .await;
+
+        // Nothing listens on the relayer, reaching it would surface as a relayer error
+        let client = PrivacyClient::new_direct(test_config("http://127.0.0.1:9", false)).unwrap();
+        client.params.set(test_params()).unwrap();
+        let note = client.create_deposit_note(5_000_000_000);
+        let mut tree = MerkleTree::new(4).unwrap();
+        tree.insert(note.commitment().unwrap()).unwrap();
+        let request = WithdrawalRequest::new(
+            &note,
+            &tree.proof(0).unwrap(),
+            tree.root().unwrap(),
+            &client.derive_stealth_address(0),
+            Pubkey::new_unique(),
+            50,
+        )
+        .unwrap();
+
+        let result = client
+            .submit_withdrawal_guarded(
+                request.clone(),
+                WithdrawalOptions::default(),
+                &rpc_url,
+                AnonymityGuard::with_min_set_size(50),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(SdkError::WeakAnonymity { set_size: 40 })
+        ));
+
+        // Overridden, the pool isn't read and the request goes to the relayer
+        let result = client
+            .submit_withdrawal_guarded(
+                request,
+                WithdrawalOptions::default(),
+                "http://127.0.0.1:9",
+                AnonymityGuard::allow_weak(),
+            )
+            .await;
+        assert!(matches!(result, Err(SdkError::Relayer(_))));
+    }
+
+    #[test]
+    fn test_next_poll_delay() {
+        let interval = Duration::from_secs(5);
+        // Timelock over or shorter than the interval
+        assert_eq!(next_poll_delay(100, 200, interval), interval);
+        assert_eq!(next_poll_delay(203, 200, interval), interval);
+        // Longer timelock, no point asking before it ends
+        assert_eq!(next_poll_delay(260, 200, interval), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_await_withdrawal() {
+        let nullifier_hash = [7u8; 32];
+        let (url, mut requests) = serve_each(vec![
+            r#"{"status":"pending","execute_after":0,"tx_signature":null}"#.to_string(),
+            r#"{"status":"executed","execute_after":0,"tx_signature":"5sig"}"#.to_string(),
+        ])
+        .await;
+
+        let client = PrivacyClient::new_direct(test_config(&url, false)).unwrap();
+        let tx = client
+            .await_withdrawal(
+                &nullifier_hash,
+                Duration::from_millis(10),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(tx, "5sig");
+        let expected = format!("GET /withdraw/status/{} ", hex::encode(nullifier_hash));
+        for _ in 0..2 {
+            assert!(requests.recv().await.unwrap().starts_with(&expected));
+        }
+
+        // Still timelocked past the deadline, gives up without waiting it out
+        let url = serve_once(format!(
+            r#"{{"status":"pending","execute_after":{},"tx_signature":null}}"#,
+            unix_now() + 3600
+        ))
+        .await;
+        let client = PrivacyClient::new_direct(test_config(&url, false)).unwrap();
+        let result = client
+            .await_withdrawal(
+                &nullifier_hash,
+                Duration::from_millis(10),
+                Duration::from_secs(5),
+            )
+            .await;
+        assert!(matches!(result, Err(SdkError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_client_across_tasks() {
+        const TASKS: usize = 4;
+        let body = r#"{"status":"executed","execute_after":0,"tx_signature":"5sig"}"#;
+        let (url, _) = serve_each(vec![body.to_string(); TASKS]).await;
+
+        let client = PrivacyClient::new_direct(test_config(&url, false)).unwrap();
+        let tasks: Vec<_> = (0..TASKS)
+            .map(|i| {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    client
+                        .await_withdrawal(&[i as u8; 32], Duration::ZERO, Duration::from_secs(5))
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), "5sig");
+        }
 
-    pub fn invalidate_tor_verification(&mut self) {
-        self.tor_verified = false;
+        // Tor verification is shared, invalidating through a clone affects the original
+        let clone = client.clone();
+        client.tor_verified.store(true, Ordering::Release);
+        assert!(clone.is_tor_verified());
+        clone.invalidate_tor_verification();
+        assert!(!client.is_tor_verified());
     }
 }
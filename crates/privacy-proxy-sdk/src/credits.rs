@@ -7,10 +7,64 @@
 use rand::RngCore;
 use rsa::RsaPublicKey;
 use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
 
-use crate::blind_sig::{blind_message, unblind_signature, BlindingFactor};
+use crate::blind_sig::{blind_message, unblind_signature, verify_signature, BlindingFactor};
+use crate::deposit::BUCKET_AMOUNTS;
 use crate::error::{Result, SdkError};
 
+/// Bucket id of the pool holding exactly `sol` SOL, e.g. `bucket_for_sol(1.0) == Some(2)`
+/// `None` for anything that isn't a denomination, including amounts off by a fraction of
+/// a lamport beyond float error (`0.1000000001` is not the 0.1 SOL bucket)
+pub fn bucket_for_sol(sol: f64) -> Option<u8> {
+    if !sol.is_finite() || sol <= 0.0 {
+        return None;
+    }
+    // Round to the nearest lamport rather than truncate: 0.1 * 1e9 is 100000000.00000001
+    // in f64 and 0.3 * 1e9 is 299999999.99999994
+    let scaled = sol * LAMPORTS_PER_SOL as f64;
+    let lamports = scaled.round();
+    if (scaled - lamports).abs() > 1e-3 || lamports > u64::MAX as f64 {
+        return None;
+    }
+    BUCKET_AMOUNTS
+        .iter()
+        .position(|&amount| amount == lamports as u64)
+        .map(|bucket| bucket as u8)
+}
+
+/// Denomination of bucket `id` in lamports, `None` past the last bucket
+pub fn lamports_for_bucket(id: u8) -> Option<u64> {
+    BUCKET_AMOUNTS.get(id as usize).copied()
+}
+
+/// Lamports to pay the treasury for an `amount` credit: the amount plus the relayer fee,
+/// rounded down. The relayer checks payments against this same function
+pub fn credit_price(amount: u64, fee_bps: u16) -> u64 {
+    amount + (amount as u128 * fee_bps as u128 / 10_000) as u64
+}
+
+/// Body of `POST /sign`
+#[derive(Serialize)]
+pub struct CreditSignRequest {
+    /// Blinded token (hex)
+    pub blinded_token: String,
+    pub amount: u64,
+    /// Payment transaction signature (base58)
+    pub payment_tx: String,
+    /// Payer's pubkey (base58)
+    pub payer: String,
+}
+
+/// Response of `POST /sign`
+#[derive(Deserialize)]
+pub struct CreditSignResponse {
+    pub success: bool,
+    /// Blinded signature (hex)
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
 /// A credit before signing - contains blinded token
 #[derive(Clone)]
 pub struct BlindedCredit {
@@ -82,14 +136,65 @@ impl SignedCredit {
     pub fn token_id_hex(&self) -> String {
         hex::encode(self.token_id)
     }
+
+    /// Check the signature locally, the same check the relayer does on deposit
+    /// Catches a botched unblinding (or the wrong relayer key) before any network call
+    pub fn verify(&self, relayer_pubkey: &RsaPublicKey) -> Result<()> {
+        if verify_signature(&self.token_id, &self.signature, relayer_pubkey)? {
+            Ok(())
+        } else {
+            Err(SdkError::Crypto(
+                "Credit signature does not verify under the relayer key".into(),
+            ))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::blind_sig::{sign_blinded, verify_signature};
+    use crate::blind_sig::sign_blinded;
     use rsa::RsaPrivateKey;
 
+    #[test]
+    fn test_credit_price() {
+        // 0.5% fee on 1 SOL
+        assert_eq!(credit_price(1_000_000_000, 50), 1_005_000_000);
+        // Fractions of a lamport round down
+        assert_eq!(credit_price(199, 50), 199);
+    }
+
+    #[test]
+    fn test_bucket_for_sol() {
+        let buckets = [0.1, 0.5, 1.0, 5.0, 10.0, 50.0, 100.0];
+        for (id, &sol) in buckets.iter().enumerate() {
+            assert_eq!(bucket_for_sol(sol), Some(id as u8), "{} SOL", sol);
+            assert_eq!(
+                lamports_for_bucket(id as u8),
+                Some((sol * LAMPORTS_PER_SOL as f64).round() as u64)
+            );
+        }
+        // Arithmetic that lands a hair off the decimal value still maps
+        assert_eq!(bucket_for_sol(0.2 + 0.3), Some(1));
+        assert_eq!(bucket_for_sol(0.7 - 0.6), Some(0));
+
+        for sol in [
+            0.0,
+            -1.0,
+            0.2,
+            2.0,
+            1000.0,
+            0.1000000001,
+            0.999999999,
+            f64::NAN,
+            f64::INFINITY,
+            1e30,
+        ] {
+            assert_eq!(bucket_for_sol(sol), None, "{} SOL", sol);
+        }
+        assert_eq!(lamports_for_bucket(BUCKET_AMOUNTS.len() as u8), None);
+    }
+
     #[test]
     fn test_credit_flow() {
         // Generate relayer keypair
@@ -116,4 +221,28 @@ mod tests {
         )
         .unwrap());
     }
+
+    #[test]
+    fn test_tampered_credit_rejected_locally() {
+        let mut rng = rand::thread_rng();
+        let private_key = RsaPrivateKey::new(&mut rng, 2048).unwrap();
+        let public_key = RsaPublicKey::from(&private_key);
+
+        let credit = BlindedCredit::new(1_000_000_000, &public_key).unwrap();
+        let blinded_sig = sign_blinded(&credit.blinded_token, &private_key).unwrap();
+        let signed_credit = credit.unblind(&blinded_sig, &public_key).unwrap();
+        assert!(signed_credit.verify(&public_key).is_ok());
+
+        let mut tampered = signed_credit.clone();
+        let last = tampered.signature.len() - 1;
+        tampered.signature[last] ^= 1;
+        assert!(matches!(
+            tampered.verify(&public_key),
+            Err(SdkError::Crypto(_))
+        ));
+
+        // Valid signature under another relayer's key
+        let other_key = RsaPublicKey::from(&RsaPrivateKey::new(&mut rng, 2048).unwrap());
+        assert!(signed_credit.verify(&other_key).is_err());
+    }
 }
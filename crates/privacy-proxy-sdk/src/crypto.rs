@@ -3,20 +3,22 @@ use aes_gcm::{
     Aes256Gcm, Nonce,
 };
 use ark_bn254::Fr;
+use hkdf::Hkdf;
 use light_poseidon::{Poseidon, PoseidonBytesHasher};
 use rand::RngCore;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
 use crate::error::{Result, SdkError};
 
-// Domain tags for hash separation (MUST match circuits/*.circom)
-pub const DOMAIN_NULLIFIER: u64 = 1853189228; // "null" as u32
-pub const DOMAIN_COMMIT: u64 = 1668246637; // "comm" as u32
-pub const DOMAIN_BIND: u64 = 1651076196; // "bind" as u32
-pub const DOMAIN_OWNER_BIND: u64 = 1869771618; // "ownb" as u32
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_vectors;
+
+// Domain tags for hash separation, derived in `domains`
+pub use crate::domains::{DOMAIN_BIND, DOMAIN_COMMIT, DOMAIN_NULLIFIER, DOMAIN_OWNER_BIND};
 
 // BN254 field modulus (approximately 2^254)
 // We ensure all inputs are less than this by masking the top bits
-#[allow(dead_code)]
 const BN254_MODULUS_BYTES: [u8; 32] = [
     0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
     0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x01,
@@ -47,6 +49,18 @@ pub fn reduce_to_field(value: &[u8; 32]) -> [u8; 32] {
     result
 }
 
+/// Check a big-endian 32-byte value is a canonical BN254 field element (< modulus)
+pub fn is_field_element(value: &[u8; 32]) -> bool {
+    value < &BN254_MODULUS_BYTES
+}
+
+/// `value` as a big-endian field element
+pub fn u64_to_field(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
 pub fn validate_non_zero(value: &[u8; 32]) -> Result<()> {
     if value.iter().all(|&b| b == 0) {
         return Err(SdkError::Crypto("Value must be non-zero".into()));
@@ -80,8 +94,7 @@ pub fn poseidon_hash(inputs: &[&[u8; 32]]) -> Result<[u8; 32]> {
 }
 
 pub fn poseidon_hash_with_domain(domain: u64, inputs: &[&[u8; 32]]) -> Result<[u8; 32]> {
-    let mut domain_bytes = [0u8; 32];
-    domain_bytes[24..32].copy_from_slice(&domain.to_be_bytes());
+    let domain_bytes = u64_to_field(domain);
 
     let mut all_inputs = vec![&domain_bytes];
     all_inputs.extend(inputs);
@@ -102,10 +115,7 @@ pub fn generate_commitment(
         return Err(SdkError::Crypto("Amount must be non-zero".into()));
     }
 
-    let mut amount_bytes = [0u8; 32];
-    amount_bytes[24..32].copy_from_slice(&amount.to_be_bytes());
-
-    poseidon_hash_with_domain(DOMAIN_COMMIT, &[nullifier, secret, &amount_bytes])
+    poseidon_hash_with_domain(DOMAIN_COMMIT, &[nullifier, secret, &u64_to_field(amount)])
 }
 
 /// Generate nullifier hash: Poseidon(DOMAIN_NULLIFIER, nullifier)
@@ -122,15 +132,12 @@ pub fn generate_withdrawal_binding_hash(
     relayer: &[u8; 32],
     fee: u64,
 ) -> Result<[u8; 32]> {
-    let mut fee_bytes = [0u8; 32];
-    fee_bytes[24..32].copy_from_slice(&fee.to_be_bytes());
-
     // recipient and relayer are already field elements from the circuit
     // (snarkjs reduces them mod BN254 if needed)
     // DO NOT apply additional reduction here
     poseidon_hash_with_domain(
         DOMAIN_BIND,
-        &[nullifier_hash, recipient, relayer, &fee_bytes],
+        &[nullifier_hash, recipient, relayer, &u64_to_field(fee)],
     )
 }
 
@@ -141,10 +148,10 @@ pub fn generate_ownership_binding_hash(
 ) -> Result<[u8; 32]> {
     validate_non_zero(nullifier)?;
 
-    let mut id_bytes = [0u8; 32];
-    id_bytes[24..32].copy_from_slice(&pending_withdrawal_id.to_be_bytes());
-
-    poseidon_hash_with_domain(DOMAIN_OWNER_BIND, &[nullifier, &id_bytes])
+    poseidon_hash_with_domain(
+        DOMAIN_OWNER_BIND,
+        &[nullifier, &u64_to_field(pending_withdrawal_id)],
+    )
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
@@ -170,6 +177,259 @@ pub fn encrypt_payload(plaintext: &[u8], key: &[u8; 32]) -> EncryptedPayload {
     }
 }
 
+/// ECDH payload v1: the raw X25519 shared secret is the AES-256-GCM key
+/// Still accepted by the relayer for clients that predate versioning
+pub const PAYLOAD_VERSION_RAW: u8 = 1;
+/// ECDH payload v2: AES-256-GCM key = HKDF-SHA256(shared secret, salt, info)
+pub const PAYLOAD_VERSION_HKDF: u8 = 2;
+/// Versions the relayer accepts, newest first
+pub const PAYLOAD_VERSIONS: [u8; 2] = [PAYLOAD_VERSION_HKDF, PAYLOAD_VERSION_RAW];
+
+// HKDF parameters for v2 (MUST match app/src/lib/api/relayer.ts)
+const PAYLOAD_HKDF_SALT: &[u8] = b"tracezero-ecdh-payload";
+const PAYLOAD_HKDF_INFO: &[u8] = b"aes-256-gcm-key-v2";
+
+/// AES key for an ECDH payload of the given version
+pub fn payload_key(shared_secret: &[u8; 32], version: u8) -> Result<[u8; 32]> {
+    match version {
+        PAYLOAD_VERSION_RAW => Ok(*shared_secret),
+        PAYLOAD_VERSION_HKDF => {
+            let mut key = [0u8; 32];
+            Hkdf::<Sha256>::new(Some(PAYLOAD_HKDF_SALT), shared_secret)
+                .expand(PAYLOAD_HKDF_INFO, &mut key)
+                .map_err(|e| SdkError::Crypto(format!("HKDF expand failed: {}", e)))?;
+            Ok(key)
+        }
+        other => Err(SdkError::Crypto(format!(
+            "Unsupported payload version {}",
+            other
+        ))),
+    }
+}
+
+/// Body of the relayer's `POST /deposit`, encrypted to its X25519 key
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct EcdhPayload {
+    /// Key derivation scheme, see `PAYLOAD_VERSIONS`
+    pub version: u8,
+    pub encrypted: bool,
+    pub ciphertext: Vec<u8>,
+    pub nonce: [u8; 12],
+    /// Sender's ephemeral X25519 public key (hex)
+    pub client_pubkey: String,
+}
+
+/// Encrypt to `peer_public` with a fresh ephemeral key, using the current payload version
+pub fn encrypt_payload_ecdh(plaintext: &[u8], peer_public: &[u8; 32]) -> Result<EcdhPayload> {
+    let ephemeral = random_secret();
+    let ephemeral_public = X25519PublicKey::from(&StaticSecret::from(ephemeral));
+    let shared_secret = ecdh_shared_secret(&ephemeral, peer_public)?;
+    let key = payload_key(&shared_secret, PAYLOAD_VERSION_HKDF)?;
+    let encrypted = encrypt_payload(plaintext, &key);
+    Ok(EcdhPayload {
+        version: PAYLOAD_VERSION_HKDF,
+        encrypted: true,
+        ciphertext: encrypted.ciphertext,
+        nonce: encrypted.nonce,
+        client_pubkey: hex::encode(ephemeral_public.as_bytes()),
+    })
+}
+
+/// Largest ciphertext an `EncryptedNote` account holds (MUST match the program's
+/// MAX_ENCRYPTED_NOTE_SIZE)
+pub const MAX_ENCRYPTED_NOTE_SIZE: usize = 128;
+
+/// AES-GCM tag appended to every note ciphertext
+const NOTE_TAG_LEN: usize = 16;
+
+/// How a note's plaintext is laid out before encryption. The ciphertext length is public
+/// on-chain (`ciphertext_len`), so unpadded notes reveal their content length
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotePadding {
+    /// Plaintext as-is
+    #[default]
+    None,
+    /// Length-prefixed and zero-padded so the ciphertext is exactly this many bytes
+    PadTo(usize),
+}
+
+impl NotePadding {
+    /// Every note the size of a full `EncryptedNote` ciphertext
+    pub const fn fixed() -> Self {
+        NotePadding::PadTo(MAX_ENCRYPTED_NOTE_SIZE)
+    }
+
+    /// Lay out `plaintext` for encryption: `len (u16 LE) || plaintext || zeros` when padding
+    pub fn apply(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let NotePadding::PadTo(ciphertext_len) = *self else {
+            return Ok(plaintext.to_vec());
+        };
+        let padded_len = ciphertext_len.saturating_sub(NOTE_TAG_LEN);
+        if ciphertext_len > MAX_ENCRYPTED_NOTE_SIZE || plaintext.len() + 2 > padded_len {
+            return Err(SdkError::InvalidInput(format!(
+                "Can't pad a {}-byte note to a {}-byte ciphertext (max {})",
+                plaintext.len(),
+                ciphertext_len,
+                MAX_ENCRYPTED_NOTE_SIZE
+            )));
+        }
+        let mut padded = Vec::with_capacity(padded_len);
+        padded.extend_from_slice(&(plaintext.len() as u16).to_le_bytes());
+        padded.extend_from_slice(plaintext);
+        padded.resize(padded_len, 0);
+        Ok(padded)
+    }
+}
+
+/// Inverse of `NotePadding::PadTo`, the prefixed length must fit and the padding be zeros
+pub fn strip_note_padding(padded: &[u8]) -> Result<Vec<u8>> {
+    let (prefix, rest) = padded
+        .split_first_chunk::<2>()
+        .ok_or_else(|| SdkError::Crypto("Padded note too short".into()))?;
+    let len = u16::from_le_bytes(*prefix) as usize;
+    if len > rest.len() || rest[len..].iter().any(|&b| b != 0) {
+        return Err(SdkError::Crypto("Malformed note padding".into()));
+    }
+    Ok(rest[..len].to_vec())
+}
+
+// HKDF parameters for on-chain notes, separate from the relayer payload ones
+const NOTE_HKDF_SALT: &[u8] = b"tracezero-encrypted-note";
+const NOTE_HKDF_KEY_INFO: &[u8] = b"note-aes-256-gcm-key";
+const NOTE_HKDF_NONCE_INFO: &[u8] = b"note-aes-256-gcm-nonce";
+
+/// A note as the deposit instruction stores it in its `EncryptedNote` account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SealedNote {
+    /// AES-256-GCM ciphertext (tag appended), at most `MAX_ENCRYPTED_NOTE_SIZE` bytes
+    pub ciphertext: Vec<u8>,
+    /// Sender's ephemeral X25519 public key, the account's `ephemeral_pubkey`
+    pub ephemeral_pubkey: [u8; 32],
+}
+
+/// AES key and nonce of a note, both expanded from the ECDH shared secret so the nonce
+/// needn't be stored. Every note gets a fresh ephemeral key, hence a fresh shared secret,
+/// so no (key, nonce) pair repeats. The leaf index can't be mixed in, it is only assigned
+/// when the relayer inserts the note
+fn note_key_and_nonce(shared_secret: &[u8; 32]) -> Result<([u8; 32], [u8; 12])> {
+    let hkdf = Hkdf::<Sha256>::new(Some(NOTE_HKDF_SALT), shared_secret);
+    let mut key = [0u8; 32];
+    let mut nonce = [0u8; 12];
+    hkdf.expand(NOTE_HKDF_KEY_INFO, &mut key)
+        .and_then(|_| hkdf.expand(NOTE_HKDF_NONCE_INFO, &mut nonce))
+        .map_err(|e| SdkError::Crypto(format!("HKDF expand failed: {}", e)))?;
+    Ok((key, nonce))
+}
+
+/// Encrypt a note for on-chain storage, readable only with the secret of `viewing_pubkey`
+pub fn encrypt_note(plaintext: &[u8], viewing_pubkey: &[u8; 32]) -> Result<SealedNote> {
+    let ephemeral = random_secret();
+    let ephemeral_pubkey = X25519PublicKey::from(&StaticSecret::from(ephemeral)).to_bytes();
+    let shared_secret = ecdh_shared_secret(&ephemeral, viewing_pubkey)?;
+    let (key, nonce) = note_key_and_nonce(&shared_secret)?;
+
+    let ciphertext = Aes256Gcm::new_from_slice(&key)
+        .expect("Valid key length")
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| SdkError::Crypto("Note encryption failed".into()))?;
+    if ciphertext.len() > MAX_ENCRYPTED_NOTE_SIZE {
+        return Err(SdkError::InvalidInput(format!(
+            "Encrypted note is {} bytes, accounts hold at most {}",
+            ciphertext.len(),
+            MAX_ENCRYPTED_NOTE_SIZE
+        )));
+    }
+    Ok(SealedNote {
+        ciphertext,
+        ephemeral_pubkey,
+    })
+}
+
+/// Decrypt a stored note with the viewing secret
+pub fn decrypt_note(sealed: &SealedNote, viewing_secret: &[u8; 32]) -> Result<Vec<u8>> {
+    let shared_secret = ecdh_shared_secret(viewing_secret, &sealed.ephemeral_pubkey)?;
+    let (key, nonce) = note_key_and_nonce(&shared_secret)?;
+    Aes256Gcm::new_from_slice(&key)
+        .expect("Valid key length")
+        .decrypt(Nonce::from_slice(&nonce), sealed.ciphertext.as_ref())
+        .map_err(|_| SdkError::Crypto("Note decryption failed".into()))
+}
+
+// Curve25519 field prime 2^255 - 19, little-endian
+const X25519_FIELD_PRIME: [u8; 32] = [
+    0xed, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+];
+
+// Canonical u-coordinates of the small-order points (0, 1, order-8 points, p-1)
+// DH against any of these yields a shared secret the attacker can predict
+const X25519_LOW_ORDER_POINTS: [[u8; 32]; 5] = [
+    [0u8; 32],
+    [
+        0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0,
+    ],
+    [
+        0xe0, 0xeb, 0x7a, 0x7c, 0x3b, 0x41, 0xb8, 0xae, 0x16, 0x56, 0xe3, 0xfa, 0xf1, 0x9f, 0xc4,
+        0x6a, 0xda, 0x09, 0x8d, 0xeb, 0x9c, 0x32, 0xb1, 0xfd, 0x86, 0x62, 0x05, 0x16, 0x5f, 0x49,
+        0xb8, 0x00,
+    ],
+    [
+        0x5f, 0x9c, 0x95, 0xbc, 0xa3, 0x50, 0x8c, 0x24, 0xb1, 0xd0, 0xb1, 0x55, 0x9c, 0x83, 0xef,
+        0x5b, 0x04, 0x44, 0x5c, 0xc4, 0x58, 0x1c, 0x8e, 0x86, 0xd8, 0x22, 0x4e, 0xdd, 0xd0, 0x9f,
+        0x11, 0x57,
+    ],
+    [
+        0xec, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x7f,
+    ],
+];
+
+/// Reject X25519 public keys that are non-canonical (high bit set or >= p) or of small order
+pub fn validate_x25519_public_key(key: &[u8; 32]) -> Result<()> {
+    if key[31] & 0x80 != 0 {
+        return Err(SdkError::Crypto(
+            "Malformed X25519 public key: high bit set".into(),
+        ));
+    }
+    // Little-endian comparison against p, most significant byte first
+    if key.iter().rev().cmp(X25519_FIELD_PRIME.iter().rev()) != std::cmp::Ordering::Less {
+        return Err(SdkError::Crypto(
+            "Malformed X25519 public key: not reduced mod p".into(),
+        ));
+    }
+    if X25519_LOW_ORDER_POINTS.contains(key) {
+        return Err(SdkError::Crypto(
+            "Invalid X25519 public key: low-order point".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// X25519 shared secret with a peer's (ephemeral) public key, rejecting keys that would
+/// make the secret predictable
+pub fn ecdh_shared_secret(secret: &[u8; 32], peer_public: &[u8; 32]) -> Result<[u8; 32]> {
+    validate_x25519_public_key(peer_public)?;
+    let shared = StaticSecret::from(*secret).diffie_hellman(&X25519PublicKey::from(*peer_public));
+    if !shared.was_contributory() {
+        return Err(SdkError::Crypto(
+            "Invalid X25519 public key: non-contributory shared secret".into(),
+        ));
+    }
+    Ok(shared.to_bytes())
+}
+
+/// Decrypt a payload encrypted to `secret` by a sender using `ephemeral_pubkey`
+pub fn decrypt_payload_ecdh(
+    encrypted: &EncryptedPayload,
+    secret: &[u8; 32],
+    ephemeral_pubkey: &[u8; 32],
+) -> Result<Vec<u8>> {
+    let key = ecdh_shared_secret(secret, ephemeral_pubkey)?;
+    decrypt_payload(encrypted, &key)
+}
+
 pub fn decrypt_payload(encrypted: &EncryptedPayload, key: &[u8; 32]) -> Result<Vec<u8>> {
     let cipher = Aes256Gcm::new_from_slice(key).expect("Valid key length");
     let nonce = Nonce::from_slice(&encrypted.nonce);
@@ -230,4 +490,82 @@ mod tests {
         let non_zero = random_secret();
         assert!(validate_non_zero(&non_zero).is_ok());
     }
+
+    #[test]
+    fn test_x25519_public_key_validation() {
+        let ours = random_secret();
+        let theirs = random_secret();
+        let their_public = X25519PublicKey::from(&StaticSecret::from(theirs)).to_bytes();
+        let our_public = X25519PublicKey::from(&StaticSecret::from(ours)).to_bytes();
+
+        // Valid keys agree on the secret
+        assert!(validate_x25519_public_key(&their_public).is_ok());
+        assert_eq!(
+            ecdh_shared_secret(&ours, &their_public).unwrap(),
+            ecdh_shared_secret(&theirs, &our_public).unwrap()
+        );
+
+        // Identity and other small-order points are rejected
+        for point in &X25519_LOW_ORDER_POINTS {
+            assert!(validate_x25519_public_key(point).is_err());
+            assert!(ecdh_shared_secret(&ours, point).is_err());
+            // Sanity check the table: DH with these is non-contributory
+            let shared = StaticSecret::from(ours).diffie_hellman(&X25519PublicKey::from(*point));
+            assert!(!shared.was_contributory());
+        }
+
+        // Non-canonical encodings (p and p + 1 alias 0 and 1, high bit set)
+        let mut p = X25519_FIELD_PRIME;
+        assert!(validate_x25519_public_key(&p).is_err());
+        p[0] += 1;
+        assert!(validate_x25519_public_key(&p).is_err());
+        let mut high_bit = their_public;
+        high_bit[31] |= 0x80;
+        assert!(validate_x25519_public_key(&high_bit).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_payload_ecdh() {
+        let recipient = random_secret();
+        let recipient_public = X25519PublicKey::from(&StaticSecret::from(recipient)).to_bytes();
+        let ephemeral = random_secret();
+        let ephemeral_public = X25519PublicKey::from(&StaticSecret::from(ephemeral)).to_bytes();
+
+        let key = ecdh_shared_secret(&ephemeral, &recipient_public).unwrap();
+        let encrypted = encrypt_payload(b"note", &key);
+        assert_eq!(
+            decrypt_payload_ecdh(&encrypted, &recipient, &ephemeral_public).unwrap(),
+            b"note"
+        );
+        assert!(decrypt_payload_ecdh(&encrypted, &recipient, &[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_ecdh_payload_versions() {
+        let relayer = random_secret();
+        let relayer_public = X25519PublicKey::from(&StaticSecret::from(relayer)).to_bytes();
+
+        let payload = encrypt_payload_ecdh(b"deposit", &relayer_public).unwrap();
+        assert_eq!(payload.version, PAYLOAD_VERSION_HKDF);
+        let client_public: [u8; 32] = hex::decode(&payload.client_pubkey)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let shared = ecdh_shared_secret(&relayer, &client_public).unwrap();
+        let encrypted = EncryptedPayload {
+            ciphertext: payload.ciphertext,
+            nonce: payload.nonce,
+        };
+
+        // Decrypts only under the key derivation it was sent with
+        let v2_key = payload_key(&shared, PAYLOAD_VERSION_HKDF).unwrap();
+        assert_ne!(v2_key, shared);
+        assert_eq!(decrypt_payload(&encrypted, &v2_key).unwrap(), b"deposit");
+        let v1_key = payload_key(&shared, PAYLOAD_VERSION_RAW).unwrap();
+        assert_eq!(v1_key, shared);
+        assert!(decrypt_payload(&encrypted, &v1_key).is_err());
+
+        assert!(payload_key(&shared, 0).is_err());
+        assert!(payload_key(&shared, 3).is_err());
+    }
 }
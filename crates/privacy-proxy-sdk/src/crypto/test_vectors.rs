@@ -0,0 +1,124 @@
+//! Poseidon test vectors pinning the SDK's hashes to the circuits' (`test-utils` feature)
+//!
+//! The SDK hashes with `light_poseidon`, the circuits with circomlib. Any drift between the
+//! two only shows up as proofs the program rejects, so every hash the protocol relies on is
+//! checked here for fixed inputs. The raw permutation vectors are circomlibjs's published
+//! ones; `circuits/scripts/poseidon_vectors.js` prints the whole table from circomlibjs and
+//! its output MUST equal `EXPECTED`
+use super::{
+    generate_commitment, generate_nullifier_hash, generate_ownership_binding_hash,
+    generate_withdrawal_binding_hash, poseidon_hash, u64_to_field,
+};
+use crate::error::{Result, SdkError};
+
+pub const NULLIFIER: u64 = 12345;
+pub const SECRET: u64 = 67890;
+pub const AMOUNT: u64 = 100_000_000;
+pub const RECIPIENT: [u8; 32] = [0x01; 32];
+pub const RELAYER: [u8; 32] = [0x02; 32];
+pub const FEE: u64 = 500_000;
+pub const PENDING_WITHDRAWAL_ID: u64 = 7;
+
+/// Expected outputs (big-endian hex) by name
+pub const EXPECTED: [(&str, &str); 7] = [
+    // circomlibjs's own reference vectors for the raw permutation
+    (
+        "poseidon(1)",
+        "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133",
+    ),
+    (
+        "poseidon(1, 2)",
+        "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a",
+    ),
+    (
+        "poseidon(1, 2, 3, 4)",
+        "299c867db6c1fdd79dcefa40e4510b9837e60ebb1ce0663dbaa525df65250465",
+    ),
+    // Protocol hashes over the inputs above
+    (
+        "commitment",
+        "2231621cb324d9b4726b2c7b60b3fd1b19214238ac8f7ee6a63a6fabb61e847a",
+    ),
+    (
+        "nullifier_hash",
+        "0377cf3d56aefd1dc46d975b79eaef383fc4794c9bb6e6655e8350ee97a85056",
+    ),
+    (
+        "withdrawal_binding_hash",
+        "072ac88c2e1a65a00e7163b471c5ab309c0e0f0da0d7e44eff7897a85c349d94",
+    ),
+    (
+        "ownership_binding_hash",
+        "1ab9275337af13d5363486d6088bd19ce42668eb44953cf560694be9975b7265",
+    ),
+];
+
+/// Every vector as the SDK computes it today, in `EXPECTED` order
+pub fn compute() -> Result<Vec<(&'static str, [u8; 32])>> {
+    let nullifier = u64_to_field(NULLIFIER);
+    let nullifier_hash = generate_nullifier_hash(&nullifier)?;
+    Ok(vec![
+        ("poseidon(1)", poseidon_hash(&[&u64_to_field(1)])?),
+        (
+            "poseidon(1, 2)",
+            poseidon_hash(&[&u64_to_field(1), &u64_to_field(2)])?,
+        ),
+        (
+            "poseidon(1, 2, 3, 4)",
+            poseidon_hash(&[
+                &u64_to_field(1),
+                &u64_to_field(2),
+                &u64_to_field(3),
+                &u64_to_field(4),
+            ])?,
+        ),
+        (
+            "commitment",
+            generate_commitment(&nullifier, &u64_to_field(SECRET), AMOUNT)?,
+        ),
+        ("nullifier_hash", nullifier_hash),
+        (
+            "withdrawal_binding_hash",
+            generate_withdrawal_binding_hash(&nullifier_hash, &RECIPIENT, &RELAYER, FEE)?,
+        ),
+        (
+            "ownership_binding_hash",
+            generate_ownership_binding_hash(&nullifier, PENDING_WITHDRAWAL_ID)?,
+        ),
+    ])
+}
+
+/// Fails on the first vector that no longer matches the circuits, naming it
+pub fn check() -> Result<()> {
+    for ((name, computed), (expected_name, expected)) in compute()?.into_iter().zip(EXPECTED) {
+        debug_assert_eq!(name, expected_name);
+        if hex::encode(computed) != expected {
+            return Err(SdkError::Crypto(format!(
+                "Poseidon drift in {}: expected {}, got {}",
+                name,
+                expected,
+                hex::encode(computed)
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashes_match_circuit_vectors() {
+        check().unwrap();
+    }
+
+    #[test]
+    fn test_merkle_zero_matches_circuit() {
+        // Level 1 of the empty tree, from `circuits/scripts/compute_zeros.js`
+        assert_eq!(
+            hex::encode(poseidon_hash(&[&[0u8; 32], &[0u8; 32]]).unwrap()),
+            "2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864"
+        );
+    }
+}
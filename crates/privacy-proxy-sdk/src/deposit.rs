@@ -4,9 +4,26 @@
 use serde::{Deserialize, Serialize};
 
 use crate::credits::SignedCredit;
-use crate::crypto::{generate_commitment, random_secret, validate_non_zero};
+use crate::crypto::{
+    decrypt_note, encrypt_note, generate_commitment, random_secret, strip_note_padding,
+    validate_non_zero, NotePadding, SealedNote,
+};
 use crate::error::{Result, SdkError};
 
+/// Standard pool denominations in lamports, indexed by bucket id (MUST match on-chain constants)
+pub const BUCKET_AMOUNTS: [u64; 7] = [
+    100_000_000,     // 0.1 SOL
+    500_000_000,     // 0.5 SOL
+    1_000_000_000,   // 1 SOL
+    5_000_000_000,   // 5 SOL
+    10_000_000_000,  // 10 SOL
+    50_000_000_000,  // 50 SOL
+    100_000_000_000, // 100 SOL
+];
+
+/// nullifier (32) + secret (32) + amount (8)
+const NOTE_PLAINTEXT_LEN: usize = 72;
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct DepositRequest {
     /// The signed credit being redeemed
@@ -15,6 +32,9 @@ pub struct DepositRequest {
     pub commitment: [u8; 32],
     /// Encrypted note (optional, for recovery)
     pub encrypted_note: Option<Vec<u8>>,
+    /// ECDH ephemeral key of `encrypted_note`, stored next to it on-chain
+    #[serde(default)]
+    pub note_ephemeral_pubkey: Option<[u8; 32]>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -63,6 +83,47 @@ impl DepositNote {
         serde_json::from_slice(bytes).map_err(|e| SdkError::Serialization(e.to_string()))
     }
 
+    /// Encrypt nullifier, secret and amount to `viewing_pubkey` for the note's on-chain
+    /// `EncryptedNote`. The commitment is recomputed and the leaf index read from the
+    /// account on recovery, so neither is stored
+    pub fn seal(&self, viewing_pubkey: &[u8; 32]) -> Result<SealedNote> {
+        self.seal_with(viewing_pubkey, NotePadding::None)
+    }
+
+    /// `seal`, with the plaintext laid out per `padding` first
+    pub fn seal_with(&self, viewing_pubkey: &[u8; 32], padding: NotePadding) -> Result<SealedNote> {
+        self.validate()?;
+        let mut plaintext = Vec::with_capacity(NOTE_PLAINTEXT_LEN);
+        plaintext.extend_from_slice(&self.nullifier);
+        plaintext.extend_from_slice(&self.secret);
+        plaintext.extend_from_slice(&self.amount.to_le_bytes());
+        encrypt_note(&padding.apply(&plaintext)?, viewing_pubkey)
+    }
+
+    /// Recover a note from its `EncryptedNote` account, padded or not
+    pub fn open(sealed: &SealedNote, viewing_secret: &[u8; 32], leaf_index: u64) -> Result<Self> {
+        let mut plaintext = decrypt_note(sealed, viewing_secret)?;
+        // Only an unpadded note is exactly the raw length, padding adds at least the prefix
+        if plaintext.len() != NOTE_PLAINTEXT_LEN {
+            plaintext = strip_note_padding(&plaintext)?;
+        }
+        if plaintext.len() != NOTE_PLAINTEXT_LEN {
+            return Err(SdkError::Crypto(format!(
+                "Note plaintext is {} bytes, expected {}",
+                plaintext.len(),
+                NOTE_PLAINTEXT_LEN
+            )));
+        }
+        let note = Self {
+            nullifier: plaintext[..32].try_into().expect("length checked"),
+            secret: plaintext[32..64].try_into().expect("length checked"),
+            amount: u64::from_le_bytes(plaintext[64..].try_into().expect("length checked")),
+            leaf_index: Some(leaf_index),
+        };
+        note.validate()?;
+        Ok(note)
+    }
+
     pub fn validate(&self) -> Result<()> {
         validate_non_zero(&self.nullifier)?;
         validate_non_zero(&self.secret)?;
@@ -82,6 +143,7 @@ impl DepositRequest {
             credit,
             commitment,
             encrypted_note: None,
+            note_ephemeral_pubkey: None,
         })
     }
 
@@ -90,6 +152,13 @@ impl DepositRequest {
         self
     }
 
+    /// Attach a note from `DepositNote::seal` for on-chain storage
+    pub fn with_sealed_note(mut self, sealed: SealedNote) -> Self {
+        self.encrypted_note = Some(sealed.ciphertext);
+        self.note_ephemeral_pubkey = Some(sealed.ephemeral_pubkey);
+        self
+    }
+
     pub fn to_bytes(&self) -> Result<Vec<u8>> {
         serde_json::to_vec(self).map_err(|e| SdkError::Serialization(e.to_string()))
     }
@@ -135,4 +204,93 @@ mod tests {
         assert!(note.validate().is_err());
         assert!(note.commitment().is_err());
     }
+
+    #[test]
+    fn test_sealed_note_round_trip() {
+        use crate::crypto::MAX_ENCRYPTED_NOTE_SIZE;
+        use crate::stealth::StealthMaster;
+
+        let owner = StealthMaster::new();
+        let note = DepositNote::new(1_000_000_000);
+        let sealed = note.seal(&owner.viewing_pubkey()).unwrap();
+
+        // Stored the way the deposit instruction fills an EncryptedNote account
+        let mut stored = [0u8; MAX_ENCRYPTED_NOTE_SIZE];
+        stored[..sealed.ciphertext.len()].copy_from_slice(&sealed.ciphertext);
+        let ciphertext_len = sealed.ciphertext.len() as u16;
+        let request = DepositRequest::new(
+            SignedCredit {
+                token_id: [1u8; 32],
+                signature: vec![],
+                amount: note.amount,
+            },
+            &note,
+        )
+        .unwrap()
+        .with_sealed_note(sealed.clone());
+        let ephemeral_pubkey = request.note_ephemeral_pubkey.unwrap();
+
+        // Read back from the account fields
+        let loaded = SealedNote {
+            ciphertext: stored[..ciphertext_len as usize].to_vec(),
+            ephemeral_pubkey,
+        };
+        let recovered = DepositNote::open(&loaded, &owner.viewing_secret(), 7).unwrap();
+        assert_eq!(recovered.nullifier, note.nullifier);
+        assert_eq!(recovered.secret, note.secret);
+        assert_eq!(recovered.amount, note.amount);
+        assert_eq!(recovered.leaf_index, Some(7));
+        assert_eq!(recovered.commitment().unwrap(), request.commitment);
+
+        // Fresh ephemeral key per seal, so the same note never repeats a ciphertext
+        assert_ne!(note.seal(&owner.viewing_pubkey()).unwrap(), sealed);
+
+        // Other users can't open it
+        let stranger = StealthMaster::new();
+        assert!(DepositNote::open(&loaded, &stranger.viewing_secret(), 7).is_err());
+    }
+
+    #[test]
+    fn test_padded_notes_hide_length() {
+        use crate::crypto::MAX_ENCRYPTED_NOTE_SIZE;
+        use crate::stealth::StealthMaster;
+
+        let owner = StealthMaster::new();
+        let note = DepositNote::new(5_000_000_000);
+
+        // Fixed padding fills the whole account, whatever the note holds
+        let padded = note
+            .seal_with(&owner.viewing_pubkey(), NotePadding::fixed())
+            .unwrap();
+        assert_eq!(padded.ciphertext.len(), MAX_ENCRYPTED_NOTE_SIZE);
+        let other = DepositNote::new(100_000_000)
+            .seal_with(&owner.viewing_pubkey(), NotePadding::fixed())
+            .unwrap();
+        assert_eq!(other.ciphertext.len(), padded.ciphertext.len());
+
+        // Padded and unpadded notes both open
+        let recovered = DepositNote::open(&padded, &owner.viewing_secret(), 3).unwrap();
+        assert_eq!(recovered.nullifier, note.nullifier);
+        assert_eq!(recovered.amount, note.amount);
+        let unpadded = note
+            .seal_with(&owner.viewing_pubkey(), NotePadding::None)
+            .unwrap();
+        assert!(unpadded.ciphertext.len() < MAX_ENCRYPTED_NOTE_SIZE);
+        assert!(DepositNote::open(&unpadded, &owner.viewing_secret(), 3).is_ok());
+
+        // Targets the note doesn't fit in, or the account can't hold, are refused
+        assert!(note
+            .seal_with(&owner.viewing_pubkey(), NotePadding::PadTo(80))
+            .is_err());
+        assert!(note
+            .seal_with(
+                &owner.viewing_pubkey(),
+                NotePadding::PadTo(MAX_ENCRYPTED_NOTE_SIZE + 1)
+            )
+            .is_err());
+
+        // Garbage in the padding is rejected rather than silently dropped
+        assert!(strip_note_padding(&[2, 0, 9, 9, 0, 1]).is_err());
+        assert_eq!(strip_note_padding(&[2, 0, 9, 9, 0, 0]).unwrap(), vec![9, 9]);
+    }
 }
@@ -0,0 +1,30 @@
+//! Poseidon domain tags, each the big-endian u32 of a 4-char ASCII tag
+//! Every value MUST match circuits/*.circom and the programs, a mismatch doesn't
+//! fail loudly, it just yields proofs nothing will verify
+//!
+//! The deployed values spell "colm", "bild" and "orsb", not the "comm", "bind" and
+//! "ownb" older comments claimed. The circuits are built on the values, so those stay
+
+/// Domain value of a 4-byte tag, `b"null"` -> `0x6e756c6c`
+pub const fn from_tag(tag: &[u8; 4]) -> u64 {
+    u32::from_be_bytes(*tag) as u64
+}
+
+pub const DOMAIN_NULLIFIER: u64 = from_tag(b"null");
+pub const DOMAIN_COMMIT: u64 = from_tag(b"colm");
+pub const DOMAIN_BIND: u64 = from_tag(b"bild");
+pub const DOMAIN_OWNER_BIND: u64 = from_tag(b"orsb");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domains_match_circuits() {
+        // Literals copied from circuits/withdrawal.circom and circuits/ownership.circom
+        assert_eq!(DOMAIN_NULLIFIER, 1853189228);
+        assert_eq!(DOMAIN_COMMIT, 1668246637);
+        assert_eq!(DOMAIN_BIND, 1651076196);
+        assert_eq!(DOMAIN_OWNER_BIND, 1869771618);
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::flow::FlowPhase;
+
 pub type Result<T> = std::result::Result<T, SdkError>;
 
 #[derive(Error, Debug)]
@@ -16,15 +18,87 @@ pub enum SdkError {
     #[error("Relayer error: {0}")]
     Relayer(String),
 
+    /// The relayer answered with an error, `code` is its stable machine-readable code
+    /// (e.g. `token_already_redeemed`), `message` is for humans only
+    #[error("Relayer rejected request ({code}): {message}")]
+    RelayerRejected { code: String, message: String },
+
     #[error("Merkle tree error: {0}")]
     MerkleTree(String),
 
+    /// Every leaf of the tree is taken, the on-chain pool rejects deposits (`PoolFull`) too
+    #[error("Merkle tree is full ({capacity} leaves)")]
+    TreeFull { capacity: u64 },
+
     #[error("Invalid proof: {0}")]
     InvalidProof(String),
 
     #[error("Tor connection required: {0}")]
     TorRequired(String),
 
+    /// The Tor check says traffic doesn't exit through Tor, `checked_exit_ip` is the address
+    /// it saw instead, if it reported one. The SOCKS proxy answered but isn't Tor, e.g. another
+    /// proxy on the configured port (with Tor down, the connect fails as `Network` instead)
+    #[error("Tor not detected (exit IP: {})", checked_exit_ip.as_deref().unwrap_or("unknown"))]
+    TorNotDetected { checked_exit_ip: Option<String> },
+
+    /// The pool has fewer unspent deposits than the caller's `AnonymityGuard` asks for
+    #[error("Anonymity set too small: {set_size} unspent deposits in the pool")]
+    WeakAnonymity { set_size: u64 },
+
+    #[error("Clearnet relayer refused: {0}")]
+    ClearnetRelayer(String),
+
+    #[error("Relayer params rejected: {0}")]
+    ParamsRejected(String),
+
+    #[error("Solana RPC error: {0}")]
+    Rpc(String),
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
+
+    /// Reading or writing a local file failed, e.g. persisting a note
+    #[error("IO error: {0}")]
+    Io(String),
+
+    #[error("Proof generation failed: {0}")]
+    Prover(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// A step of `PrivacyClient::run_full_flow` failed, everything before `phase` went through
+    #[error("Flow failed at {phase}: {source}")]
+    FlowFailed {
+        phase: FlowPhase,
+        #[source]
+        source: Box<SdkError>,
+    },
+}
+
+impl SdkError {
+    /// Relayer error code, if the relayer rejected the request
+    pub fn relayer_code(&self) -> Option<&str> {
+        match self {
+            SdkError::RelayerRejected { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Attribute this error to `phase` of the end-to-end flow
+    pub fn in_phase(self, phase: FlowPhase) -> Self {
+        SdkError::FlowFailed {
+            phase,
+            source: Box::new(self),
+        }
+    }
+
+    /// Flow step that failed, if this came out of `run_full_flow`
+    pub fn flow_phase(&self) -> Option<FlowPhase> {
+        match self {
+            SdkError::FlowFailed { phase, .. } => Some(*phase),
+            _ => None,
+        }
+    }
 }
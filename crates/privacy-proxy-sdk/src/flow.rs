@@ -0,0 +1,275 @@
+/// End-to-end flow helpers: paying for a credit over Solana RPC and keeping the deposit note
+/// on disk. `PrivacyClient::run_full_flow` (`prover` feature) chains them with the relayer calls
+use std::fmt;
+use std::path::Path;
+#[cfg(feature = "prover")]
+use std::path::PathBuf;
+
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::signer::Signer;
+use solana_sdk::transaction::Transaction;
+use tracezero::TorHttpClient;
+
+use crate::credits::credit_price;
+use crate::deposit::DepositNote;
+use crate::error::{Result, SdkError};
+#[cfg(feature = "prover")]
+use crate::prover::WithdrawalProver;
+
+/// Step of `run_full_flow`, carried by `SdkError::FlowFailed` so callers know how far it got
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlowPhase {
+    /// Fetching relayer params and checking the inputs against them
+    Params,
+    /// Paying the treasury for the credit
+    Payment,
+    /// Getting the blinded credit signed
+    Signing,
+    /// Writing the deposit note to disk
+    PersistNote,
+    Deposit,
+    /// Fetching the deposit's Merkle path
+    MerkleProof,
+    /// Generating the withdrawal proof
+    Proving,
+    Withdrawal,
+}
+
+impl fmt::Display for FlowPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FlowPhase::Params => "params",
+            FlowPhase::Payment => "payment",
+            FlowPhase::Signing => "signing",
+            FlowPhase::PersistNote => "persist note",
+            FlowPhase::Deposit => "deposit",
+            FlowPhase::MerkleProof => "merkle proof",
+            FlowPhase::Proving => "proving",
+            FlowPhase::Withdrawal => "withdrawal",
+        })
+    }
+}
+
+/// Everything `run_full_flow` needs besides the amount, recipient and delay
+#[cfg(feature = "prover")]
+pub struct FlowSetup {
+    /// Pays for the credit. Only the purchase is linked to it, never the withdrawal
+    pub payer: Keypair,
+    /// Solana RPC endpoint, reached over Tor like the relayer
+    pub rpc_url: String,
+    pub prover: WithdrawalProver,
+    /// Where the deposit note is kept, owner-only. It's the only way to recover the funds if
+    /// the flow stops after the deposit
+    pub note_path: PathBuf,
+}
+
+/// Outcome of a completed `run_full_flow`
+#[derive(Clone, Debug)]
+pub struct FlowResult {
+    pub deposit_signature: String,
+    /// `request_withdrawal` transaction. Funds reach the recipient once the delay is over,
+    /// see `PrivacyClient::await_withdrawal`
+    pub withdrawal_signature: String,
+    pub leaf_index: u64,
+    pub nullifier_hash: [u8; 32],
+    pub recipient: Pubkey,
+}
+
+/// Transfer of the price of an `amount` credit from `payer` to `treasury`
+pub fn payment_transaction(
+    payer: &Keypair,
+    treasury: &Pubkey,
+    amount: u64,
+    fee_bps: u16,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let transfer = solana_sdk::system_instruction::transfer(
+        &payer.pubkey(),
+        treasury,
+        credit_price(amount, fee_bps),
+    );
+    Transaction::new_signed_with_payer(
+        &[transfer],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    )
+}
+
+/// Pay `treasury` for an `amount` credit through `rpc_url`, returning the transaction
+/// signature the relayer checks before signing. The relayer waits for it to confirm
+pub async fn send_payment(
+    client: &TorHttpClient,
+    rpc_url: &str,
+    payer: &Keypair,
+    treasury: &Pubkey,
+    amount: u64,
+    fee_bps: u16,
+) -> Result<Signature> {
+    let latest: LatestBlockhash = rpc_call(
+        client,
+        rpc_url,
+        "getLatestBlockhash",
+        serde_json::json!([{ "commitment": "confirmed" }]),
+    )
+    .await?;
+    let recent_blockhash = latest
+        .value
+        .blockhash
+        .parse::<Hash>()
+        .map_err(|e| SdkError::Rpc(format!("malformed blockhash: {}", e)))?;
+
+    let transaction = payment_transaction(payer, treasury, amount, fee_bps, recent_blockhash);
+    let wire =
+        bincode::serialize(&transaction).map_err(|e| SdkError::Serialization(e.to_string()))?;
+    let signature: String = rpc_call(
+        client,
+        rpc_url,
+        "sendTransaction",
+        serde_json::json!([
+            base64::engine::general_purpose::STANDARD.encode(wire),
+            { "encoding": "base64", "preflightCommitment": "confirmed" },
+        ]),
+    )
+    .await?;
+    signature
+        .parse()
+        .map_err(|e| SdkError::Rpc(format!("malformed signature: {}", e)))
+}
+
+/// Write `note` to `path`, readable by the owner only
+/// Goes through a temporary file so an interrupted write never leaves a truncated note
+pub fn persist_note(path: &Path, note: &DepositNote) -> Result<()> {
+    use std::io::Write;
+
+    let bytes = note.to_bytes()?;
+    let tmp_path = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let write = |tmp_path: &Path| -> std::io::Result<()> {
+        let mut file = options.open(tmp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        std::fs::rename(tmp_path, path)
+    };
+    write(&tmp_path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp_path);
+        SdkError::Io(format!("failed to write note to {}: {}", path.display(), e))
+    })
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct LatestBlockhash {
+    value: BlockhashValue,
+}
+
+#[derive(Deserialize)]
+struct BlockhashValue {
+    blockhash: String,
+}
+
+async fn rpc_call<R: DeserializeOwned>(
+    client: &TorHttpClient,
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<R> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let response: RpcResponse<R> = client.post_json(rpc_url, &request).await?;
+    if let Some(error) = response.error {
+        return Err(SdkError::Rpc(format!("{} failed: {}", method, error)));
+    }
+    response
+        .result
+        .ok_or_else(|| SdkError::Rpc(format!("{} returned no result", method)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payment_transaction_pays_price_to_treasury() {
+        let payer = Keypair::new();
+        let treasury = Pubkey::new_unique();
+        let tx = payment_transaction(&payer, &treasury, 1_000_000_000, 50, Hash::new_unique());
+        tx.verify().unwrap();
+
+        let message = &tx.message;
+        assert_eq!(message.instructions.len(), 1);
+        let instruction = &message.instructions[0];
+        let accounts: Vec<_> = instruction
+            .accounts
+            .iter()
+            .map(|&i| message.account_keys[i as usize])
+            .collect();
+        assert_eq!(accounts, [payer.pubkey(), treasury]);
+        let transfer: solana_sdk::system_instruction::SystemInstruction =
+            bincode::deserialize(&instruction.data).unwrap();
+        assert!(matches!(
+            transfer,
+            solana_sdk::system_instruction::SystemInstruction::Transfer {
+                lamports: 1_005_000_000
+            }
+        ));
+    }
+
+    #[test]
+    fn test_persist_note() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.json");
+
+        let mut note = DepositNote::new(100_000_000);
+        persist_note(&path, &note).unwrap();
+        note.set_leaf_index(7);
+        persist_note(&path, &note).unwrap();
+
+        let stored = DepositNote::from_bytes(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(stored.nullifier, note.nullifier);
+        assert_eq!(stored.leaf_index, Some(7));
+        assert!(!path.with_extension("tmp").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // A directory that doesn't exist fails as IO, not as bad input
+        let missing = dir.path().join("missing").join("note.json");
+        assert!(matches!(
+            persist_note(&missing, &note),
+            Err(SdkError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_flow_error_names_phase() {
+        let err = SdkError::Relayer("pool is paused".into()).in_phase(FlowPhase::Deposit);
+        assert_eq!(err.flow_phase(), Some(FlowPhase::Deposit));
+        assert_eq!(
+            err.to_string(),
+            "Flow failed at deposit: Relayer error: pool is paused"
+        );
+        assert_eq!(SdkError::Timeout("x".into()).flow_phase(), None);
+    }
+}
@@ -3,12 +3,27 @@ pub mod client;
 pub mod credits;
 pub mod crypto;
 pub mod deposit;
+pub mod domains;
 pub mod error;
+pub mod flow;
 pub mod merkle;
+pub mod params;
+pub mod pool;
+#[cfg(feature = "prover")]
+pub mod prover;
 pub mod stealth;
+#[cfg(test)]
+mod test_server;
 pub mod withdrawal;
 
 pub use client::PrivacyClient;
 pub use credits::{BlindedCredit, SignedCredit};
 pub use error::{Result, SdkError};
+#[cfg(feature = "prover")]
+pub use flow::FlowSetup;
+pub use flow::{FlowPhase, FlowResult};
+pub use params::RelayerParams;
+pub use pool::{fetch_pool_stats, DepositPool, PoolStats};
+#[cfg(feature = "prover")]
+pub use prover::{OwnershipProver, WithdrawalProver};
 pub use stealth::StealthAddress;
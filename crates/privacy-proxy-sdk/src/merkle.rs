@@ -27,6 +27,64 @@ pub struct MerkleProof {
     pub leaf_index: u64,
 }
 
+/// Response of `GET /proof/:bucket_id/:leaf_index`
+#[derive(Deserialize)]
+pub struct MerkleProofResponse {
+    pub success: bool,
+    /// Sibling hashes (hex)
+    pub siblings: Option<Vec<String>>,
+    pub path_indices: Option<Vec<u8>>,
+    pub leaf_index: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl MerkleProofResponse {
+    pub fn into_proof(self) -> Result<MerkleProof> {
+        if !self.success {
+            return Err(SdkError::MerkleTree(
+                self.error.unwrap_or_else(|| "proof unavailable".into()),
+            ));
+        }
+        let (Some(siblings), Some(path_indices), Some(leaf_index)) =
+            (self.siblings, self.path_indices, self.leaf_index)
+        else {
+            return Err(SdkError::MerkleTree("incomplete proof response".into()));
+        };
+        let siblings = siblings
+            .iter()
+            .map(|sibling| {
+                hex::decode(sibling)
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| SdkError::MerkleTree(format!("malformed sibling {:?}", sibling)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if siblings.len() != path_indices.len() || path_indices.iter().any(|&i| i > 1) {
+            return Err(SdkError::MerkleTree("malformed proof path".into()));
+        }
+        Ok(MerkleProof {
+            siblings,
+            path_indices,
+            leaf_index,
+        })
+    }
+}
+
+impl MerkleProof {
+    /// Root of the tree `leaf` sits in, following the path up from it
+    pub fn root(&self, leaf: &[u8; 32]) -> Result<[u8; 32]> {
+        let mut current = *leaf;
+        for (sibling, &path_index) in self.siblings.iter().zip(self.path_indices.iter()) {
+            current = if path_index == 0 {
+                hash_pair(&current, sibling)?
+            } else {
+                hash_pair(sibling, &current)?
+            };
+        }
+        Ok(current)
+    }
+}
+
 /// Sparse Merkle tree
 pub struct MerkleTree {
     /// Tree depth
@@ -54,10 +112,12 @@ impl MerkleTree {
     }
 
     pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u64> {
-        let index = self.leaves.len() as u64;
-        if index >= (1u64 << self.depth) {
-            return Err(SdkError::MerkleTree("Tree is full".into()));
+        if self.is_full() {
+            return Err(SdkError::TreeFull {
+                capacity: self.capacity(),
+            });
         }
+        let index = self.leaves.len() as u64;
 
         self.leaves.push(leaf);
         self.update_path(index as usize);
@@ -96,7 +156,7 @@ impl MerkleTree {
                 break;
             }
 
-            let mut next_level = Vec::with_capacity((current_level.len() + 1) / 2);
+            let mut next_level = Vec::with_capacity(current_level.len().div_ceil(2));
             for chunk in current_level.chunks(2) {
                 let left = chunk[0];
                 let right = chunk.get(1).copied().unwrap_or(ZEROS[level]);
@@ -134,16 +194,7 @@ impl MerkleTree {
     }
 
     pub fn verify_proof(root: &[u8; 32], leaf: &[u8; 32], proof: &MerkleProof) -> Result<bool> {
-        let mut current = *leaf;
-        for (sibling, &path_index) in proof.siblings.iter().zip(proof.path_indices.iter()) {
-            current = if path_index == 0 {
-                hash_pair(&current, sibling)?
-            } else {
-                hash_pair(sibling, &current)?
-            };
-        }
-
-        Ok(&current == root)
+        Ok(&proof.root(leaf)? == root)
     }
 
     pub fn len(&self) -> usize {
@@ -154,6 +205,15 @@ impl MerkleTree {
         self.leaves.is_empty()
     }
 
+    /// Number of leaves the tree can hold, `2^depth`
+    pub fn capacity(&self) -> u64 {
+        1u64 << self.depth
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.leaves.len() as u64 >= self.capacity()
+    }
+
     fn update_path(&mut self, leaf_index: usize) {
         let mut current_index = leaf_index;
         for level in 0..self.depth {
@@ -199,7 +259,7 @@ impl MerkleTree {
             if current_level.len() == 1 {
                 current_level = vec![hash_pair(&current_level[0], &ZEROS[level])?];
             } else {
-                let mut next_level = Vec::with_capacity((current_level.len() + 1) / 2);
+                let mut next_level = Vec::with_capacity(current_level.len().div_ceil(2));
                 for chunk in current_level.chunks(2) {
                     let left = chunk[0];
                     let right = chunk.get(1).copied().unwrap_or(ZEROS[level]);
@@ -287,6 +347,53 @@ mod tests {
         assert!(MerkleTree::verify_proof(&root, &leaf, &proof).unwrap());
     }
 
+    #[test]
+    fn test_full_tree_rejects_insert() {
+        let mut tree = MerkleTree::new(2).unwrap();
+        assert_eq!(tree.capacity(), 4);
+        for _ in 0..4 {
+            assert!(!tree.is_full());
+            tree.insert(random_secret()).unwrap();
+        }
+        assert!(tree.is_full());
+
+        let root = tree.root().unwrap();
+        assert!(matches!(
+            tree.insert(random_secret()),
+            Err(SdkError::TreeFull { capacity: 4 })
+        ));
+        // The rejected leaf left the tree untouched
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.root().unwrap(), root);
+    }
+
+    #[test]
+    fn test_proof_response_round_trip() {
+        let mut tree = MerkleTree::new(4).unwrap();
+        let leaf = random_secret();
+        let idx = tree.insert(leaf).unwrap();
+        let proof = tree.proof(idx).unwrap();
+
+        // As served by the relayer's `/proof` endpoint
+        let response = |siblings: Vec<String>| MerkleProofResponse {
+            success: true,
+            siblings: Some(siblings),
+            path_indices: Some(proof.path_indices.clone()),
+            leaf_index: Some(idx),
+            error: None,
+        };
+        let decoded = response(proof.siblings.iter().map(hex::encode).collect())
+            .into_proof()
+            .unwrap();
+        assert_eq!(decoded.root(&leaf).unwrap(), tree.root().unwrap());
+
+        let mut short = proof.siblings.iter().map(hex::encode).collect::<Vec<_>>();
+        short[0].truncate(62);
+        assert!(response(short).into_proof().is_err());
+        let missing = proof.siblings[1..].iter().map(hex::encode).collect();
+        assert!(response(missing).into_proof().is_err());
+    }
+
     #[test]
     fn print_initial_root() {
         // Print the initial root for an empty tree of depth 20
@@ -0,0 +1,165 @@
+/// Relayer public parameters, fetched from `GET /params` as one bundle signed by the relayer
+/// Nothing in the bundle is trusted until the signature checks out against a pubkey the
+/// client already knows, so a malicious exit or mirror can't swap in its own keys or fees
+use rsa::{BigUint, RsaPublicKey};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::str::FromStr;
+use tracezero::TorHttpClient;
+
+use crate::error::{Result, SdkError};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RelayerParams {
+    /// RSA public keys accepted for credits, signing key first
+    pub rsa_keys: Vec<RsaKeyParams>,
+    /// X25519 public key for payload encryption (hex)
+    pub ecdh_pubkey: String,
+    /// Treasury that receives credit payments (base58)
+    pub treasury: String,
+    /// Privacy proxy program (base58)
+    pub program_id: String,
+    /// Fee in basis points
+    pub fee_bps: u16,
+    /// Pool denominations in lamports, indexed by bucket id
+    pub bucket_amounts: Vec<u64>,
+    /// Withdrawal delay bounds enforced by the program
+    pub min_delay_hours: u8,
+    pub max_delay_hours: u8,
+    /// Circuit build the withdrawal proofs must come from
+    pub circuit_version: String,
+    /// snarkjs verification key of the withdrawal circuit
+    pub withdrawal_vk: Option<serde_json::Value>,
+    /// Unix timestamp the bundle was signed at
+    pub issued_at: u64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RsaKeyParams {
+    /// Modulus (hex)
+    pub n: String,
+    /// Exponent (hex)
+    pub e: String,
+    /// Unix timestamp when a retired key stops being accepted (None = current key)
+    pub expires_at: Option<u64>,
+}
+
+/// Wire format of `GET /params`
+#[derive(Clone, Debug, Deserialize)]
+pub struct SignedParams {
+    /// JSON encoding of `RelayerParams`, exactly as signed
+    pub params: String,
+    /// Relayer pubkey that produced `signature` (base58)
+    pub signer: String,
+    /// ed25519 signature over the bytes of `params` (base58)
+    pub signature: String,
+}
+
+impl SignedParams {
+    /// Check the bundle was signed by `expected_pubkey`, then decode it
+    pub fn verify(&self, expected_pubkey: &Pubkey) -> Result<RelayerParams> {
+        if self.signer != expected_pubkey.to_string() {
+            return Err(SdkError::ParamsRejected(format!(
+                "signed by {}, expected {}",
+                self.signer, expected_pubkey
+            )));
+        }
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|e| SdkError::ParamsRejected(format!("malformed signature: {}", e)))?;
+        if !signature.verify(expected_pubkey.as_ref(), self.params.as_bytes()) {
+            return Err(SdkError::ParamsRejected("signature does not match".into()));
+        }
+        serde_json::from_str(&self.params).map_err(|e| SdkError::Serialization(e.to_string()))
+    }
+}
+
+impl RelayerParams {
+    /// Fetch `<relayer_url>/params` and return it only if signed by `expected_pubkey`
+    pub async fn fetch_and_verify(
+        client: &TorHttpClient,
+        relayer_url: &str,
+        expected_pubkey: &Pubkey,
+    ) -> Result<Self> {
+        let url = format!("{}/params", relayer_url.trim_end_matches('/'));
+        let bundle: SignedParams = client
+            .get_json(&url)
+            .await
+            .map_err(|e| SdkError::Relayer(e.to_string()))?;
+        bundle.verify(expected_pubkey)
+    }
+
+    /// RSA key the relayer currently blind-signs credits with
+    pub fn signing_key(&self) -> Result<RsaPublicKey> {
+        let key = self
+            .rsa_keys
+            .first()
+            .ok_or_else(|| SdkError::ParamsRejected("no RSA keys".into()))?;
+        let decode = |s: &str| hex::decode(s).map_err(|e| SdkError::Serialization(e.to_string()));
+        let n = BigUint::from_bytes_be(&decode(&key.n)?);
+        let e = BigUint::from_bytes_be(&decode(&key.e)?);
+        RsaPublicKey::new(n, e).map_err(|e| SdkError::Crypto(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_server::serve_once;
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::signer::Signer;
+
+    /// `/params` response signed by `keypair`
+    pub(crate) fn signed_bundle(keypair: &Keypair) -> serde_json::Value {
+        let params = serde_json::json!({
+            "rsa_keys": [{ "n": "ab".repeat(128), "e": "010001", "expires_at": null }],
+            "ecdh_pubkey": "09".repeat(32),
+            "treasury": Pubkey::new_unique().to_string(),
+            "program_id": Pubkey::new_unique().to_string(),
+            "fee_bps": 50,
+            "bucket_amounts": [100_000_000u64, 500_000_000u64],
+            "min_delay_hours": 0,
+            "max_delay_hours": 24,
+            "circuit_version": "0.1.0",
+            "withdrawal_vk": null,
+            "issued_at": 1_700_000_000u64,
+        })
+        .to_string();
+        serde_json::json!({
+            "signature": keypair.sign_message(params.as_bytes()).to_string(),
+            "signer": keypair.pubkey().to_string(),
+            "params": params,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fetch_and_verify_params() {
+        let keypair = Keypair::new();
+        let client = TorHttpClient::new_direct().unwrap();
+
+        // Valid bundle
+        let url = serve_once(signed_bundle(&keypair).to_string()).await;
+        let params = RelayerParams::fetch_and_verify(&client, &url, &keypair.pubkey())
+            .await
+            .unwrap();
+        assert_eq!(params.fee_bps, 50);
+        assert_eq!(params.max_delay_hours, 24);
+        assert!(params.signing_key().is_ok());
+
+        // Fee rewritten in transit, signature no longer matches
+        let mut tampered = signed_bundle(&keypair);
+        let forged = tampered["params"]
+            .as_str()
+            .unwrap()
+            .replace("\"fee_bps\":50", "\"fee_bps\":5000");
+        tampered["params"] = serde_json::Value::String(forged);
+        let url = serve_once(tampered.to_string()).await;
+        let result = RelayerParams::fetch_and_verify(&client, &url, &keypair.pubkey()).await;
+        assert!(matches!(result, Err(SdkError::ParamsRejected(_))));
+
+        // Validly signed, but by a different relayer
+        let url = serve_once(signed_bundle(&Keypair::new()).to_string()).await;
+        let result = RelayerParams::fetch_and_verify(&client, &url, &keypair.pubkey()).await;
+        assert!(matches!(result, Err(SdkError::ParamsRejected(_))));
+    }
+}
@@ -0,0 +1,219 @@
+/// Read-only view of a deposit pool, for picking the bucket with the largest anonymity set
+/// There is no on-chain "view" call, so this reads the `DepositPool` account over JSON-RPC
+/// and decodes it the way Anchor does: check the 8-byte `account:DepositPool` discriminator,
+/// then borsh-deserialize the fields
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use tracezero::TorHttpClient;
+
+use crate::error::{Result, SdkError};
+
+/// Must match the program's `HISTORICAL_ROOTS_COUNT`
+pub const POOL_HISTORICAL_ROOTS: usize = 2;
+
+/// `DepositPool` account, field order and types mirror
+/// `programs/privacy_proxy/src/state/deposit_pool.rs`
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DepositPool {
+    pub bucket_id: u8,
+    pub amount_lamports: u64,
+    pub merkle_root: [u8; 32],
+    pub next_index: u64,
+    pub total_deposits: u64,
+    pub anonymity_set_size: u64,
+    pub historical_roots: [[u8; 32]; POOL_HISTORICAL_ROOTS],
+    pub historical_roots_index: u8,
+    pub bump: u8,
+    pub paused: bool,
+    pub pending_counter: u64,
+    pub max_deposits: u64,
+}
+
+impl DepositPool {
+    /// Anchor account discriminator: sha256("account:DepositPool")[..8]
+    pub fn discriminator() -> [u8; 8] {
+        Sha256::digest(b"account:DepositPool")[..8]
+            .try_into()
+            .unwrap()
+    }
+
+    /// Decode raw account data, trailing padding is ignored
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        let (discriminator, mut fields) = data
+            .split_first_chunk::<8>()
+            .ok_or_else(|| SdkError::Serialization("pool account data too short".into()))?;
+        if *discriminator != Self::discriminator() {
+            return Err(SdkError::Serialization(
+                "account is not a DepositPool".into(),
+            ));
+        }
+        Self::deserialize(&mut fields)
+            .map_err(|e| SdkError::Serialization(format!("invalid pool account: {}", e)))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolStats {
+    pub bucket_id: u8,
+    /// Denomination in lamports
+    pub amount_lamports: u64,
+    /// Deposits ever made, including withdrawn ones
+    pub total_deposits: u64,
+    /// Deposits not yet withdrawn, the set a withdrawal hides in
+    pub anonymity_set_size: u64,
+    /// Leaf index the next deposit is inserted at
+    pub next_index: u64,
+    pub current_root: [u8; 32],
+    /// Paused by the admin, deposits and withdrawals are refused
+    pub paused: bool,
+}
+
+impl PoolStats {
+    /// Decode raw `DepositPool` account data
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        DepositPool::from_account_data(data).map(Self::from)
+    }
+}
+
+impl From<DepositPool> for PoolStats {
+    fn from(pool: DepositPool) -> Self {
+        Self {
+            bucket_id: pool.bucket_id,
+            amount_lamports: pool.amount_lamports,
+            total_deposits: pool.total_deposits,
+            anonymity_set_size: pool.anonymity_set_size,
+            next_index: pool.next_index,
+            current_root: pool.merkle_root,
+            paused: pool.paused,
+        }
+    }
+}
+
+/// `DepositPool` PDA for a bucket
+pub fn pool_pda(program_id: &Pubkey, bucket_id: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool", &[bucket_id]], program_id).0
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<RpcResult>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct RpcResult {
+    value: Option<RpcAccount>,
+}
+
+#[derive(Deserialize)]
+struct RpcAccount {
+    /// `[base64 data, "base64"]`
+    data: (String, String),
+}
+
+/// Fetch a bucket's pool stats from `rpc_url` with `getAccountInfo`
+/// Goes through `client`, so a Tor client keeps the query off the user's IP
+pub async fn fetch_pool_stats(
+    client: &TorHttpClient,
+    rpc_url: &str,
+    program_id: &Pubkey,
+    bucket_id: u8,
+) -> Result<PoolStats> {
+    use base64::Engine;
+
+    let pda = pool_pda(program_id, bucket_id);
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getAccountInfo",
+        "params": [pda.to_string(), { "encoding": "base64" }],
+    });
+    let response: RpcResponse = client.post_json(rpc_url, &request).await?;
+    if let Some(error) = response.error {
+        return Err(SdkError::Rpc(error.to_string()));
+    }
+    let account = response
+        .result
+        .and_then(|result| result.value)
+        .ok_or_else(|| SdkError::Rpc(format!("pool for bucket {} not found", bucket_id)))?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(&account.data.0)
+        .map_err(|e| SdkError::Serialization(e.to_string()))?;
+    PoolStats::from_account_data(&data)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_server::serve_once;
+
+    /// DepositPool as the program stores it: discriminator, fields, padding
+    pub(crate) fn pool_account() -> Vec<u8> {
+        let mut data = DepositPool::discriminator().to_vec();
+        data.push(3); // bucket_id
+        data.extend_from_slice(&5_000_000_000u64.to_le_bytes()); // amount_lamports
+        data.extend_from_slice(&[7u8; 32]); // merkle_root
+        data.extend_from_slice(&42u64.to_le_bytes()); // next_index
+        data.extend_from_slice(&45u64.to_le_bytes()); // total_deposits
+        data.extend_from_slice(&40u64.to_le_bytes()); // anonymity_set_size
+        data.extend_from_slice(&[9u8; 64]); // historical_roots
+        data.push(1); // historical_roots_index
+        data.push(254); // bump
+        data.push(1); // paused
+        data.extend_from_slice(&4u64.to_le_bytes()); // pending_counter
+        data.extend_from_slice(&0u64.to_le_bytes()); // max_deposits
+        data.extend_from_slice(&[0u8; 47]);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_fetch_pool_stats() {
+        use base64::Engine;
+
+        let expected = PoolStats {
+            bucket_id: 3,
+            amount_lamports: 5_000_000_000,
+            total_deposits: 45,
+            anonymity_set_size: 40,
+            next_index: 42,
+            current_root: [7u8; 32],
+            paused: true,
+        };
+        assert_eq!(
+            PoolStats::from_account_data(&pool_account()).unwrap(),
+            expected
+        );
+        let mut other = pool_account();
+        other[0] ^= 1;
+        assert!(PoolStats::from_account_data(&other).is_err());
+        assert!(PoolStats::from_account_data(&pool_account()[..60]).is_err());
+
+        // Served by a one-shot JSON-RPC endpoint
+        let url = serve_once(
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 1 },
+                    "value": {
+                        "data": [base64::engine::general_purpose::STANDARD.encode(pool_account()), "base64"],
+                        "executable": false,
+                        "lamports": 1_000_000,
+                        "owner": Pubkey::new_unique().to_string(),
+                        "rentEpoch": 0,
+                    },
+                },
+            })
+            .to_string(),
+        )
+        .await;
+
+        let client = TorHttpClient::new_direct().unwrap();
+        let stats = fetch_pool_stats(&client, &url, &Pubkey::new_unique(), 3)
+            .await
+            .unwrap();
+        assert_eq!(stats, expected);
+    }
+}
@@ -0,0 +1,345 @@
+/// Local proof generation (`prover` feature)
+/// Proofs are produced by the snarkjs CLI against the compiled circuit, the same prover the
+/// frontend runs in WASM, so they verify against the program's verifying keys unchanged.
+/// The output is converted to the byte layout groth16-solana expects by `ZkProof::from_snarkjs`
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use rand::RngCore;
+use solana_sdk::pubkey::Pubkey;
+use tokio::process::Command;
+
+use crate::crypto::u64_to_field;
+use crate::crypto::{generate_nullifier_hash, validate_non_zero};
+use crate::deposit::DepositNote;
+use crate::error::{Result, SdkError};
+use crate::merkle::{MerkleProof, TREE_DEPTH};
+use crate::stealth::StealthAddress;
+use crate::withdrawal::{
+    OwnershipProofRequest, WithdrawalPublicInputs, WithdrawalRequest, ZkProof,
+};
+
+/// Compiled ownership circuit (`circuits/scripts/recompile_ownership.sh`)
+pub struct OwnershipProver {
+    circuit: Circuit,
+}
+
+impl OwnershipProver {
+    /// `ownership.wasm` and `ownership_final.zkey` from the circuit build, snarkjs from PATH
+    pub fn new(wasm_path: impl Into<PathBuf>, zkey_path: impl Into<PathBuf>) -> Self {
+        Self {
+            circuit: Circuit::new(wasm_path.into(), zkey_path.into()),
+        }
+    }
+
+    pub fn with_snarkjs(mut self, snarkjs: impl Into<PathBuf>) -> Self {
+        self.circuit.snarkjs = snarkjs.into();
+        self
+    }
+
+    /// Prove knowledge of `nullifier` for the pending withdrawal `pending_withdrawal_id`
+    /// (its on-chain `tx_id`). The binding hash is taken from the circuit's output
+    pub async fn prove(
+        &self,
+        nullifier: &[u8; 32],
+        pending_withdrawal_id: u64,
+    ) -> Result<OwnershipProofRequest> {
+        validate_non_zero(nullifier)?;
+        let nullifier_hash = generate_nullifier_hash(nullifier)?;
+
+        let input = serde_json::json!({
+            "nullifierHash": field_to_decimal(&nullifier_hash),
+            "pendingWithdrawalId": pending_withdrawal_id.to_string(),
+            "nullifier": field_to_decimal(nullifier),
+        });
+        let (proof, public_signals) = self.circuit.fullprove(&input).await?;
+
+        // Public signals: [bindingHash, nullifierHash, pendingWithdrawalId], outputs first
+        let [binding_hash, proven_nullifier_hash, proven_id] = parse_signals(&public_signals)?;
+        if proven_nullifier_hash != nullifier_hash
+            || proven_id != u64_to_field(pending_withdrawal_id)
+        {
+            return Err(SdkError::Prover(
+                "public signals don't match the requested withdrawal".into(),
+            ));
+        }
+
+        Ok(OwnershipProofRequest {
+            proof,
+            nullifier_hash,
+            pending_withdrawal_id,
+            binding_hash,
+        })
+    }
+}
+
+/// Compiled withdrawal circuit (`circuits/scripts/compile.sh`)
+pub struct WithdrawalProver {
+    circuit: Circuit,
+}
+
+impl WithdrawalProver {
+    /// `withdrawal.wasm` and `withdrawal_final.zkey` from the circuit build, snarkjs from PATH
+    pub fn new(wasm_path: impl Into<PathBuf>, zkey_path: impl Into<PathBuf>) -> Self {
+        Self {
+            circuit: Circuit::new(wasm_path.into(), zkey_path.into()),
+        }
+    }
+
+    pub fn with_snarkjs(mut self, snarkjs: impl Into<PathBuf>) -> Self {
+        self.circuit.snarkjs = snarkjs.into();
+        self
+    }
+
+    /// Prove `note` is a leaf of the tree with `root` (path `merkle_proof`) and bind its
+    /// withdrawal to `recipient`, `relayer` and the fee at `fee_bps`
+    /// The binding hash is taken from the circuit's output
+    pub async fn prove(
+        &self,
+        note: &DepositNote,
+        merkle_proof: &MerkleProof,
+        root: [u8; 32],
+        recipient: &StealthAddress,
+        relayer: Pubkey,
+        fee_bps: u16,
+    ) -> Result<WithdrawalRequest> {
+        if merkle_proof.siblings.len() != TREE_DEPTH
+            || merkle_proof.path_indices.len() != TREE_DEPTH
+        {
+            return Err(SdkError::MerkleTree(format!(
+                "proof must have {} levels",
+                TREE_DEPTH
+            )));
+        }
+        let mut public_inputs =
+            WithdrawalPublicInputs::new(note, root, recipient, relayer, fee_bps)?;
+
+        let input = serde_json::json!({
+            "root": field_to_decimal(&public_inputs.root),
+            "nullifierHash": field_to_decimal(&public_inputs.nullifier_hash),
+            "recipient": field_to_decimal(&public_inputs.recipient),
+            "amount": public_inputs.amount.to_string(),
+            "relayer": field_to_decimal(&public_inputs.relayer),
+            "fee": public_inputs.fee.to_string(),
+            "nullifier": field_to_decimal(&note.nullifier),
+            "secret": field_to_decimal(&note.secret),
+            "pathElements": merkle_proof.siblings.iter().map(field_to_decimal).collect::<Vec<_>>(),
+            "pathIndices": merkle_proof.path_indices,
+        });
+        let (proof, public_signals) = self.circuit.fullprove(&input).await?;
+
+        // Public signals come in `WITHDRAWAL_PUBLIC_SIGNALS` order, binding hash first
+        // A recipient or relayer outside the field would come back reduced, and no longer match
+        let signals = parse_signals::<7>(&public_signals)?;
+        if signals[1..] != public_inputs.to_ordered_array()[1..] {
+            return Err(SdkError::Prover(
+                "public signals don't match the requested withdrawal".into(),
+            ));
+        }
+        public_inputs.binding_hash = signals[0];
+
+        Ok(WithdrawalRequest {
+            proof,
+            public_inputs,
+        })
+    }
+}
+
+/// Circuit artifacts and the snarkjs binary that proves with them
+struct Circuit {
+    wasm_path: PathBuf,
+    zkey_path: PathBuf,
+    snarkjs: PathBuf,
+}
+
+impl Circuit {
+    fn new(wasm_path: PathBuf, zkey_path: PathBuf) -> Self {
+        Self {
+            wasm_path,
+            zkey_path,
+            snarkjs: PathBuf::from("snarkjs"),
+        }
+    }
+
+    /// Run `snarkjs groth16 fullprove` in a private scratch directory
+    /// The input holds the nullifier, so the directory is owner-only and removed afterwards
+    async fn fullprove(&self, input: &serde_json::Value) -> Result<(ZkProof, Vec<String>)> {
+        let mut suffix = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        let dir = std::env::temp_dir().join(format!("tracezero-prover-{}", hex::encode(suffix)));
+        create_private_dir(&dir)?;
+
+        let result = self.fullprove_in(&dir, input).await;
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    async fn fullprove_in(
+        &self,
+        dir: &Path,
+        input: &serde_json::Value,
+    ) -> Result<(ZkProof, Vec<String>)> {
+        let input_path = dir.join("input.json");
+        let proof_path = dir.join("proof.json");
+        let public_path = dir.join("public.json");
+        std::fs::write(&input_path, input.to_string())
+            .map_err(|e| SdkError::Prover(format!("failed to write circuit input: {}", e)))?;
+
+        let output = Command::new(&self.snarkjs)
+            .args(["groth16", "fullprove"])
+            .args([
+                &input_path,
+                &self.wasm_path,
+                &self.zkey_path,
+                &proof_path,
+                &public_path,
+            ])
+            .output()
+            .await
+            .map_err(|e| {
+                SdkError::Prover(format!("failed to run {}: {}", self.snarkjs.display(), e))
+            })?;
+        if !output.status.success() {
+            return Err(SdkError::Prover(format!(
+                "snarkjs exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        let read = |path: &Path| -> Result<String> {
+            std::fs::read_to_string(path)
+                .map_err(|e| SdkError::Prover(format!("missing {}: {}", path.display(), e)))
+        };
+        let proof = ZkProof::from_snarkjs(&read(&proof_path)?)?;
+        let public_signals = serde_json::from_str(&read(&public_path)?)
+            .map_err(|e| SdkError::Serialization(e.to_string()))?;
+        Ok((proof, public_signals))
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(dir)
+        .map_err(|e| SdkError::Prover(format!("failed to create {}: {}", dir.display(), e)))
+}
+
+fn to_bytes(value: impl BigInteger) -> [u8; 32] {
+    value
+        .to_bytes_be()
+        .try_into()
+        .expect("BN254 elements are 32 bytes")
+}
+
+/// Decode exactly `N` public signals
+fn parse_signals<const N: usize>(public_signals: &[String]) -> Result<[[u8; 32]; N]> {
+    public_signals
+        .iter()
+        .map(|signal| decimal_to_field(signal))
+        .collect::<Result<Vec<_>>>()?
+        .try_into()
+        .map_err(|signals: Vec<_>| {
+            SdkError::Prover(format!(
+                "expected {} public signals, got {}",
+                N,
+                signals.len()
+            ))
+        })
+}
+
+/// Big-endian field element as the decimal string circom inputs use
+fn field_to_decimal(value: &[u8; 32]) -> String {
+    Fr::from_be_bytes_mod_order(value).into_bigint().to_string()
+}
+
+/// Decimal public signal back to a big-endian field element
+fn decimal_to_field(value: &str) -> Result<[u8; 32]> {
+    let parsed = Fr::from_str(value)
+        .map_err(|_| SdkError::Prover(format!("invalid public signal {:?}", value)))?;
+    Ok(to_bytes(parsed.into_bigint()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_round_trip() {
+        let nullifier = crate::crypto::random_secret();
+        let decimal = field_to_decimal(&nullifier);
+        assert!(decimal.bytes().all(|b| b.is_ascii_digit()));
+        assert_eq!(decimal_to_field(&decimal).unwrap(), nullifier);
+        assert_eq!(field_to_decimal(&u64_to_field(42)), "42");
+    }
+
+    #[tokio::test]
+    async fn test_missing_snarkjs_is_reported() {
+        let prover = OwnershipProver::new("ownership.wasm", "ownership_final.zkey")
+            .with_snarkjs("/nonexistent/snarkjs");
+        let nullifier = crate::crypto::random_secret();
+        let result = prover.prove(&nullifier, 7).await;
+        assert!(matches!(result, Err(SdkError::Prover(_))));
+
+        // Zero nullifiers are refused before anything is written to disk
+        let result = prover.prove(&[0u8; 32], 7).await;
+        assert!(matches!(result, Err(SdkError::Crypto(_))));
+    }
+
+    #[tokio::test]
+    async fn test_withdrawal_prover_checks_inputs() {
+        use crate::merkle::MerkleTree;
+
+        let prover = WithdrawalProver::new("withdrawal.wasm", "withdrawal_final.zkey")
+            .with_snarkjs("/nonexistent/snarkjs");
+        let note = DepositNote::new(1_000_000_000);
+        let recipient = crate::stealth::StealthMaster::new().derive(0);
+
+        let mut tree = MerkleTree::new(TREE_DEPTH).unwrap();
+        tree.insert(note.commitment().unwrap()).unwrap();
+        let proof = tree.proof(0).unwrap();
+        let root = tree.root().unwrap();
+        let relayer = Pubkey::new_unique();
+        let result = prover
+            .prove(&note, &proof, root, &recipient, relayer, 50)
+            .await;
+        assert!(matches!(result, Err(SdkError::Prover(_))));
+
+        // A path for a shallower tree can't satisfy the circuit
+        let mut short = MerkleTree::new(4).unwrap();
+        short.insert(note.commitment().unwrap()).unwrap();
+        let result = prover
+            .prove(
+                &note,
+                &short.proof(0).unwrap(),
+                root,
+                &recipient,
+                relayer,
+                50,
+            )
+            .await;
+        assert!(matches!(result, Err(SdkError::MerkleTree(_))));
+
+        // Fees of the whole amount are refused before anything is written to disk
+        let result = prover
+            .prove(&note, &proof, root, &recipient, relayer, 10_000)
+            .await;
+        assert!(matches!(result, Err(SdkError::Crypto(_))));
+    }
+
+    #[test]
+    fn test_parse_signals() {
+        let signals = ["1".to_string(), "2".to_string()];
+        let [a, b] = parse_signals::<2>(&signals).unwrap();
+        assert_eq!(a, u64_to_field(1));
+        assert_eq!(b, u64_to_field(2));
+        assert!(matches!(
+            parse_signals::<3>(&signals),
+            Err(SdkError::Prover(_))
+        ));
+    }
+}
@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signer::Signer;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+use crate::crypto::is_field_element;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct StealthAddress {
@@ -34,23 +37,37 @@ impl StealthMaster {
         Self { secret }
     }
 
+    /// Derive the stealth address at `index`
+    ///
+    /// The address is the recipient public input of the withdrawal circuit, so it must be a
+    /// canonical BN254 field element as well as an ed25519 point (which any keypair gives).
+    /// Keys whose address isn't a field element are skipped by re-hashing with a counter
     pub fn derive(&self, index: u64) -> StealthAddress {
-        // Derive spending key: H(master || index)
-        let mut hasher = Sha256::new();
-        hasher.update(&self.secret);
-        hasher.update(&index.to_le_bytes());
-        let spending_key: [u8; 32] = hasher.finalize().into();
-
-        // Derive public key from spending key
-        let keypair =
-            solana_sdk::signer::keypair::keypair_from_seed(&spending_key).expect("Valid seed");
-        let address = keypair.pubkey();
-
-        StealthAddress {
-            address,
-            spending_key,
-            index,
+        for attempt in 0u32.. {
+            // Derive spending key: H(master || index), then H(master || index || attempt)
+            let mut hasher = Sha256::new();
+            hasher.update(self.secret);
+            hasher.update(index.to_le_bytes());
+            if attempt > 0 {
+                hasher.update(attempt.to_le_bytes());
+            }
+            let spending_key: [u8; 32] = hasher.finalize().into();
+
+            // Derive public key from spending key
+            let keypair =
+                solana_sdk::signer::keypair::keypair_from_seed(&spending_key).expect("Valid seed");
+            let address = keypair.pubkey();
+            if !is_field_element(&address.to_bytes()) {
+                continue;
+            }
+
+            return StealthAddress {
+                address,
+                spending_key,
+                index,
+            };
         }
+        unreachable!("ran out of derivation attempts")
     }
 
     /// Derive next unused stealth address
@@ -61,6 +78,20 @@ impl StealthMaster {
     pub fn export_secret(&self) -> [u8; 32] {
         self.secret
     }
+
+    /// X25519 secret that decrypts this user's on-chain notes
+    /// Derived from the master secret, so recovering the master recovers the notes
+    pub fn viewing_secret(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"tracezero-viewing-key");
+        hasher.update(self.secret);
+        hasher.finalize().into()
+    }
+
+    /// Public half of `viewing_secret`, notes are encrypted to it
+    pub fn viewing_pubkey(&self) -> [u8; 32] {
+        X25519PublicKey::from(&StaticSecret::from(self.viewing_secret())).to_bytes()
+    }
 }
 
 impl Default for StealthMaster {
@@ -103,6 +134,18 @@ mod tests {
         assert_eq!(keypair.pubkey(), addr1.address);
     }
 
+    #[test]
+    fn test_derived_addresses_are_field_elements() {
+        // Roughly 4 in 5 raw ed25519 keys are >= the BN254 modulus, so 64 indices
+        // exercise the retry path many times over
+        let master = StealthMaster::from_secret([7u8; 32]);
+        for index in 0..64 {
+            let stealth = master.derive(index);
+            assert!(is_field_element(&stealth.address.to_bytes()));
+            assert_eq!(stealth.keypair().pubkey(), stealth.address);
+        }
+    }
+
     #[test]
     fn test_master_restore() {
         let master1 = StealthMaster::new();
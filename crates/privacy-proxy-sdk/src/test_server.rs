@@ -0,0 +1,68 @@
+/// One-shot HTTP servers standing in for the relayer and Solana RPC in tests
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// Serve `body` as the JSON response to a single request, returning the server's URL
+pub async fn serve_once(body: impl Into<String>) -> String {
+    serve_once_with_status("200 OK", body).await
+}
+
+/// `serve_once` answering with `status`, e.g. "409 Conflict"
+pub async fn serve_once_with_status(status: &'static str, body: impl Into<String>) -> String {
+    spawn_server(vec![(status, body.into())]).await.0
+}
+
+/// Serve `bodies` in order, one request each, returning the server's URL and the raw
+/// requests as they arrive
+pub async fn serve_each(bodies: Vec<String>) -> (String, mpsc::UnboundedReceiver<String>) {
+    spawn_server(bodies.into_iter().map(|body| ("200 OK", body)).collect()).await
+}
+
+async fn spawn_server(
+    responses: Vec<(&'static str, String)>,
+) -> (String, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = tx.send(read_request(&mut stream).await);
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (url, rx)
+}
+
+/// Head and the body announced by Content-Length, or whatever came before the client hung up
+async fn read_request(stream: &mut TcpStream) -> String {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let text = String::from_utf8_lossy(&buf).to_string();
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text[..end]
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length: ")
+                        .map(str::to_string)
+                })
+                .map_or(0, |len| len.trim().parse::<usize>().unwrap());
+            if buf.len() >= end + 4 + length {
+                return text;
+            }
+        }
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return String::from_utf8_lossy(&buf).to_string(),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+}
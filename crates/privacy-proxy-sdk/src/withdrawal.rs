@@ -1,13 +1,17 @@
 /// User generates ZK proof that they know a valid deposit without revealing which one
+use ark_bn254::Fq;
+use ark_ff::{BigInteger, PrimeField};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serde_big_array::BigArray;
 use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
 
 use crate::crypto::{
     generate_nullifier_hash, generate_ownership_binding_hash, generate_withdrawal_binding_hash,
-    validate_fee, validate_non_zero,
+    is_field_element, u64_to_field, validate_fee, validate_non_zero,
 };
-use crate::deposit::DepositNote;
+use crate::deposit::{DepositNote, BUCKET_AMOUNTS};
 use crate::error::{Result, SdkError};
 use crate::merkle::MerkleProof;
 use crate::stealth::StealthAddress;
@@ -20,6 +24,18 @@ pub struct WithdrawalRequest {
     pub public_inputs: WithdrawalPublicInputs,
 }
 
+/// Withdrawal circuit public signals in snarkjs order (outputs first), the order
+/// `zk_verifier` feeds them to groth16. Mirrored in `circuits/fixtures/withdrawal_public_signals.txt`
+pub const WITHDRAWAL_PUBLIC_SIGNALS: [&str; 7] = [
+    "bindingHash",
+    "root",
+    "nullifierHash",
+    "recipient",
+    "amount",
+    "relayer",
+    "fee",
+];
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WithdrawalPublicInputs {
     /// Merkle root (proves deposit exists)
@@ -41,39 +57,21 @@ pub struct WithdrawalPublicInputs {
     pub binding_hash: [u8; 32],
 }
 
-/// ZK proof (Groth16)
-#[derive(Clone, Serialize, Deserialize)]
-pub struct ZkProof {
-    #[serde(with = "BigArray")]
-    pub a: [u8; 64],
-    #[serde(with = "BigArray")]
-    pub b: [u8; 128],
-    #[serde(with = "BigArray")]
-    pub c: [u8; 64],
-}
-
-/// Private inputs for proof generation (never sent to relayer)
-pub struct WithdrawalPrivateInputs {
-    /// Deposit note with secret and nullifier
-    pub note: DepositNote,
-    /// Merkle proof for the commitment
-    pub merkle_proof: MerkleProof,
-}
-
-impl WithdrawalRequest {
+impl WithdrawalPublicInputs {
+    /// Public inputs for withdrawing `note` against `root` to `recipient` through `relayer`
     pub fn new(
         note: &DepositNote,
-        _merkle_proof: &MerkleProof,
         root: [u8; 32],
         recipient: &StealthAddress,
         relayer: Pubkey,
-        fee: u64,
+        fee_bps: u16,
     ) -> Result<Self> {
         validate_non_zero(&note.nullifier)?;
         validate_non_zero(&note.secret)?;
         if note.amount == 0 {
             return Err(SdkError::Crypto("Amount must be non-zero".into()));
         }
+        let fee = compute_withdrawal_fee(note.amount, fee_bps);
         validate_fee(fee, note.amount)?;
 
         let nullifier_hash = generate_nullifier_hash(&note.nullifier)?;
@@ -83,7 +81,7 @@ impl WithdrawalRequest {
             &relayer.to_bytes(),
             fee,
         )?;
-        let public_inputs = WithdrawalPublicInputs {
+        Ok(Self {
             root,
             nullifier_hash,
             recipient: recipient.address.to_bytes(),
@@ -91,7 +89,161 @@ impl WithdrawalRequest {
             relayer: relayer.to_bytes(),
             fee,
             binding_hash,
+        })
+    }
+
+    /// Public inputs as 32-byte big-endian field elements, ordered as `WITHDRAWAL_PUBLIC_SIGNALS`
+    pub fn to_ordered_array(&self) -> [[u8; 32]; 7] {
+        [
+            self.binding_hash,
+            self.root,
+            self.nullifier_hash,
+            self.recipient,
+            u64_to_field(self.amount),
+            self.relayer,
+            u64_to_field(self.fee),
+        ]
+    }
+}
+
+/// ZK proof (Groth16)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ZkProof {
+    #[serde(with = "BigArray")]
+    pub a: [u8; 64],
+    #[serde(with = "BigArray")]
+    pub b: [u8; 128],
+    #[serde(with = "BigArray")]
+    pub c: [u8; 64],
+}
+
+impl ZkProof {
+    /// Parse a snarkjs `proof.json`: affine points as decimal strings, into the layout
+    /// groth16-solana expects (see `app/src/lib/zk/prover.ts`): big-endian coordinates,
+    /// A negated, B's Fq2 coefficients in (c1, c0) order
+    pub fn from_snarkjs(json: &str) -> Result<Self> {
+        let proof: SnarkjsProof =
+            serde_json::from_str(json).map_err(|e| SdkError::Serialization(e.to_string()))?;
+        if proof.pi_a[2] != "1" || proof.pi_b[2] != ["1", "0"] || proof.pi_c[2] != "1" {
+            return Err(SdkError::InvalidProof("proof points are not affine".into()));
+        }
+
+        let mut a = [0u8; 64];
+        a[..32].copy_from_slice(&fq_to_bytes(parse_fq(&proof.pi_a[0])?));
+        a[32..].copy_from_slice(&fq_to_bytes(-parse_fq(&proof.pi_a[1])?));
+
+        let mut b = [0u8; 128];
+        for (chunk, value) in b.chunks_exact_mut(32).zip([
+            &proof.pi_b[0][1],
+            &proof.pi_b[0][0],
+            &proof.pi_b[1][1],
+            &proof.pi_b[1][0],
+        ]) {
+            chunk.copy_from_slice(&fq_to_bytes(parse_fq(value)?));
+        }
+
+        let mut c = [0u8; 64];
+        c[..32].copy_from_slice(&fq_to_bytes(parse_fq(&proof.pi_c[0])?));
+        c[32..].copy_from_slice(&fq_to_bytes(parse_fq(&proof.pi_c[1])?));
+
+        Ok(Self { a, b, c })
+    }
+
+    /// Inverse of `from_snarkjs`, the `proof.json` snarkjs would have written for this proof
+    pub fn to_snarkjs(&self) -> Result<String> {
+        let coordinate =
+            |bytes: &[u8]| -> Result<String> { Ok(bytes_to_fq(bytes)?.into_bigint().to_string()) };
+        let proof = SnarkjsProof {
+            pi_a: [
+                coordinate(&self.a[..32])?,
+                (-bytes_to_fq(&self.a[32..])?).into_bigint().to_string(),
+                "1".into(),
+            ],
+            pi_b: [
+                [coordinate(&self.b[32..64])?, coordinate(&self.b[..32])?],
+                [coordinate(&self.b[96..])?, coordinate(&self.b[64..96])?],
+                ["1".into(), "0".into()],
+            ],
+            pi_c: [
+                coordinate(&self.c[..32])?,
+                coordinate(&self.c[32..])?,
+                "1".into(),
+            ],
+            protocol: "groth16".into(),
+            curve: "bn128".into(),
         };
+        serde_json::to_string(&proof).map_err(|e| SdkError::Serialization(e.to_string()))
+    }
+}
+
+/// `proof.json` as written by snarkjs: projective coordinates as decimal strings
+#[derive(Serialize, Deserialize)]
+struct SnarkjsProof {
+    pi_a: [String; 3],
+    pi_b: [[String; 2]; 3],
+    pi_c: [String; 3],
+    #[serde(default)]
+    protocol: String,
+    #[serde(default)]
+    curve: String,
+}
+
+fn parse_fq(value: &str) -> Result<Fq> {
+    // Fq::from_str reduces silently, so reject anything that isn't canonical
+    let parsed = Fq::from_str(value)
+        .map_err(|_| SdkError::InvalidProof(format!("invalid coordinate {:?}", value)))?;
+    if parsed.into_bigint().to_string() != value {
+        return Err(SdkError::InvalidProof(format!(
+            "coordinate {:?} out of range",
+            value
+        )));
+    }
+    Ok(parsed)
+}
+
+fn fq_to_bytes(value: Fq) -> [u8; 32] {
+    value
+        .into_bigint()
+        .to_bytes_be()
+        .try_into()
+        .expect("BN254 elements are 32 bytes")
+}
+
+/// Big-endian coordinate back to a base field element, refusing non-canonical encodings
+fn bytes_to_fq(bytes: &[u8]) -> Result<Fq> {
+    let value = Fq::from_be_bytes_mod_order(bytes);
+    if fq_to_bytes(value) != bytes {
+        return Err(SdkError::InvalidProof("coordinate out of range".into()));
+    }
+    Ok(value)
+}
+
+/// Private inputs for proof generation (never sent to relayer)
+pub struct WithdrawalPrivateInputs {
+    /// Deposit note with secret and nullifier
+    pub note: DepositNote,
+    /// Merkle proof for the commitment
+    pub merkle_proof: MerkleProof,
+}
+
+/// Fee the program deducts from a withdrawal of `amount` at `fee_bps`
+/// MUST match request_withdrawal: `amount * fee_bps / 10000`, rounded down.
+/// The program multiplies in checked u64; a u128 intermediate gives the same result
+/// wherever that doesn't overflow, so the proof's bound fee always matches on-chain
+pub fn compute_withdrawal_fee(amount: u64, fee_bps: u16) -> u64 {
+    (amount as u128 * fee_bps as u128 / 10_000) as u64
+}
+
+impl WithdrawalRequest {
+    pub fn new(
+        note: &DepositNote,
+        _merkle_proof: &MerkleProof,
+        root: [u8; 32],
+        recipient: &StealthAddress,
+        relayer: Pubkey,
+        fee_bps: u16,
+    ) -> Result<Self> {
+        let public_inputs = WithdrawalPublicInputs::new(note, root, recipient, relayer, fee_bps)?;
 
         // ZK proof generation happens in the FRONTEND using WASM (snarkjs)
         // The SDK is used by the relayer to validate proofs, not generate them
@@ -105,10 +257,10 @@ impl WithdrawalRequest {
         #[cfg(not(test))]
         {
             let _ = public_inputs; // Suppress unused warning
-            return Err(SdkError::Crypto(
+            Err(SdkError::Crypto(
                 "Cannot generate ZK proof in SDK. Proofs must be generated in frontend using WASM. Use deserialization instead."
                     .into(),
-            ));
+            ))
         }
 
         #[cfg(test)]
@@ -122,6 +274,79 @@ impl WithdrawalRequest {
         serde_json::to_vec(self).map_err(|e| SdkError::Serialization(e.to_string()))
     }
 
+    /// Render the proof and public inputs for support tickets
+    /// Values that could link a user to this withdrawal (nullifier hash, recipient, binding hash)
+    /// are truncated, the request itself never holds the note secret or nullifier
+    pub fn debug_dump(&self) -> String {
+        use std::fmt::Write;
+
+        let inputs = &self.public_inputs;
+        let field = |value: &[u8; 32]| {
+            if is_field_element(value) {
+                "ok"
+            } else {
+                "NOT A FIELD ELEMENT"
+            }
+        };
+        let bucket = match BUCKET_AMOUNTS.iter().position(|&a| a == inputs.amount) {
+            Some(id) => format!(
+                "bucket {}, {} SOL",
+                id,
+                inputs.amount as f64 / 1_000_000_000.0
+            ),
+            None => "no matching bucket".to_string(),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(out, "WithdrawalRequest");
+        let _ = writeln!(out, "  public_inputs:");
+        let _ = writeln!(
+            out,
+            "    root:           {} [field: {}]",
+            hex::encode(inputs.root),
+            field(&inputs.root)
+        );
+        // (name, value, linkable)
+        for (name, value, linkable) in [
+            ("nullifier_hash", &inputs.nullifier_hash, true),
+            ("recipient", &inputs.recipient, true),
+            ("relayer", &inputs.relayer, false),
+            ("binding_hash", &inputs.binding_hash, true),
+        ] {
+            let shown = if linkable {
+                truncate_hex(value)
+            } else {
+                hex::encode(value)
+            };
+            let _ = writeln!(
+                out,
+                "    {:<15} {} [field: {}]",
+                format!("{}:", name),
+                shown,
+                field(value)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "    amount:         {} lamports ({})",
+            inputs.amount, bucket
+        );
+        let _ = writeln!(out, "    fee:            {} lamports", inputs.fee);
+        let _ = writeln!(out, "  proof:");
+        let _ = writeln!(out, "    a: {}", hex::encode(self.proof.a));
+        let _ = writeln!(out, "    b: {}", hex::encode(self.proof.b));
+        let _ = writeln!(out, "    c: {}", hex::encode(self.proof.c));
+        let _ = write!(
+            out,
+            "  validate: {}",
+            match self.validate() {
+                Ok(()) => "ok".to_string(),
+                Err(e) => e.to_string(),
+            }
+        );
+        out
+    }
+
     /// Validate the request matches circuit constraints
     /// We do NOT validate the binding hash here because it's computed by
     /// the circuit using circomlibjs Poseidon, which may differ from the Rust
@@ -153,6 +378,114 @@ impl WithdrawalRequest {
     }
 }
 
+/// Program-wide withdrawal delay bounds, used until the relayer's own are known
+pub const MIN_DELAY_HOURS: u8 = 0;
+pub const MAX_DELAY_HOURS: u8 = 24;
+
+/// Choices for a withdrawal that aren't part of its proof
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WithdrawalOptions {
+    /// Hours the withdrawal stays timelocked before the relayer may execute it,
+    /// `None` picks one with `random_delay`
+    pub delay_hours: Option<u8>,
+}
+
+impl WithdrawalOptions {
+    pub fn with_delay_hours(delay_hours: u8) -> Self {
+        Self {
+            delay_hours: Some(delay_hours),
+        }
+    }
+
+    /// The delay to submit: the chosen one if it's within `min..=max`, a random one otherwise
+    pub fn resolve_delay(&self, min: u8, max: u8) -> Result<u8> {
+        match self.delay_hours {
+            Some(hours) => validate_delay(hours, min, max).map(|_| hours),
+            None => Ok(random_delay(min, max)),
+        }
+    }
+}
+
+/// Smallest anonymity set `AnonymityGuard::default()` withdraws from
+pub const DEFAULT_MIN_ANONYMITY_SET: u64 = 10;
+
+/// How many unspent deposits a pool must hold before `submit_withdrawal_guarded` withdraws
+/// from it. With only a handful, the withdrawal is easy to tie back to its deposit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnonymityGuard {
+    pub min_set_size: u64,
+    /// Withdraw anyway, without checking the pool
+    pub allow_weak: bool,
+}
+
+impl Default for AnonymityGuard {
+    fn default() -> Self {
+        Self {
+            min_set_size: DEFAULT_MIN_ANONYMITY_SET,
+            allow_weak: false,
+        }
+    }
+}
+
+impl AnonymityGuard {
+    pub fn with_min_set_size(min_set_size: u64) -> Self {
+        Self {
+            min_set_size,
+            allow_weak: false,
+        }
+    }
+
+    /// Skip the check, for a user who accepts the weaker privacy
+    pub fn allow_weak() -> Self {
+        Self {
+            allow_weak: true,
+            ..Self::default()
+        }
+    }
+
+    /// Refuse a pool of `set_size` unspent deposits if it's below the minimum
+    pub fn check(&self, set_size: u64) -> Result<()> {
+        if !self.allow_weak && set_size < self.min_set_size {
+            return Err(SdkError::WeakAnonymity { set_size });
+        }
+        Ok(())
+    }
+}
+
+/// Uniformly random delay in `min..=max` hours
+///
+/// If every user took the same delay (say the minimum), a withdrawal would land a fixed
+/// time after its deposit and the two could be matched by timing alone. Spreading delays
+/// over the whole allowed range mixes each withdrawal with deposits from many different
+/// hours, which keeps the timing anonymity set as large as the relayer permits
+pub fn random_delay(min: u8, max: u8) -> u8 {
+    if min >= max {
+        return min;
+    }
+    rand::thread_rng().gen_range(min..=max)
+}
+
+/// Check `hours` against the delay bounds (`min_delay_hours`/`max_delay_hours` from the
+/// relayer params), the program would reject it only after a full round-trip
+pub fn validate_delay(hours: u8, min: u8, max: u8) -> Result<()> {
+    if !(min..=max).contains(&hours) {
+        return Err(SdkError::InvalidInput(format!(
+            "Delay of {} hours outside the allowed {}..={} hours",
+            hours, min, max
+        )));
+    }
+    Ok(())
+}
+
+/// Body of `POST /withdraw`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WithdrawalSubmission {
+    pub request: WithdrawalRequest,
+    pub delay_hours: u8,
+    /// Bucket the note was deposited into, when the client knows it
+    pub bucket_id: Option<u8>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct WithdrawalResponse {
     /// Whether withdrawal was successful
@@ -163,6 +496,34 @@ pub struct WithdrawalResponse {
     pub error: Option<String>,
 }
 
+/// Lifecycle of a submitted withdrawal as tracked by the relayer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalState {
+    /// Timelocked, or waiting for the relayer's next execution poll
+    Pending,
+    Executed,
+    /// Cancelled by its owner, funds stayed in the pool
+    Cancelled,
+}
+
+/// Response of `GET /withdraw/status/:nullifier_hash`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WithdrawalStatusResponse {
+    pub status: WithdrawalState,
+    /// Unix timestamp after which the relayer may execute
+    pub execute_after: i64,
+    /// Execution transaction, set once `status` is `executed`
+    pub tx_signature: Option<String>,
+}
+
+/// Response of `GET /nullifier/:nullifier_hash`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NullifierStatusResponse {
+    /// A withdrawal of this note already executed
+    pub spent: bool,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct OwnershipProofRequest {
     /// ZK proof (Groth16)
@@ -195,10 +556,10 @@ impl OwnershipProofRequest {
         #[cfg(not(test))]
         {
             let _ = (nullifier_hash, binding_hash); // Suppress unused warnings
-            return Err(SdkError::Crypto(
-                "Cannot generate ZK proof in SDK. Proofs must be generated in frontend using WASM. Use deserialization instead."
+            Err(SdkError::Crypto(
+                "Cannot generate ZK proof here. Use prover::OwnershipProver (prover feature) or deserialize a frontend proof."
                     .into(),
-            ));
+            ))
         }
 
         #[cfg(test)]
@@ -220,12 +581,133 @@ impl OwnershipProofRequest {
     }
 }
 
+/// First and last 4 bytes of a 32-byte value, enough to match against on-chain data
+fn truncate_hex(value: &[u8; 32]) -> String {
+    format!("{}…{}", hex::encode(&value[..4]), hex::encode(&value[28..]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::merkle::MerkleTree;
     use crate::stealth::StealthMaster;
 
+    /// BN254 base field modulus
+    const P: &str = "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+    const MINUS_TWO_HEX: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45";
+
+    #[test]
+    fn test_anonymity_guard() {
+        let guard = AnonymityGuard::default();
+        assert!(guard.check(DEFAULT_MIN_ANONYMITY_SET).is_ok());
+        assert!(matches!(
+            guard.check(1),
+            Err(SdkError::WeakAnonymity { set_size: 1 })
+        ));
+        assert!(AnonymityGuard::with_min_set_size(0).check(0).is_ok());
+        assert!(AnonymityGuard::allow_weak().check(0).is_ok());
+    }
+
+    #[test]
+    fn test_public_inputs_order() {
+        let inputs = WithdrawalPublicInputs {
+            root: [2; 32],
+            nullifier_hash: [3; 32],
+            recipient: [4; 32],
+            amount: 5,
+            relayer: [6; 32],
+            fee: 7,
+            binding_hash: [1; 32],
+        };
+        let ordered = inputs.to_ordered_array();
+        assert_eq!(ordered[0], inputs.binding_hash);
+        assert_eq!(ordered[1], inputs.root);
+        assert_eq!(ordered[2], inputs.nullifier_hash);
+        assert_eq!(ordered[3], inputs.recipient);
+        assert_eq!(ordered[4], u64_to_field(5));
+        assert_eq!(ordered[5], inputs.relayer);
+        assert_eq!(ordered[6], u64_to_field(7));
+
+        // Same order the verifier's fixture pins
+        let fixture: Vec<_> =
+            include_str!("../../../circuits/fixtures/withdrawal_public_signals.txt")
+                .lines()
+                .collect();
+        assert_eq!(fixture, WITHDRAWAL_PUBLIC_SIGNALS);
+    }
+
+    fn snarkjs_json(pi_a: [&str; 3], pi_b: [[&str; 2]; 3], pi_c: [&str; 3]) -> String {
+        serde_json::json!({
+            "pi_a": pi_a,
+            "pi_b": pi_b,
+            "pi_c": pi_c,
+            "protocol": "groth16",
+            "curve": "bn128",
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_snarkjs_proof_layout() {
+        let json = snarkjs_json(
+            ["1", "2", "1"],
+            [["3", "4"], ["5", "6"], ["1", "0"]],
+            ["7", "8", "1"],
+        );
+        let zk = ZkProof::from_snarkjs(&json).unwrap();
+
+        assert_eq!(zk.a[..32], u64_to_field(1));
+        // p - 2
+        let mut minus_two = [0u8; 32];
+        minus_two.copy_from_slice(&hex::decode(MINUS_TWO_HEX).unwrap());
+        assert_eq!(zk.a[32..], minus_two);
+
+        for (i, expected) in [4, 3, 6, 5].into_iter().enumerate() {
+            assert_eq!(zk.b[i * 32..(i + 1) * 32], u64_to_field(expected));
+        }
+        assert_eq!(zk.c[..32], u64_to_field(7));
+        assert_eq!(zk.c[32..], u64_to_field(8));
+
+        // And back to exactly what snarkjs wrote
+        let round_trip: serde_json::Value =
+            serde_json::from_str(&zk.to_snarkjs().unwrap()).unwrap();
+        let original: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, original);
+        assert_eq!(
+            ZkProof::from_snarkjs(&zk.to_snarkjs().unwrap()).unwrap().a,
+            zk.a
+        );
+    }
+
+    #[test]
+    fn test_snarkjs_proof_rejects_bad_points() {
+        let projective = snarkjs_json(
+            ["1", "2", "3"],
+            [["3", "4"], ["5", "6"], ["1", "0"]],
+            ["7", "8", "1"],
+        );
+        assert!(ZkProof::from_snarkjs(&projective).is_err());
+        let out_of_range = snarkjs_json(
+            [P, "2", "1"],
+            [["3", "4"], ["5", "6"], ["1", "0"]],
+            ["7", "8", "1"],
+        );
+        assert!(ZkProof::from_snarkjs(&out_of_range).is_err());
+        assert!(ZkProof::from_snarkjs("{}").is_err());
+
+        assert!(parse_fq(P).is_err());
+        assert!(parse_fq("not a number").is_err());
+        assert_eq!(fq_to_bytes(parse_fq("0").unwrap()), [0u8; 32]);
+
+        // Non-canonical bytes can't be turned back into a proof.json
+        let proof = ZkProof {
+            a: [0xff; 64],
+            b: [0u8; 128],
+            c: [0u8; 64],
+        };
+        assert!(proof.to_snarkjs().is_err());
+    }
+
     #[test]
     fn test_withdrawal_request() {
         let note = DepositNote::new(1_000_000_000);
@@ -236,21 +718,117 @@ mod tests {
         let root = tree.root().unwrap();
         let proof = tree.proof(0).unwrap();
 
-        let master = StealthMaster::new();
-        let stealth = master.derive(0);
+        // Derived stealth addresses are always valid BN254 field elements
+        let stealth = StealthMaster::new().derive(0);
 
         let relayer = Pubkey::new_unique();
-        let request =
-            WithdrawalRequest::new(&note, &proof, root, &stealth, relayer, 10000).unwrap();
+        let request = WithdrawalRequest::new(&note, &proof, root, &stealth, relayer, 50).unwrap();
 
         assert_eq!(request.public_inputs.recipient, stealth.address.to_bytes());
-        assert_eq!(request.public_inputs.fee, 10000);
+        assert_eq!(request.public_inputs.fee, 5_000_000);
         assert!(request.validate().is_ok());
 
         // Verify binding hash is non-zero
         assert!(request.public_inputs.binding_hash.iter().any(|&b| b != 0));
     }
 
+    #[test]
+    fn test_validate_delay() {
+        assert!(validate_delay(1, MIN_DELAY_HOURS, MAX_DELAY_HOURS).is_ok());
+        assert!(validate_delay(0, 0, 24).is_ok());
+        assert!(validate_delay(24, 0, 24).is_ok());
+        assert!(matches!(
+            validate_delay(25, 0, 24),
+            Err(SdkError::InvalidInput(_))
+        ));
+        // A relayer with a stricter minimum than the program's
+        assert!(validate_delay(1, 2, 24).is_err());
+        assert!(WithdrawalOptions::with_delay_hours(1)
+            .resolve_delay(2, 24)
+            .is_err());
+        assert_eq!(
+            WithdrawalOptions::with_delay_hours(5)
+                .resolve_delay(2, 24)
+                .unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_random_delay_covers_range() {
+        let mut seen = [false; 7];
+        for _ in 0..1_000 {
+            let hours = random_delay(2, 8);
+            assert!((2..=8).contains(&hours));
+            seen[(hours - 2) as usize] = true;
+        }
+        assert!(
+            seen.iter().all(|&s| s),
+            "not every delay was picked: {:?}",
+            seen
+        );
+
+        assert_eq!(random_delay(6, 6), 6);
+        // Unset delays are drawn from the relayer's range, not fixed
+        let options = WithdrawalOptions::default();
+        assert_eq!(options.delay_hours, None);
+        for _ in 0..100 {
+            let hours = options.resolve_delay(3, 12).unwrap();
+            assert!((3..=12).contains(&hours));
+        }
+    }
+
+    #[test]
+    fn test_debug_dump() {
+        let note = DepositNote::new(1_000_000_000);
+        let mut tree = MerkleTree::new(4).unwrap();
+        tree.insert(note.commitment().unwrap()).unwrap();
+        let root = tree.root().unwrap();
+        let proof = tree.proof(0).unwrap();
+
+        let master = StealthMaster::new();
+        let stealth = (0..)
+            .map(|i| master.derive(i))
+            .find(|s| s.address.to_bytes()[0] < 0x20)
+            .unwrap();
+        let request =
+            WithdrawalRequest::new(&note, &proof, root, &stealth, Pubkey::new_unique(), 50)
+                .unwrap();
+
+        let dump = request.debug_dump();
+        for expected in [
+            "root:",
+            "nullifier_hash:",
+            "recipient:",
+            "relayer:",
+            "binding_hash:",
+            "amount:         1000000000 lamports (bucket 2, 1 SOL)",
+            "fee:            5000000 lamports",
+            "a: ",
+            "b: ",
+            "c: ",
+            "validate: ok",
+        ] {
+            assert!(
+                dump.contains(expected),
+                "missing {:?} in:\n{}",
+                expected,
+                dump
+            );
+        }
+        assert!(dump.contains(&hex::encode(root)));
+        assert!(!dump.contains("NOT A FIELD ELEMENT"));
+
+        // Linkable values are truncated, note secrets never appear
+        let inputs = &request.public_inputs;
+        assert!(!dump.contains(&hex::encode(inputs.nullifier_hash)));
+        assert!(!dump.contains(&hex::encode(inputs.recipient)));
+        assert!(!dump.contains(&hex::encode(inputs.binding_hash)));
+        assert!(dump.contains(&hex::encode(&inputs.nullifier_hash[..4])));
+        assert!(!dump.contains(&hex::encode(note.secret)));
+        assert!(!dump.contains(&hex::encode(note.nullifier)));
+    }
+
     #[test]
     fn test_fee_validation() {
         let note = DepositNote::new(1_000_000_000);
@@ -267,27 +845,41 @@ mod tests {
 
         // Fee >= amount should fail
         let result = WithdrawalRequest::new(
-            &note,
-            &proof,
-            root,
-            &stealth,
-            relayer,
-            note.amount, // fee == amount
+            &note, &proof, root, &stealth, relayer, 10_000, // fee == amount
         );
         assert!(result.is_err());
 
         // Fee > amount should fail
         let result = WithdrawalRequest::new(
-            &note,
-            &proof,
-            root,
-            &stealth,
-            relayer,
-            note.amount + 1, // fee > amount
+            &note, &proof, root, &stealth, relayer, 10_001, // fee > amount
         );
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_compute_withdrawal_fee_matches_program() {
+        // request_withdrawal: amount.checked_mul(fee_bps as u64)?.checked_div(10000)?
+        fn program_fee(amount: u64, fee_bps: u16) -> Option<u64> {
+            amount.checked_mul(fee_bps as u64)?.checked_div(10000)
+        }
+
+        for &amount in BUCKET_AMOUNTS.iter() {
+            for fee_bps in [0u16, 1, 7, 25, 50, 99, 100, 333, 500, 1000, 9999, 10000] {
+                assert_eq!(
+                    Some(compute_withdrawal_fee(amount, fee_bps)),
+                    program_fee(amount, fee_bps),
+                    "amount {} fee_bps {}",
+                    amount,
+                    fee_bps
+                );
+            }
+        }
+
+        // Rounds down like the program
+        assert_eq!(compute_withdrawal_fee(19_999, 5), 9);
+        assert_eq!(compute_withdrawal_fee(1_999, 50), 9);
+    }
+
     #[test]
     fn test_ownership_proof_binding() {
         let nullifier = crate::crypto::random_secret();
@@ -0,0 +1,60 @@
+/// Bearer-token auth for operator-only endpoints
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::error::RelayerError;
+use crate::server::RelayerState;
+
+/// Reject requests without `Authorization: Bearer <ADMIN_TOKEN>`
+/// With no ADMIN_TOKEN configured every admin request is refused
+pub async fn require_admin(
+    State(state): State<Arc<RelayerState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, RelayerError> {
+    let header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+
+    if !is_authorized(header, state.config.admin_token.as_deref()) {
+        return Err(RelayerError::Unauthorized);
+    }
+    Ok(next.run(request).await)
+}
+
+fn is_authorized(header: Option<&str>, admin_token: Option<&str>) -> bool {
+    let (Some(header), Some(admin_token)) = (header, admin_token) else {
+        return false;
+    };
+    let Some(token) = header.strip_prefix("Bearer ") else {
+        return false;
+    };
+    // Compare digests so timing doesn't reveal how much of the token matched
+    Sha256::digest(token.trim().as_bytes()) == Sha256::digest(admin_token.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_authorized() {
+        let token = Some("s3cret");
+        assert!(is_authorized(Some("Bearer s3cret"), token));
+
+        assert!(!is_authorized(None, token));
+        assert!(!is_authorized(Some("Bearer wrong"), token));
+        assert!(!is_authorized(Some("s3cret"), token));
+        assert!(!is_authorized(Some("Basic s3cret"), token));
+        // Admin routes stay locked when no token is configured
+        assert!(!is_authorized(Some("Bearer "), None));
+        assert!(!is_authorized(Some("Bearer s3cret"), None));
+    }
+}
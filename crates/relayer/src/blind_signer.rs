@@ -1,13 +1,16 @@
 /// Signs blinded tokens without seeing the actual token value
 /// RSA keypair is saved to disk to survive restarts. This ensures credits purchased before a restart remain valid
+/// Keys can be rotated: retired public keys keep verifying credits until their grace window expires
 use rsa::pkcs8::{DecodePrivateKey, EncodePrivateKey};
 use rsa::{
     traits::{PrivateKeyParts, PublicKeyParts},
     RsaPrivateKey, RsaPublicKey,
 };
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use tracing::{info, warn};
 
@@ -17,18 +20,103 @@ type BigUint = rsa::BigUint;
 
 const DEFAULT_RSA_KEY_PATH: &str = "rsa_signing_key.der";
 
+/// Retired public keys are stored next to the signing key with this extension
+const RETIRED_KEYS_EXTENSION: &str = "retired.json";
+
+/// A public key that no longer signs but still verifies credits until `expires_at`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RetiredKey {
+    /// RSA modulus N (hex)
+    pub n: String,
+    /// RSA exponent E (hex)
+    pub e: String,
+    /// Unix timestamp after which credits signed by this key are rejected
+    pub expires_at: u64,
+}
+
+impl RetiredKey {
+    fn public_key(&self) -> Result<RsaPublicKey> {
+        let n = hex::decode(&self.n)
+            .map_err(|e| RelayerError::Crypto(format!("Invalid retired key N: {}", e)))?;
+        let e = hex::decode(&self.e)
+            .map_err(|e| RelayerError::Crypto(format!("Invalid retired key E: {}", e)))?;
+        RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e))
+            .map_err(|e| RelayerError::Crypto(format!("Invalid retired key: {}", e)))
+    }
+}
+
+/// A currently valid verification key, as exposed to clients
+pub struct ValidKey {
+    pub n: Vec<u8>,
+    pub e: Vec<u8>,
+    /// None for the current signing key
+    pub expires_at: Option<u64>,
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Verify `signature` over SHA-256(`message`) with a raw RSA public key
+fn verify_with_key(public_key: &RsaPublicKey, message: &[u8], signature: &[u8]) -> bool {
+    let hash = Sha256::digest(message);
+    let m = BigUint::from_bytes_be(&hash);
+
+    // Verify: m == s^e mod n
+    let s = BigUint::from_bytes_be(signature);
+    s.modpow(public_key.e(), public_key.n()) == m
+}
+
+/// CRT form of the private exponent: two half-size exponentiations instead of one full one
+struct CrtParams {
+    p: BigUint,
+    q: BigUint,
+    /// d mod (p - 1)
+    dp: BigUint,
+    /// d mod (q - 1)
+    dq: BigUint,
+    /// q^-1 mod p
+    qinv: BigUint,
+}
+
+impl CrtParams {
+    /// None for multi-prime keys, which we never generate
+    fn new(private_key: &RsaPrivateKey) -> Option<Self> {
+        let [p, q] = private_key.primes() else {
+            return None;
+        };
+        let one = BigUint::from(1u8);
+        Some(Self {
+            dp: private_key.d() % (p - &one),
+            dq: private_key.d() % (q - &one),
+            qinv: private_key.crt_coefficient()?,
+            p: p.clone(),
+            q: q.clone(),
+        })
+    }
+
+    /// m^d mod n via Garner's recombination
+    fn modpow(&self, m: &BigUint) -> BigUint {
+        let m1 = (m % &self.p).modpow(&self.dp, &self.p);
+        let m2 = (m % &self.q).modpow(&self.dq, &self.q);
+        let h = (&self.qinv * ((m1 + &self.p - (&m2 % &self.p)) % &self.p)) % &self.p;
+        m2 + h * &self.q
+    }
+}
+
 pub struct BlindSigner {
     private_key: RsaPrivateKey,
     public_key: RsaPublicKey,
+    crt: Option<CrtParams>,
 }
 
 impl BlindSigner {
-    pub fn new_or_load(key_bits: usize) -> Result<Self> {
-        let key_path = std::env::var("RSA_KEY_PATH")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from(DEFAULT_RSA_KEY_PATH));
+    pub fn new_or_load(key_bits: usize, key_path: &Path) -> Result<Self> {
         if key_path.exists() {
-            match Self::load_from_file(&key_path) {
+            match Self::load_from_file(key_path) {
                 Ok(signer) => {
                     info!("Loaded RSA keypair from {}", key_path.display());
                     return Ok(signer);
@@ -41,7 +129,7 @@ impl BlindSigner {
         }
 
         let signer = Self::new(key_bits)?;
-        if let Err(e) = signer.save_to_file(&key_path) {
+        if let Err(e) = signer.save_to_file(key_path) {
             warn!("Failed to save RSA key to {}: {}", key_path.display(), e);
         } else {
             info!("Saved RSA keypair to {}", key_path.display());
@@ -54,23 +142,29 @@ impl BlindSigner {
         let mut rng = rand::thread_rng();
         let private_key = RsaPrivateKey::new(&mut rng, key_bits)
             .map_err(|e| RelayerError::Crypto(format!("Failed to generate RSA key: {}", e)))?;
-        let public_key = RsaPublicKey::from(&private_key);
 
         info!("Generated RSA-{} keypair for blind signatures", key_bits);
 
-        Ok(Self {
+        Ok(Self::from_private_key(private_key))
+    }
+
+    fn from_private_key(private_key: RsaPrivateKey) -> Self {
+        let public_key = RsaPublicKey::from(&private_key);
+        let crt = CrtParams::new(&private_key);
+        Self {
             private_key,
             public_key,
-        })
+            crt,
+        }
     }
 
-    fn load_from_file(path: &PathBuf) -> Result<Self> {
+    fn load_from_file(path: &Path) -> Result<Self> {
         let bytes = std::fs::read(path)
             .map_err(|e| RelayerError::Crypto(format!("Failed to read key file: {}", e)))?;
         Self::from_private_key_bytes(&bytes)
     }
 
-    fn save_to_file(&self, path: &PathBuf) -> Result<()> {
+    fn save_to_file(&self, path: &Path) -> Result<()> {
         let bytes = self
             .private_key
             .to_pkcs8_der()
@@ -83,12 +177,7 @@ impl BlindSigner {
     pub fn from_private_key_bytes(bytes: &[u8]) -> Result<Self> {
         let private_key = RsaPrivateKey::from_pkcs8_der(bytes)
             .map_err(|e| RelayerError::Crypto(format!("Invalid private key: {}", e)))?;
-        let public_key = RsaPublicKey::from(&private_key);
-
-        Ok(Self {
-            private_key,
-            public_key,
-        })
+        Ok(Self::from_private_key(private_key))
     }
 
     /// Get the public key for clients
@@ -107,9 +196,9 @@ impl BlindSigner {
         self.public_key.e().to_bytes_be()
     }
 
+    /// CPU-heavy (a full-size modpow), call from a blocking thread
     pub fn sign_blinded(&self, blinded_message: &[u8]) -> Result<Vec<u8>> {
         let n = self.private_key.n();
-        let d = self.private_key.d();
 
         let m_blind = BigUint::from_bytes_be(blinded_message);
         if m_blind >= *n {
@@ -117,55 +206,181 @@ impl BlindSigner {
         }
 
         // Sign: s' = m'^d mod n
-        let s_blind = m_blind.modpow(d, n);
+        let s_blind = match &self.crt {
+            Some(crt) => crt.modpow(&m_blind),
+            None => m_blind.modpow(self.private_key.d(), n),
+        };
+
+        // A faulty CRT result would leak a factor of n, never hand one out
+        if s_blind.modpow(self.public_key.e(), n) != m_blind {
+            return Err(RelayerError::Crypto(
+                "Blind signature self-check failed".into(),
+            ));
+        }
 
         Ok(s_blind.to_bytes_be())
     }
 
     pub fn verify_signature(&self, message: &[u8], signature: &[u8]) -> Result<bool> {
-        let n = self.public_key.n();
-        let e = self.public_key.e();
-
-        let hash = Sha256::digest(message);
-        let m = BigUint::from_bytes_be(&hash);
-
-        // Verify: m == s^e mod n
-        let s = BigUint::from_bytes_be(signature);
-        let computed = s.modpow(e, n);
-
-        Ok(computed == m)
+        Ok(verify_with_key(&self.public_key, message, signature))
     }
 }
 
 pub struct BlindSignerService {
-    signer: Arc<RwLock<BlindSigner>>,
+    /// Swapped whole on rotation, so signing can clone it out and release the lock
+    signer: Arc<RwLock<Arc<BlindSigner>>>,
+    /// Recently rotated-out keys, still accepted for verification until expiry
+    retired: Arc<RwLock<Vec<RetiredKey>>>,
+    key_bits: usize,
+    key_path: PathBuf,
+    /// How long a retired key keeps verifying credits after rotation
+    rotation_grace_secs: u64,
 }
 
 impl BlindSignerService {
-    pub fn new(key_bits: usize) -> Result<Self> {
+    pub fn new(key_bits: usize, rotation_grace_secs: u64) -> Result<Self> {
+        let key_path = std::env::var("RSA_KEY_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_RSA_KEY_PATH));
+        Self::with_key_path(key_bits, rotation_grace_secs, key_path)
+    }
+
+    pub fn with_key_path(
+        key_bits: usize,
+        rotation_grace_secs: u64,
+        key_path: PathBuf,
+    ) -> Result<Self> {
+        let signer = BlindSigner::new_or_load(key_bits, &key_path)?;
+        let retired = Self::load_retired(&key_path);
+        if !retired.is_empty() {
+            info!("Loaded {} retired RSA key(s)", retired.len());
+        }
         Ok(Self {
-            signer: Arc::new(RwLock::new(BlindSigner::new_or_load(key_bits)?)),
+            signer: Arc::new(RwLock::new(Arc::new(signer))),
+            retired: Arc::new(RwLock::new(retired)),
+            key_bits,
+            key_path,
+            rotation_grace_secs,
         })
     }
 
+    fn retired_path(key_path: &Path) -> PathBuf {
+        key_path.with_extension(RETIRED_KEYS_EXTENSION)
+    }
+
+    fn load_retired(key_path: &Path) -> Vec<RetiredKey> {
+        let path = Self::retired_path(key_path);
+        let Ok(bytes) = std::fs::read(&path) else {
+            return Vec::new();
+        };
+        match serde_json::from_slice::<Vec<RetiredKey>>(&bytes) {
+            Ok(keys) => {
+                let now = unix_now();
+                keys.into_iter().filter(|k| k.expires_at > now).collect()
+            }
+            Err(e) => {
+                warn!("Failed to parse retired keys {}: {}", path.display(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn save_retired(&self, retired: &[RetiredKey]) -> Result<()> {
+        let bytes = serde_json::to_vec(retired)
+            .map_err(|e| RelayerError::Internal(format!("Failed to encode retired keys: {}", e)))?;
+        std::fs::write(Self::retired_path(&self.key_path), bytes)
+            .map_err(|e| RelayerError::Crypto(format!("Failed to write retired keys: {}", e)))
+    }
+
+    /// The modpow runs on the blocking pool so it doesn't stall other requests
     pub async fn sign_blinded(&self, blinded_message: &[u8]) -> Result<Vec<u8>> {
-        let signer = self.signer.read().await;
-        signer.sign_blinded(blinded_message)
+        let signer = self.signer.read().await.clone();
+        let blinded_message = blinded_message.to_vec();
+        tokio::task::spawn_blocking(move || signer.sign_blinded(&blinded_message))
+            .await
+            .map_err(|e| RelayerError::Internal(format!("Signing task failed: {}", e)))?
     }
 
+    /// Verify against the current key, then any retired key still inside its grace window
     pub async fn verify_signature(&self, message: &[u8], signature: &[u8]) -> Result<bool> {
-        let signer = self.signer.read().await;
-        signer.verify_signature(message, signature)
+        {
+            let signer = self.signer.read().await;
+            if signer.verify_signature(message, signature)? {
+                return Ok(true);
+            }
+        }
+
+        let now = unix_now();
+        let retired = self.retired.read().await;
+        for key in retired.iter().filter(|k| k.expires_at > now) {
+            if verify_with_key(&key.public_key()?, message, signature) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Replace the signing key with a freshly generated one
+    /// The old public key keeps verifying credits for `rotation_grace_secs`
+    pub async fn rotate(&self) -> Result<()> {
+        let key_bits = self.key_bits;
+        let new_signer = tokio::task::spawn_blocking(move || BlindSigner::new(key_bits))
+            .await
+            .map_err(|e| RelayerError::Internal(format!("Key generation task failed: {}", e)))??;
+
+        let mut signer = self.signer.write().await;
+        let mut retired = self.retired.write().await;
+
+        let now = unix_now();
+        retired.retain(|k| k.expires_at > now);
+        retired.push(RetiredKey {
+            n: hex::encode(signer.public_key_n_bytes()),
+            e: hex::encode(signer.public_key_e_bytes()),
+            expires_at: now + self.rotation_grace_secs,
+        });
+
+        // Persist retired keys first so a crash never loses verification of old credits
+        if let Err(e) = self.save_retired(&retired) {
+            warn!("Failed to persist retired RSA keys: {}", e);
+        }
+        new_signer.save_to_file(&self.key_path)?;
+        *signer = Arc::new(new_signer);
+
+        info!(
+            "✓ Rotated RSA signing key ({} retired key(s) still valid)",
+            retired.len()
+        );
+        Ok(())
     }
 
-    pub async fn public_key_n_bytes(&self) -> Vec<u8> {
+    /// All public keys currently accepted for verification, current key first
+    pub async fn valid_keys(&self) -> Vec<ValidKey> {
         let signer = self.signer.read().await;
-        signer.public_key_n_bytes()
+        let mut keys = vec![ValidKey {
+            n: signer.public_key_n_bytes(),
+            e: signer.public_key_e_bytes(),
+            expires_at: None,
+        }];
+
+        let now = unix_now();
+        let retired = self.retired.read().await;
+        for key in retired.iter().filter(|k| k.expires_at > now) {
+            let (Ok(n), Ok(e)) = (hex::decode(&key.n), hex::decode(&key.e)) else {
+                continue;
+            };
+            keys.push(ValidKey {
+                n,
+                e,
+                expires_at: Some(key.expires_at),
+            });
+        }
+        keys
     }
 
-    pub async fn public_key_e_bytes(&self) -> Vec<u8> {
+    /// Whether the loaded RSA key is internally consistent and usable for signing
+    pub async fn is_ready(&self) -> bool {
         let signer = self.signer.read().await;
-        signer.public_key_e_bytes()
+        signer.private_key.validate().is_ok()
     }
 }
 
@@ -192,4 +407,110 @@ mod tests {
         // Verify signature is valid for original token
         assert!(signer.verify_signature(&token_id, &signature).unwrap());
     }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_old_credits_in_grace_window() {
+        let dir = std::env::temp_dir().join(format!("rsa-rotation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key_path = dir.join("signing_key.der");
+
+        let service = BlindSignerService::with_key_path(1024, 3600, key_path.clone()).unwrap();
+        let old_keys = service.valid_keys().await;
+        let old_pubkey = RsaPublicKey::new(
+            BigUint::from_bytes_be(&old_keys[0].n),
+            BigUint::from_bytes_be(&old_keys[0].e),
+        )
+        .unwrap();
+
+        // Credit minted before rotation
+        let token_id = [7u8; 32];
+        let (blinded, r) = blind_message(&token_id, &old_pubkey).unwrap();
+        let blinded_sig = service.sign_blinded(&blinded).await.unwrap();
+        let signature = unblind_signature(&blinded_sig, &r, &old_pubkey).unwrap();
+
+        service.rotate().await.unwrap();
+
+        let keys = service.valid_keys().await;
+        assert_eq!(keys.len(), 2);
+        assert_ne!(keys[0].n, old_keys[0].n);
+        assert_eq!(keys[1].n, old_keys[0].n);
+        assert!(keys[1].expires_at.is_some());
+        assert!(service
+            .verify_signature(&token_id, &signature)
+            .await
+            .unwrap());
+
+        // Retired keys survive a restart
+        let reloaded = BlindSignerService::with_key_path(1024, 3600, key_path.clone()).unwrap();
+        assert_eq!(reloaded.valid_keys().await.len(), 2);
+        assert!(reloaded
+            .verify_signature(&token_id, &signature)
+            .await
+            .unwrap());
+
+        // Once the grace window has passed the old credit is rejected
+        reloaded.retired.write().await[0].expires_at = 0;
+        assert!(!reloaded
+            .verify_signature(&token_id, &signature)
+            .await
+            .unwrap());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_crt_signature_matches_plain_modpow() {
+        let signer = BlindSigner::new(1024).unwrap();
+        assert!(signer.crt.is_some());
+
+        let n = signer.private_key.n();
+        let m = BigUint::from_bytes_be(&Sha256::digest(b"blinded")) % n;
+        let expected = m.modpow(signer.private_key.d(), n);
+        assert_eq!(
+            signer.sign_blinded(&m.to_bytes_be()).unwrap(),
+            expected.to_bytes_be()
+        );
+
+        // Keys loaded from disk get the same CRT parameters
+        let der = signer.private_key.to_pkcs8_der().unwrap();
+        let loaded = BlindSigner::from_private_key_bytes(der.as_bytes()).unwrap();
+        assert_eq!(
+            loaded.sign_blinded(&m.to_bytes_be()).unwrap(),
+            expected.to_bytes_be()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_signing_on_blocking_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = Arc::new(
+            BlindSignerService::with_key_path(1024, 3600, dir.path().join("key.der")).unwrap(),
+        );
+        let keys = service.valid_keys().await;
+        let pubkey = RsaPublicKey::new(
+            BigUint::from_bytes_be(&keys[0].n),
+            BigUint::from_bytes_be(&keys[0].e),
+        )
+        .unwrap();
+
+        let handles: Vec<_> = (0..16u8)
+            .map(|i| {
+                let service = service.clone();
+                let pubkey = pubkey.clone();
+                tokio::spawn(async move {
+                    let token_id = [i; 32];
+                    let (blinded, r) = blind_message(&token_id, &pubkey).unwrap();
+                    let blinded_sig = service.sign_blinded(&blinded).await.unwrap();
+                    let signature = unblind_signature(&blinded_sig, &r, &pubkey).unwrap();
+                    service
+                        .verify_signature(&token_id, &signature)
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.await.unwrap());
+        }
+    }
 }
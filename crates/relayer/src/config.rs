@@ -1,19 +1,79 @@
-use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use privacy_proxy_sdk::credits::credit_price;
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+};
+use std::collections::HashMap;
 use std::str::FromStr;
 
-pub const BUCKET_AMOUNTS: [u64; 7] = [
-    100_000_000,     // 0.1 SOL
-    500_000_000,     // 0.5 SOL
-    1_000_000_000,   // 1 SOL
-    5_000_000_000,   // 5 SOL
-    10_000_000_000,  // 10 SOL
-    50_000_000_000,  // 50 SOL
-    100_000_000_000, // 100 SOL
-];
+use crate::error::{RelayerError, Result};
+
+/// Default denominations, the SDK's table (matching the on-chain program's constants)
+pub const DEFAULT_BUCKET_AMOUNTS: [u64; 7] = privacy_proxy_sdk::deposit::BUCKET_AMOUNTS;
+
+/// Must match the program's `NUM_BUCKETS`, it has no pools past these
+pub const MAX_BUCKETS: usize = DEFAULT_BUCKET_AMOUNTS.len();
+
+/// Compute units the runtime assigns per instruction when no limit is requested
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Version of the circuits package the bundled verifying keys were generated from
+pub const DEFAULT_CIRCUIT_VERSION: &str = "0.1.0";
+
+/// Per-IP token bucket applied to a group of routes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct RateLimit {
+    /// Sustained requests per second
+    pub per_second: u32,
+    /// Requests allowed at once before the sustained rate applies
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Interval after which one request of the burst is replenished
+    pub fn replenish_period(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(1) / self.per_second.max(1)
+    }
+
+    /// Reads `<PREFIX>_PER_SECOND` and `<PREFIX>_BURST`, ignoring zero values
+    fn from_env(prefix: &str, default: RateLimit) -> Self {
+        let read = |name: &str| {
+            std::env::var(format!("{}_{}", prefix, name))
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|&n: &u32| n > 0)
+        };
+        Self {
+            per_second: read("PER_SECOND").unwrap_or(default.per_second),
+            burst: read("BURST").unwrap_or(default.burst),
+        }
+    }
+}
+
+/// Limit for read endpoints (info, pools, proofs) and withdrawals
+pub const DEFAULT_RATE_LIMIT: RateLimit = RateLimit {
+    per_second: 10,
+    burst: 20,
+};
+
+/// Limit for `/sign` and `/deposit`, which each do several RPC round trips
+pub const DEFAULT_STRICT_RATE_LIMIT: RateLimit = RateLimit {
+    per_second: 1,
+    burst: 5,
+};
 
 #[derive(Clone)]
 pub struct RelayerConfig {
-    pub rpc_url: String,
+    /// Endpoint for account, signature and transaction lookups (RPC_READ_URL, else RPC_URL)
+    pub rpc_read_url: String,
+    /// Endpoint for blockhashes, simulations and sends (RPC_WRITE_URL, else RPC_URL)
+    pub rpc_write_url: String,
+    /// Commitment used by both RPC clients
+    pub rpc_commitment: CommitmentConfig,
     pub keypair: std::sync::Arc<Keypair>,
     pub treasury_keypair: std::sync::Arc<Keypair>,
     pub program_id: Pubkey,
@@ -22,12 +82,70 @@ pub struct RelayerConfig {
     pub port: u16,
     pub fee_bps: u16,
     pub rsa_key_bits: usize,
+    /// How long a rotated-out RSA key keeps verifying credits
+    pub rsa_rotation_grace_secs: u64,
+    /// Pool denominations in lamports, indexed by bucket id (ascending)
+    pub bucket_amounts: Vec<u64>,
+    /// SPL mints accepted for credit payments, with price in token base units per SOL
+    pub payment_mints: HashMap<Pubkey, u64>,
+    /// Compute unit limit for relayer transactions (None = runtime default)
+    pub compute_unit_limit: Option<u32>,
+    /// Priority fee in micro-lamports per compute unit (None = no priority fee)
+    pub compute_unit_price: Option<u64>,
+    /// Max deposits per bucket within `deposit_rate_interval_secs` (None = unlimited)
+    pub deposit_rate_limit: Option<u32>,
+    /// Window for the per-bucket deposit rate limit
+    pub deposit_rate_interval_secs: u64,
+    /// How often pending withdrawals are checked for auto-execution
+    pub poll_interval_secs: u64,
+    /// Max time to wait for one withdrawal poll before skipping ticks until it finishes
+    pub poll_tick_deadline_secs: u64,
+    /// Status checks `/sign` makes for a payment tx before answering `payment_pending`
+    pub payment_poll_attempts: u32,
+    /// Delay between payment status checks
+    pub payment_poll_interval_ms: u64,
+    /// Times a transaction is signed and sent while its blockhash keeps expiring
+    pub tx_submit_attempts: u32,
+    /// Max withdrawals being executed at once
+    pub max_concurrent_executions: usize,
+    /// Bearer token for admin endpoints (None = admin endpoints locked)
+    pub admin_token: Option<String>,
+    /// How often to verify persisted merkle state (0 = disabled)
+    pub merkle_integrity_interval_secs: u64,
+    /// Rebuild a bucket from chain history when its merkle state fails verification
+    pub merkle_resync_on_corruption: bool,
+    /// Max chained HistoricalRoots accounts searched, newest first, when validating a
+    /// withdrawal root. Older roots are refused before submitting
+    pub max_root_accounts: usize,
+    /// Reject withdrawals whose proof amount differs from the requested bucket's amount
+    pub reject_amount_mismatch: bool,
+    /// URL notified (through Tor) when a withdrawal executes (None = no webhooks)
+    pub webhook_url: Option<String>,
+    /// Tor SOCKS proxy for outbound webhook calls
+    pub tor_socks_addr: String,
+    /// Version of the circuit build proofs must come from, published in `/params`
+    pub circuit_version: String,
+    /// snarkjs verification key of the withdrawal circuit, published in `/params`
+    pub withdrawal_vk: Option<serde_json::Value>,
+    /// Per-IP limit on every route except `/sign` and `/deposit`
+    pub rate_limit: RateLimit,
+    /// Per-IP limit on `/sign` and `/deposit`
+    pub strict_rate_limit: RateLimit,
 }
 
 impl RelayerConfig {
     pub fn from_env() -> anyhow::Result<Self> {
         let rpc_url = std::env::var("RPC_URL")
             .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        let rpc_read_url = std::env::var("RPC_READ_URL").unwrap_or_else(|_| rpc_url.clone());
+        let rpc_write_url = std::env::var("RPC_WRITE_URL").unwrap_or_else(|_| rpc_url.clone());
+        let rpc_commitment = match std::env::var("RPC_COMMITMENT") {
+            Ok(level) => CommitmentConfig {
+                commitment: CommitmentLevel::from_str(&level)
+                    .map_err(|e| anyhow::anyhow!("Invalid RPC_COMMITMENT {}: {}", level, e))?,
+            },
+            Err(_) => CommitmentConfig::default(),
+        };
 
         let keypair_path = std::env::var("KEYPAIR_PATH")
             .unwrap_or_else(|_| shellexpand::tilde("~/.config/solana/id.json").to_string());
@@ -36,6 +154,8 @@ impl RelayerConfig {
         let keypair_json: Vec<u8> = serde_json::from_slice(&keypair_bytes)?;
         let keypair = Keypair::try_from(&keypair_json[..])?;
 
+        let require_separate_treasury = env_flag("REQUIRE_SEPARATE_TREASURY", false);
+
         let treasury_keypair = if let Ok(treasury_path) = std::env::var("TREASURY_KEYPAIR_PATH") {
             let treasury_bytes = std::fs::read(&treasury_path).map_err(|e| {
                 anyhow::anyhow!(
@@ -59,6 +179,7 @@ impl RelayerConfig {
             );
             Keypair::try_from(&keypair.to_bytes()[..])?
         };
+        check_treasury_separation(&keypair, &treasury_keypair, require_separate_treasury)?;
 
         let program_id = std::env::var("PROGRAM_ID")
             .map(|s| Pubkey::from_str(&s))
@@ -88,8 +209,152 @@ impl RelayerConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(2048);
 
+        let rsa_rotation_grace_secs = std::env::var("RSA_ROTATION_GRACE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(7 * 24 * 3600);
+
+        let bucket_amounts = match std::env::var("BUCKET_AMOUNTS") {
+            Ok(s) => {
+                let amounts = parse_bucket_amounts(&s)?;
+                tracing::info!(
+                    "Using custom bucket amounts: {:?} (on-chain pools must match)",
+                    amounts
+                );
+                amounts
+            }
+            Err(_) => DEFAULT_BUCKET_AMOUNTS.to_vec(),
+        };
+
+        let payment_mints = match std::env::var("PAYMENT_MINTS") {
+            Ok(s) => parse_payment_mints(&s)?,
+            Err(_) => HashMap::new(),
+        };
+        for (mint, units_per_sol) in &payment_mints {
+            tracing::info!(
+                "Accepting SPL payments in {} at {} base units per SOL",
+                mint,
+                units_per_sol
+            );
+        }
+
+        let compute_unit_limit = std::env::var("COMPUTE_UNIT_LIMIT")
+            .ok()
+            .and_then(|s| s.parse().ok());
+
+        let compute_unit_price = std::env::var("COMPUTE_UNIT_PRICE")
+            .ok()
+            .and_then(|s| s.parse().ok());
+
+        let deposit_rate_limit = std::env::var("DEPOSIT_RATE_LIMIT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u32| n > 0);
+
+        let deposit_rate_interval_secs = std::env::var("DEPOSIT_RATE_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        let poll_interval_secs = std::env::var("POLL_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u64| n > 0)
+            .unwrap_or(30);
+
+        let poll_tick_deadline_secs = std::env::var("POLL_TICK_DEADLINE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(25);
+        if poll_tick_deadline_secs >= poll_interval_secs {
+            tracing::warn!(
+                poll_interval_secs,
+                poll_tick_deadline_secs,
+                "Withdrawal poll deadline is not shorter than its interval, slow polls will skip ticks"
+            );
+        }
+
+        let payment_poll_attempts = std::env::var("PAYMENT_POLL_ATTEMPTS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u32| n > 0)
+            .unwrap_or(10);
+
+        let payment_poll_interval_ms = std::env::var("PAYMENT_POLL_INTERVAL_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(2000);
+
+        let tx_submit_attempts = std::env::var("TX_SUBMIT_ATTEMPTS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &u32| n > 0)
+            .unwrap_or(3);
+
+        let max_concurrent_executions = std::env::var("MAX_CONCURRENT_EXECUTIONS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(4);
+
+        let admin_token = std::env::var("ADMIN_TOKEN")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+        if admin_token.is_none() {
+            tracing::warn!("ADMIN_TOKEN not set, admin endpoints are disabled");
+        }
+
+        let merkle_integrity_interval_secs = std::env::var("MERKLE_INTEGRITY_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(600);
+
+        let merkle_resync_on_corruption = env_flag("MERKLE_RESYNC_ON_CORRUPTION", false);
+
+        let max_root_accounts = std::env::var("MAX_ROOT_ACCOUNTS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&n: &usize| n > 0)
+            .unwrap_or(4);
+
+        let reject_amount_mismatch = env_flag("REJECT_AMOUNT_MISMATCH", true);
+
+        let webhook_url = std::env::var("WEBHOOK_URL")
+            .ok()
+            .filter(|s| !s.trim().is_empty());
+        let tor_socks_addr = std::env::var("TOR_SOCKS_ADDR")
+            .unwrap_or_else(|_| tracezero::DEFAULT_TOR_SOCKS_ADDR.to_string());
+
+        let circuit_version = std::env::var("CIRCUIT_VERSION")
+            .unwrap_or_else(|_| DEFAULT_CIRCUIT_VERSION.to_string());
+        let withdrawal_vk =
+            match std::env::var("WITHDRAWAL_VK_PATH") {
+                Ok(path) => {
+                    let bytes = std::fs::read(&path).map_err(|e| {
+                        anyhow::anyhow!("Failed to read verification key from {}: {}", path, e)
+                    })?;
+                    Some(serde_json::from_slice(&bytes).map_err(|e| {
+                        anyhow::anyhow!("Invalid verification key in {}: {}", path, e)
+                    })?)
+                }
+                Err(_) => None,
+            };
+
+        let rate_limit = RateLimit::from_env("RATE_LIMIT", DEFAULT_RATE_LIMIT);
+        let strict_rate_limit = RateLimit::from_env("STRICT_RATE_LIMIT", DEFAULT_STRICT_RATE_LIMIT);
+
+        if let Some(price) = compute_unit_price {
+            tracing::info!(
+                "Priority fee enabled: {} micro-lamports/CU (max {} lamports per instruction)",
+                price,
+                priority_fee_lamports(compute_unit_limit, price, 1)
+            );
+        }
+
         Ok(Self {
-            rpc_url,
+            rpc_read_url,
+            rpc_write_url,
+            rpc_commitment,
             keypair: std::sync::Arc::new(keypair),
             treasury_keypair: std::sync::Arc::new(treasury_keypair),
             program_id,
@@ -98,20 +363,235 @@ impl RelayerConfig {
             port,
             fee_bps,
             rsa_key_bits,
+            rsa_rotation_grace_secs,
+            bucket_amounts,
+            payment_mints,
+            compute_unit_limit,
+            compute_unit_price,
+            deposit_rate_limit,
+            deposit_rate_interval_secs,
+            poll_interval_secs,
+            poll_tick_deadline_secs,
+            payment_poll_attempts,
+            payment_poll_interval_ms,
+            tx_submit_attempts,
+            max_concurrent_executions,
+            admin_token,
+            merkle_integrity_interval_secs,
+            merkle_resync_on_corruption,
+            max_root_accounts,
+            reject_amount_mismatch,
+            webhook_url,
+            tor_socks_addr,
+            circuit_version,
+            withdrawal_vk,
+            rate_limit,
+            strict_rate_limit,
+        })
+    }
+
+    /// Whether credit payments land in the same wallet that signs deposits,
+    /// which lets an observer link a user's payment to their deposit
+    pub fn treasury_is_deposit_wallet(&self) -> bool {
+        self.treasury_keypair.pubkey() == self.keypair.pubkey()
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.bucket_amounts.len()
+    }
+
+    /// Every configured bucket id, `bucket_amounts` is capped at `MAX_BUCKETS` so they fit a u8
+    pub fn bucket_ids(&self) -> impl Iterator<Item = u8> {
+        0..self.num_buckets() as u8
+    }
+
+    pub fn bucket_amount(&self, bucket_id: u8) -> Option<u64> {
+        self.bucket_amounts.get(bucket_id as usize).copied()
+    }
+
+    /// Defaults with freshly generated keypairs, for unit tests that need a config
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self {
+            rpc_read_url: "mock".to_string(),
+            rpc_write_url: "mock".to_string(),
+            rpc_commitment: CommitmentConfig::default(),
+            keypair: std::sync::Arc::new(Keypair::new()),
+            treasury_keypair: std::sync::Arc::new(Keypair::new()),
+            program_id: Pubkey::new_unique(),
+            zk_verifier_id: Pubkey::new_unique(),
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            fee_bps: 50,
+            rsa_key_bits: 1024,
+            rsa_rotation_grace_secs: 3600,
+            bucket_amounts: DEFAULT_BUCKET_AMOUNTS.to_vec(),
+            payment_mints: HashMap::new(),
+            compute_unit_limit: None,
+            compute_unit_price: None,
+            deposit_rate_limit: None,
+            deposit_rate_interval_secs: 60,
+            poll_interval_secs: 30,
+            poll_tick_deadline_secs: 25,
+            payment_poll_attempts: 10,
+            payment_poll_interval_ms: 2000,
+            tx_submit_attempts: 3,
+            max_concurrent_executions: 4,
+            admin_token: None,
+            merkle_integrity_interval_secs: 0,
+            merkle_resync_on_corruption: false,
+            max_root_accounts: 4,
+            reject_amount_mismatch: true,
+            webhook_url: None,
+            tor_socks_addr: tracezero::DEFAULT_TOR_SOCKS_ADDR.to_string(),
+            circuit_version: DEFAULT_CIRCUIT_VERSION.to_string(),
+            withdrawal_vk: None,
+            rate_limit: DEFAULT_RATE_LIMIT,
+            strict_rate_limit: DEFAULT_STRICT_RATE_LIMIT,
+        }
+    }
+
+    /// Prepend the configured compute-budget instructions to a transaction's instructions
+    pub fn with_compute_budget(&self, instructions: Vec<Instruction>) -> Vec<Instruction> {
+        let mut all = compute_budget_instructions(self.compute_unit_limit, self.compute_unit_price);
+        if let Some(price) = self.compute_unit_price {
+            tracing::info!(
+                "Paying priority fee: {} micro-lamports/CU, up to {} lamports",
+                price,
+                priority_fee_lamports(self.compute_unit_limit, price, instructions.len())
+            );
+        }
+        all.extend(instructions);
+        all
+    }
+}
+
+pub fn compute_budget_instructions(
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    if let Some(limit) = compute_unit_limit {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    }
+    if let Some(price) = compute_unit_price {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+    }
+    instructions
+}
+
+/// Maximum priority fee (lamports) for a transaction with `num_instructions` instructions
+pub fn priority_fee_lamports(
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: u64,
+    num_instructions: usize,
+) -> u64 {
+    let limit = compute_unit_limit
+        .map(|l| l as u128)
+        .unwrap_or(DEFAULT_COMPUTE_UNIT_LIMIT as u128 * num_instructions as u128);
+    let micro_lamports = limit * compute_unit_price as u128;
+    micro_lamports.div_ceil(1_000_000) as u64
+}
+
+/// Parse a comma-separated list of lamport amounts, e.g. "100000000,1000000000"
+pub fn parse_bucket_amounts(s: &str) -> anyhow::Result<Vec<u64>> {
+    let amounts = s
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            part.parse::<u64>()
+                .map_err(|e| anyhow::anyhow!("Invalid bucket amount '{}': {}", part, e))
         })
+        .collect::<anyhow::Result<Vec<u64>>>()?;
+
+    if amounts.is_empty() || amounts.len() > MAX_BUCKETS {
+        anyhow::bail!(
+            "BUCKET_AMOUNTS must list between 1 and {} amounts",
+            MAX_BUCKETS
+        );
+    }
+    if amounts[0] == 0 {
+        anyhow::bail!("BUCKET_AMOUNTS must not contain zero");
     }
+    if !amounts.windows(2).all(|w| w[0] < w[1]) {
+        anyhow::bail!("BUCKET_AMOUNTS must be sorted ascending without duplicates");
+    }
+    Ok(amounts)
 }
 
-pub fn get_bucket_id(amount: u64) -> Option<u8> {
-    BUCKET_AMOUNTS
+/// Parse accepted payment mints, e.g. "<mint>:<base units per SOL>,<mint>:<base units per SOL>"
+pub fn parse_payment_mints(s: &str) -> anyhow::Result<HashMap<Pubkey, u64>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (mint, rate) = part.split_once(':').ok_or_else(|| {
+                anyhow::anyhow!("Expected <mint>:<units per SOL>, got '{}'", part)
+            })?;
+            let mint = Pubkey::from_str(mint.trim())
+                .map_err(|e| anyhow::anyhow!("Invalid payment mint '{}': {}", mint, e))?;
+            let rate: u64 = rate
+                .trim()
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid rate for mint {}: {}", mint, e))?;
+            if rate == 0 {
+                anyhow::bail!("Rate for mint {} must be non-zero", mint);
+            }
+            Ok((mint, rate))
+        })
+        .collect()
+}
+
+/// Boolean env var: `1`/`true`/`yes` or `0`/`false`/`no`, any case
+/// Unset or anything else is `default`
+fn env_flag(name: &str, default: bool) -> bool {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| parse_flag(&value))
+        .unwrap_or(default)
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn get_bucket_id(bucket_amounts: &[u64], amount: u64) -> Option<u8> {
+    bucket_amounts
         .iter()
         .position(|&a| a == amount)
         .map(|i| i as u8)
 }
 
-pub fn calculate_total_with_fee(amount: u64, fee_bps: u16) -> u64 {
-    let fee = (amount as u128 * fee_bps as u128 / 10000) as u64;
-    amount + fee
+/// Payment due for credits of `amounts`, each a bucket amount plus its own fee
+/// Fees are rounded per credit, the same as buying them one by one
+pub fn batch_total_with_fee(bucket_amounts: &[u64], amounts: &[u64], fee_bps: u16) -> Result<u64> {
+    amounts.iter().try_fold(0u64, |total, &amount| {
+        get_bucket_id(bucket_amounts, amount).ok_or(RelayerError::InvalidBucket(amount))?;
+        total
+            .checked_add(credit_price(amount, fee_bps))
+            .ok_or_else(|| RelayerError::InvalidRequest("Batch total overflows".into()))
+    })
+}
+
+/// With `require_separate` (REQUIRE_SEPARATE_TREASURY), refuse to start when credit payments
+/// would be received by the deposit wallet instead of only warning about it
+pub fn check_treasury_separation(
+    keypair: &Keypair,
+    treasury_keypair: &Keypair,
+    require_separate: bool,
+) -> anyhow::Result<()> {
+    if require_separate && keypair.pubkey() == treasury_keypair.pubkey() {
+        anyhow::bail!(
+            "REQUIRE_SEPARATE_TREASURY is set but the treasury is the deposit wallet {}; \
+             set TREASURY_KEYPAIR_PATH to a separate keypair",
+            keypair.pubkey()
+        );
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -120,17 +600,122 @@ mod tests {
 
     #[test]
     fn test_bucket_id() {
-        assert_eq!(get_bucket_id(100_000_000), Some(0));
-        assert_eq!(get_bucket_id(1_000_000_000), Some(1));
-        assert_eq!(get_bucket_id(10_000_000_000), Some(2));
-        assert_eq!(get_bucket_id(100_000_000_000), Some(3));
-        assert_eq!(get_bucket_id(999), None);
+        let amounts = &DEFAULT_BUCKET_AMOUNTS;
+        assert_eq!(get_bucket_id(amounts, 100_000_000), Some(0));
+        assert_eq!(get_bucket_id(amounts, 1_000_000_000), Some(2));
+        assert_eq!(get_bucket_id(amounts, 10_000_000_000), Some(4));
+        assert_eq!(get_bucket_id(amounts, 100_000_000_000), Some(6));
+        assert_eq!(get_bucket_id(amounts, 999), None);
     }
 
     #[test]
-    fn test_fee_calculation() {
-        // 0.5% fee on 1 SOL
-        let total = calculate_total_with_fee(1_000_000_000, 50);
-        assert_eq!(total, 1_005_000_000);
+    fn test_parse_bucket_amounts() {
+        let amounts = parse_bucket_amounts("100000000, 1000000000").unwrap();
+        assert_eq!(amounts, vec![100_000_000, 1_000_000_000]);
+        assert_eq!(get_bucket_id(&amounts, 1_000_000_000), Some(1));
+
+        assert!(parse_bucket_amounts("").is_err());
+        assert!(parse_bucket_amounts("1000000000,100000000").is_err());
+        assert!(parse_bucket_amounts("100000000,100000000").is_err());
+        assert!(parse_bucket_amounts("0,100000000").is_err());
+        assert!(parse_bucket_amounts("0.1").is_err());
+
+        // As many buckets as the program has pools, and no more
+        let amounts = |n: u64| {
+            (1..=n)
+                .map(|amount| amount.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let max = parse_bucket_amounts(&amounts(MAX_BUCKETS as u64)).unwrap();
+        assert_eq!(max.len(), MAX_BUCKETS);
+        assert!(parse_bucket_amounts(&amounts(MAX_BUCKETS as u64 + 1)).is_err());
+        assert!(parse_bucket_amounts(&amounts(256)).is_err());
+    }
+
+    #[test]
+    fn test_parse_payment_mints() {
+        let usdc = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+        let mints = parse_payment_mints(&format!("{}:150000000", usdc)).unwrap();
+        assert_eq!(
+            mints.get(&Pubkey::from_str(usdc).unwrap()),
+            Some(&150_000_000)
+        );
+
+        assert!(parse_payment_mints("").unwrap().is_empty());
+        assert!(parse_payment_mints(usdc).is_err());
+        assert!(parse_payment_mints(&format!("{}:0", usdc)).is_err());
+        assert!(parse_payment_mints("notamint:1").is_err());
+    }
+
+    #[test]
+    fn test_parse_flag() {
+        for value in ["1", "true", "TRUE", "yes", " Yes "] {
+            assert_eq!(parse_flag(value), Some(true), "{:?}", value);
+        }
+        for value in ["0", "false", "False", "no"] {
+            assert_eq!(parse_flag(value), Some(false), "{:?}", value);
+        }
+        assert_eq!(parse_flag(""), None);
+        assert_eq!(parse_flag("on"), None);
+    }
+
+    #[test]
+    fn test_batch_total_with_fee() {
+        let buckets = [100_000_000, 1_000_000_000];
+        assert_eq!(
+            batch_total_with_fee(&buckets, &[1_000_000_000, 100_000_000, 100_000_000], 50).unwrap(),
+            1_005_000_000 + 2 * 100_500_000
+        );
+        // Per-credit rounding, never less than buying them separately
+        assert_eq!(
+            batch_total_with_fee(&[199], &[199, 199], 50).unwrap(),
+            2 * credit_price(199, 50)
+        );
+        assert!(matches!(
+            batch_total_with_fee(&buckets, &[1_000_000_000, 5], 50),
+            Err(RelayerError::InvalidBucket(5))
+        ));
+        assert!(batch_total_with_fee(&[u64::MAX / 2], &[u64::MAX / 2; 3], 0).is_err());
+    }
+
+    #[test]
+    fn test_compute_budget_instructions() {
+        assert!(compute_budget_instructions(None, None).is_empty());
+        assert_eq!(compute_budget_instructions(Some(400_000), None).len(), 1);
+        assert_eq!(
+            compute_budget_instructions(Some(400_000), Some(1_000)).len(),
+            2
+        );
+
+        // 400k CU at 1000 micro-lamports/CU = 400 lamports
+        assert_eq!(priority_fee_lamports(Some(400_000), 1_000, 1), 400);
+        // Unset limit falls back to the per-instruction runtime default
+        assert_eq!(priority_fee_lamports(None, 1_000, 2), 400);
+        // Fractional lamports round up
+        assert_eq!(priority_fee_lamports(Some(1), 1, 1), 1);
+    }
+
+    #[test]
+    fn test_rate_limit_replenish_period() {
+        assert_eq!(
+            DEFAULT_RATE_LIMIT.replenish_period(),
+            std::time::Duration::from_millis(100)
+        );
+        assert_eq!(
+            DEFAULT_STRICT_RATE_LIMIT.replenish_period(),
+            std::time::Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_check_treasury_separation() {
+        let keypair = Keypair::new();
+        let same = Keypair::try_from(&keypair.to_bytes()[..]).unwrap();
+        let separate = Keypair::new();
+
+        assert!(check_treasury_separation(&keypair, &same, false).is_ok());
+        assert!(check_treasury_separation(&keypair, &same, true).is_err());
+        assert!(check_treasury_separation(&keypair, &separate, true).is_ok());
     }
 }
@@ -2,18 +2,20 @@ use privacy_proxy_sdk::credits::SignedCredit;
 use privacy_proxy_sdk::deposit::{DepositRequest, DepositResponse};
 use sha2::{Digest, Sha256};
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    signature::Signature,
     signer::Signer,
     system_program::ID as SYSTEM_PROGRAM_ID,
-    transaction::Transaction,
 };
 use solana_transaction_status::UiTransactionEncoding;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
 use tracing::{error, info, warn};
 
 use crate::blind_signer::BlindSignerService;
@@ -21,6 +23,9 @@ use crate::config::{get_bucket_id, RelayerConfig};
 use crate::encryption::hash_token_id;
 use crate::error::{RelayerError, Result};
 use crate::merkle_service::MerkleService;
+use crate::on_chain::{historical_roots_account_index, DepositPoolView};
+use crate::rpc::{rpc_read, with_retry_if, RPC_READ_ATTEMPTS, RPC_READ_BACKOFF};
+use crate::tx::{submit_with_blockhash_refresh, SubmitError};
 
 /// Persistent token store to prevent double-spend across restarts, Uses checksums to detect file corruption
 struct TokenStore {
@@ -122,48 +127,108 @@ impl TokenStore {
         if self.cache.insert(hash) {
             // Update checksum
             self.checksum = Self::compute_checksum(&self.cache);
+            self.persist()?;
+        }
+        Ok(())
+    }
 
-            // Write full file (atomic update)
-            let temp_path = self.path.with_extension("tmp");
-            let checksum_path = self.path.with_extension("checksum");
+    /// Write full file (atomic update)
+    fn persist(&self) -> Result<()> {
+        let temp_path = self.path.with_extension("tmp");
+        let checksum_path = self.path.with_extension("checksum");
 
-            // Write tokens to temp file
-            {
-                use std::io::Write;
-                let mut file = std::fs::File::create(&temp_path).map_err(|e| {
-                    RelayerError::Internal(format!("Failed to create temp token store: {}", e))
-                })?;
+        // Write tokens to temp file
+        {
+            use std::io::Write;
+            let mut file = std::fs::File::create(&temp_path).map_err(|e| {
+                RelayerError::Internal(format!("Failed to create temp token store: {}", e))
+            })?;
 
-                for token in &self.cache {
-                    file.write_all(token).map_err(|e| {
-                        RelayerError::Internal(format!("Failed to write token: {}", e))
-                    })?;
-                }
-                file.sync_all().map_err(|e| {
-                    RelayerError::Internal(format!("Failed to sync token store: {}", e))
-                })?;
+            for token in &self.cache {
+                file.write_all(token)
+                    .map_err(|e| RelayerError::Internal(format!("Failed to write token: {}", e)))?;
             }
+            file.sync_all().map_err(|e| {
+                RelayerError::Internal(format!("Failed to sync token store: {}", e))
+            })?;
+        }
 
-            // Write checksum
-            std::fs::write(&checksum_path, &self.checksum)
-                .map_err(|e| RelayerError::Internal(format!("Failed to write checksum: {}", e)))?;
+        // Write checksum
+        std::fs::write(&checksum_path, self.checksum)
+            .map_err(|e| RelayerError::Internal(format!("Failed to write checksum: {}", e)))?;
 
-            // Atomic rename
-            std::fs::rename(&temp_path, &self.path).map_err(|e| {
-                RelayerError::Internal(format!("Failed to rename token store: {}", e))
-            })?;
+        // Atomic rename
+        std::fs::rename(&temp_path, &self.path)
+            .map_err(|e| RelayerError::Internal(format!("Failed to rename token store: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Global per-bucket sliding-window limiter for deposits
+/// Keyed only by bucket so nothing user-identifying is tracked
+struct DepositThrottle {
+    max_deposits: u32,
+    interval: Duration,
+    /// Accepted deposit times per bucket, oldest first
+    history: HashMap<u8, VecDeque<Instant>>,
+}
+
+impl DepositThrottle {
+    fn new(max_deposits: u32, interval: Duration) -> Self {
+        Self {
+            max_deposits,
+            interval,
+            history: HashMap::new(),
+        }
+    }
+
+    /// Record a deposit for `bucket_id` at `now`, or return the seconds until a slot frees up
+    fn try_acquire(&mut self, bucket_id: u8, now: Instant) -> std::result::Result<(), u64> {
+        let window = self.history.entry(bucket_id).or_default();
+        while let Some(&oldest) = window.front() {
+            if now.duration_since(oldest) >= self.interval {
+                window.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if window.len() >= self.max_deposits as usize {
+            let oldest = window.front().copied().unwrap_or(now);
+            let wait = self.interval.saturating_sub(now.duration_since(oldest));
+            // Round up so clients never retry before the slot is actually free
+            let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            return Err(retry_after.max(1));
         }
+
+        window.push_back(now);
         Ok(())
     }
+
+    /// Give back the slot taken at `acquired_at` by a deposit that didn't go through
+    fn release(&mut self, bucket_id: u8, acquired_at: Instant) {
+        if let Some(window) = self.history.get_mut(&bucket_id) {
+            if let Some(pos) = window.iter().rposition(|&at| at == acquired_at) {
+                window.remove(pos);
+            }
+        }
+    }
 }
 
 pub struct DepositService {
     config: RelayerConfig,
     rpc_client: Arc<RpcClient>,
+    /// Blockhashes, sends and confirmations (defaults to `rpc_client`)
+    send_client: Arc<RpcClient>,
     blind_signer: Arc<BlindSignerService>,
     merkle_service: Arc<MerkleService>,
     /// Persistent token store (prevents double-spend across restarts)
     token_store: Arc<RwLock<TokenStore>>,
+    /// Per-bucket deposit rate limiter (None when disabled)
+    throttle: Option<RwLock<DepositThrottle>>,
+    /// Per-bucket lock serializing next_index read, local insert and submit,
+    /// so concurrent deposits never derive the same note PDA
+    index_locks: Vec<Mutex<()>>,
 }
 
 impl DepositService {
@@ -176,17 +241,53 @@ impl DepositService {
         let token_path = std::env::var("TOKEN_STORE_PATH")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("used_tokens.dat"));
+        Self::with_token_store_path(config, rpc_client, blind_signer, merkle_service, token_path)
+    }
+
+    pub fn with_token_store_path(
+        config: RelayerConfig,
+        rpc_client: Arc<RpcClient>,
+        blind_signer: Arc<BlindSignerService>,
+        merkle_service: Arc<MerkleService>,
+        token_path: PathBuf,
+    ) -> Self {
         let token_store = TokenStore::load(token_path);
+        let index_locks = config.bucket_ids().map(|_| Mutex::new(())).collect();
+
+        let throttle = config.deposit_rate_limit.map(|max| {
+            info!(
+                "Deposit rate limit: {} per bucket every {}s",
+                max, config.deposit_rate_interval_secs
+            );
+            RwLock::new(DepositThrottle::new(
+                max,
+                Duration::from_secs(config.deposit_rate_interval_secs),
+            ))
+        });
 
         Self {
             config,
+            send_client: rpc_client.clone(),
             rpc_client,
             blind_signer,
             merkle_service,
             token_store: Arc::new(RwLock::new(token_store)),
+            throttle,
+            index_locks,
         }
     }
 
+    /// Send transactions through a separate endpoint from the one used for reads
+    pub fn with_send_client(mut self, send_client: Arc<RpcClient>) -> Self {
+        self.send_client = send_client;
+        self
+    }
+
+    /// Persist the token store, called on shutdown
+    pub async fn flush(&self) -> Result<()> {
+        self.token_store.read().await.persist()
+    }
+
     fn get_pool_pda(&self, bucket_id: u8) -> Pubkey {
         let (pool_pda, _) =
             Pubkey::find_program_address(&[b"pool", &[bucket_id]], &self.config.program_id);
@@ -202,11 +303,52 @@ impl DepositService {
         self.check_token_not_used(&token_hash).await?;
 
         // 3. Get bucket ID from amount
-        let bucket_id = get_bucket_id(request.credit.amount)
+        let bucket_id = get_bucket_id(&self.config.bucket_amounts, request.credit.amount)
             .ok_or(RelayerError::InvalidBucket(request.credit.amount))?;
 
+        // Shed excess load before touching the tree or the chain
+        let acquired_at = Instant::now();
+        if let Some(throttle) = &self.throttle {
+            throttle
+                .write()
+                .await
+                .try_acquire(bucket_id, acquired_at)
+                .map_err(|retry_after| {
+                    warn!(
+                        "Deposit rate limit hit for bucket {}, retry after {}s",
+                        bucket_id, retry_after
+                    );
+                    RelayerError::RateLimited(retry_after)
+                })?;
+        }
+
+        let result = self
+            .deposit_into_bucket(bucket_id, request, token_hash)
+            .await;
+        // A rejected deposit (paused or full pool, failed transaction) shouldn't use up the bucket's quota
+        if result.is_err() {
+            if let Some(throttle) = &self.throttle {
+                throttle.write().await.release(bucket_id, acquired_at);
+            }
+        }
+        result
+    }
+
+    async fn deposit_into_bucket(
+        &self,
+        bucket_id: u8,
+        request: DepositRequest,
+        token_hash: [u8; 32],
+    ) -> Result<DepositResponse> {
+        // Held until the deposit lands so the next one sees the incremented next_index
+        let _index_guard = self.index_locks[bucket_id as usize].lock().await;
+
         // 4. Fetch on-chain next_index FIRST to ensure sync
-        let on_chain_next_index = self.get_on_chain_next_index(bucket_id).await?;
+        // A paused or full pool would reject the deposit, so don't touch the tree or the credit
+        let pool = self.fetch_pool(bucket_id).await?;
+        pool.ensure_active()?;
+        pool.ensure_accepts_deposit()?;
+        let on_chain_next_index = pool.next_index();
         let local_size = self.merkle_service.size(bucket_id).await.unwrap_or(0) as u64;
 
         // Verify local tree is in sync with on-chain state
@@ -231,9 +373,8 @@ impl DepositService {
         let tx_signature = self
             .execute_deposit(
                 bucket_id,
-                request.commitment,
+                &request,
                 token_hash,
-                request.encrypted_note,
                 merkle_root,
                 on_chain_next_index,
             )
@@ -243,8 +384,10 @@ impl DepositService {
         self.mark_token_used(token_hash).await?;
 
         info!(
-            "Deposit successful: bucket={}, leaf_index={}, tx={}",
-            bucket_id, leaf_index, tx_signature
+            bucket_id,
+            leaf_index,
+            tx_signature = %tx_signature,
+            "Deposit successful"
         );
 
         Ok(DepositResponse {
@@ -256,207 +399,68 @@ impl DepositService {
         })
     }
 
-    async fn get_on_chain_next_index(&self, bucket_id: u8) -> Result<u64> {
-        let (pool_pda, _) =
-            Pubkey::find_program_address(&[b"pool", &[bucket_id]], &self.config.program_id);
-
-        let pool_data = self
-            .rpc_client
-            .get_account_data(&pool_pda)
-            .await
-            .map_err(|e| RelayerError::TransactionFailed(format!("Failed to fetch pool: {}", e)))?;
-
-        // Parse next_index from pool account data
-        // DepositPool layout:
-        // - discriminator: 8 bytes (offset 0)
-        // - bucket_id: 1 byte (offset 8)
-        // - amount_lamports: 8 bytes (offset 9)
-        // - merkle_root: 32 bytes (offset 17)
-        // - next_index: 8 bytes (offset 49)
-        let next_index = if pool_data.len() >= 57 {
-            u64::from_le_bytes(pool_data[49..57].try_into().unwrap_or([0u8; 8]))
-        } else {
-            0u64
-        };
+    async fn fetch_pool(&self, bucket_id: u8) -> Result<DepositPoolView> {
+        DepositPoolView::fetch(&self.rpc_client, &self.get_pool_pda(bucket_id)).await
+    }
 
-        Ok(next_index)
+    async fn get_on_chain_next_index(&self, bucket_id: u8) -> Result<u64> {
+        Ok(self.fetch_pool(bucket_id).await?.next_index())
     }
 
+    /// Rebuild a bucket's tree from chain history so it holds `on_chain_size` leaves
+    /// The tree is only replaced once the whole history is recovered, otherwise it's left as is
     async fn sync_local_tree(&self, bucket_id: u8, on_chain_size: u64) -> Result<()> {
         let local_size = self.merkle_service.size(bucket_id).await.unwrap_or(0) as u64;
         if local_size > on_chain_size {
             error!(
-                "Local tree has more entries ({}) than on-chain ({}). This should never happen! Resetting local tree.",
+                "Local tree has more entries ({}) than on-chain ({}). This should never happen! Rebuilding local tree.",
                 local_size, on_chain_size
             );
-            // Re-initialize the tree (this will clear it)
-            self.merkle_service
-                .sync_from_chain(bucket_id, vec![])
-                .await?;
-
-            // After reset, we need to fetch all on-chain commitments
-            if on_chain_size > 0 {
-                warn!(
-                    "Fetching {} commitments from on-chain to rebuild tree...",
-                    on_chain_size
-                );
-            }
-        }
-
-        // Fetch missing commitments from transaction history
-        let current_local_size = self.merkle_service.size(bucket_id).await.unwrap_or(0) as u64;
-        if current_local_size < on_chain_size {
+        } else {
             warn!(
                 "On-chain has {} entries, local has {}. Fetching missing commitments from transaction history...",
-                on_chain_size, current_local_size
-            );
-
-            let pool_pda = self.get_pool_pda(bucket_id);
-
-            // Fetch transaction signatures for the pool account
-            let signatures = self
-                .rpc_client
-                .get_signatures_for_address(&pool_pda)
-                .await
-                .map_err(|e| {
-                    RelayerError::TransactionFailed(format!(
-                        "Failed to fetch transaction history: {}",
-                        e
-                    ))
-                })?;
-
-            info!(
-                "Found {} transactions for pool {}",
-                signatures.len(),
-                bucket_id
+                on_chain_size, local_size
             );
+        }
 
-            // OPTIMIZATION: If there are too many transactions (>50), skip the slow scan
-            // This prevents 20+ second delays on devnet where logs are often pruned anyway
-            if signatures.len() > 50 {
-                warn!(
-                    "Too many transactions ({}) to scan efficiently. Skipping history scan.",
-                    signatures.len()
-                );
-                warn!("⚠ CONTINUING WITH EMPTY TREE - Old deposits (if any) will NOT be withdrawable!");
-                warn!("⚠ The relayer will track new deposits from this point forward.");
-                warn!("⚠ If you need to recover old deposits, you must restore the merkle_state/ from backup.");
-
-                // Reset the tree to empty and continue
-                self.merkle_service
-                    .sync_from_chain(bucket_id, vec![])
-                    .await?;
-
-                return Ok(());
-            }
-
-            // Parse deposit events from transaction logs (only scan recent transactions)
-            let mut commitments = Vec::new();
-            for sig_info in signatures.iter().rev().take(20) {
-                // Only scan last 20 transactions
-                // Skip failed transactions
-                if sig_info.err.is_some() {
-                    continue;
-                }
-
-                // Fetch full transaction to get logs
-                let signature = sig_info.signature.parse().map_err(|e| {
-                    RelayerError::InvalidRequest(format!("Invalid signature: {}", e))
-                })?;
-
-                match self
-                    .rpc_client
-                    .get_transaction(&signature, UiTransactionEncoding::Json)
-                    .await
-                {
-                    Ok(tx) => {
-                        if let Some(meta) = tx.transaction.meta {
-                            let log_messages: Option<Vec<String>> = meta.log_messages.into();
-                            if let Some(logs) = log_messages {
-                                for log in logs {
-                                    if log.contains("Program log: Deposit: commitment=") {
-                                        if let Some(hex_start) = log.find("commitment=") {
-                                            let hex_str = &log[hex_start + 11..];
-                                            // Extract 64 hex chars (32 bytes)
-                                            if hex_str.len() >= 64 {
-                                                let commitment_hex = &hex_str[..64];
-                                                match hex::decode(commitment_hex) {
-                                                    Ok(bytes) if bytes.len() == 32 => {
-                                                        let mut commitment = [0u8; 32];
-                                                        commitment.copy_from_slice(&bytes);
-                                                        commitments.push(commitment);
-                                                        info!(
-                                                            "Found commitment from tx {}: {}",
-                                                            signature, commitment_hex
-                                                        );
-                                                    }
-                                                    _ => {
-                                                        warn!(
-                                                            "Invalid commitment hex in log: {}",
-                                                            commitment_hex
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to fetch transaction {}: {}", signature, e);
-                    }
-                }
-            }
-
-            if commitments.is_empty() {
-                warn!(
-                    "Could not find any commitments in transaction history for bucket {}",
-                    bucket_id
-                );
-                warn!("This may happen if transactions are too old or logs are not available.");
-
-                // IMPORTANT: Instead of returning an error, we'll continue with a warning
-                // This allows the relayer to start accepting new deposits even if old ones can't be recovered
-                warn!(
-                    "⚠ CONTINUING WITH EMPTY TREE - Old deposits (if any) will NOT be withdrawable!"
-                );
-                warn!("⚠ The relayer will track new deposits from this point forward.");
-                warn!(
-                    "⚠ If you need to recover old deposits, you must restore the merkle_state/ from backup."
-                );
-
-                // Reset the tree to empty and continue
-                self.merkle_service
-                    .sync_from_chain(bucket_id, vec![])
-                    .await?;
+        let pool_pda = self.get_pool_pda(bucket_id);
+        let commitments =
+            fetch_deposit_commitments(&self.rpc_client, &pool_pda, on_chain_size).await?;
 
-                return Ok(());
-            }
+        if commitments.len() as u64 != on_chain_size {
+            return Err(RelayerError::MerkleTree(format!(
+                "Sync of bucket {} recovered only {}/{} commitments, keeping the local tree",
+                bucket_id,
+                commitments.len(),
+                on_chain_size
+            )));
+        }
 
-            info!(
-                "Found {} commitments from transaction history",
-                commitments.len()
-            );
+        self.merkle_service
+            .sync_from_chain(bucket_id, commitments)
+            .await?;
+        info!("✓ Successfully synced local tree with on-chain state");
+        Ok(())
+    }
 
-            // Rebuild local tree with found commitments
-            self.merkle_service
-                .sync_from_chain(bucket_id, commitments)
-                .await?;
+    /// Discard the local tree for a bucket and rebuild it entirely from chain history
+    pub async fn resync_bucket(&self, bucket_id: u8) -> Result<()> {
+        let on_chain_size = self.get_on_chain_next_index(bucket_id).await?;
+        let pool_pda = self.get_pool_pda(bucket_id);
+        let commitments =
+            fetch_deposit_commitments(&self.rpc_client, &pool_pda, on_chain_size).await?;
 
-            let new_local_size = self.merkle_service.size(bucket_id).await.unwrap_or(0) as u64;
-            if new_local_size != on_chain_size {
-                warn!(
-                    "After sync: local size {} still doesn't match on-chain size {}",
-                    new_local_size, on_chain_size
-                );
-                warn!("Some commitments may be missing from transaction history.");
-            } else {
-                info!("✓ Successfully synced local tree with on-chain state");
-            }
+        if commitments.len() as u64 != on_chain_size {
+            return Err(RelayerError::MerkleTree(format!(
+                "Resync of bucket {} recovered only {}/{} commitments",
+                bucket_id,
+                commitments.len(),
+                on_chain_size
+            )));
         }
 
+        self.merkle_service.rebuild(bucket_id, commitments).await?;
+        info!("✓ Resynced bucket {} from chain", bucket_id);
         Ok(())
     }
 
@@ -488,13 +492,13 @@ impl DepositService {
     async fn execute_deposit(
         &self,
         bucket_id: u8,
-        commitment: [u8; 32],
+        request: &DepositRequest,
         token_hash: [u8; 32],
-        encrypted_note: Option<Vec<u8>>,
         merkle_root: [u8; 32],
         on_chain_next_index: u64,
     ) -> Result<String> {
         let relayer = &self.config.keypair;
+        let commitment = request.commitment;
 
         // Derive PDAs
         let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &self.config.program_id);
@@ -502,14 +506,26 @@ impl DepositService {
         let (pool_pda, _) =
             Pubkey::find_program_address(&[b"pool", &[bucket_id]], &self.config.program_id);
 
+        // The program saves the current root into the chained account for this leaf,
+        // creating it when the previous one is full
         let (historical_roots_pda, _) = Pubkey::find_program_address(
-            &[b"historical_roots", pool_pda.as_ref(), &[0u8]],
+            &[
+                b"historical_roots",
+                pool_pda.as_ref(),
+                &[historical_roots_account_index(on_chain_next_index)],
+            ],
             &self.config.program_id,
         );
 
         let (used_token_pda, _) =
             Pubkey::find_program_address(&[b"used_token", &token_hash], &self.config.program_id);
 
+        // The program refuses a commitment it has already seen
+        let (used_commitment_pda, _) = Pubkey::find_program_address(
+            &[b"used_commitment", &commitment],
+            &self.config.program_id,
+        );
+
         // Use the on-chain next_index for note PDA derivation
         // This ensures we match what the on-chain program expects
         let (note_pda, _) = Pubkey::find_program_address(
@@ -522,7 +538,8 @@ impl DepositService {
         );
 
         // Build instruction data
-        // deposit(bucket_id: u8, commitment: [u8; 32], token_hash: [u8; 32], encrypted_note: Vec<u8>, merkle_root: [u8; 32])
+        // deposit(bucket_id: u8, commitment: [u8; 32], token_hash: [u8; 32], encrypted_note: Vec<u8>,
+        //         merkle_root: [u8; 32], note_ephemeral_pubkey: [u8; 32])
         let mut data = vec![0u8; 8]; // Anchor discriminator for "deposit"
         let discriminator = anchor_discriminator("deposit");
         data[..8].copy_from_slice(&discriminator);
@@ -531,12 +548,13 @@ impl DepositService {
         data.extend_from_slice(&token_hash);
 
         // Serialize encrypted_note as Vec<u8>
-        let note_data = encrypted_note.unwrap_or_default();
+        let note_data = request.encrypted_note.as_deref().unwrap_or_default();
         data.extend_from_slice(&(note_data.len() as u32).to_le_bytes());
-        data.extend_from_slice(&note_data);
+        data.extend_from_slice(note_data);
 
         // Add merkle_root
         data.extend_from_slice(&merkle_root);
+        data.extend_from_slice(&request.note_ephemeral_pubkey.unwrap_or_default());
 
         let instruction = Instruction {
             program_id: self.config.program_id,
@@ -547,27 +565,225 @@ impl DepositService {
                 AccountMeta::new(historical_roots_pda, false), // historical_roots (mut)
                 AccountMeta::new(used_token_pda, false),  // used_token (init)
                 AccountMeta::new(note_pda, false),        // encrypted_note (init)
+                AccountMeta::new(used_commitment_pda, false), // used_commitment (init)
                 AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
             ],
             data,
         };
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
-        let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&relayer.pubkey()),
-            &[relayer.as_ref()],
-            recent_blockhash,
+        let signature = match submit_with_blockhash_refresh(
+            &self.send_client,
+            &self.config.with_compute_budget(vec![instruction]),
+            relayer,
+            self.config.tx_submit_attempts,
+        )
+        .await
+        {
+            Ok(signature) => signature,
+            Err(SubmitError {
+                signature: None,
+                error,
+            }) => return Err(error.into()),
+            Err(SubmitError {
+                signature: Some(signature),
+                error,
+            }) => {
+                // Rejected outright, the transaction never took effect
+                if error.get_transaction_error().is_some() {
+                    return Err(RelayerError::TransactionFailed(error.to_string()));
+                }
+                warn!(
+                    tx_signature = %signature,
+                    "Deposit confirmation failed after broadcast ({}), checking chain",
+                    error
+                );
+                signature
+            }
+        };
+
+        // Only report success (and burn the credit) once the chain shows the deposit
+        let status = self.send_client.get_signature_status(&signature).await?;
+        let next_index = self.get_on_chain_next_index(bucket_id).await?;
+        match deposit_outcome(status, on_chain_next_index, next_index) {
+            DepositOutcome::Landed => Ok(signature.to_string()),
+            DepositOutcome::Failed(e) => Err(RelayerError::TransactionFailed(e)),
+            DepositOutcome::Unconfirmed => {
+                warn!(
+                    bucket_id,
+                    tx_signature = %signature,
+                    "Deposit not visible on-chain, leaving credit unspent"
+                );
+                Err(RelayerError::DepositUnconfirmed(signature.to_string()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DepositOutcome {
+    Landed,
+    Failed(String),
+    /// Not seen on-chain yet, it may still land or may have been dropped
+    Unconfirmed,
+}
+
+/// Classify a deposit from its signature status and the pool's `next_index` before and after
+/// A successful status alone isn't enough: the index must have moved past our leaf
+fn deposit_outcome(
+    status: Option<std::result::Result<(), solana_sdk::transaction::TransactionError>>,
+    submitted_index: u64,
+    next_index: u64,
+) -> DepositOutcome {
+    match status {
+        Some(Ok(())) if next_index > submitted_index => DepositOutcome::Landed,
+        Some(Err(e)) => DepositOutcome::Failed(e.to_string()),
+        _ => DepositOutcome::Unconfirmed,
+    }
+}
+
+/// Max signatures requested per `getSignaturesForAddress` page (RPC upper bound)
+const SIGNATURE_PAGE_LIMIT: usize = 1000;
+
+/// Rebuild a pool's deposit commitments from its transaction history
+/// Pages backwards with the `before` cursor until `expected` commitments are found or history
+/// is exhausted, returns them in chronological (leaf index) order
+async fn fetch_deposit_commitments(
+    rpc_client: &RpcClient,
+    pool_pda: &Pubkey,
+    expected: u64,
+) -> Result<Vec<[u8; 32]>> {
+    // Collected newest-first while paging, reversed at the end
+    let mut commitments = Vec::new();
+    let mut before: Option<Signature> = None;
+    let mut scanned = 0usize;
+
+    while (commitments.len() as u64) < expected {
+        let page = rpc_read(|| {
+            rpc_client.get_signatures_for_address_with_config(
+                pool_pda,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(SIGNATURE_PAGE_LIMIT),
+                    commitment: None,
+                },
+            )
+        })
+        .await
+        .map_err(|e| {
+            RelayerError::TransactionFailed(format!("Failed to fetch transaction history: {}", e))
+        })?;
+
+        // An empty page means we've reached the pool's first transaction
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(
+            last.signature
+                .parse()
+                .map_err(|e| RelayerError::InvalidRequest(format!("Invalid signature: {}", e)))?,
         );
+        scanned += page.len();
 
-        let signature = self
-            .rpc_client
-            .send_and_confirm_transaction(&transaction)
+        for sig_info in &page {
+            // Skip failed transactions
+            if sig_info.err.is_some() {
+                continue;
+            }
+
+            let signature: Signature = sig_info
+                .signature
+                .parse()
+                .map_err(|e| RelayerError::InvalidRequest(format!("Invalid signature: {}", e)))?;
+
+            // Skipping a deposit would shift every later leaf, so retry any failure (the node
+            // may not serve the transaction yet) and give up on the scan if it persists
+            let tx = with_retry_if(
+                || rpc_client.get_transaction(&signature, UiTransactionEncoding::Json),
+                RPC_READ_ATTEMPTS,
+                RPC_READ_BACKOFF,
+                |_| true,
+            )
             .await
-            .map_err(|e| RelayerError::TransactionFailed(e.to_string()))?;
+            .map_err(|e| {
+                RelayerError::TransactionFailed(format!(
+                    "Failed to fetch transaction {}: {}",
+                    signature, e
+                ))
+            })?;
+            let log_messages: Option<Vec<String>> = tx
+                .transaction
+                .meta
+                .and_then(|meta| meta.log_messages.into());
+            let found = parse_deposit_commitments(&log_messages.unwrap_or_default());
+            for commitment in found.iter().rev() {
+                info!(
+                    "Found commitment from tx {}: {}",
+                    signature,
+                    hex::encode(commitment)
+                );
+            }
+            commitments.extend(found.into_iter().rev());
 
-        Ok(signature.to_string())
+            if commitments.len() as u64 >= expected {
+                break;
+            }
+        }
     }
+
+    info!(
+        "Scanned {} transactions, found {}/{} commitments",
+        scanned,
+        commitments.len(),
+        expected
+    );
+
+    commitments.reverse();
+    Ok(commitments)
+}
+
+/// Extract deposit commitments from program logs, in log order
+/// Reads the `DepositEvent` from `Program data: <base64>` logs, layout:
+/// sha256("event:DepositEvent")[..8] + pool (32) + bucket_id (1) + leaf_index (u64 LE)
+/// + commitment (32) + merkle_root (32)
+///
+/// Falls back to `Deposit: commitment=<hex>` log lines from older deployments
+fn parse_deposit_commitments(logs: &[String]) -> Vec<[u8; 32]> {
+    use base64::Engine;
+
+    let discriminator = &Sha256::digest(b"event:DepositEvent")[..8];
+    let mut commitments = Vec::new();
+    for log in logs {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(data) {
+                if bytes.len() >= 113 && &bytes[..8] == discriminator {
+                    commitments.push(bytes[49..81].try_into().unwrap());
+                }
+            }
+            continue;
+        }
+        if !log.contains("Program log: Deposit: commitment=") {
+            continue;
+        }
+        if let Some(hex_start) = log.find("commitment=") {
+            let hex_str = &log[hex_start + 11..];
+            // Extract 64 hex chars (32 bytes)
+            let Some(commitment_hex) = hex_str.get(..64) else {
+                continue;
+            };
+            match hex::decode(commitment_hex) {
+                Ok(bytes) if bytes.len() == 32 => {
+                    let mut commitment = [0u8; 32];
+                    commitment.copy_from_slice(&bytes);
+                    commitments.push(commitment);
+                }
+                _ => {
+                    warn!("Invalid commitment hex in log: {}", commitment_hex);
+                }
+            }
+        }
+    }
+    commitments
 }
 
 fn anchor_discriminator(name: &str) -> [u8; 8] {
@@ -581,6 +797,10 @@ fn anchor_discriminator(name: &str) -> [u8; 8] {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mock_rpc::{self, with_context, MockRpc};
+    use privacy_proxy_sdk::crypto::u64_to_field;
+    use privacy_proxy_sdk::merkle::{MerkleTree, TREE_DEPTH};
+    use solana_client::rpc_request::RpcRequest;
 
     #[test]
     fn test_anchor_discriminator() {
@@ -590,4 +810,468 @@ mod tests {
         // Different names produce different discriminators
         assert_ne!(disc, anchor_discriminator("withdraw"));
     }
+
+    #[test]
+    fn test_deposit_throttle() {
+        let mut throttle = DepositThrottle::new(3, Duration::from_secs(60));
+        let start = Instant::now();
+
+        // Burst beyond the limit is shed with a retry hint
+        for _ in 0..3 {
+            assert!(throttle.try_acquire(0, start).is_ok());
+        }
+        assert_eq!(throttle.try_acquire(0, start), Err(60));
+        assert_eq!(
+            throttle.try_acquire(0, start + Duration::from_secs(45)),
+            Err(15)
+        );
+
+        // Other buckets are unaffected
+        assert!(throttle.try_acquire(1, start).is_ok());
+
+        // Window slides once the oldest deposits expire
+        assert!(throttle
+            .try_acquire(0, start + Duration::from_secs(60))
+            .is_ok());
+
+        // Steady traffic under the limit always passes
+        let mut steady = DepositThrottle::new(3, Duration::from_secs(60));
+        for i in 0..20 {
+            assert!(steady
+                .try_acquire(2, start + Duration::from_secs(20 * i))
+                .is_ok());
+        }
+
+        // A failed deposit hands its slot back
+        let mut released = DepositThrottle::new(1, Duration::from_secs(60));
+        assert!(released.try_acquire(3, start).is_ok());
+        assert_eq!(released.try_acquire(3, start), Err(60));
+        released.release(3, start);
+        assert!(released.try_acquire(3, start).is_ok());
+    }
+
+    /// Commitment of the deposit at `index` in test histories
+    fn history_commitment(index: usize) -> [u8; 32] {
+        u64_to_field(index as u64)
+    }
+
+    /// RPC double serving a fixed pool history, capping pages below what we request
+    /// `signatures` are in chronological order, the transaction at `unavailable` can't be fetched
+    fn paged_history(
+        signatures: Vec<Signature>,
+        page_cap: usize,
+        unavailable: Option<usize>,
+    ) -> MockRpc {
+        let signatures = Arc::new(signatures);
+        let pages = signatures.clone();
+        MockRpc::new()
+            .on(RpcRequest::GetSignaturesForAddress, move |params| {
+                let config = &params[1];
+                let end = match config["before"].as_str() {
+                    Some(before) => pages
+                        .iter()
+                        .position(|sig| sig.to_string() == before)
+                        .unwrap(),
+                    None => pages.len(),
+                };
+                let limit = config["limit"].as_u64().unwrap() as usize;
+                let start = end.saturating_sub(limit.min(page_cap));
+                let page: Vec<_> = pages[start..end]
+                    .iter()
+                    .rev()
+                    .map(|sig| {
+                        serde_json::json!({
+                            "signature": sig.to_string(),
+                            "slot": 1,
+                            "err": null,
+                            "memo": null,
+                            "blockTime": null,
+                            "confirmationStatus": "finalized",
+                        })
+                    })
+                    .collect();
+                Ok(serde_json::json!(page))
+            })
+            .on(RpcRequest::GetTransaction, move |params| {
+                let sig = params[0].as_str().unwrap();
+                let index = signatures
+                    .iter()
+                    .position(|s| s.to_string() == sig)
+                    .unwrap();
+                if unavailable == Some(index) {
+                    return Err(solana_client::rpc_request::RpcError::ForUser(
+                        "transaction not available".to_string(),
+                    )
+                    .into());
+                }
+                Ok(serde_json::json!({
+                    "slot": 1,
+                    "blockTime": null,
+                    "transaction": "",
+                    "meta": {
+                        "err": null,
+                        "status": { "Ok": null },
+                        "fee": 5000,
+                        "preBalances": [],
+                        "postBalances": [],
+                        "logMessages": [
+                            "Program log: Instruction: Deposit",
+                            format!(
+                                "Program log: Deposit: commitment={}",
+                                hex::encode(history_commitment(index))
+                            ),
+                        ],
+                    },
+                }))
+            })
+    }
+
+    #[tokio::test]
+    async fn test_fetch_deposit_commitments_paginates() {
+        let signatures: Vec<Signature> = (0..120u8)
+            .map(|i| Signature::from([i.wrapping_add(1); 64]))
+            .collect();
+        let rpc_client = paged_history(signatures, 50, None).into_client();
+
+        let commitments = fetch_deposit_commitments(&rpc_client, &Pubkey::new_unique(), 120)
+            .await
+            .unwrap();
+
+        // Full history recovered across 3 pages, in leaf order
+        let expected: Vec<[u8; 32]> = (0..120).map(history_commitment).collect();
+        assert_eq!(commitments, expected);
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let merkle_service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        merkle_service.init_tree(0).await.unwrap();
+        merkle_service
+            .sync_from_chain(0, commitments)
+            .await
+            .unwrap();
+        assert_eq!(merkle_service.size(0).await.unwrap(), 120);
+
+        let mut reference = MerkleTree::new(TREE_DEPTH).unwrap();
+        for commitment in &expected {
+            reference.insert(*commitment).unwrap();
+        }
+        assert_eq!(
+            merkle_service.root(0).await.unwrap(),
+            reference.root().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_fails_on_unfetchable_transaction() {
+        let signatures: Vec<Signature> = (0..5u8).map(|i| Signature::from([i + 1; 64])).collect();
+        // Newest first: 4 and 3 come back, 2 keeps failing
+        let rpc = paged_history(signatures, 50, Some(2));
+        let calls = rpc.calls();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RelayerConfig::for_tests();
+        let blind_signer = Arc::new(
+            BlindSignerService::with_key_path(
+                config.rsa_key_bits,
+                config.rsa_rotation_grace_secs,
+                temp_dir.path().join("signing_key.der"),
+            )
+            .unwrap(),
+        );
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().join("merkle"),
+        ));
+        merkle_service.init_tree(0).await.unwrap();
+        for index in 0..2 {
+            merkle_service
+                .insert(0, history_commitment(index))
+                .await
+                .unwrap();
+        }
+        let root = merkle_service.root(0).await.unwrap();
+        let service = DepositService::with_token_store_path(
+            config,
+            Arc::new(rpc.into_client()),
+            blind_signer,
+            merkle_service.clone(),
+            temp_dir.path().join("tokens.dat"),
+        );
+
+        let err = service.sync_local_tree(0, 5).await.unwrap_err();
+        assert!(matches!(err, RelayerError::TransactionFailed(_)), "{err}");
+        assert_eq!(
+            calls.get(RpcRequest::GetTransaction),
+            2 + RPC_READ_ATTEMPTS as usize
+        );
+
+        // The previous tree is kept rather than rebuilt with a gap
+        assert_eq!(merkle_service.size(0).await.unwrap(), 2);
+        assert_eq!(merkle_service.root(0).await.unwrap(), root);
+    }
+
+    #[test]
+    fn test_parse_deposit_commitments() {
+        let logs = vec![
+            "Program log: Instruction: Deposit".to_string(),
+            format!(
+                "Program log: Deposit: commitment={}",
+                hex::encode([7u8; 32])
+            ),
+            "Program log: Deposit: commitment=deadbeef".to_string(),
+        ];
+        assert_eq!(parse_deposit_commitments(&logs), vec![[7u8; 32]]);
+    }
+
+    #[test]
+    fn test_parse_deposit_events() {
+        use base64::Engine;
+
+        let event = |commitment: [u8; 32]| {
+            let mut data = Sha256::digest(b"event:DepositEvent")[..8].to_vec();
+            data.extend_from_slice(Pubkey::new_unique().as_ref()); // pool
+            data.push(1); // bucket_id
+            data.extend_from_slice(&5u64.to_le_bytes()); // leaf_index
+            data.extend_from_slice(&commitment);
+            data.extend_from_slice(&[9u8; 32]); // merkle_root
+            format!(
+                "Program data: {}",
+                base64::engine::general_purpose::STANDARD.encode(data)
+            )
+        };
+        let mut other_event = Sha256::digest(b"event:WithdrawalRequested")[..8].to_vec();
+        other_event.extend_from_slice(&[0u8; 105]);
+        let logs = vec![
+            "Program log: Instruction: Deposit".to_string(),
+            event([3u8; 32]),
+            format!(
+                "Program data: {}",
+                base64::engine::general_purpose::STANDARD.encode(other_event)
+            ),
+            event([4u8; 32]),
+        ];
+        assert_eq!(parse_deposit_commitments(&logs), vec![[3u8; 32], [4u8; 32]]);
+    }
+
+    /// RPC double for a single pool: serves next_index and accepts a deposit only if it
+    /// targets the note PDA for the current next_index, like the program's `init` would
+    /// `lost_send` fails the send RPC as if the connection dropped after broadcast,
+    /// `Some(true)` if the deposit still landed
+    fn deposit_chain(program_id: Pubkey, pool_pda: Pubkey, lost_send: Option<bool>) -> RpcClient {
+        let next_index = Arc::new(std::sync::Mutex::new(0u64));
+        let pool = next_index.clone();
+        MockRpc::new()
+            .accounts(move |_| {
+                let next_index = *pool.lock().unwrap();
+                let data = DepositPoolView::with_indices(next_index, next_index).to_account_data();
+                Some(mock_rpc::account(&data, &program_id))
+            })
+            .blockhash()
+            .on(RpcRequest::SendTransaction, move |params| {
+                let tx = mock_rpc::sent_transaction(&params);
+                let keys = tx.message.static_account_keys();
+                let ix = tx
+                    .message
+                    .instructions()
+                    .iter()
+                    .find(|ix| keys[ix.program_id_index as usize] == program_id)
+                    .unwrap();
+                let historical_roots_pda = keys[ix.accounts[3] as usize];
+                let note_pda = keys[ix.accounts[5] as usize];
+                let used_commitment_pda = keys[ix.accounts[6] as usize];
+                // discriminator (8) + bucket_id (1), then the commitment
+                let (expected, _) = Pubkey::find_program_address(
+                    &[b"used_commitment", &ix.data[9..41]],
+                    &program_id,
+                );
+                assert_eq!(
+                    used_commitment_pda, expected,
+                    "deposit must create the commitment's used_commitment record"
+                );
+
+                let mut next_index = next_index.lock().unwrap();
+                let (expected, _) = Pubkey::find_program_address(
+                    &[b"note", pool_pda.as_ref(), &next_index.to_le_bytes()],
+                    &program_id,
+                );
+                if note_pda != expected {
+                    return Err(solana_client::rpc_request::RpcError::ForUser(
+                        "note account already in use".to_string(),
+                    )
+                    .into());
+                }
+                let (expected, _) = Pubkey::find_program_address(
+                    &[
+                        b"historical_roots",
+                        pool_pda.as_ref(),
+                        &[historical_roots_account_index(*next_index)],
+                    ],
+                    &program_id,
+                );
+                assert_eq!(
+                    historical_roots_pda, expected,
+                    "deposit must write to the chained roots account for its leaf"
+                );
+                if lost_send != Some(false) {
+                    *next_index += 1;
+                }
+                match lost_send {
+                    None => Ok(serde_json::json!(tx.signatures[0].to_string())),
+                    Some(_) => Err(solana_client::rpc_request::RpcError::ForUser(
+                        "connection reset".to_string(),
+                    )
+                    .into()),
+                }
+            })
+            .on(RpcRequest::GetSignatureStatuses, move |_| {
+                let status = if lost_send == Some(false) {
+                    serde_json::Value::Null
+                } else {
+                    mock_rpc::finalized_status()
+                };
+                Ok(with_context(serde_json::json!([status])))
+            })
+            .into_client()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_deposits_get_unique_indices() {
+        // Enough to roll over into the second HistoricalRoots account
+        const DEPOSITS: usize = 12;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RelayerConfig::for_tests();
+        let bucket_id = 2u8;
+        let (pool_pda, _) =
+            Pubkey::find_program_address(&[b"pool", &[bucket_id]], &config.program_id);
+
+        let rpc_client = Arc::new(deposit_chain(config.program_id, pool_pda, None));
+        let blind_signer = Arc::new(
+            BlindSignerService::with_key_path(
+                config.rsa_key_bits,
+                config.rsa_rotation_grace_secs,
+                temp_dir.path().join("signing_key.der"),
+            )
+            .unwrap(),
+        );
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().join("merkle"),
+        ));
+        merkle_service.init_tree(bucket_id).await.unwrap();
+        let amount = config.bucket_amount(bucket_id).unwrap();
+        let service = Arc::new(DepositService::with_token_store_path(
+            config,
+            rpc_client,
+            blind_signer.clone(),
+            merkle_service,
+            temp_dir.path().join("tokens.dat"),
+        ));
+
+        let mut handles = Vec::new();
+        for i in 0..DEPOSITS {
+            let token_id = [i as u8 + 1; 32];
+            // Signing the token hash directly yields the unblinded signature
+            let signature = blind_signer
+                .sign_blinded(&Sha256::digest(token_id))
+                .await
+                .unwrap();
+            let request = DepositRequest {
+                credit: SignedCredit {
+                    token_id,
+                    signature,
+                    amount,
+                },
+                commitment: history_commitment(i + 1),
+                encrypted_note: None,
+                note_ephemeral_pubkey: None,
+            };
+            let service = service.clone();
+            handles.push(tokio::spawn(async move {
+                service.handle_deposit(request).await
+            }));
+        }
+
+        let mut indices = Vec::new();
+        for handle in handles {
+            let response = handle.await.unwrap().unwrap();
+            indices.push(response.leaf_index.unwrap());
+        }
+        indices.sort();
+        assert_eq!(indices, (0..DEPOSITS as u64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_deposit_outcome() {
+        use solana_sdk::transaction::TransactionError;
+
+        assert_eq!(deposit_outcome(Some(Ok(())), 4, 5), DepositOutcome::Landed);
+        assert_eq!(
+            deposit_outcome(Some(Err(TransactionError::AccountInUse)), 4, 4),
+            DepositOutcome::Failed(TransactionError::AccountInUse.to_string())
+        );
+        // Status lagging behind, or reported by a node that hasn't seen the pool update
+        assert_eq!(deposit_outcome(None, 4, 4), DepositOutcome::Unconfirmed);
+        assert_eq!(
+            deposit_outcome(Some(Ok(())), 4, 4),
+            DepositOutcome::Unconfirmed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lost_send_response_spends_credit_only_if_deposit_landed() {
+        for landed in [false, true] {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let config = RelayerConfig::for_tests();
+            let bucket_id = 2u8;
+            let (pool_pda, _) =
+                Pubkey::find_program_address(&[b"pool", &[bucket_id]], &config.program_id);
+            let rpc_client = Arc::new(deposit_chain(config.program_id, pool_pda, Some(landed)));
+            let blind_signer = Arc::new(
+                BlindSignerService::with_key_path(
+                    config.rsa_key_bits,
+                    config.rsa_rotation_grace_secs,
+                    temp_dir.path().join("signing_key.der"),
+                )
+                .unwrap(),
+            );
+            let merkle_service = Arc::new(MerkleService::with_persistence_path(
+                temp_dir.path().join("merkle"),
+            ));
+            merkle_service.init_tree(bucket_id).await.unwrap();
+            let amount = config.bucket_amount(bucket_id).unwrap();
+            let service = DepositService::with_token_store_path(
+                config,
+                rpc_client,
+                blind_signer.clone(),
+                merkle_service,
+                temp_dir.path().join("tokens.dat"),
+            );
+
+            let token_id = [9u8; 32];
+            let signature = blind_signer
+                .sign_blinded(&Sha256::digest(token_id))
+                .await
+                .unwrap();
+            let request = DepositRequest {
+                credit: SignedCredit {
+                    token_id,
+                    signature,
+                    amount,
+                },
+                commitment: history_commitment(1),
+                encrypted_note: None,
+                note_ephemeral_pubkey: None,
+            };
+
+            let result = service.handle_deposit(request).await;
+            let token_spent = service
+                .check_token_not_used(&hash_token_id(&token_id))
+                .await
+                .is_err();
+            if landed {
+                assert_eq!(result.unwrap().leaf_index, Some(0));
+                assert!(token_spent);
+            } else {
+                assert!(matches!(result, Err(RelayerError::DepositUnconfirmed(_))));
+                assert!(!token_spent);
+            }
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -25,20 +25,93 @@ pub enum RelayerError {
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Proof amount {amount} does not match bucket {bucket_id} amount {expected}")]
+    AmountMismatch {
+        bucket_id: u8,
+        amount: u64,
+        expected: u64,
+    },
+
     #[error("Merkle tree error: {0}")]
     MerkleTree(String),
 
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
 
+    #[error("Deposit {0} could not be confirmed, credit was not spent, retry later")]
+    DepositUnconfirmed(String),
+
+    #[error("Payment {0} not confirmed yet, retry later")]
+    PaymentPending(String),
+
     #[error("Cryptographic error: {0}")]
     Crypto(String),
 
     #[error("Internal error: {0}")]
     Internal(String),
 
+    #[error("Pool for bucket {0} is paused")]
+    PoolPaused(u8),
+
+    #[error("Pool for bucket {0} has reached its deposit cap")]
+    PoolFull(u8),
+
+    #[error("A withdrawal for this nullifier is already pending")]
+    WithdrawalAlreadyPending,
+
+    #[error("Merkle root not found within {0} historical roots accounts")]
+    RootTooOld(usize),
+
+    #[error("Merkle root was replaced more than {0} hours ago")]
+    RootExpired(u16),
+
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    #[error("Rate limit exceeded, retry after {0}s")]
+    RateLimited(u64),
+
     #[error("Solana client error: {0}")]
-    SolanaClient(#[from] solana_client::client_error::ClientError),
+    SolanaClient(Box<solana_client::client_error::ClientError>),
+}
+
+impl From<solana_client::client_error::ClientError> for RelayerError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        RelayerError::SolanaClient(Box::new(e))
+    }
+}
+
+impl RelayerError {
+    /// Stable machine-readable code sent alongside the message, clients branch on this
+    /// Never rename an existing code, add a new variant instead
+    pub fn code(&self) -> &'static str {
+        match self {
+            RelayerError::InvalidBlindedToken => "invalid_blinded_token",
+            RelayerError::InvalidSignature => "invalid_signature",
+            RelayerError::TokenAlreadyRedeemed => "token_already_redeemed",
+            RelayerError::InvalidBucket(_) => "invalid_bucket",
+            RelayerError::InvalidRequest(_) => "invalid_request",
+            RelayerError::NotFound(_) => "not_found",
+            RelayerError::AmountMismatch { .. } => "amount_mismatch",
+            RelayerError::MerkleTree(_) => "merkle_tree",
+            RelayerError::TransactionFailed(_) => "transaction_failed",
+            RelayerError::DepositUnconfirmed(_) => "deposit_unconfirmed",
+            RelayerError::PaymentPending(_) => "payment_pending",
+            RelayerError::Crypto(_) => "crypto",
+            RelayerError::Internal(_) => "internal",
+            RelayerError::PoolPaused(_) => "pool_paused",
+            RelayerError::PoolFull(_) => "pool_full",
+            RelayerError::WithdrawalAlreadyPending => "withdrawal_already_pending",
+            RelayerError::RootTooOld(_) => "root_too_old",
+            RelayerError::RootExpired(_) => "root_expired",
+            RelayerError::Unauthorized => "unauthorized",
+            RelayerError::RateLimited(_) => "rate_limited",
+            RelayerError::SolanaClient(_) => "solana_client",
+        }
+    }
 }
 
 impl IntoResponse for RelayerError {
@@ -49,20 +122,104 @@ impl IntoResponse for RelayerError {
             RelayerError::TokenAlreadyRedeemed => (StatusCode::CONFLICT, self.to_string()),
             RelayerError::InvalidBucket(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             RelayerError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            RelayerError::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
+            RelayerError::AmountMismatch { .. } => (StatusCode::BAD_REQUEST, self.to_string()),
             RelayerError::MerkleTree(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
             RelayerError::TransactionFailed(_) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, self.to_string())
             }
             RelayerError::Crypto(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
             RelayerError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
+            RelayerError::RootTooOld(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            RelayerError::RootExpired(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            RelayerError::PoolPaused(_) => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            RelayerError::PoolFull(_) => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
+            RelayerError::WithdrawalAlreadyPending => (StatusCode::CONFLICT, self.to_string()),
+            RelayerError::Unauthorized => (StatusCode::UNAUTHORIZED, self.to_string()),
+            RelayerError::RateLimited(_) => (StatusCode::TOO_MANY_REQUESTS, self.to_string()),
+            RelayerError::DepositUnconfirmed(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
+            }
+            RelayerError::PaymentPending(_) => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
             RelayerError::SolanaClient(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
 
         let body = Json(json!({
             "success": false,
             "error": message,
+            "code": self.code(),
         }));
 
+        if let RelayerError::RateLimited(retry_after) = self {
+            return (
+                status,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                body,
+            )
+                .into_response();
+        }
+
         (status, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_error_codes_are_unique() {
+        let errors = [
+            RelayerError::InvalidBlindedToken,
+            RelayerError::InvalidSignature,
+            RelayerError::TokenAlreadyRedeemed,
+            RelayerError::InvalidBucket(0),
+            RelayerError::InvalidRequest(String::new()),
+            RelayerError::NotFound(String::new()),
+            RelayerError::AmountMismatch {
+                bucket_id: 0,
+                amount: 0,
+                expected: 0,
+            },
+            RelayerError::MerkleTree(String::new()),
+            RelayerError::TransactionFailed(String::new()),
+            RelayerError::DepositUnconfirmed(String::new()),
+            RelayerError::PaymentPending(String::new()),
+            RelayerError::Crypto(String::new()),
+            RelayerError::Internal(String::new()),
+            RelayerError::PoolPaused(0),
+            RelayerError::PoolFull(0),
+            RelayerError::WithdrawalAlreadyPending,
+            RelayerError::RootTooOld(0),
+            RelayerError::RootExpired(0),
+            RelayerError::Unauthorized,
+            RelayerError::RateLimited(0),
+        ];
+        let codes: HashSet<_> = errors.iter().map(RelayerError::code).collect();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(
+            RelayerError::from(solana_client::client_error::ClientError::from(
+                std::io::Error::other("rpc")
+            ))
+            .code()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_error_body_carries_code() {
+        let response = RelayerError::TokenAlreadyRedeemed.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["code"], "token_already_redeemed");
+        assert_eq!(body["error"], "Token already redeemed");
+
+        // Lookups of something the relayer doesn't know are 404, not a bad request
+        let response = RelayerError::NotFound("withdrawal".into()).into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,54 +1,209 @@
+// MockRpc handlers are closures returning solana_client's large ClientError
+#![cfg_attr(test, allow(clippy::result_large_err))]
+
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
 use tracing::info;
-use tracing::warn;
+use tracing::{error, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod auth;
 mod blind_signer;
 mod config;
 mod deposit;
 mod encryption;
 mod error;
 mod merkle_service;
+#[cfg(test)]
+mod mock_rpc;
+mod on_chain;
+mod params;
+mod payment;
+mod poller;
+mod rpc;
 mod server;
+mod tx;
+mod webhook;
 mod withdrawal;
 
 use config::RelayerConfig;
+use poller::{TickGuard, TickOutcome};
 use server::RelayerState;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "relayer=info,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    dotenvy::dotenv().ok();
+
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "relayer=info,tower_http=debug".into());
+    // LOG_FORMAT=json emits one JSON object per event for log aggregators
+    let json_logs = std::env::var("LOG_FORMAT").is_ok_and(|f| f.eq_ignore_ascii_case("json"));
+    let registry = tracing_subscriber::registry().with(filter);
+    if json_logs {
+        registry
+            .with(tracing_subscriber::fmt::layer().json())
+            .init();
+    } else {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    }
 
     info!("Starting Privacy-Proxy Relayer");
 
-    dotenvy::dotenv().ok();
     let config = RelayerConfig::from_env()?;
 
-    info!("RPC endpoint: {}", config.rpc_url);
+    if config.rpc_read_url == config.rpc_write_url {
+        info!("RPC endpoint: {}", config.rpc_read_url);
+    } else {
+        info!(
+            "RPC endpoints: reads {}, sends {}",
+            config.rpc_read_url, config.rpc_write_url
+        );
+    }
+    info!("RPC commitment: {:?}", config.rpc_commitment.commitment);
     info!("Listening on: {}:{}", config.host, config.port);
 
     let state = Arc::new(RelayerState::new(config).await?);
+    let shutdown = CancellationToken::new();
+    tokio::spawn(shutdown_signal(shutdown.clone()));
+
     let poll_state = state.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+    let poll_shutdown = shutdown.clone();
+    let poller = tokio::spawn(async move {
+        let deadline = Duration::from_secs(poll_state.config.poll_tick_deadline_secs);
+        let guard = TickGuard::new();
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(poll_state.config.poll_interval_secs));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
         loop {
-            interval.tick().await;
-            let results = poll_state.withdrawal_service.poll_and_execute().await;
-            for (recipient, result) in &results {
-                match result {
-                    Ok(tx) => info!("✓ Auto-executed withdrawal to {}: {}", recipient, tx),
-                    Err(e) => warn!("✗ Failed auto-execute to {}: {}", recipient, e),
+            // Only check for cancellation between ticks so a running poll can finish its transactions
+            tokio::select! {
+                _ = poll_shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            let tick_state = poll_state.clone();
+            let outcome = guard
+                .run(deadline, async move {
+                    let results = tick_state.withdrawal_service.poll_and_execute().await;
+                    for (recipient, result) in &results {
+                        match result {
+                            Ok(tx) => info!("✓ Auto-executed withdrawal to {}: {}", recipient, tx),
+                            Err(e) => warn!("✗ Failed auto-execute to {}: {}", recipient, e),
+                        }
+                    }
+                })
+                .await;
+            match outcome {
+                TickOutcome::Completed(()) => {}
+                TickOutcome::Overran => warn!(
+                    "⚠ Withdrawal poll exceeded {}s deadline, skipping ticks until it finishes",
+                    deadline.as_secs()
+                ),
+                TickOutcome::Skipped => {
+                    warn!("⚠ Previous withdrawal poll still running, skipping tick")
                 }
+                TickOutcome::Panicked => error!("✗ Withdrawal poll panicked"),
             }
         }
+        guard.wait_idle().await;
+        info!("Withdrawal poller stopped");
     });
 
-    server::run(state).await?;
+    let integrity_checker = (state.config.merkle_integrity_interval_secs > 0)
+        .then(|| tokio::spawn(run_integrity_checks(state.clone(), shutdown.clone())));
+
+    server::run(state.clone(), shutdown.clone()).await?;
+
+    // Server has drained; make sure the poller is done before flushing state
+    shutdown.cancel();
+    if let Err(e) = poller.await {
+        error!("Withdrawal poller panicked: {}", e);
+    }
+    if let Some(checker) = integrity_checker {
+        if let Err(e) = checker.await {
+            error!("Merkle integrity checker panicked: {}", e);
+        }
+    }
+
+    if let Err(e) = state.deposit_service.flush().await {
+        error!("Failed to flush token store: {}", e);
+    }
+    if let Err(e) = state.merkle_service.flush().await {
+        error!("Failed to flush merkle state: {}", e);
+    }
+
+    info!("Relayer shut down cleanly");
     Ok(())
 }
+
+/// Periodically verify each bucket's merkle state, optionally resyncing corrupted ones from chain
+async fn run_integrity_checks(state: Arc<RelayerState>, shutdown: CancellationToken) {
+    let period = Duration::from_secs(state.config.merkle_integrity_interval_secs);
+    info!(
+        "Merkle integrity check every {}s",
+        state.config.merkle_integrity_interval_secs
+    );
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        for bucket_id in state.config.bucket_ids() {
+            match state.merkle_service.verify_integrity(bucket_id).await {
+                Ok(true) => {}
+                Ok(false) if state.config.merkle_resync_on_corruption => {
+                    warn!(
+                        "⚠ Merkle state for bucket {} corrupted, resyncing from chain",
+                        bucket_id
+                    );
+                    if let Err(e) = state.deposit_service.resync_bucket(bucket_id).await {
+                        error!("✗ Resync of bucket {} failed: {}", bucket_id, e);
+                    }
+                }
+                Ok(false) => {
+                    error!(
+                        "✗ Merkle state for bucket {} corrupted! Proofs may be wrong until it is restored",
+                        bucket_id
+                    );
+                }
+                Err(e) => warn!("Integrity check for bucket {} failed: {}", bucket_id, e),
+            }
+        }
+    }
+}
+
+/// Cancel `shutdown` on Ctrl+C or SIGTERM
+async fn shutdown_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    info!("Shutdown signal received, draining requests...");
+    shutdown.cancel();
+}
@@ -1,9 +1,10 @@
 use privacy_proxy_sdk::merkle::{MerkleProof, MerkleTree, TREE_DEPTH};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
@@ -19,7 +20,7 @@ impl TreeState {
     fn compute_checksum(commitments: &[[u8; 32]]) -> [u8; 32] {
         let mut hasher = Sha256::new();
         hasher.update(b"merkle_tree_state_v1:");
-        hasher.update(&(commitments.len() as u64).to_le_bytes());
+        hasher.update((commitments.len() as u64).to_le_bytes());
         for commitment in commitments {
             hasher.update(commitment);
         }
@@ -43,10 +44,114 @@ impl TreeState {
     }
 }
 
+/// Persist a rename in `dir`, directories can't be opened for syncing outside unix
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Atomically replace the state file at `path` in `dir` with `commitments`
+fn write_state(dir: &Path, path: &Path, commitments: Vec<[u8; 32]>) -> Result<()> {
+    let state = TreeState::new(commitments);
+    let json = serde_json::to_string_pretty(&state)
+        .map_err(|e| RelayerError::Internal(format!("Serialize failed: {}", e)))?;
+
+    let temp_path = path.with_extension("tmp");
+
+    // Data must be on disk before the rename makes it the live state, and the rename
+    // itself durable, or a crash can leave an empty or stale file behind
+    {
+        use std::io::Write;
+        let mut file = std::fs::File::create(&temp_path)
+            .map_err(|e| RelayerError::Internal(format!("Write failed: {}", e)))?;
+        file.write_all(json.as_bytes())
+            .map_err(|e| RelayerError::Internal(format!("Write failed: {}", e)))?;
+        file.sync_all()
+            .map_err(|e| RelayerError::Internal(format!("Sync failed: {}", e)))?;
+    }
+    std::fs::rename(&temp_path, path)
+        .map_err(|e| RelayerError::Internal(format!("Rename failed: {}", e)))?;
+    sync_dir(dir).map_err(|e| RelayerError::Internal(format!("Directory sync failed: {}", e)))?;
+
+    Ok(())
+}
+
+/// Proofs kept across all buckets, enough for a recovery client's batch
+const PROOF_CACHE_CAPACITY: usize = 1024;
+
+type ProofKey = (u8, u64, [u8; 32]);
+
+/// Least recently used proofs by (bucket, leaf index, root), plus each bucket's root
+/// Computing either walks the whole tree. A bucket is invalidated whenever its tree changes,
+/// proofs against an older root would never be asked for again
+#[derive(Default)]
+struct ProofCache {
+    roots: HashMap<u8, [u8; 32]>,
+    proofs: HashMap<ProofKey, MerkleProof>,
+    /// Keys from least to most recently used
+    order: VecDeque<ProofKey>,
+}
+
+impl ProofCache {
+    fn get(&mut self, key: &ProofKey) -> Option<MerkleProof> {
+        let proof = self.proofs.get(key)?.clone();
+        self.order.retain(|k| k != key);
+        self.order.push_back(*key);
+        Some(proof)
+    }
+
+    fn put(&mut self, key: ProofKey, proof: MerkleProof) {
+        if self.proofs.insert(key, proof).is_some() {
+            self.order.retain(|k| *k != key);
+        } else if self.proofs.len() > PROOF_CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.proofs.remove(&oldest);
+            }
+        }
+        self.order.push_back(key);
+    }
+
+    fn invalidate(&mut self, bucket_id: u8) {
+        self.roots.remove(&bucket_id);
+        self.proofs.retain(|(bucket, _, _), _| *bucket != bucket_id);
+        self.order.retain(|(bucket, _, _)| *bucket != bucket_id);
+    }
+}
+
+/// A bucket's tree and the commitments it was built from, in leaf order
+struct BucketState {
+    tree: MerkleTree,
+    commitments: Vec<[u8; 32]>,
+    /// Leaf index of each commitment, the first one if it appears more than once
+    indices: HashMap<[u8; 32], u64>,
+}
+
+impl BucketState {
+    fn new(commitments: Vec<[u8; 32]>) -> Result<Self> {
+        let mut indices = HashMap::with_capacity(commitments.len());
+        for (index, commitment) in commitments.iter().enumerate() {
+            indices.entry(*commitment).or_insert(index as u64);
+        }
+        Ok(Self {
+            tree: build_tree(&commitments)?,
+            commitments,
+            indices,
+        })
+    }
+}
+
+type Bucket = Arc<RwLock<BucketState>>;
+
 /// Merkle tree service managing trees for all pools
+/// Each bucket has its own lock, so deposits into different pools don't wait on each other.
+/// The map lock is only held to look a bucket up or add one
 pub struct MerkleService {
-    trees: Arc<RwLock<HashMap<u8, MerkleTree>>>,
-    commitments: Arc<RwLock<HashMap<u8, Vec<[u8; 32]>>>>,
+    buckets: RwLock<HashMap<u8, Bucket>>,
+    /// A bucket's entries are only touched while holding its lock, so they always match it
+    proof_cache: Mutex<ProofCache>,
     persistence_path: PathBuf,
 }
 
@@ -55,13 +160,17 @@ impl MerkleService {
         let persistence_path = std::env::var("MERKLE_STATE_PATH")
             .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("merkle_state"));
+        Self::with_persistence_path(persistence_path)
+    }
+
+    pub fn with_persistence_path(persistence_path: PathBuf) -> Self {
         if let Err(e) = std::fs::create_dir_all(&persistence_path) {
             warn!("Failed to create merkle state directory: {}", e);
         }
 
         Self {
-            trees: Arc::new(RwLock::new(HashMap::new())),
-            commitments: Arc::new(RwLock::new(HashMap::new())),
+            buckets: RwLock::new(HashMap::new()),
+            proof_cache: Mutex::new(ProofCache::default()),
             persistence_path,
         }
     }
@@ -106,74 +215,77 @@ impl MerkleService {
         }
     }
 
-    async fn save_state(&self, bucket_id: u8) -> Result<()> {
-        let commitments = self.commitments.read().await;
-        let bucket_commitments = commitments.get(&bucket_id).cloned().unwrap_or_default();
-        drop(commitments);
-
-        let state = TreeState::new(bucket_commitments);
-        let json = serde_json::to_string_pretty(&state)
-            .map_err(|e| RelayerError::Internal(format!("Serialize failed: {}", e)))?;
-
+    /// Write a bucket's commitments, with the bucket locked so its writes happen in order
+    /// Serializing and syncing run on the blocking pool, on a snapshot of the commitments
+    async fn save_state(&self, bucket_id: u8, commitments: Vec<[u8; 32]>) -> Result<()> {
+        let dir = self.persistence_path.clone();
         let path = self.state_file_path(bucket_id);
-        let temp_path = path.with_extension("tmp");
-
-        std::fs::write(&temp_path, &json)
-            .map_err(|e| RelayerError::Internal(format!("Write failed: {}", e)))?;
-        std::fs::rename(&temp_path, &path)
-            .map_err(|e| RelayerError::Internal(format!("Rename failed: {}", e)))?;
-
-        Ok(())
+        tokio::task::spawn_blocking(move || write_state(&dir, &path, commitments))
+            .await
+            .map_err(|e| RelayerError::Internal(format!("State write task failed: {}", e)))?
     }
 
     pub async fn init_tree(&self, bucket_id: u8) -> Result<()> {
-        {
-            let trees = self.trees.read().await;
-            if trees.contains_key(&bucket_id) {
-                return Ok(());
-            }
+        if self.is_initialized(bucket_id).await {
+            return Ok(());
         }
 
-        let mut tree =
-            MerkleTree::new(TREE_DEPTH).map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
-
-        if let Some(saved) = self.load_state(bucket_id).await {
-            for commitment in &saved {
-                tree.insert(*commitment)
-                    .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        let saved = self.load_state(bucket_id).await;
+        let restored = saved.is_some();
+        let state = BucketState::new(saved.unwrap_or_default())?;
+        match self.buckets.write().await.entry(bucket_id) {
+            // Initialized concurrently, keep what's there
+            Entry::Occupied(_) => return Ok(()),
+            Entry::Vacant(entry) => {
+                entry.insert(Arc::new(RwLock::new(state)));
             }
-            let mut trees = self.trees.write().await;
-            let mut commitments = self.commitments.write().await;
-            trees.insert(bucket_id, tree);
-            commitments.insert(bucket_id, saved);
+        }
+        if restored {
             info!("Restored Merkle tree for bucket {} from disk", bucket_id);
         } else {
-            let mut trees = self.trees.write().await;
-            let mut commitments = self.commitments.write().await;
-            trees.insert(bucket_id, tree);
-            commitments.insert(bucket_id, Vec::new());
             info!("Initialized new Merkle tree for bucket {}", bucket_id);
         }
         Ok(())
     }
 
-    pub async fn insert(&self, bucket_id: u8, commitment: [u8; 32]) -> Result<u64> {
-        let mut trees = self.trees.write().await;
-        let mut commitments = self.commitments.write().await;
-
-        let tree = trees.get_mut(&bucket_id).ok_or_else(|| {
-            RelayerError::MerkleTree(format!("Tree not initialized: {}", bucket_id))
-        })?;
+    pub async fn is_initialized(&self, bucket_id: u8) -> bool {
+        self.buckets.read().await.contains_key(&bucket_id)
+    }
 
-        let index = tree
-            .insert(commitment)
-            .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
-        commitments.entry(bucket_id).or_default().push(commitment);
+    async fn bucket(&self, bucket_id: u8) -> Result<Bucket> {
+        self.buckets
+            .read()
+            .await
+            .get(&bucket_id)
+            .cloned()
+            .ok_or_else(|| RelayerError::MerkleTree(format!("Tree not initialized: {}", bucket_id)))
+    }
 
-        drop(trees);
-        drop(commitments);
+    /// Append `commitment` to the bucket's tree and return its leaf index
+    /// Idempotent: a commitment already in the tree (a retried deposit) keeps its index and
+    /// the tree is left untouched
+    pub async fn insert(&self, bucket_id: u8, commitment: [u8; 32]) -> Result<u64> {
+        let bucket = self.bucket(bucket_id).await?;
+        let mut state = bucket.write().await;
+        if let Some(&index) = state.indices.get(&commitment) {
+            warn!(
+                "Commitment already at index {} in bucket {}, not inserting again",
+                index, bucket_id
+            );
+            return Ok(index);
+        }
 
-        if let Err(e) = self.save_state(bucket_id).await {
+        let index = state
+            .tree
+            .insert(commitment)
+            .map_err(|e| insert_error(bucket_id, e))?;
+        state.commitments.push(commitment);
+        state.indices.insert(commitment, index);
+        self.invalidate_proofs(bucket_id);
+
+        // Readers may go on, the next insert into this bucket waits until this one is saved
+        let state = state.downgrade();
+        if let Err(e) = self.save_state(bucket_id, state.commitments.clone()).await {
             error!("Failed to persist state for bucket {}: {}", bucket_id, e);
         }
 
@@ -185,21 +297,50 @@ impl MerkleService {
     }
 
     pub async fn root(&self, bucket_id: u8) -> Result<[u8; 32]> {
-        let trees = self.trees.read().await;
-        let tree = trees.get(&bucket_id).ok_or_else(|| {
-            RelayerError::MerkleTree(format!("Tree not initialized: {}", bucket_id))
-        })?;
-        tree.root()
-            .map_err(|e| RelayerError::MerkleTree(e.to_string()))
+        let bucket = self.bucket(bucket_id).await?;
+        let state = bucket.read().await;
+        self.cached_root(bucket_id, &state.tree)
     }
 
+    /// Proofs are cached until the bucket's tree next changes
     pub async fn proof(&self, bucket_id: u8, leaf_index: u64) -> Result<MerkleProof> {
-        let trees = self.trees.read().await;
-        let tree = trees.get(&bucket_id).ok_or_else(|| {
-            RelayerError::MerkleTree(format!("Tree not initialized: {}", bucket_id))
-        })?;
-        tree.proof(leaf_index)
-            .map_err(|e| RelayerError::MerkleTree(e.to_string()))
+        let bucket = self.bucket(bucket_id).await?;
+        let state = bucket.read().await;
+        let tree = &state.tree;
+        let key = (bucket_id, leaf_index, self.cached_root(bucket_id, tree)?);
+        if let Some(proof) = self.lock_proof_cache().get(&key) {
+            return Ok(proof);
+        }
+
+        let proof = tree
+            .proof(leaf_index)
+            .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        self.lock_proof_cache().put(key, proof.clone());
+        Ok(proof)
+    }
+
+    /// `tree`'s root, computed once per tree state. The caller holds the bucket's lock
+    fn cached_root(&self, bucket_id: u8, tree: &MerkleTree) -> Result<[u8; 32]> {
+        if let Some(root) = self.lock_proof_cache().roots.get(&bucket_id) {
+            return Ok(*root);
+        }
+        let root = tree
+            .root()
+            .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        self.lock_proof_cache().roots.insert(bucket_id, root);
+        Ok(root)
+    }
+
+    /// Drop a bucket's cached root and proofs, with the bucket write-locked
+    fn invalidate_proofs(&self, bucket_id: u8) {
+        self.lock_proof_cache().invalidate(bucket_id);
+    }
+
+    fn lock_proof_cache(&self) -> std::sync::MutexGuard<'_, ProofCache> {
+        // The cache is rebuilt on demand, a panic mid-update can't leave it worse than empty
+        self.proof_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
     #[allow(dead_code)]
@@ -214,19 +355,113 @@ impl MerkleService {
     }
 
     pub async fn size(&self, bucket_id: u8) -> Result<usize> {
-        let trees = self.trees.read().await;
-        let tree = trees.get(&bucket_id).ok_or_else(|| {
-            RelayerError::MerkleTree(format!("Tree not initialized: {}", bucket_id))
-        })?;
-        Ok(tree.len())
+        let bucket = self.bucket(bucket_id).await?;
+        let len = bucket.read().await.tree.len();
+        Ok(len)
     }
 
     pub async fn get_commitments(&self, bucket_id: u8) -> Result<Vec<[u8; 32]>> {
-        let commitments = self.commitments.read().await;
-        let bucket_commitments = commitments.get(&bucket_id).ok_or_else(|| {
-            RelayerError::MerkleTree(format!("Tree not initialized: {}", bucket_id))
-        })?;
-        Ok(bucket_commitments.clone())
+        let bucket = self.bucket(bucket_id).await?;
+        let commitments = bucket.read().await.commitments.clone();
+        Ok(commitments)
+    }
+
+    /// Check a bucket's in-memory tree against its commitments and against the persisted file
+    /// Returns false (logging the reason) if either has diverged
+    pub async fn verify_integrity(&self, bucket_id: u8) -> Result<bool> {
+        let (snapshot, root) = {
+            let bucket = self.bucket(bucket_id).await?;
+            let state = bucket.read().await;
+            let root = state
+                .tree
+                .root()
+                .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+            (state.commitments.clone(), root)
+        };
+
+        let mut rebuilt =
+            MerkleTree::new(TREE_DEPTH).map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        for commitment in &snapshot {
+            rebuilt
+                .insert(*commitment)
+                .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        }
+        let rebuilt_root = rebuilt
+            .root()
+            .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        if rebuilt_root != root {
+            error!(
+                "Integrity check failed for bucket {}: in-memory root {} does not match its commitments ({})",
+                bucket_id,
+                hex::encode(root),
+                hex::encode(rebuilt_root)
+            );
+            return Ok(false);
+        }
+
+        let path = self.state_file_path(bucket_id);
+        if !path.exists() {
+            // Empty trees are never written
+            if snapshot.is_empty() {
+                return Ok(true);
+            }
+            error!(
+                "Integrity check failed for bucket {}: state file missing",
+                bucket_id
+            );
+            return Ok(false);
+        }
+
+        let persisted = match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| serde_json::from_str::<TreeState>(&data).map_err(|e| e.to_string()))
+        {
+            Ok(state) if state.verify() => state.commitments,
+            Ok(_) => {
+                error!(
+                    "Integrity check failed for bucket {}: checksum mismatch on disk",
+                    bucket_id
+                );
+                return Ok(false);
+            }
+            Err(e) => {
+                error!(
+                    "Integrity check failed for bucket {}: unreadable state file: {}",
+                    bucket_id, e
+                );
+                return Ok(false);
+            }
+        };
+
+        // An insert that just landed in memory may not have been written yet
+        if !snapshot.starts_with(&persisted) {
+            error!(
+                "Integrity check failed for bucket {}: disk has {} commitments that diverge from memory ({})",
+                bucket_id,
+                persisted.len(),
+                snapshot.len()
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Persist every tree, used on shutdown to catch any earlier failed writes
+    pub async fn flush(&self) -> Result<()> {
+        let buckets: Vec<(u8, Bucket)> = self
+            .buckets
+            .read()
+            .await
+            .iter()
+            .map(|(bucket_id, bucket)| (*bucket_id, bucket.clone()))
+            .collect();
+        for (bucket_id, bucket) in buckets {
+            let state = bucket.read().await;
+            self.save_state(bucket_id, state.commitments.clone())
+                .await?;
+        }
+        Ok(())
     }
 
     pub async fn sync_from_chain(
@@ -244,30 +479,81 @@ impl MerkleService {
             return Ok(());
         }
 
-        let mut tree =
-            MerkleTree::new(TREE_DEPTH).map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
-        for commitment in &on_chain_commitments {
-            tree.insert(*commitment)
-                .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
-        }
+        self.rebuild(bucket_id, on_chain_commitments).await
+    }
 
-        let mut trees = self.trees.write().await;
-        let mut commitments = self.commitments.write().await;
-        trees.insert(bucket_id, tree);
-        commitments.insert(bucket_id, on_chain_commitments.clone());
-        drop(trees);
-        drop(commitments);
+    /// Replace a bucket's tree with one built from `on_chain_commitments`, even if sizes match
+    pub async fn rebuild(&self, bucket_id: u8, on_chain_commitments: Vec<[u8; 32]>) -> Result<()> {
+        let state = BucketState::new(on_chain_commitments)?;
+        self.replace_tree(bucket_id, state).await
+    }
 
-        self.save_state(bucket_id).await?;
+    /// Like `rebuild`, but only if the rebuilt root equals `expected_root` (the pool's
+    /// on-chain root). On mismatch the current state is kept, so an incomplete or
+    /// misordered scan can't replace good state
+    pub async fn rebuild_from_commitments(
+        &self,
+        bucket_id: u8,
+        commitments: Vec<[u8; 32]>,
+        expected_root: [u8; 32],
+    ) -> Result<()> {
+        let state = BucketState::new(commitments)?;
+        let root = state
+            .tree
+            .root()
+            .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+        if root != expected_root {
+            return Err(RelayerError::MerkleTree(format!(
+                "Rebuilt root {} of bucket {} does not match expected root {}, state left unchanged",
+                hex::encode(root),
+                bucket_id,
+                hex::encode(expected_root)
+            )));
+        }
+        self.replace_tree(bucket_id, state).await
+    }
+
+    async fn replace_tree(&self, bucket_id: u8, new_state: BucketState) -> Result<()> {
+        let bucket = match self.buckets.write().await.entry(bucket_id) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => entry
+                .insert(Arc::new(RwLock::new(BucketState::new(Vec::new())?)))
+                .clone(),
+        };
+        let mut state = bucket.write().await;
+        *state = new_state;
+        self.invalidate_proofs(bucket_id);
+
+        let state = state.downgrade();
+        self.save_state(bucket_id, state.commitments.clone())
+            .await?;
         info!(
             "Synced bucket {} from chain: {} commitments",
             bucket_id,
-            on_chain_commitments.len()
+            state.commitments.len()
         );
         Ok(())
     }
 }
 
+/// A full tree means the pool can't take deposits, which the program reports as `PoolFull`
+fn insert_error(bucket_id: u8, error: privacy_proxy_sdk::SdkError) -> RelayerError {
+    match error {
+        privacy_proxy_sdk::SdkError::TreeFull { .. } => RelayerError::PoolFull(bucket_id),
+        error => RelayerError::MerkleTree(error.to_string()),
+    }
+}
+
+fn build_tree(commitments: &[[u8; 32]]) -> Result<MerkleTree> {
+    let mut tree =
+        MerkleTree::new(TREE_DEPTH).map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+    for commitment in commitments {
+        tree.insert(*commitment)
+            .map_err(|e| RelayerError::MerkleTree(e.to_string()))?;
+    }
+    Ok(tree)
+}
+
 impl Default for MerkleService {
     fn default() -> Self {
         Self::new()
@@ -297,4 +583,222 @@ mod tests {
         let proof = service.proof(0, 0).await.unwrap();
         assert!(service.verify_proof(&root, &c1, &proof).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_insert_is_idempotent() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        service.init_tree(0).await.unwrap();
+
+        assert_eq!(service.insert(0, [1u8; 32]).await.unwrap(), 0);
+        assert_eq!(service.insert(0, [2u8; 32]).await.unwrap(), 1);
+        let root = service.root(0).await.unwrap();
+
+        // A retried deposit gets its original index back and changes nothing
+        assert_eq!(service.insert(0, [1u8; 32]).await.unwrap(), 0);
+        assert_eq!(service.size(0).await.unwrap(), 2);
+        assert_eq!(service.root(0).await.unwrap(), root);
+
+        // New commitments still append in order
+        assert_eq!(service.insert(0, [3u8; 32]).await.unwrap(), 2);
+
+        // A tree rebuilt from chain knows the indices of the commitments it was built from
+        service
+            .rebuild(0, vec![[4u8; 32], [1u8; 32], [4u8; 32]])
+            .await
+            .unwrap();
+        assert_eq!(service.insert(0, [1u8; 32]).await.unwrap(), 1);
+        assert_eq!(service.insert(0, [4u8; 32]).await.unwrap(), 0);
+        assert_eq!(service.insert(0, [3u8; 32]).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_proof_cache() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        service.init_tree(0).await.unwrap();
+        service.insert(0, [1u8; 32]).await.unwrap();
+
+        let root = service.root(0).await.unwrap();
+        let proof = service.proof(0, 0).await.unwrap();
+        let key = (0, 0, root);
+        assert!(service.lock_proof_cache().proofs.contains_key(&key));
+
+        // Served from the cache: a planted entry comes back instead of a recomputed proof
+        let mut planted = proof.clone();
+        planted.siblings[0] = [9u8; 32];
+        service
+            .lock_proof_cache()
+            .proofs
+            .insert(key, planted.clone());
+        assert_eq!(
+            service.proof(0, 0).await.unwrap().siblings,
+            planted.siblings
+        );
+
+        // A new leaf changes the root, the stale proof is dropped and recomputed
+        service.insert(0, [2u8; 32]).await.unwrap();
+        assert!(service.lock_proof_cache().proofs.is_empty());
+        let new_root = service.root(0).await.unwrap();
+        assert_ne!(new_root, root);
+        let proof = service.proof(0, 0).await.unwrap();
+        assert_eq!(proof.siblings[0], [2u8; 32]);
+        assert!(service
+            .verify_proof(&new_root, &[1u8; 32], &proof)
+            .await
+            .unwrap());
+    }
+
+    #[test]
+    fn test_proof_cache_evicts_least_recently_used() {
+        let proof = MerkleProof {
+            siblings: Vec::new(),
+            path_indices: Vec::new(),
+            leaf_index: 0,
+        };
+        let mut cache = ProofCache::default();
+        for leaf in 0..PROOF_CACHE_CAPACITY as u64 {
+            cache.put((0, leaf, [0u8; 32]), proof.clone());
+        }
+        // Leaf 0 was just used, so leaf 1 is the one to go
+        assert!(cache.get(&(0, 0, [0u8; 32])).is_some());
+        cache.put((1, 0, [0u8; 32]), proof);
+        assert_eq!(cache.proofs.len(), PROOF_CACHE_CAPACITY);
+        assert!(cache.proofs.contains_key(&(0, 0, [0u8; 32])));
+        assert!(!cache.proofs.contains_key(&(0, 1, [0u8; 32])));
+
+        cache.invalidate(0);
+        assert_eq!(cache.proofs.len(), 1);
+        assert_eq!(cache.order.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_buckets_lock_independently() {
+        use std::time::Duration;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        service.init_tree(0).await.unwrap();
+        service.init_tree(5).await.unwrap();
+
+        // Bucket 0 busy, e.g. mid-insert
+        let bucket = service.bucket(0).await.unwrap();
+        let held = bucket.write().await;
+
+        let insert = service.insert(5, [1u8; 32]);
+        let index = tokio::time::timeout(Duration::from_secs(5), insert)
+            .await
+            .expect("bucket 5 waited on bucket 0");
+        assert_eq!(index.unwrap(), 0);
+
+        // The same bucket does wait
+        let insert = service.insert(0, [2u8; 32]);
+        assert!(tokio::time::timeout(Duration::from_millis(100), insert)
+            .await
+            .is_err());
+        drop(held);
+        assert_eq!(service.insert(0, [2u8; 32]).await.unwrap(), 0);
+        assert!(service.verify_integrity(0).await.unwrap());
+        assert!(service.verify_integrity(5).await.unwrap());
+    }
+
+    #[test]
+    fn test_full_tree_maps_to_pool_full() {
+        let mut tree = MerkleTree::new(1).unwrap();
+        tree.insert([1u8; 32]).unwrap();
+        tree.insert([2u8; 32]).unwrap();
+        let err = insert_error(3, tree.insert([3u8; 32]).unwrap_err());
+        assert!(matches!(err, RelayerError::PoolFull(3)));
+    }
+
+    #[tokio::test]
+    async fn test_integrity_detects_corruption() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        service.init_tree(0).await.unwrap();
+        service.init_tree(1).await.unwrap();
+
+        service.insert(0, [1u8; 32]).await.unwrap();
+        service.insert(0, [2u8; 32]).await.unwrap();
+        assert!(service.verify_integrity(0).await.unwrap());
+        // Empty, never-persisted bucket is healthy
+        assert!(service.verify_integrity(1).await.unwrap());
+
+        // Flip a byte of a stored commitment without updating the checksum
+        let path = service.state_file_path(0);
+        let mut state: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        state["commitments"][1][5] = serde_json::json!(3);
+        std::fs::write(&path, serde_json::to_string(&state).unwrap()).unwrap();
+        assert!(!service.verify_integrity(0).await.unwrap());
+
+        // Garbage on disk is also caught
+        std::fs::write(&path, b"\x00\x01garbage").unwrap();
+        assert!(!service.verify_integrity(0).await.unwrap());
+
+        // Rewriting from memory heals it
+        service.flush().await.unwrap();
+        assert!(service.verify_integrity(0).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_partial_write_rejected_on_reload() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        service.init_tree(0).await.unwrap();
+        for i in 0..4u8 {
+            service.insert(0, [i + 1; 32]).await.unwrap();
+        }
+        let path = service.state_file_path(0);
+        let saved = std::fs::read(&path).unwrap();
+        // Nothing left behind by the atomic write
+        assert!(!path.with_extension("tmp").exists());
+
+        // Write cut off mid-file
+        std::fs::write(&path, &saved[..saved.len() / 2]).unwrap();
+        let reloaded = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        assert!(reloaded.load_state(0).await.is_none());
+
+        // Well-formed JSON that lost its tail commitments still fails the checksum
+        let mut state: serde_json::Value = serde_json::from_slice(&saved).unwrap();
+        state["commitments"].as_array_mut().unwrap().truncate(2);
+        std::fs::write(&path, serde_json::to_vec(&state).unwrap()).unwrap();
+        assert!(reloaded.load_state(0).await.is_none());
+
+        // The intact file loads
+        std::fs::write(&path, &saved).unwrap();
+        assert_eq!(reloaded.load_state(0).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_from_commitments_checks_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let service = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        service.init_tree(0).await.unwrap();
+        service.insert(0, [1u8; 32]).await.unwrap();
+        let good_root = service.root(0).await.unwrap();
+
+        let commitments = vec![[1u8; 32], [2u8; 32], [3u8; 32]];
+        let expected_root = build_tree(&commitments).unwrap().root().unwrap();
+
+        // Misordered scan gives another root and is refused, state untouched
+        let misordered = vec![[2u8; 32], [1u8; 32], [3u8; 32]];
+        assert!(matches!(
+            service
+                .rebuild_from_commitments(0, misordered, expected_root)
+                .await,
+            Err(RelayerError::MerkleTree(_))
+        ));
+        assert_eq!(service.root(0).await.unwrap(), good_root);
+        assert_eq!(service.size(0).await.unwrap(), 1);
+
+        // Matching root is committed and persisted
+        service
+            .rebuild_from_commitments(0, commitments.clone(), expected_root)
+            .await
+            .unwrap();
+        assert_eq!(service.root(0).await.unwrap(), expected_root);
+        let reloaded = MerkleService::with_persistence_path(temp_dir.path().to_path_buf());
+        assert_eq!(reloaded.load_state(0).await.unwrap(), commitments);
+    }
 }
@@ -0,0 +1,168 @@
+/// Configurable RPC double for tests
+/// Each request type is answered by the handler registered for it and any other request
+/// panics. Calls are counted per request type, and every call yields to the scheduler
+/// first so concurrent callers interleave between RPC calls as they would over the network
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+use solana_client::client_error::Result as ClientResult;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+type Response = Pin<Box<dyn Future<Output = ClientResult<Value>> + Send>>;
+type Handler = Box<dyn Fn(Value) -> Response + Send + Sync>;
+
+#[derive(Default)]
+pub struct MockRpc {
+    handlers: HashMap<RpcRequest, Handler>,
+    calls: Calls,
+}
+
+/// Calls a `MockRpc` received, per request type
+#[derive(Clone, Default)]
+pub struct Calls(Arc<Mutex<HashMap<RpcRequest, usize>>>);
+
+impl Calls {
+    pub fn get(&self, request: RpcRequest) -> usize {
+        self.0.lock().unwrap().get(&request).copied().unwrap_or(0)
+    }
+}
+
+impl MockRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `request` with `handler`, called with the request params
+    pub fn on<F>(self, request: RpcRequest, handler: F) -> Self
+    where
+        F: Fn(Value) -> ClientResult<Value> + Send + Sync + 'static,
+    {
+        self.on_async(request, move |params| std::future::ready(handler(params)))
+    }
+
+    /// `on` with a handler that awaits, e.g. to keep the call in flight for a while
+    pub fn on_async<F, Fut>(mut self, request: RpcRequest, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ClientResult<Value>> + Send + 'static,
+    {
+        self.handlers
+            .insert(request, Box::new(move |params| Box::pin(handler(params))));
+        self
+    }
+
+    /// getAccountInfo and getMultipleAccounts, `account` gives the account JSON at an
+    /// address or None if there is none
+    pub fn accounts<F>(self, account: F) -> Self
+    where
+        F: Fn(&Pubkey) -> Option<Value> + Send + Sync + 'static,
+    {
+        let account = Arc::new(account);
+        let lookup = account.clone();
+        self.on(RpcRequest::GetAccountInfo, move |params| {
+            Ok(with_context(json!(lookup(&address(&params[0])))))
+        })
+        .on(RpcRequest::GetMultipleAccounts, move |params| {
+            let accounts: Vec<_> = params[0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|key| account(&address(key)))
+                .collect();
+            Ok(with_context(json!(accounts)))
+        })
+    }
+
+    /// getLatestBlockhash with the default hash, valid up to block height 100
+    pub fn blockhash(self) -> Self {
+        self.on(RpcRequest::GetLatestBlockhash, |_| {
+            Ok(with_context(json!({
+                "blockhash": solana_sdk::hash::Hash::default().to_string(),
+                "lastValidBlockHeight": 100,
+            })))
+        })
+    }
+
+    pub fn calls(&self) -> Calls {
+        self.calls.clone()
+    }
+
+    pub fn into_client(self) -> RpcClient {
+        RpcClient::new_sender(self, RpcClientConfig::default())
+    }
+}
+
+#[async_trait::async_trait]
+impl RpcSender for MockRpc {
+    async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        *self.calls.0.lock().unwrap().entry(request).or_default() += 1;
+        let handler = self
+            .handlers
+            .get(&request)
+            .unwrap_or_else(|| panic!("unexpected RPC request: {}", request));
+        tokio::task::yield_now().await;
+        handler(params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        RpcTransportStats::default()
+    }
+
+    fn url(&self) -> String {
+        "mock".to_string()
+    }
+}
+
+fn address(value: &Value) -> Pubkey {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+/// `value` wrapped the way RPC responses carry it
+pub fn with_context(value: Value) -> Value {
+    json!({ "context": { "slot": 1 }, "value": value })
+}
+
+/// Account JSON holding `data`, owned by `owner`
+pub fn account(data: &[u8], owner: &Pubkey) -> Value {
+    use base64::Engine;
+
+    json!({
+        "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+        "executable": false,
+        "lamports": 1_000_000_000,
+        "owner": owner.to_string(),
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+/// Status of a transaction that succeeded and is finalized
+pub fn finalized_status() -> Value {
+    json!({
+        "slot": 1,
+        "confirmations": null,
+        "err": null,
+        "status": { "Ok": null },
+        "confirmationStatus": "finalized",
+    })
+}
+
+/// The transaction a sendTransaction call carries
+pub fn sent_transaction(params: &Value) -> VersionedTransaction {
+    use solana_transaction_status::{EncodedTransaction, TransactionBinaryEncoding};
+
+    EncodedTransaction::Binary(
+        params[0].as_str().unwrap().to_string(),
+        TransactionBinaryEncoding::Base64,
+    )
+    .decode()
+    .unwrap()
+}
@@ -0,0 +1,538 @@
+/// Typed views of the program's accounts, decoded the way Anchor does it:
+/// check the 8-byte `account:<Name>` discriminator, then borsh-deserialize the fields
+/// Field order and types mirror `programs/privacy_proxy/src/state`
+use borsh::{BorshDeserialize, BorshSerialize};
+use privacy_proxy_sdk::merkle::TREE_DEPTH;
+use privacy_proxy_sdk::pool::DepositPool;
+use sha2::{Digest, Sha256};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{RelayerError, Result};
+use crate::rpc::rpc_read;
+
+/// Must match the program's `ROOTS_PER_ACCOUNT` and `MAX_CHAINED_ACCOUNTS`
+pub const ROOTS_PER_ACCOUNT: usize = 8;
+pub const MAX_CHAINED_ACCOUNTS: u64 = 32;
+
+/// Chained HistoricalRoots account the deposit at `leaf_index` saves the previous root to
+/// Mirrors `HistoricalRoots::account_index_for`: accounts fill in turn, then wrap to 0
+pub fn historical_roots_account_index(leaf_index: u64) -> u8 {
+    ((leaf_index / ROOTS_PER_ACCOUNT as u64) % MAX_CHAINED_ACCOUNTS) as u8
+}
+
+/// Anchor account discriminator: sha256("account:<name>")[..8]
+fn account_discriminator(name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("account:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash[..8]);
+    discriminator
+}
+
+/// `DepositPool` account, decoded by the SDK's mirror of the layout
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DepositPoolView(DepositPool);
+
+impl DepositPoolView {
+    /// Decode raw account data, trailing padding is ignored
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        DepositPool::from_account_data(data)
+            .map(Self)
+            .map_err(|e| RelayerError::Internal(format!("Invalid pool account: {}", e)))
+    }
+
+    pub async fn fetch(rpc_client: &RpcClient, pool_pda: &Pubkey) -> Result<Self> {
+        let data = rpc_read(|| rpc_client.get_account_data(pool_pda))
+            .await
+            .map_err(|e| RelayerError::TransactionFailed(format!("Failed to fetch pool: {}", e)))?;
+        Self::from_account_data(&data)
+    }
+
+    /// Fetch several pools in one RPC call, `None` for pools that don't exist (yet)
+    pub async fn fetch_many(
+        rpc_client: &RpcClient,
+        pool_pdas: &[Pubkey],
+    ) -> Result<Vec<Option<Self>>> {
+        rpc_read(|| rpc_client.get_multiple_accounts(pool_pdas))
+            .await
+            .map_err(|e| RelayerError::TransactionFailed(format!("Failed to fetch pools: {}", e)))?
+            .into_iter()
+            .map(|account| {
+                account
+                    .map(|account| Self::from_account_data(&account.data))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    pub fn merkle_root(&self) -> [u8; 32] {
+        self.0.merkle_root
+    }
+
+    /// Current root or one of the two the pool replaced last, mirrors `DepositPool::is_valid_root`
+    /// The program accepts these regardless of age
+    pub fn is_valid_root(&self, root: &[u8; 32]) -> bool {
+        &self.0.merkle_root == root || self.0.historical_roots.contains(root)
+    }
+
+    /// Leaf index the next deposit is inserted at
+    pub fn next_index(&self) -> u64 {
+        self.0.next_index
+    }
+
+    pub fn total_deposits(&self) -> u64 {
+        self.0.total_deposits
+    }
+
+    /// Deposits not yet withdrawn, the pool's actual anonymity set
+    pub fn anonymity_set_size(&self) -> u64 {
+        self.0.anonymity_set_size
+    }
+
+    /// Error out if the admin paused this pool, the program would reject
+    /// deposits and withdrawals on it (the global flag is checked by the program only)
+    pub fn ensure_active(&self) -> Result<()> {
+        if self.0.paused {
+            return Err(RelayerError::PoolPaused(self.0.bucket_id));
+        }
+        Ok(())
+    }
+
+    /// Error out if the pool took as many deposits as its cap allows,
+    /// mirrors `DepositPool::deposit_cap` (0 means the whole tree)
+    pub fn ensure_accepts_deposit(&self) -> Result<()> {
+        let tree_leaves = 1u64 << TREE_DEPTH;
+        let cap = match self.0.max_deposits {
+            0 => tree_leaves,
+            cap => cap.min(tree_leaves),
+        };
+        if self.0.total_deposits >= cap {
+            return Err(RelayerError::PoolFull(self.0.bucket_id));
+        }
+        Ok(())
+    }
+
+    /// Account data as the program would store it, for RPC test doubles
+    #[cfg(test)]
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = DepositPool::discriminator().to_vec();
+        self.0.serialize(&mut data).unwrap();
+        // Program allocates 47 bytes of padding after the fields
+        data.resize(data.len() + 47, 0);
+        data
+    }
+
+    #[cfg(test)]
+    pub fn with_indices(next_index: u64, total_deposits: u64) -> Self {
+        Self(DepositPool {
+            next_index,
+            total_deposits,
+            ..Default::default()
+        })
+    }
+}
+
+/// `GlobalConfig` account at `[CONFIG_SEED]`
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq, Eq)]
+#[allow(dead_code)] // mirrors the full layout, not every field is read
+pub struct GlobalConfigView {
+    admin: [u8; 32],
+    relayer_treasury: [u8; 32],
+    authorized_relayer: [u8; 32],
+    relayer_signing_key_n: [u8; 256],
+    relayer_signing_key_e: [u8; 4],
+    fee_bps: u16,
+    min_delay_hours: u8,
+    max_delay_hours: u8,
+    paused: bool,
+    bump: u8,
+    pending_admin: [u8; 32],
+    max_root_age_hours: u16,
+}
+
+impl GlobalConfigView {
+    /// Decode raw account data, trailing padding is ignored
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        let (discriminator, mut fields) = data
+            .split_first_chunk::<8>()
+            .ok_or_else(|| RelayerError::Internal("Config account data too short".into()))?;
+        if *discriminator != account_discriminator("GlobalConfig") {
+            return Err(RelayerError::Internal(
+                "Account is not a GlobalConfig".into(),
+            ));
+        }
+        Self::deserialize(&mut fields)
+            .map_err(|e| RelayerError::Internal(format!("Invalid config account: {}", e)))
+    }
+
+    /// Oldest historical root request_withdrawal accepts, in hours (0 = no limit)
+    pub fn max_root_age_hours(&self) -> u16 {
+        self.max_root_age_hours
+    }
+
+    /// Whether a root replaced at `added_at` is still accepted at `now`,
+    /// mirrors `GlobalConfig::root_is_fresh`
+    pub fn root_is_fresh(&self, added_at: i64, now: i64) -> bool {
+        self.max_root_age_hours == 0
+            || now.saturating_sub(added_at) <= self.max_root_age_hours as i64 * 3600
+    }
+
+    /// Account data as the program would store it, for RPC test doubles
+    #[cfg(test)]
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = account_discriminator("GlobalConfig").to_vec();
+        self.serialize(&mut data).unwrap();
+        // Program allocates 30 bytes of padding after the fields
+        data.resize(data.len() + 30, 0);
+        data
+    }
+
+    #[cfg(test)]
+    pub fn with_max_root_age(max_root_age_hours: u16) -> Self {
+        Self {
+            admin: [0; 32],
+            relayer_treasury: [0; 32],
+            authorized_relayer: [0; 32],
+            relayer_signing_key_n: [0; 256],
+            relayer_signing_key_e: [0; 4],
+            fee_bps: 50,
+            min_delay_hours: 1,
+            max_delay_hours: 24,
+            paused: false,
+            bump: 0,
+            pending_admin: [0; 32],
+            max_root_age_hours,
+        }
+    }
+}
+
+/// `HistoricalRoots` account, one per `[HISTORICAL_ROOTS_SEED, pool, account_index]`
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, Default, PartialEq, Eq)]
+#[allow(dead_code)] // mirrors the full layout, not every field is read
+pub struct HistoricalRootsView {
+    pool: [u8; 32],
+    bucket_id: u8,
+    account_index: u8,
+    write_index: u8,
+    count: u8,
+    roots: [[u8; 32]; ROOTS_PER_ACCOUNT],
+    bump: u8,
+    added_at: [i64; ROOTS_PER_ACCOUNT],
+}
+
+impl HistoricalRootsView {
+    /// Decode raw account data, trailing padding is ignored
+    /// Accounts the program hasn't migrated to carry `added_at` yet are too short and fail
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        let (discriminator, mut fields) = data.split_first_chunk::<8>().ok_or_else(|| {
+            RelayerError::Internal("Historical roots account data too short".into())
+        })?;
+        if *discriminator != account_discriminator("HistoricalRoots") {
+            return Err(RelayerError::Internal(
+                "Account is not a HistoricalRoots".into(),
+            ));
+        }
+        Self::deserialize(&mut fields)
+            .map_err(|e| RelayerError::Internal(format!("Invalid historical roots account: {}", e)))
+    }
+
+    /// When `root` was saved to this account, None if it isn't among the stored roots
+    /// Mirrors `HistoricalRoots::root_added_at`
+    pub fn root_added_at(&self, root: &[u8; 32]) -> Option<i64> {
+        let count = (self.count as usize).min(ROOTS_PER_ACCOUNT);
+        (0..count)
+            .find(|&i| &self.roots[i] == root)
+            .map(|i| self.added_at[i])
+    }
+
+    /// Account data as the program would store it, for RPC test doubles
+    #[cfg(test)]
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = account_discriminator("HistoricalRoots").to_vec();
+        self.serialize(&mut data).unwrap();
+        // Program allocates 8 bytes of padding after the fields
+        data.resize(data.len() + 8, 0);
+        data
+    }
+
+    /// Account holding `roots`, all saved at `added_at`
+    #[cfg(test)]
+    pub fn with_roots(roots: &[[u8; 32]], added_at: i64) -> Self {
+        let mut view = Self::default();
+        for (i, root) in roots.iter().enumerate() {
+            view.roots[i] = *root;
+            view.added_at[i] = added_at;
+        }
+        view.count = roots.len() as u8;
+        view
+    }
+}
+
+/// Must match the program's `MAX_ENCRYPTED_NOTE_SIZE`
+const MAX_ENCRYPTED_NOTE_SIZE: usize = 128;
+
+/// `EncryptedNote` account, one per deposit at `[NOTE_SEED, pool, leaf_index]`
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq, Eq)]
+#[allow(dead_code)] // mirrors the full layout, not every field is read
+pub struct EncryptedNoteView {
+    pool: [u8; 32],
+    leaf_index: u64,
+    ciphertext: [u8; MAX_ENCRYPTED_NOTE_SIZE],
+    ciphertext_len: u16,
+    ephemeral_pubkey: [u8; 32],
+    created_at: i64,
+    bump: u8,
+}
+
+impl EncryptedNoteView {
+    /// Note PDA for the deposit at `leaf_index`
+    pub fn pda(program_id: &Pubkey, pool_pda: &Pubkey, leaf_index: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"note", pool_pda.as_ref(), &leaf_index.to_le_bytes()],
+            program_id,
+        )
+        .0
+    }
+
+    /// Decode raw account data, trailing padding is ignored
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        let (discriminator, mut fields) = data
+            .split_first_chunk::<8>()
+            .ok_or_else(|| RelayerError::Internal("Note account data too short".into()))?;
+        if *discriminator != account_discriminator("EncryptedNote") {
+            return Err(RelayerError::Internal(
+                "Account is not an EncryptedNote".into(),
+            ));
+        }
+        let note = Self::deserialize(&mut fields)
+            .map_err(|e| RelayerError::Internal(format!("Invalid note account: {}", e)))?;
+        if note.ciphertext_len as usize > MAX_ENCRYPTED_NOTE_SIZE {
+            return Err(RelayerError::Internal(format!(
+                "Note ciphertext length {} exceeds {}",
+                note.ciphertext_len, MAX_ENCRYPTED_NOTE_SIZE
+            )));
+        }
+        Ok(note)
+    }
+
+    /// `None` when no deposit has been made at this PDA
+    pub async fn fetch(rpc_client: &RpcClient, note_pda: &Pubkey) -> Result<Option<Self>> {
+        let account =
+            rpc_read(|| rpc_client.get_account_with_commitment(note_pda, rpc_client.commitment()))
+                .await
+                .map_err(|e| {
+                    RelayerError::TransactionFailed(format!("Failed to fetch note: {}", e))
+                })?
+                .value;
+        account
+            .map(|account| Self::from_account_data(&account.data))
+            .transpose()
+    }
+
+    /// The stored ciphertext without the unused tail of the fixed buffer
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext[..self.ciphertext_len as usize]
+    }
+
+    pub fn ephemeral_pubkey(&self) -> [u8; 32] {
+        self.ephemeral_pubkey
+    }
+
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{self, MockRpc};
+
+    #[test]
+    fn test_deposit_pool_view_decodes_fixture() {
+        // DepositPool as laid out by the program: discriminator, fields, 47 bytes of padding
+        let mut data = Vec::new();
+        data.extend_from_slice(&account_discriminator("DepositPool"));
+        data.push(2); // bucket_id
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes()); // amount_lamports
+        data.extend_from_slice(&[7u8; 32]); // merkle_root
+        data.extend_from_slice(&42u64.to_le_bytes()); // next_index
+        data.extend_from_slice(&45u64.to_le_bytes()); // total_deposits
+        data.extend_from_slice(&40u64.to_le_bytes()); // anonymity_set_size
+        data.extend_from_slice(&[[8u8; 32], [9u8; 32]].concat()); // historical_roots
+        data.push(1); // historical_roots_index
+        data.push(254); // bump
+        data.push(0); // paused
+        data.extend_from_slice(&3u64.to_le_bytes()); // pending_counter
+        data.extend_from_slice(&46u64.to_le_bytes()); // max_deposits
+        data.extend_from_slice(&[0u8; 47]);
+        assert_eq!(
+            data.len(),
+            8 + 1 + 8 + 32 + 8 + 8 + 8 + 64 + 1 + 1 + 1 + 8 + 8 + 47
+        );
+
+        let pool = DepositPoolView::from_account_data(&data).unwrap();
+        assert_eq!(pool.0.bucket_id, 2);
+        assert_eq!(pool.0.amount_lamports, 1_000_000_000);
+        assert_eq!(pool.merkle_root(), [7u8; 32]);
+        assert_eq!(pool.next_index(), 42);
+        assert_eq!(pool.total_deposits(), 45);
+        assert_eq!(pool.anonymity_set_size(), 40);
+        assert_eq!(pool.0.pending_counter, 3);
+        assert_eq!(pool.0.max_deposits, 46);
+        assert_eq!(pool.0.historical_roots, [[8u8; 32], [9u8; 32]]);
+        assert_eq!(pool.0.bump, 254);
+        assert!(pool.ensure_active().is_ok());
+        assert!(pool.ensure_accepts_deposit().is_ok());
+        assert_eq!(pool.to_account_data(), data);
+
+        let mut paused = data.clone();
+        paused[8 + 1 + 8 + 32 + 8 + 8 + 8 + 64 + 1 + 1] = 1;
+        let pool = DepositPoolView::from_account_data(&paused).unwrap();
+        assert!(pool.0.paused);
+        assert!(matches!(
+            pool.ensure_active(),
+            Err(RelayerError::PoolPaused(2))
+        ));
+
+        // Wrong account type or truncated data is an error, not a zeroed view
+        let mut other = data.clone();
+        other[..8].copy_from_slice(&account_discriminator("HistoricalRoots"));
+        assert!(DepositPoolView::from_account_data(&other).is_err());
+        assert!(DepositPoolView::from_account_data(&data[..60]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_many_pools() {
+        let mut pool = DepositPoolView::with_indices(12, 12);
+        pool.0.anonymity_set_size = 9;
+        let pda = Pubkey::new_unique();
+        let data = pool.to_account_data();
+        // Every address but the pool's is empty
+        let rpc_client = MockRpc::new()
+            .accounts(move |address| {
+                (*address == pda).then(|| mock_rpc::account(&data, &Pubkey::default()))
+            })
+            .into_client();
+
+        let pools = DepositPoolView::fetch_many(&rpc_client, &[Pubkey::new_unique(), pda])
+            .await
+            .unwrap();
+        assert_eq!(pools, vec![None, Some(pool)]);
+    }
+
+    #[test]
+    fn test_deposit_cap() {
+        let mut pool = DepositPoolView::with_indices(46, 46);
+        pool.0.bucket_id = 2;
+        pool.0.max_deposits = 46;
+        assert!(matches!(
+            pool.ensure_accepts_deposit(),
+            Err(RelayerError::PoolFull(2))
+        ));
+
+        // No cap: only the tree itself limits the pool
+        pool.0.max_deposits = 0;
+        assert!(pool.ensure_accepts_deposit().is_ok());
+        pool.0.total_deposits = 1 << TREE_DEPTH;
+        assert!(pool.ensure_accepts_deposit().is_err());
+    }
+
+    #[test]
+    fn test_historical_roots_account_index() {
+        assert_eq!(historical_roots_account_index(0), 0);
+        assert_eq!(historical_roots_account_index(7), 0);
+        assert_eq!(historical_roots_account_index(8), 1);
+        assert_eq!(historical_roots_account_index(8 * 31 + 7), 31);
+        // Past the last chained account the program wraps back to account 0
+        assert_eq!(historical_roots_account_index(8 * 32), 0);
+    }
+
+    #[test]
+    fn test_encrypted_note_view_decodes_fixture() {
+        // EncryptedNote as laid out by the program: discriminator, fields, 32 bytes of padding
+        let mut data = Vec::new();
+        data.extend_from_slice(&account_discriminator("EncryptedNote"));
+        data.extend_from_slice(&[1u8; 32]); // pool
+        data.extend_from_slice(&9u64.to_le_bytes()); // leaf_index
+        let mut ciphertext = [0u8; MAX_ENCRYPTED_NOTE_SIZE];
+        ciphertext[..88].fill(0xab);
+        data.extend_from_slice(&ciphertext); // ciphertext
+        data.extend_from_slice(&88u16.to_le_bytes()); // ciphertext_len
+        data.extend_from_slice(&[5u8; 32]); // ephemeral_pubkey
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // created_at
+        data.push(253); // bump
+        data.extend_from_slice(&[0u8; 32]);
+        assert_eq!(data.len(), 8 + 32 + 8 + 128 + 2 + 32 + 8 + 1 + 32);
+
+        let note = EncryptedNoteView::from_account_data(&data).unwrap();
+        assert_eq!(note.leaf_index, 9);
+        assert_eq!(note.ciphertext(), &[0xab; 88][..]);
+        assert_eq!(note.ephemeral_pubkey(), [5u8; 32]);
+        assert_eq!(note.created_at(), 1_700_000_000);
+
+        // A length past the buffer would slice out of bounds, reject it at decode
+        let mut corrupt = data.clone();
+        corrupt[8 + 32 + 8 + 128..8 + 32 + 8 + 128 + 2].copy_from_slice(&129u16.to_le_bytes());
+        assert!(EncryptedNoteView::from_account_data(&corrupt).is_err());
+
+        let mut other = data.clone();
+        other[..8].copy_from_slice(&account_discriminator("DepositPool"));
+        assert!(EncryptedNoteView::from_account_data(&other).is_err());
+        assert!(EncryptedNoteView::from_account_data(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_historical_roots_view_decodes_fixture() {
+        // HistoricalRoots as laid out by the program, `added_at` appended after `bump`
+        let mut data = Vec::new();
+        data.extend_from_slice(&account_discriminator("HistoricalRoots"));
+        data.extend_from_slice(&[1u8; 32]); // pool
+        data.push(2); // bucket_id
+        data.push(4); // account_index
+        data.push(2); // write_index
+        data.push(2); // count
+        let mut roots = [[0u8; 32]; ROOTS_PER_ACCOUNT];
+        roots[0] = [7u8; 32];
+        roots[1] = [8u8; 32];
+        data.extend_from_slice(&roots.concat()); // roots
+        data.push(251); // bump
+        let legacy_len = data.len() + 8;
+        let mut added_at = [0i64; ROOTS_PER_ACCOUNT];
+        added_at[0] = 1_700_000_000;
+        added_at[1] = 1_700_003_600;
+        for t in added_at {
+            data.extend_from_slice(&t.to_le_bytes()); // added_at
+        }
+        data.extend_from_slice(&[0u8; 8]);
+        assert_eq!(data.len(), 8 + 32 + 4 + 256 + 1 + 64 + 8);
+
+        let view = HistoricalRootsView::from_account_data(&data).unwrap();
+        assert_eq!(view.account_index, 4);
+        assert_eq!(view.bump, 251);
+        assert_eq!(view.root_added_at(&[8u8; 32]), Some(1_700_003_600));
+        // Slots past `count` aren't roots, even if their bytes match
+        assert_eq!(view.root_added_at(&[0u8; 32]), None);
+        assert_eq!(view.to_account_data(), data);
+
+        // Accounts from before `added_at` existed must be migrated first
+        assert!(HistoricalRootsView::from_account_data(&data[..legacy_len]).is_err());
+    }
+
+    #[test]
+    fn test_global_config_view_decodes_fixture() {
+        let config = GlobalConfigView::with_max_root_age(48);
+        let data = config.to_account_data();
+        // Must match the program's GlobalConfig::SIZE
+        assert_eq!(
+            data.len(),
+            8 + 32 * 3 + 256 + 4 + 2 + 1 + 1 + 1 + 1 + 32 + 2 + 30
+        );
+        let decoded = GlobalConfigView::from_account_data(&data).unwrap();
+        assert_eq!(decoded, config);
+        assert_eq!(decoded.max_root_age_hours(), 48);
+        assert!(decoded.root_is_fresh(0, 48 * 3600));
+        assert!(!decoded.root_is_fresh(0, 48 * 3600 + 1));
+        assert!(GlobalConfigView::with_max_root_age(0).root_is_fresh(0, i64::MAX));
+    }
+}
@@ -0,0 +1,124 @@
+/// Everything a client needs to talk to this relayer, in one bundle signed by the relayer keypair
+/// The params are carried as the exact JSON string that was signed, so clients verify the bytes
+/// they received instead of a re-encoding of them
+use serde::Serialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+
+use crate::error::{RelayerError, Result};
+use crate::rpc::rpc_read;
+
+/// Offset of `min_delay_hours` in the GlobalConfig account
+/// (discriminator + admin + treasury + authorized relayer + RSA n + RSA e + fee_bps)
+const GLOBAL_CONFIG_MIN_DELAY_OFFSET: usize = 8 + 32 + 32 + 32 + 256 + 4 + 2;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RelayerParams {
+    /// RSA public keys accepted for credits, signing key first
+    pub rsa_keys: Vec<RsaKeyParams>,
+    /// X25519 public key for payload encryption (hex)
+    pub ecdh_pubkey: String,
+    /// Treasury that receives credit payments (base58)
+    pub treasury: String,
+    /// Privacy proxy program (base58)
+    pub program_id: String,
+    /// Fee in basis points
+    pub fee_bps: u16,
+    /// Pool denominations in lamports, indexed by bucket id
+    pub bucket_amounts: Vec<u64>,
+    /// Withdrawal delay bounds enforced by the program
+    pub min_delay_hours: u8,
+    pub max_delay_hours: u8,
+    /// Circuit build the withdrawal proofs must come from
+    pub circuit_version: String,
+    /// snarkjs verification key of the withdrawal circuit (None = not configured)
+    pub withdrawal_vk: Option<serde_json::Value>,
+    /// Unix timestamp the bundle was signed at
+    pub issued_at: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RsaKeyParams {
+    /// Modulus (hex)
+    pub n: String,
+    /// Exponent (hex)
+    pub e: String,
+    /// Unix timestamp when a retired key stops being accepted (None = current key)
+    pub expires_at: Option<u64>,
+}
+
+/// Body of `GET /params`
+#[derive(Serialize)]
+pub struct SignedParams {
+    /// Compact JSON encoding of `RelayerParams`, exactly as signed
+    pub params: String,
+    /// Relayer pubkey that produced `signature` (base58)
+    pub signer: String,
+    /// ed25519 signature over the bytes of `params` (base58)
+    pub signature: String,
+}
+
+impl SignedParams {
+    pub fn sign(params: &RelayerParams, keypair: &Keypair) -> Result<Self> {
+        let params = serde_json::to_string(params)
+            .map_err(|e| RelayerError::Internal(format!("Failed to encode params: {}", e)))?;
+        Ok(Self {
+            signer: keypair.pubkey().to_string(),
+            signature: keypair.sign_message(params.as_bytes()).to_string(),
+            params,
+        })
+    }
+}
+
+/// Read the withdrawal delay bounds from the program's GlobalConfig
+pub async fn fetch_delay_bounds(rpc_client: &RpcClient, program_id: &Pubkey) -> Result<(u8, u8)> {
+    let (config_pda, _) = Pubkey::find_program_address(&[b"config"], program_id);
+    let data = rpc_read(|| rpc_client.get_account_data(&config_pda)).await?;
+    match data.get(GLOBAL_CONFIG_MIN_DELAY_OFFSET..GLOBAL_CONFIG_MIN_DELAY_OFFSET + 2) {
+        Some(bounds) => Ok((bounds[0], bounds[1])),
+        None => Err(RelayerError::Internal(
+            "Global config account too small".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_signed_params_verify_against_relayer_key() {
+        let keypair = Keypair::new();
+        let params = RelayerParams {
+            rsa_keys: vec![RsaKeyParams {
+                n: "ab".repeat(128),
+                e: "010001".into(),
+                expires_at: None,
+            }],
+            ecdh_pubkey: "00".repeat(32),
+            treasury: Pubkey::new_unique().to_string(),
+            program_id: Pubkey::new_unique().to_string(),
+            fee_bps: 50,
+            bucket_amounts: crate::config::DEFAULT_BUCKET_AMOUNTS.to_vec(),
+            min_delay_hours: 0,
+            max_delay_hours: 24,
+            circuit_version: "0.1.0".into(),
+            withdrawal_vk: Some(serde_json::json!({ "protocol": "groth16" })),
+            issued_at: 1_700_000_000,
+        };
+
+        let signed = SignedParams::sign(&params, &keypair).unwrap();
+        assert_eq!(signed.signer, keypair.pubkey().to_string());
+
+        let signature = Signature::from_str(&signed.signature).unwrap();
+        assert!(signature.verify(keypair.pubkey().as_ref(), signed.params.as_bytes()));
+
+        let decoded: serde_json::Value = serde_json::from_str(&signed.params).unwrap();
+        assert_eq!(decoded["max_delay_hours"], 24);
+        assert_eq!(decoded["withdrawal_vk"]["protocol"], "groth16");
+    }
+}
@@ -0,0 +1,407 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+    EncodedTransactionWithStatusMeta, UiMessage, UiTransactionEncoding, UiTransactionStatusMeta,
+    UiTransactionTokenBalance,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::error::{RelayerError, Result};
+
+/// Balances of `mint` (base units) in token accounts owned by `owner` before and after a
+/// transaction, keyed by account index
+fn spl_balances(
+    meta: &UiTransactionStatusMeta,
+    mint: &str,
+    owner: &str,
+) -> (HashMap<u8, u64>, HashMap<u8, u64>) {
+    let balances = |list: Option<&Vec<UiTransactionTokenBalance>>| -> HashMap<u8, u64> {
+        list.into_iter()
+            .flatten()
+            .filter(|b| {
+                b.mint == mint && matches!(&b.owner, OptionSerializer::Some(o) if o == owner)
+            })
+            .map(|b| {
+                (
+                    b.account_index,
+                    b.ui_token_amount.amount.parse::<u64>().unwrap_or(0),
+                )
+            })
+            .collect()
+    };
+
+    (
+        balances(Option::from(meta.pre_token_balances.as_ref())),
+        balances(Option::from(meta.post_token_balances.as_ref())),
+    )
+}
+
+/// Net amount of `mint` (base units) credited to token accounts owned by `owner` in a transaction
+pub fn spl_amount_received(meta: &UiTransactionStatusMeta, mint: &str, owner: &str) -> u64 {
+    let (pre, post) = spl_balances(meta, mint, owner);
+    // Accounts created in this tx only appear in post balances
+    post.iter()
+        .map(|(index, &after)| after.saturating_sub(pre.get(index).copied().unwrap_or(0)))
+        .fold(0u64, u64::saturating_add)
+}
+
+/// Net amount of `mint` (base units) debited from token accounts owned by `owner` in a transaction
+pub fn spl_amount_sent(meta: &UiTransactionStatusMeta, mint: &str, owner: &str) -> u64 {
+    let (pre, post) = spl_balances(meta, mint, owner);
+    // Accounts closed in this tx only appear in pre balances
+    pre.iter()
+        .map(|(index, &before)| before.saturating_sub(post.get(index).copied().unwrap_or(0)))
+        .fold(0u64, u64::saturating_add)
+}
+
+/// Whether `key` signed a transaction fetched with `UiTransactionEncoding::Json`
+fn signed_by(transaction: &EncodedTransaction, key: &Pubkey) -> bool {
+    let EncodedTransaction::Json(ui_tx) = transaction else {
+        return false;
+    };
+    let key = key.to_string();
+    match &ui_tx.message {
+        UiMessage::Parsed(parsed) => parsed
+            .account_keys
+            .iter()
+            .any(|k| k.signer && k.pubkey == key),
+        // Signers come first in the account keys
+        UiMessage::Raw(raw) => raw
+            .account_keys
+            .iter()
+            .take(raw.header.num_required_signatures as usize)
+            .any(|k| *k == key),
+    }
+}
+
+/// Check an SPL payment moved at least `expected` of `mint` from `payer` to `treasury`
+/// The treasury's token accounts must have gained it, and `payer` must have signed the
+/// transaction and lost at least as much from its own token accounts, so nobody can claim
+/// credits for a transfer someone else made. Returns the amount received
+pub fn verify_spl_payment(
+    transaction: &EncodedTransactionWithStatusMeta,
+    mint: &Pubkey,
+    payer: &Pubkey,
+    treasury: &Pubkey,
+    expected: u64,
+) -> Result<u64> {
+    let meta = transaction.meta.as_ref().ok_or_else(|| {
+        RelayerError::InvalidRequest("Payment transaction has no status meta".into())
+    })?;
+    let mint_str = mint.to_string();
+
+    let received = spl_amount_received(meta, &mint_str, &treasury.to_string());
+    if received < expected {
+        return Err(RelayerError::InvalidRequest(format!(
+            "Insufficient payment: received {} of mint {}, expected {}",
+            received, mint, expected
+        )));
+    }
+    let sent = spl_amount_sent(meta, &mint_str, &payer.to_string());
+    if !signed_by(&transaction.transaction, payer) || sent < expected {
+        return Err(RelayerError::InvalidRequest(format!(
+            "Payment of mint {} was not sent by {}",
+            mint, payer
+        )));
+    }
+    Ok(received)
+}
+
+/// Token amount (base units) equivalent to `lamports` at `units_per_sol`, rounded up
+pub fn required_token_amount(lamports: u64, units_per_sol: u64) -> u64 {
+    (lamports as u128 * units_per_sol as u128).div_ceil(LAMPORTS_PER_SOL as u128) as u64
+}
+
+/// Wait for a payment tx to reach `confirmed`, then fetch it in full
+/// Polls the cheap `getSignatureStatuses` up to `attempts` times, `interval` apart, and only
+/// calls `getTransaction` once the signature is known. Still unseen afterwards is
+/// `PaymentPending`, so the client can retry the same request later
+pub async fn fetch_confirmed_payment(
+    rpc_client: &RpcClient,
+    signature: &Signature,
+    attempts: u32,
+    interval: Duration,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let mut confirmed = false;
+    for attempt in 1..=attempts {
+        match rpc_client.get_signature_statuses(&[*signature]).await {
+            Ok(response) => match response.value.into_iter().next().flatten() {
+                Some(status) if status.err.is_some() => {
+                    return Err(RelayerError::InvalidRequest(
+                        "Payment transaction failed".into(),
+                    ));
+                }
+                Some(status) if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                    confirmed = true;
+                    break;
+                }
+                _ => {}
+            },
+            // Transient RPC trouble looks the same as a slow tx to the client
+            Err(e) => warn!("Payment status check failed: {}", e),
+        }
+        if attempt < attempts {
+            info!(
+                "Payment tx not confirmed yet (attempt {}/{}), retrying in {:?}",
+                attempt, attempts, interval
+            );
+            tokio::time::sleep(interval).await;
+        }
+    }
+    if !confirmed {
+        return Err(RelayerError::PaymentPending(signature.to_string()));
+    }
+
+    rpc_client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .map_err(|e| {
+            // Status is visible before the node serves the full tx
+            warn!(
+                "Payment tx {} confirmed but not fetchable: {}",
+                signature, e
+            );
+            RelayerError::PaymentPending(signature.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const TREASURY: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+    const OTHER: &str = "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+
+    fn balance(index: u8, mint: &str, owner: &str, amount: u64) -> serde_json::Value {
+        serde_json::json!({
+            "accountIndex": index,
+            "mint": mint,
+            "owner": owner,
+            "uiTokenAmount": {
+                "uiAmount": null,
+                "decimals": 6,
+                "amount": amount.to_string(),
+                "uiAmountString": "",
+            },
+        })
+    }
+
+    fn meta(pre: Vec<serde_json::Value>, post: Vec<serde_json::Value>) -> UiTransactionStatusMeta {
+        serde_json::from_value(serde_json::json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "preTokenBalances": pre,
+            "postTokenBalances": post,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_spl_amount_received() {
+        // Payer (index 1) sends 15 USDC to the treasury's token account (index 2)
+        let meta = meta(
+            vec![
+                balance(1, MINT, OTHER, 20_000_000),
+                balance(2, MINT, TREASURY, 1_000_000),
+            ],
+            vec![
+                balance(1, MINT, OTHER, 5_000_000),
+                balance(2, MINT, TREASURY, 16_000_000),
+            ],
+        );
+        assert_eq!(spl_amount_received(&meta, MINT, TREASURY), 15_000_000);
+        // Wrong mint or owner sees nothing
+        assert_eq!(spl_amount_received(&meta, OTHER, TREASURY), 0);
+        assert_eq!(spl_amount_received(&meta, MINT, OTHER), 0);
+
+        // Treasury token account created in the payment tx
+        let meta = self::meta(vec![], vec![balance(3, MINT, TREASURY, 7)]);
+        assert_eq!(spl_amount_received(&meta, MINT, TREASURY), 7);
+    }
+
+    /// Payment tx signed by `signers`, with `others` as the remaining account keys
+    fn payment(
+        signers: &[&str],
+        others: &[&str],
+        meta: UiTransactionStatusMeta,
+    ) -> EncodedTransactionWithStatusMeta {
+        let account_keys = [signers, others].concat();
+        EncodedTransactionWithStatusMeta {
+            transaction: serde_json::from_value(serde_json::json!({
+                "signatures": vec![Signature::default().to_string(); signers.len()],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": signers.len(),
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 0,
+                    },
+                    "accountKeys": account_keys,
+                    "recentBlockhash": solana_sdk::hash::Hash::default().to_string(),
+                    "instructions": [],
+                },
+            }))
+            .unwrap(),
+            meta: Some(meta),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_spl_payment_must_come_from_payer() {
+        use std::str::FromStr;
+
+        const THIRD_PARTY: &str = "So11111111111111111111111111111111111111112";
+        let key = |s: &str| Pubkey::from_str(s).unwrap();
+        let check = |tx: &EncodedTransactionWithStatusMeta, payer: &str| {
+            verify_spl_payment(tx, &key(MINT), &key(payer), &key(TREASURY), 15_000_000)
+        };
+        // `sender` (index 1) sends 15 USDC to the treasury's token account (index 2)
+        let transfer_from = |sender: &str| {
+            meta(
+                vec![
+                    balance(1, MINT, sender, 20_000_000),
+                    balance(2, MINT, TREASURY, 1_000_000),
+                ],
+                vec![
+                    balance(1, MINT, sender, 5_000_000),
+                    balance(2, MINT, TREASURY, 16_000_000),
+                ],
+            )
+        };
+
+        let own = payment(&[OTHER], &[TREASURY], transfer_from(OTHER));
+        assert_eq!(check(&own, OTHER).unwrap(), 15_000_000);
+
+        // Someone else's transfer to the treasury can't be claimed...
+        let third_party = payment(
+            &[THIRD_PARTY],
+            &[OTHER, TREASURY],
+            transfer_from(THIRD_PARTY),
+        );
+        assert!(check(&third_party, THIRD_PARTY).is_ok());
+        assert!(check(&third_party, OTHER).is_err());
+        // ...not even by a co-signer whose own tokens didn't move
+        let cosigned = payment(
+            &[OTHER, THIRD_PARTY],
+            &[TREASURY],
+            transfer_from(THIRD_PARTY),
+        );
+        assert!(check(&cosigned, OTHER).is_err());
+        // ...nor by the owner of the debited account if it didn't sign
+        let unsigned = payment(&[THIRD_PARTY], &[OTHER, TREASURY], transfer_from(OTHER));
+        assert!(check(&unsigned, OTHER).is_err());
+    }
+
+    #[test]
+    fn test_required_token_amount() {
+        // 1.005 SOL at 150 USDC/SOL (6 decimals)
+        assert_eq!(
+            required_token_amount(1_005_000_000, 150_000_000),
+            150_750_000
+        );
+        // Rounds up
+        assert_eq!(required_token_amount(1, 1), 1);
+    }
+
+    fn status(err: serde_json::Value, confirmation: &str) -> serde_json::Value {
+        serde_json::json!({
+            "slot": 1,
+            "confirmations": null,
+            "err": err,
+            "status": { "Ok": null },
+            "confirmationStatus": confirmation,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fetch_confirmed_payment() {
+        use crate::mock_rpc::{with_context, MockRpc};
+        use solana_client::rpc_request::RpcRequest;
+
+        let signature = Signature::default();
+        // (result, status checks, full fetches)
+        let fetch = |statuses: Vec<serde_json::Value>| async move {
+            // getSignatureStatuses answered from the script, one entry per call
+            let statuses = std::sync::Mutex::new(std::collections::VecDeque::from(statuses));
+            let rpc = MockRpc::new()
+                .on(RpcRequest::GetSignatureStatuses, move |_| {
+                    let status = statuses
+                        .lock()
+                        .unwrap()
+                        .pop_front()
+                        .unwrap_or(serde_json::Value::Null);
+                    Ok(with_context(serde_json::json!([status])))
+                })
+                .on(RpcRequest::GetTransaction, |_| {
+                    Ok(serde_json::json!({
+                        "slot": 1,
+                        "blockTime": null,
+                        "transaction": "",
+                        "meta": {
+                            "err": null,
+                            "status": { "Ok": null },
+                            "fee": 5000,
+                            "preBalances": [],
+                            "postBalances": [],
+                        },
+                    }))
+                });
+            let calls = rpc.calls();
+            let rpc_client = rpc.into_client();
+            let result = fetch_confirmed_payment(&rpc_client, &signature, 3, Duration::ZERO).await;
+            (
+                result,
+                calls.get(RpcRequest::GetSignatureStatuses),
+                calls.get(RpcRequest::GetTransaction),
+            )
+        };
+
+        // Never seen: pending after the configured attempts, full tx never requested
+        let (result, status_calls, transaction_calls) = fetch(vec![]).await;
+        assert!(matches!(result, Err(RelayerError::PaymentPending(_))));
+        assert_eq!((status_calls, transaction_calls), (3, 0));
+
+        // Processed only counts as not yet confirmed
+        let (result, _, transaction_calls) =
+            fetch(vec![status(serde_json::Value::Null, "processed"); 3]).await;
+        assert!(matches!(result, Err(RelayerError::PaymentPending(_))));
+        assert_eq!(transaction_calls, 0);
+
+        // Failed on chain is final, no point polling further
+        let (result, status_calls, _) = fetch(vec![status(
+            serde_json::json!({ "InstructionError": [0, "InvalidArgument"] }),
+            "confirmed",
+        )])
+        .await;
+        assert!(matches!(result, Err(RelayerError::InvalidRequest(_))));
+        assert_eq!(status_calls, 1);
+
+        // Shows up on the second check, then fetched once
+        let (result, status_calls, transaction_calls) = fetch(vec![
+            serde_json::Value::Null,
+            status(serde_json::Value::Null, "confirmed"),
+        ])
+        .await;
+        assert!(result.is_ok());
+        assert_eq!((status_calls, transaction_calls), (2, 1));
+    }
+}
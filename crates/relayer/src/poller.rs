@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Outcome of a single poller tick
+#[derive(Debug, PartialEq)]
+pub enum TickOutcome<T> {
+    /// Work finished within the deadline
+    Completed(T),
+    /// Work is still running past the deadline; it keeps running and later ticks are skipped
+    Overran,
+    /// Previous tick's work was still in flight, nothing was started
+    Skipped,
+    /// Work panicked
+    Panicked,
+}
+
+/// Runs periodic work on its own task, refusing to start a tick while the previous one is still
+/// running so a slow RPC can't stack up overlapping batches
+#[derive(Clone, Default)]
+pub struct TickGuard {
+    in_flight: Arc<Mutex<()>>,
+}
+
+impl TickGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start `work` unless a previous run is in flight, then wait up to `deadline` for it
+    /// Work that overruns is not cancelled (it may be mid-transaction), it just holds the guard
+    pub async fn run<F, T>(&self, deadline: Duration, work: F) -> TickOutcome<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let Ok(permit) = self.in_flight.clone().try_lock_owned() else {
+            return TickOutcome::Skipped;
+        };
+
+        let handle = tokio::spawn(async move {
+            let _permit = permit;
+            work.await
+        });
+
+        match tokio::time::timeout(deadline, handle).await {
+            Ok(Ok(value)) => TickOutcome::Completed(value),
+            Ok(Err(_)) => TickOutcome::Panicked,
+            Err(_) => TickOutcome::Overran,
+        }
+    }
+
+    /// Wait for any in-flight work to finish (used on shutdown)
+    pub async fn wait_idle(&self) {
+        let _ = self.in_flight.lock().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_slow_tick_prevents_overlap() {
+        let guard = TickGuard::new();
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+
+        let slow_work = |running: Arc<AtomicUsize>, max_running: Arc<AtomicUsize>| async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        };
+
+        // First tick overruns its deadline but keeps running
+        let outcome = guard
+            .run(
+                Duration::from_millis(50),
+                slow_work(running.clone(), max_running.clone()),
+            )
+            .await;
+        assert_eq!(outcome, TickOutcome::Overran);
+
+        // Next ticks are skipped while it is still in flight
+        for _ in 0..3 {
+            let outcome = guard
+                .run(
+                    Duration::from_millis(50),
+                    slow_work(running.clone(), max_running.clone()),
+                )
+                .await;
+            assert_eq!(outcome, TickOutcome::Skipped);
+        }
+
+        // Once it finishes, ticks run again
+        guard.wait_idle().await;
+        let outcome = guard.run(Duration::from_secs(1), async { 7 }).await;
+        assert_eq!(outcome, TickOutcome::Completed(7));
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_tick_releases_guard() {
+        let guard = TickGuard::new();
+        let outcome = guard
+            .run(Duration::from_secs(1), async { panic!("boom") })
+            .await;
+        assert_eq!(outcome, TickOutcome::<()>::Panicked);
+        assert_eq!(
+            guard.run(Duration::from_secs(1), async {}).await,
+            TickOutcome::Completed(())
+        );
+    }
+}
@@ -0,0 +1,168 @@
+/// Retries for Solana RPC reads
+/// Reads are idempotent, so a transient failure (timeout, dropped connection, 429, node
+/// behind) is retried with backoff. Anything else, e.g. an account that doesn't exist, fails
+/// right away, retrying can't change the answer
+use std::future::Future;
+use std::time::Duration;
+
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::RpcError;
+use tracing::warn;
+
+/// Attempts per read, including the first
+pub const RPC_READ_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled after each failure
+pub const RPC_READ_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Server errors that go away on their own: block/slot not available yet, unhealthy node,
+/// long-term storage unreachable
+const TRANSIENT_RPC_CODES: [i64; 5] = [-32004, -32005, -32014, -32016, -32019];
+
+/// Whether `error` is worth retrying
+pub fn is_transient(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status()
+                    .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            TRANSIENT_RPC_CODES.contains(code)
+        }
+        // Account reads report any failed call as "AccountNotFound: pubkey=<key>: <cause>",
+        // only the bare form means the account really is missing
+        ClientErrorKind::RpcError(RpcError::ForUser(message)) => message
+            .strip_prefix("AccountNotFound: pubkey=")
+            .is_some_and(|rest| rest.contains(": ")),
+        _ => false,
+    }
+}
+
+/// Run the read `op` up to `attempts` times in total while it fails transiently, sleeping
+/// `backoff` before the first retry and twice as long before each next one
+pub async fn with_rpc_retry<T, F, Fut>(
+    op: F,
+    attempts: u32,
+    backoff: Duration,
+) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    with_retry_if(op, attempts, backoff, is_transient).await
+}
+
+/// `with_rpc_retry` retrying every error `retry` accepts, not just transient ones
+pub async fn with_retry_if<T, F, Fut>(
+    mut op: F,
+    attempts: u32,
+    backoff: Duration,
+    retry: impl Fn(&ClientError) -> bool,
+) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    let mut attempt = 1;
+    let mut delay = backoff;
+    loop {
+        match op().await {
+            Err(error) if attempt < attempts && retry(&error) => {
+                warn!(%error, attempt, attempts, "RPC error, retrying in {:?}", delay);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `with_rpc_retry` with the relayer's default attempts and backoff
+pub async fn rpc_read<T, F, Fut>(op: F) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ClientError>>,
+{
+    with_rpc_retry(op, RPC_READ_ATTEMPTS, RPC_READ_BACKOFF).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{with_context, Calls, MockRpc};
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::pubkey::Pubkey;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Times out the first `failures` reads, then reports the account as missing
+    fn client(failures: u32) -> (RpcClient, Calls) {
+        let attempts = AtomicU32::new(0);
+        let rpc = MockRpc::new().on(RpcRequest::GetAccountInfo, move |_| {
+            if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out").into());
+            }
+            Ok(with_context(serde_json::Value::Null))
+        });
+        let calls = rpc.calls();
+        (rpc.into_client(), calls)
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried() {
+        let pubkey = Pubkey::new_unique();
+        let backoff = Duration::from_millis(1);
+
+        // Two timeouts, the third attempt gets through
+        let (rpc_client, calls) = client(2);
+        let account = with_rpc_retry(
+            || rpc_client.get_account_with_commitment(&pubkey, rpc_client.commitment()),
+            3,
+            backoff,
+        )
+        .await
+        .unwrap();
+        assert!(account.value.is_none());
+        assert_eq!(calls.get(RpcRequest::GetAccountInfo), 3);
+
+        // Out of attempts: the last timeout comes back
+        let (rpc_client, calls) = client(2);
+        let err = with_rpc_retry(|| rpc_client.get_account_data(&pubkey), 2, backoff)
+            .await
+            .unwrap_err();
+        assert!(is_transient(&err));
+        assert_eq!(calls.get(RpcRequest::GetAccountInfo), 2);
+    }
+
+    #[tokio::test]
+    async fn test_missing_account_is_not_retried() {
+        let pubkey = Pubkey::new_unique();
+        let (rpc_client, calls) = client(0);
+        let err = with_rpc_retry(
+            || rpc_client.get_account_data(&pubkey),
+            3,
+            Duration::from_millis(1),
+        )
+        .await
+        .unwrap_err();
+        assert!(!is_transient(&err));
+        assert_eq!(calls.get(RpcRequest::GetAccountInfo), 1);
+    }
+
+    #[test]
+    fn test_transient_rpc_codes() {
+        let response_error = |code| {
+            ClientError::from(RpcError::RpcResponseError {
+                code,
+                message: String::new(),
+                data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+            })
+        };
+        assert!(is_transient(&response_error(-32005)));
+        // Skipped slots stay skipped
+        assert!(!is_transient(&response_error(-32007)));
+    }
+}
@@ -3,7 +3,9 @@ use aes_gcm::{
     Aes256Gcm, Nonce,
 };
 use axum::{
-    extract::State,
+    extract::{rejection::JsonRejection, State},
+    http::StatusCode,
+    middleware,
     routing::{get, post},
     Json, Router,
 };
@@ -13,24 +15,45 @@ use solana_sdk::signer::Signer;
 // use solana_transaction_status::UiTransactionEncoding;
 use rand::rngs::OsRng;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing::info;
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
-use crate::blind_signer::BlindSignerService;
-use crate::config::{calculate_total_with_fee, get_bucket_id, RelayerConfig, BUCKET_AMOUNTS};
+use crate::auth::require_admin;
+use crate::blind_signer::{unix_now, BlindSignerService};
+use crate::config::{batch_total_with_fee, get_bucket_id, RateLimit, RelayerConfig};
 use crate::deposit::DepositService;
 use crate::error::RelayerError;
 use crate::merkle_service::MerkleService;
+use crate::on_chain::{DepositPoolView, EncryptedNoteView};
+use crate::params::{fetch_delay_bounds, RelayerParams, RsaKeyParams, SignedParams};
+use crate::payment::{fetch_confirmed_payment, required_token_amount, verify_spl_payment};
 use crate::withdrawal::WithdrawalService;
 
+use privacy_proxy_sdk::credits::credit_price;
+use privacy_proxy_sdk::crypto::{
+    ecdh_shared_secret, payload_key, PAYLOAD_VERSIONS, PAYLOAD_VERSION_RAW,
+};
 use privacy_proxy_sdk::deposit::{DepositRequest, DepositResponse};
-use privacy_proxy_sdk::withdrawal::{WithdrawalRequest, WithdrawalResponse};
+use privacy_proxy_sdk::withdrawal::{
+    OwnershipProofRequest, WithdrawalRequest, WithdrawalResponse, WithdrawalState,
+    WithdrawalStatusResponse,
+};
+
+/// Max leaf indices per `POST /proof/batch`
+const MAX_BATCH_PROOFS: usize = 64;
+
+/// Max credits per `POST /sign/batch`
+const MAX_BATCH_SIGNATURES: usize = 16;
 
 /// Encrypted deposit payload (ECDH + AES-256-GCM)
 #[derive(Deserialize, Debug)]
 struct DepositPayload {
+    /// Key derivation scheme (absent = v1, raw shared secret)
+    #[serde(default = "legacy_payload_version")]
+    version: u8,
     #[allow(dead_code)]
     encrypted: bool,
     ciphertext: Vec<u8>,
@@ -39,11 +62,17 @@ struct DepositPayload {
     client_pubkey: String,
 }
 
+fn legacy_payload_version() -> u8 {
+    PAYLOAD_VERSION_RAW
+}
+
 #[derive(Deserialize, Debug)]
 struct PlainDepositRequest {
     credit: CreditData,
     commitment: Vec<u8>,
     encrypted_note: Option<Vec<u8>>,
+    #[serde(default)]
+    note_ephemeral_pubkey: Option<[u8; 32]>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -67,26 +96,41 @@ pub struct RelayerState {
 
 impl RelayerState {
     pub async fn new(config: RelayerConfig) -> anyhow::Result<Self> {
-        let rpc_client = Arc::new(RpcClient::new(config.rpc_url.clone()));
-        let blind_signer = Arc::new(BlindSignerService::new(config.rsa_key_bits)?);
+        let rpc_client = Arc::new(RpcClient::new_with_commitment(
+            config.rpc_read_url.clone(),
+            config.rpc_commitment,
+        ));
+        let send_client = Arc::new(RpcClient::new_with_commitment(
+            config.rpc_write_url.clone(),
+            config.rpc_commitment,
+        ));
+        let blind_signer = Arc::new(BlindSignerService::new(
+            config.rsa_key_bits,
+            config.rsa_rotation_grace_secs,
+        )?);
         let merkle_service = Arc::new(MerkleService::new());
 
-        for bucket_id in 0..BUCKET_AMOUNTS.len() as u8 {
+        for bucket_id in config.bucket_ids() {
             merkle_service.init_tree(bucket_id).await?;
         }
 
-        let deposit_service = Arc::new(DepositService::new(
-            config.clone(),
-            rpc_client.clone(),
-            blind_signer.clone(),
-            merkle_service.clone(),
-        ));
-
-        let withdrawal_service = Arc::new(WithdrawalService::new(
-            config.clone(),
-            rpc_client.clone(),
-            merkle_service.clone(),
-        ));
+        let deposit_service = Arc::new(
+            DepositService::new(
+                config.clone(),
+                rpc_client.clone(),
+                blind_signer.clone(),
+                merkle_service.clone(),
+            )
+            .with_send_client(send_client.clone()),
+        );
+
+        let withdrawal_service = Arc::new(
+            WithdrawalService::new(config.clone(), rpc_client.clone(), merkle_service.clone())
+                .with_send_client(send_client),
+        );
+        for bucket_id in config.bucket_ids() {
+            withdrawal_service.record_historical_root(bucket_id).await?;
+        }
 
         // Generate X25519 keypair for ECDH
         let ecdh_secret = StaticSecret::random_from_rng(OsRng);
@@ -106,41 +150,78 @@ impl RelayerState {
     }
 }
 
-pub async fn run(state: Arc<RelayerState>) -> anyhow::Result<()> {
-    // 10 requests per second per IP
+pub async fn run(state: Arc<RelayerState>, shutdown: CancellationToken) -> anyhow::Result<()> {
+    // Per-IP limiter
     // Use SmartIpKeyExtractor which handles both direct connections and proxied requests
-    let governor_conf = GovernorConfigBuilder::default()
-        .per_second(10)
-        .burst_size(20)
-        .key_extractor(tower_governor::key_extractor::SmartIpKeyExtractor)
-        .finish()
-        .unwrap();
+    let governor_layer = |limit: RateLimit| GovernorLayer {
+        config: Arc::new(
+            GovernorConfigBuilder::default()
+                .period(limit.replenish_period())
+                .burst_size(limit.burst.max(1))
+                .key_extractor(tower_governor::key_extractor::SmartIpKeyExtractor)
+                .finish()
+                .unwrap(),
+        ),
+    };
+
+    // Operator-only routes, require `Authorization: Bearer <ADMIN_TOKEN>`
+    let admin = Router::new()
+        // List pending withdrawals
+        .route("/withdraw/pending", get(get_pending_withdrawals))
+        // Debug: Get commitment at leaf index
+        .route("/commitment/:bucket_id/:leaf_index", get(get_commitment))
+        // Treasury balances and fees collected
+        .route("/treasury", get(get_treasury))
+        // Rotate the blind-signing RSA key (old key stays valid for the grace window)
+        .route("/admin/rotate-key", post(rotate_signing_key))
+        // Replace a bucket's merkle state with operator-supplied commitments, root-checked
+        .route("/admin/merkle/rebuild", post(rebuild_merkle_tree))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_admin));
+
+    // Paid operations that each cost several RPC calls get their own, stricter budget
+    let strict = Router::new()
+        // Blind signature signing
+        .route("/sign", post(sign_blinded))
+        .route("/sign/batch", post(sign_blinded_batch))
+        // Deposit (via Tor)
+        .route("/deposit", post(handle_deposit))
+        .layer(governor_layer(state.config.strict_rate_limit));
 
     let app = Router::new()
-        // Health check (no rate limit)
+        // Health check
         .route("/health", get(health))
+        // Readiness: RPC, merkle trees and signing key
+        .route("/health/deep", get(deep_health))
         // Relayer info (public key, fees, etc.)
         .route("/info", get(get_info))
-        // Blind signature signing
-        .route("/sign", post(sign_blinded))
-        // Deposit (via Tor)
-        .route("/deposit", post(handle_deposit))
+        // All public parameters in one bundle signed by the relayer keypair
+        .route("/params", get(get_params))
         // Withdrawal request
         .route("/withdraw", post(handle_withdrawal))
         // Execute pending withdrawal
         .route("/withdraw/execute", post(execute_withdrawal))
-        // List pending withdrawals
-        .route("/withdraw/pending", get(get_pending_withdrawals))
+        // Cancel a pending withdrawal with an ownership proof
+        .route("/withdraw/cancel", post(cancel_withdrawal))
+        // Status of a submitted withdrawal, polled by clients awaiting execution
+        .route(
+            "/withdraw/status/:nullifier_hash",
+            get(get_withdrawal_status),
+        )
+        // Whether a nullifier was already spent on-chain
+        .route("/nullifier/:nullifier_hash", get(get_nullifier))
         // Pool status
         .route("/pools", get(get_pools))
         .route("/pools/:bucket_id", get(get_pool))
         // Merkle proof
         .route("/proof/:bucket_id/:leaf_index", get(get_proof))
-        // Debug: Get commitment at leaf index
-        .route("/commitment/:bucket_id/:leaf_index", get(get_commitment))
-        .layer(GovernorLayer {
-            config: Arc::new(governor_conf),
-        })
+        .route("/proof/batch", post(get_proof_batch))
+        // Encrypted note stored with a deposit, fetched over Tor for recovery
+        .route("/note/:bucket_id/:leaf_index", get(get_note))
+        // Roots accepted for withdrawal proofs
+        .route("/roots/:bucket_id", get(get_roots))
+        .merge(admin)
+        .layer(governor_layer(state.config.rate_limit))
+        .merge(strict)
         .layer(TraceLayer::new_for_http())
         .layer(CorsLayer::permissive())
         .with_state(state.clone());
@@ -148,14 +229,20 @@ pub async fn run(state: Arc<RelayerState>) -> anyhow::Result<()> {
     let addr = format!("{}:{}", state.config.host, state.config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     info!(
-        "Relayer listening on {} (rate limited: 10 req/s per IP)",
-        addr
+        "Relayer listening on {} (rate limited per IP: {}/s burst {}, /sign and /deposit {}/s burst {})",
+        addr,
+        state.config.rate_limit.per_second,
+        state.config.rate_limit.burst,
+        state.config.strict_rate_limit.per_second,
+        state.config.strict_rate_limit.burst
     );
 
     axum::serve(
         listener,
         app.into_make_service_with_connect_info::<std::net::SocketAddr>(), // for providing ConnectInfo for rate limiting
     )
+    // Stop accepting connections and let in-flight requests finish
+    .with_graceful_shutdown(shutdown.cancelled_owned())
     .await?;
     Ok(())
 }
@@ -172,14 +259,65 @@ struct InfoResponse {
     pub_key_n: String,
     /// RSA public key E component (hex)
     pub_key_e: String,
+    /// All RSA public keys currently accepted for credits, signing key first
+    pub_keys: Vec<PubKeyInfo>,
     /// X25519 public key for ECDH (hex)
     ecdh_pubkey: String,
+    /// Deposit payload versions accepted, newest first
+    ecdh_payload_versions: Vec<u8>,
     /// Treasury Solana pubkey for credit payments (base58)
     solana_pubkey: String,
+    /// Credit payments go to the wallet that signs deposits, linking the two
+    treasury_is_deposit_wallet: bool,
     /// Fee in basis points
     fee_bps: u16,
     /// Available bucket amounts
     buckets: Vec<BucketInfo>,
+    /// Effective per-IP rate limits
+    rate_limits: RateLimitsInfo,
+}
+
+#[derive(Serialize)]
+struct RateLimitsInfo {
+    /// Applies to every route except `/sign` and `/deposit`
+    default: RateLimit,
+    /// Applies to `/sign` and `/deposit`
+    strict: RateLimit,
+}
+
+#[derive(Serialize)]
+struct PubKeyInfo {
+    /// RSA public key N component (hex)
+    n: String,
+    /// RSA public key E component (hex)
+    e: String,
+    /// Unix timestamp when a retired key stops being accepted (None = current key)
+    expires_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct RebuildTreeRequest {
+    bucket_id: u8,
+    /// Every commitment of the pool in leaf order (hex)
+    commitments: Vec<String>,
+    /// On-chain merkle root the rebuilt tree must reproduce (hex)
+    expected_root: String,
+}
+
+#[derive(Serialize)]
+struct RebuildTreeResponse {
+    success: bool,
+    tree_size: usize,
+    merkle_root: String,
+}
+
+#[derive(Serialize)]
+struct RotateKeyResponse {
+    success: bool,
+    /// New signing key N component (hex)
+    pub_key_n: String,
+    /// Number of keys accepted for verification after rotation
+    valid_keys: usize,
 }
 
 #[derive(Serialize)]
@@ -200,6 +338,9 @@ struct SignRequest {
     payment_tx: String,
     /// Payer's public key (base58 encoded)
     payer: String,
+    /// SPL mint paid with (base58 encoded), omit for native SOL
+    #[serde(default)]
+    mint: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -210,10 +351,40 @@ struct SignResponse {
     error: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct BatchSignRequest {
+    credits: Vec<BatchSignCredit>,
+    /// One payment covering every credit's amount plus fee (base58 encoded)
+    payment_tx: String,
+    /// Payer's public key (base58 encoded)
+    payer: String,
+    /// SPL mint paid with (base58 encoded), omit for native SOL
+    #[serde(default)]
+    mint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BatchSignCredit {
+    /// Blinded token (hex encoded)
+    blinded_token: String,
+    /// Amount in lamports
+    amount: u64,
+}
+
+#[derive(Serialize)]
+struct BatchSignResponse {
+    success: bool,
+    /// Blinded signatures (hex encoded), in request order
+    signatures: Vec<String>,
+}
+
 #[derive(Deserialize)]
 struct WithdrawalRequestWrapper {
     request: WithdrawalRequest,
     delay_hours: u8,
+    /// Bucket the client deposited into, checked against the proof's amount
+    #[serde(default)]
+    bucket_id: Option<u8>,
 }
 
 #[derive(Deserialize)]
@@ -232,8 +403,13 @@ struct PoolStatus {
     bucket_id: u8,
     amount_lamports: u64,
     amount_sol: f64,
+    /// Leaves in the relayer's local tree, every deposit ever made including spent ones
     tree_size: usize,
     merkle_root: String,
+    /// Unspent deposits per the on-chain pool, the real anonymity set (None if RPC failed)
+    anonymity_set_size: Option<u64>,
+    /// Deposits the on-chain pool has taken (None if RPC failed)
+    total_deposits: Option<u64>,
 }
 
 #[derive(Serialize)]
@@ -245,6 +421,48 @@ struct ProofResponse {
     error: Option<String>,
 }
 
+impl From<privacy_proxy_sdk::merkle::MerkleProof> for ProofResponse {
+    fn from(proof: privacy_proxy_sdk::merkle::MerkleProof) -> Self {
+        Self {
+            success: true,
+            siblings: Some(proof.siblings.iter().map(hex::encode).collect()),
+            path_indices: Some(proof.path_indices),
+            leaf_index: Some(proof.leaf_index),
+            error: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RootsResponse {
+    bucket_id: u8,
+    /// Root of the relayer's local tree (hex)
+    current_root: String,
+    /// Root stored in the pool account (hex), None if the RPC read failed
+    on_chain_root: Option<String>,
+    /// Roots recorded by this relayer since startup, newest first
+    historical_roots: Vec<HistoricalRootInfo>,
+}
+
+#[derive(Serialize)]
+struct HistoricalRootInfo {
+    root: String,
+    /// Unix timestamp the root was recorded at
+    recorded_at: u64,
+}
+
+#[derive(Deserialize)]
+struct BatchProofRequest {
+    bucket_id: u8,
+    leaf_indices: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct BatchProofResponse {
+    /// One entry per requested leaf, in request order; unknown leaves carry an error
+    proofs: Vec<ProofResponse>,
+}
+
 async fn health() -> Json<HealthResponse> {
     Json(HealthResponse {
         status: "ok",
@@ -252,102 +470,306 @@ async fn health() -> Json<HealthResponse> {
     })
 }
 
+#[derive(Serialize)]
+struct ComponentHealth {
+    healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl ComponentHealth {
+    fn ok() -> Self {
+        Self {
+            healthy: true,
+            detail: None,
+        }
+    }
+
+    fn failed(detail: impl Into<String>) -> Self {
+        Self {
+            healthy: false,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DeepHealthResponse {
+    status: &'static str,
+    version: &'static str,
+    rpc: ComponentHealth,
+    merkle_trees: ComponentHealth,
+    blind_signer: ComponentHealth,
+}
+
+async fn deep_health(
+    State(state): State<Arc<RelayerState>>,
+) -> (StatusCode, Json<DeepHealthResponse>) {
+    let rpc = match tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        state.rpc_client.get_latest_blockhash(),
+    )
+    .await
+    {
+        Ok(Ok(_)) => ComponentHealth::ok(),
+        Ok(Err(e)) => ComponentHealth::failed(format!("RPC error: {}", e)),
+        Err(_) => ComponentHealth::failed("RPC timed out"),
+    };
+
+    let mut missing = Vec::new();
+    for bucket_id in state.config.bucket_ids() {
+        if !state.merkle_service.is_initialized(bucket_id).await {
+            missing.push(bucket_id.to_string());
+        }
+    }
+    let merkle_trees = if missing.is_empty() {
+        ComponentHealth::ok()
+    } else {
+        ComponentHealth::failed(format!("Uninitialized buckets: {}", missing.join(", ")))
+    };
+
+    let blind_signer = if state.blind_signer.is_ready().await {
+        ComponentHealth::ok()
+    } else {
+        ComponentHealth::failed("RSA signing key invalid")
+    };
+
+    let healthy = rpc.healthy && merkle_trees.healthy && blind_signer.healthy;
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(DeepHealthResponse {
+            status: if healthy { "ok" } else { "unhealthy" },
+            version: env!("CARGO_PKG_VERSION"),
+            rpc,
+            merkle_trees,
+            blind_signer,
+        }),
+    )
+}
+
 async fn get_info(State(state): State<Arc<RelayerState>>) -> Json<InfoResponse> {
     tracing::debug!("get_info called");
-    let pub_key_n = hex::encode(state.blind_signer.public_key_n_bytes().await);
+    let pub_keys: Vec<PubKeyInfo> = state
+        .blind_signer
+        .valid_keys()
+        .await
+        .into_iter()
+        .map(|k| PubKeyInfo {
+            n: hex::encode(k.n),
+            e: hex::encode(k.e),
+            expires_at: k.expires_at,
+        })
+        .collect();
+    let pub_key_n = pub_keys[0].n.clone();
     tracing::debug!("got pub_key_n: {} bytes", pub_key_n.len());
-    let pub_key_e = hex::encode(state.blind_signer.public_key_e_bytes().await);
+    let pub_key_e = pub_keys[0].e.clone();
     tracing::debug!("got pub_key_e: {} bytes", pub_key_e.len());
     let ecdh_pubkey = hex::encode(state.ecdh_pubkey.as_bytes());
     tracing::debug!("got ecdh_pubkey: {} bytes", ecdh_pubkey.len());
     let solana_pubkey = state.config.treasury_keypair.pubkey().to_string();
     tracing::debug!("got solana_pubkey (treasury): {}", solana_pubkey);
 
-    let buckets: Vec<BucketInfo> = BUCKET_AMOUNTS
+    let buckets: Vec<BucketInfo> = state
+        .config
+        .bucket_amounts
         .iter()
         .enumerate()
         .map(|(id, &amount)| BucketInfo {
             id: id as u8,
             amount_lamports: amount,
             amount_sol: amount as f64 / 1_000_000_000.0,
-            total_with_fee: calculate_total_with_fee(amount, state.config.fee_bps),
+            total_with_fee: credit_price(amount, state.config.fee_bps),
         })
         .collect();
 
     Json(InfoResponse {
         pub_key_n,
         pub_key_e,
+        pub_keys,
         ecdh_pubkey,
+        ecdh_payload_versions: PAYLOAD_VERSIONS.to_vec(),
         solana_pubkey,
+        treasury_is_deposit_wallet: state.config.treasury_is_deposit_wallet(),
         fee_bps: state.config.fee_bps,
         buckets,
+        rate_limits: RateLimitsInfo {
+            default: state.config.rate_limit,
+            strict: state.config.strict_rate_limit,
+        },
     })
 }
 
+async fn get_params(
+    State(state): State<Arc<RelayerState>>,
+) -> std::result::Result<Json<SignedParams>, RelayerError> {
+    let (min_delay_hours, max_delay_hours) =
+        fetch_delay_bounds(&state.rpc_client, &state.config.program_id).await?;
+    let rsa_keys = state
+        .blind_signer
+        .valid_keys()
+        .await
+        .into_iter()
+        .map(|k| RsaKeyParams {
+            n: hex::encode(k.n),
+            e: hex::encode(k.e),
+            expires_at: k.expires_at,
+        })
+        .collect();
+
+    let params = RelayerParams {
+        rsa_keys,
+        ecdh_pubkey: hex::encode(state.ecdh_pubkey.as_bytes()),
+        treasury: state.config.treasury_keypair.pubkey().to_string(),
+        program_id: state.config.program_id.to_string(),
+        fee_bps: state.config.fee_bps,
+        bucket_amounts: state.config.bucket_amounts.clone(),
+        min_delay_hours,
+        max_delay_hours,
+        circuit_version: state.config.circuit_version.clone(),
+        withdrawal_vk: state.config.withdrawal_vk.clone(),
+        issued_at: unix_now(),
+    };
+    Ok(Json(SignedParams::sign(&params, &state.config.keypair)?))
+}
+
 async fn sign_blinded(
     State(state): State<Arc<RelayerState>>,
     Json(req): Json<SignRequest>,
 ) -> std::result::Result<Json<SignResponse>, RelayerError> {
-    use solana_sdk::signature::Signature;
-    use std::str::FromStr;
+    let bucket_id = get_bucket_id(&state.config.bucket_amounts, req.amount)
+        .ok_or(RelayerError::InvalidBucket(req.amount))?;
 
-    if get_bucket_id(req.amount).is_none() {
-        return Err(RelayerError::InvalidBucket(req.amount));
+    // Calculate expected payment (amount + fee)
+    let expected_payment = credit_price(req.amount, state.config.fee_bps);
+    verify_payment(
+        &state,
+        &req.payment_tx,
+        &req.payer,
+        req.mint.as_deref(),
+        expected_payment,
+    )
+    .await?;
+
+    let blinded_token =
+        hex::decode(&req.blinded_token).map_err(|_| RelayerError::InvalidBlindedToken)?;
+    let signature = state.blind_signer.sign_blinded(&blinded_token).await?;
+    info!(
+        bucket_id,
+        payment = expected_payment,
+        "Signed blinded token after verifying payment"
+    );
+
+    Ok(Json(SignResponse {
+        success: true,
+        signature: Some(hex::encode(signature)),
+        error: None,
+    }))
+}
+
+/// Several credits paid for with one transaction, the payment is fetched and checked once
+async fn sign_blinded_batch(
+    State(state): State<Arc<RelayerState>>,
+    Json(req): Json<BatchSignRequest>,
+) -> std::result::Result<Json<BatchSignResponse>, RelayerError> {
+    if req.credits.is_empty() || req.credits.len() > MAX_BATCH_SIGNATURES {
+        return Err(RelayerError::InvalidRequest(format!(
+            "credits must hold 1 to {} entries, got {}",
+            MAX_BATCH_SIGNATURES,
+            req.credits.len()
+        )));
     }
 
-    // Calculate expected payment (amount + fee)
-    let expected_payment = calculate_total_with_fee(req.amount, state.config.fee_bps);
+    // Validate every credit before touching RPC, nothing is signed unless all are well-formed
+    let amounts: Vec<u64> = req.credits.iter().map(|c| c.amount).collect();
+    let expected_payment =
+        batch_total_with_fee(&state.config.bucket_amounts, &amounts, state.config.fee_bps)?;
+    let blinded_tokens = req
+        .credits
+        .iter()
+        .map(|c| hex::decode(&c.blinded_token).map_err(|_| RelayerError::InvalidBlindedToken))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    verify_payment(
+        &state,
+        &req.payment_tx,
+        &req.payer,
+        req.mint.as_deref(),
+        expected_payment,
+    )
+    .await?;
+
+    let mut signatures = Vec::with_capacity(blinded_tokens.len());
+    for blinded_token in &blinded_tokens {
+        let signature = state.blind_signer.sign_blinded(blinded_token).await?;
+        signatures.push(hex::encode(signature));
+    }
+    info!(
+        credits = signatures.len(),
+        payment = expected_payment,
+        "Signed blinded token batch after verifying payment"
+    );
+
+    Ok(Json(BatchSignResponse {
+        success: true,
+        signatures,
+    }))
+}
+
+/// Check `payment_tx` moved at least `expected_payment` lamports (or the equivalent in
+/// `mint`) from `payer` to the treasury
+async fn verify_payment(
+    state: &RelayerState,
+    payment_tx: &str,
+    payer: &str,
+    mint: Option<&str>,
+    expected_payment: u64,
+) -> std::result::Result<(), RelayerError> {
+    use solana_sdk::signature::Signature;
+    use std::str::FromStr;
+
+    // Resolve SPL payment mint up front so unsupported tokens fail before any RPC calls
+    let spl_payment = match mint {
+        Some(mint) => {
+            let mint = solana_sdk::pubkey::Pubkey::from_str(mint)
+                .map_err(|_| RelayerError::InvalidRequest("Invalid mint".into()))?;
+            let units_per_sol = state.config.payment_mints.get(&mint).ok_or_else(|| {
+                RelayerError::InvalidRequest(format!("Payments in mint {} not accepted", mint))
+            })?;
+            Some((
+                mint,
+                required_token_amount(expected_payment, *units_per_sol),
+            ))
+        }
+        None => None,
+    };
 
     // Parse payment transaction signature
-    let payment_sig = Signature::from_str(&req.payment_tx).map_err(|_| {
+    let payment_sig = Signature::from_str(payment_tx).map_err(|_| {
         RelayerError::InvalidRequest("Invalid payment transaction signature".into())
     })?;
 
     // Parse payer pubkey
-    let payer_pubkey = solana_sdk::pubkey::Pubkey::from_str(&req.payer)
+    let payer_pubkey = solana_sdk::pubkey::Pubkey::from_str(payer)
         .map_err(|_| RelayerError::InvalidRequest("Invalid payer public key".into()))?;
 
     // Verify payment on-chain against TREASURY wallet (not deposit wallet)
     let relayer_pubkey = state.config.treasury_keypair.pubkey();
 
-    // Fetch transaction with retries (devnet can be slow)
+    // Wait for the payment to confirm (devnet can be slow)
     info!("Fetching payment transaction: {}", payment_sig);
-    let mut tx_result = None;
-    for attempt in 0..10 {
-        match state
-            .rpc_client
-            .get_transaction_with_config(
-                &payment_sig,
-                solana_client::rpc_config::RpcTransactionConfig {
-                    encoding: Some(solana_transaction_status::UiTransactionEncoding::Json),
-                    commitment: Some(solana_sdk::commitment_config::CommitmentConfig::confirmed()),
-                    max_supported_transaction_version: Some(0),
-                },
-            )
-            .await
-        {
-            Ok(tx) => {
-                tx_result = Some(tx);
-                break;
-            }
-            Err(e) => {
-                if attempt < 9 {
-                    info!(
-                        "Payment tx not found yet (attempt {}), retrying in 2s...",
-                        attempt + 1
-                    );
-                    tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-                } else {
-                    return Err(RelayerError::InvalidRequest(format!(
-                        "Payment transaction not found: {}. Transaction exists but full data not available yet. Please try again in a moment.",
-                        e
-                    )));
-                }
-            }
-        }
-    }
-
-    let tx_info = tx_result.unwrap();
+    let tx_info = fetch_confirmed_payment(
+        &state.rpc_client,
+        &payment_sig,
+        state.config.payment_poll_attempts,
+        std::time::Duration::from_millis(state.config.payment_poll_interval_ms),
+    )
+    .await?;
     if let Some(meta) = &tx_info.transaction.meta {
         if meta.err.is_some() {
             return Err(RelayerError::InvalidRequest(
@@ -358,10 +780,23 @@ async fn sign_blinded(
 
     // Extract and verify the transfer
     // We need to check that:
-    // 1. The payer sent SOL to the relayer
+    // 1. The payer sent SOL (or the SPL mint) to the relayer
     // 2. The amount is at least expected_payment
     let mut payment_verified = false;
-    if let Some(meta) = &tx_info.transaction.meta {
+    if let Some((mint, expected_tokens)) = spl_payment {
+        let received = verify_spl_payment(
+            &tx_info.transaction,
+            &mint,
+            &payer_pubkey,
+            &relayer_pubkey,
+            expected_tokens,
+        )?;
+        payment_verified = true;
+        info!(
+            "Payment verified: {} of mint {} from {} (expected {})",
+            received, mint, payer_pubkey, expected_tokens
+        );
+    } else if let Some(meta) = &tx_info.transaction.meta {
         let pre_balances: &Vec<u64> = &meta.pre_balances;
         let post_balances: &Vec<u64> = &meta.post_balances;
 
@@ -411,23 +846,12 @@ async fn sign_blinded(
 
     if !payment_verified {
         return Err(RelayerError::InvalidRequest(
-            "Could not verify payment. Ensure you sent SOL to the relayer.".into(),
+            "Could not verify payment. Ensure you sent SOL (or the accepted token) to the relayer."
+                .into(),
         ));
     }
 
-    let blinded_token =
-        hex::decode(&req.blinded_token).map_err(|_| RelayerError::InvalidBlindedToken)?;
-    let signature = state.blind_signer.sign_blinded(&blinded_token).await?;
-    info!(
-        "Signed blinded token after verifying payment of {} lamports",
-        expected_payment
-    );
-
-    Ok(Json(SignResponse {
-        success: true,
-        signature: Some(hex::encode(signature)),
-        error: None,
-    }))
+    Ok(())
 }
 
 async fn handle_deposit(
@@ -444,10 +868,10 @@ async fn handle_deposit(
 
     let mut pk_array = [0u8; 32];
     pk_array.copy_from_slice(&client_pk_bytes);
-    let client_pubkey = X25519PublicKey::from(pk_array);
 
-    // Derive shared secret
-    let shared_secret = state.ecdh_secret.diffie_hellman(&client_pubkey);
+    // Derive shared secret, rejecting malformed or low-order client keys
+    let shared_secret = ecdh_shared_secret(&state.ecdh_secret.to_bytes(), &pk_array)
+        .map_err(|e| RelayerError::InvalidRequest(e.to_string()))?;
 
     // Decrypt with AES-256-GCM
     if payload.nonce.len() != 12 {
@@ -456,7 +880,13 @@ async fn handle_deposit(
         ));
     }
 
-    let cipher = Aes256Gcm::new_from_slice(shared_secret.as_bytes())
+    let key = payload_key(&shared_secret, payload.version)
+        .map_err(|e| RelayerError::InvalidRequest(e.to_string()))?;
+    if payload.version == PAYLOAD_VERSION_RAW {
+        tracing::debug!("Deposit payload uses legacy raw ECDH key");
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(&key)
         .map_err(|_| RelayerError::Internal("Failed to create cipher".into()))?;
     let nonce_arr = Nonce::from_slice(&payload.nonce);
 
@@ -473,7 +903,18 @@ async fn handle_deposit(
         .map_err(|e| RelayerError::InvalidRequest(format!("Invalid decrypted payload: {}", e)))?;
 
     let request = convert_plain_to_deposit_request(plain_req)?;
+    let bucket_id = get_bucket_id(&state.config.bucket_amounts, request.credit.amount);
     let response = state.deposit_service.handle_deposit(request).await?;
+    if let Some(bucket_id) = bucket_id {
+        // Keep the new root acceptable for withdrawals after later deposits move it
+        if let Err(e) = state
+            .withdrawal_service
+            .record_historical_root(bucket_id)
+            .await
+        {
+            tracing::warn!(bucket_id, "Failed to record historical root: {}", e);
+        }
+    }
     Ok(Json(response))
 }
 
@@ -507,16 +948,20 @@ fn convert_plain_to_deposit_request(
         },
         commitment,
         encrypted_note: plain.encrypted_note,
+        note_ephemeral_pubkey: plain.note_ephemeral_pubkey,
     })
 }
 
 async fn handle_withdrawal(
     State(state): State<Arc<RelayerState>>,
-    Json(req): Json<WithdrawalRequestWrapper>,
+    req: std::result::Result<Json<WithdrawalRequestWrapper>, JsonRejection>,
 ) -> std::result::Result<Json<WithdrawalResponse>, RelayerError> {
+    // Surface serde's message (e.g. "invalid length 63, expected an array of length 64")
+    // in the usual error body rather than axum's plain-text rejection
+    let Json(req) = req.map_err(|e| RelayerError::InvalidRequest(e.body_text()))?;
     let response = state
         .withdrawal_service
-        .handle_withdrawal(req.request, req.delay_hours)
+        .handle_withdrawal(req.request, req.delay_hours, req.bucket_id)
         .await?;
     Ok(Json(response))
 }
@@ -542,6 +987,62 @@ async fn execute_withdrawal(
     }))
 }
 
+async fn cancel_withdrawal(
+    State(state): State<Arc<RelayerState>>,
+    Json(req): Json<OwnershipProofRequest>,
+) -> std::result::Result<Json<WithdrawalResponse>, RelayerError> {
+    let tx_signature = state.withdrawal_service.cancel_withdrawal(&req).await?;
+
+    Ok(Json(WithdrawalResponse {
+        success: true,
+        tx_signature: Some(tx_signature),
+        error: None,
+    }))
+}
+
+async fn get_nullifier(
+    State(state): State<Arc<RelayerState>>,
+    axum::extract::Path(nullifier_hash): axum::extract::Path<String>,
+) -> std::result::Result<Json<NullifierResponse>, RelayerError> {
+    let nullifier_hash: [u8; 32] = hex::decode(&nullifier_hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| RelayerError::InvalidRequest("Invalid nullifier hash".into()))?;
+    let spent = state
+        .withdrawal_service
+        .is_nullifier_spent(&nullifier_hash)
+        .await?;
+    Ok(Json(NullifierResponse { spent }))
+}
+
+async fn get_withdrawal_status(
+    State(state): State<Arc<RelayerState>>,
+    axum::extract::Path(nullifier_hash): axum::extract::Path<String>,
+) -> std::result::Result<Json<WithdrawalStatusResponse>, RelayerError> {
+    let nullifier_hash: [u8; 32] = hex::decode(&nullifier_hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| RelayerError::InvalidRequest("Invalid nullifier hash".into()))?;
+    let record = state
+        .withdrawal_service
+        .withdrawal_status(&nullifier_hash)
+        .await
+        .ok_or_else(|| RelayerError::NotFound("No withdrawal for this nullifier hash".into()))?;
+
+    let status = if record.executed {
+        WithdrawalState::Executed
+    } else if record.cancelled {
+        WithdrawalState::Cancelled
+    } else {
+        WithdrawalState::Pending
+    };
+    Ok(Json(WithdrawalStatusResponse {
+        status,
+        execute_after: record.execute_after,
+        tx_signature: record.tx_signature,
+    }))
+}
+
 async fn get_pending_withdrawals(
     State(state): State<Arc<RelayerState>>,
 ) -> Json<PendingWithdrawalsResponse> {
@@ -558,17 +1059,136 @@ async fn get_pending_withdrawals(
             amount: r.amount,
             fee: r.fee,
             executed: r.executed,
+            cancelled: r.cancelled,
         })
         .collect();
     Json(PendingWithdrawalsResponse { pending })
 }
 
+async fn get_treasury(
+    State(state): State<Arc<RelayerState>>,
+) -> std::result::Result<Json<TreasuryResponse>, RelayerError> {
+    // Withdrawal fees are paid to the program's treasury PDA, credit payments to the treasury wallet
+    let (fee_treasury, _) =
+        solana_sdk::pubkey::Pubkey::find_program_address(&[b"treasury"], &state.config.program_id);
+    let credit_treasury = state.config.treasury_keypair.pubkey();
+    let fee_treasury_lamports = state.rpc_client.get_balance(&fee_treasury).await?;
+    let credit_treasury_lamports = state.rpc_client.get_balance(&credit_treasury).await?;
+    let fees = state.withdrawal_service.fee_summary().await;
+
+    Ok(Json(TreasuryResponse {
+        fee_treasury: TreasuryBalance {
+            address: fee_treasury.to_string(),
+            lamports: fee_treasury_lamports,
+        },
+        credit_treasury: TreasuryBalance {
+            address: credit_treasury.to_string(),
+            lamports: credit_treasury_lamports,
+        },
+        total_lamports: fee_treasury_lamports.saturating_add(credit_treasury_lamports),
+        executed_withdrawals: fees.executed_withdrawals,
+        fees_collected: fees.fees_collected,
+    }))
+}
+
+async fn rotate_signing_key(
+    State(state): State<Arc<RelayerState>>,
+) -> std::result::Result<Json<RotateKeyResponse>, RelayerError> {
+    state.blind_signer.rotate().await?;
+    let keys = state.blind_signer.valid_keys().await;
+    Ok(Json(RotateKeyResponse {
+        success: true,
+        pub_key_n: hex::encode(&keys[0].n),
+        valid_keys: keys.len(),
+    }))
+}
+
+async fn rebuild_merkle_tree(
+    State(state): State<Arc<RelayerState>>,
+    Json(req): Json<RebuildTreeRequest>,
+) -> std::result::Result<Json<RebuildTreeResponse>, RelayerError> {
+    state
+        .config
+        .bucket_amount(req.bucket_id)
+        .ok_or(RelayerError::InvalidBucket(req.bucket_id as u64))?;
+    let parse = |value: &str, what: &str| -> std::result::Result<[u8; 32], RelayerError> {
+        hex::decode(value)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| RelayerError::InvalidRequest(format!("Invalid {}: {}", what, value)))
+    };
+    let commitments = req
+        .commitments
+        .iter()
+        .map(|c| parse(c, "commitment"))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let expected_root = parse(&req.expected_root, "expected root")?;
+
+    state
+        .merkle_service
+        .rebuild_from_commitments(req.bucket_id, commitments, expected_root)
+        .await?;
+    info!(
+        "Admin rebuilt merkle tree for bucket {} ({} commitments)",
+        req.bucket_id,
+        req.commitments.len()
+    );
+
+    Ok(Json(RebuildTreeResponse {
+        success: true,
+        tree_size: state.merkle_service.size(req.bucket_id).await?,
+        merkle_root: hex::encode(state.merkle_service.root(req.bucket_id).await?),
+    }))
+}
+
 async fn get_pools(
     State(state): State<Arc<RelayerState>>,
 ) -> std::result::Result<Json<PoolsResponse>, RelayerError> {
-    let mut pools = Vec::new();
-    for (bucket_id, &amount) in BUCKET_AMOUNTS.iter().enumerate() {
-        let bucket_id = bucket_id as u8;
+    let bucket_ids: Vec<u8> = state.config.bucket_ids().collect();
+    let pools = pool_statuses(&state, &bucket_ids).await?;
+    Ok(Json(PoolsResponse { pools }))
+}
+
+async fn get_pool(
+    State(state): State<Arc<RelayerState>>,
+    axum::extract::Path(bucket_id): axum::extract::Path<u8>,
+) -> std::result::Result<Json<PoolStatus>, RelayerError> {
+    if bucket_id as usize >= state.config.num_buckets() {
+        return Err(RelayerError::InvalidBucket(bucket_id as u64));
+    }
+    let mut pools = pool_statuses(&state, &[bucket_id]).await?;
+    Ok(Json(pools.remove(0)))
+}
+
+/// Local tree state of each bucket, plus the on-chain counters fetched in one RPC call
+async fn pool_statuses(
+    state: &RelayerState,
+    bucket_ids: &[u8],
+) -> std::result::Result<Vec<PoolStatus>, RelayerError> {
+    let pool_pdas: Vec<_> = bucket_ids
+        .iter()
+        .map(|&bucket_id| {
+            solana_sdk::pubkey::Pubkey::find_program_address(
+                &[b"pool", &[bucket_id]],
+                &state.config.program_id,
+            )
+            .0
+        })
+        .collect();
+    let on_chain = match DepositPoolView::fetch_many(&state.rpc_client, &pool_pdas).await {
+        Ok(pools) => pools,
+        Err(e) => {
+            tracing::warn!("Failed to fetch on-chain pools: {}", e);
+            vec![None; bucket_ids.len()]
+        }
+    };
+
+    let mut pools = Vec::with_capacity(bucket_ids.len());
+    for (&bucket_id, on_chain) in bucket_ids.iter().zip(on_chain) {
+        let amount = state
+            .config
+            .bucket_amount(bucket_id)
+            .ok_or(RelayerError::InvalidBucket(bucket_id as u64))?;
         let tree_size = state.merkle_service.size(bucket_id).await?;
         let merkle_root = state.merkle_service.root(bucket_id).await?;
 
@@ -578,50 +1198,99 @@ async fn get_pools(
             amount_sol: amount as f64 / 1_000_000_000.0,
             tree_size,
             merkle_root: hex::encode(merkle_root),
+            anonymity_set_size: on_chain.as_ref().map(DepositPoolView::anonymity_set_size),
+            total_deposits: on_chain.as_ref().map(DepositPoolView::total_deposits),
         });
     }
+    Ok(pools)
+}
 
-    Ok(Json(PoolsResponse { pools }))
+async fn get_proof(
+    State(state): State<Arc<RelayerState>>,
+    axum::extract::Path((bucket_id, leaf_index)): axum::extract::Path<(u8, u64)>,
+) -> std::result::Result<Json<ProofResponse>, RelayerError> {
+    if bucket_id as usize >= state.config.num_buckets() {
+        return Err(RelayerError::InvalidBucket(bucket_id as u64));
+    }
+
+    let proof = state.merkle_service.proof(bucket_id, leaf_index).await?;
+
+    Ok(Json(proof.into()))
 }
 
-async fn get_pool(
+/// Roots a withdrawal proof for this bucket can currently be built against
+async fn get_roots(
     State(state): State<Arc<RelayerState>>,
     axum::extract::Path(bucket_id): axum::extract::Path<u8>,
-) -> std::result::Result<Json<PoolStatus>, RelayerError> {
-    if bucket_id as usize >= BUCKET_AMOUNTS.len() {
+) -> std::result::Result<Json<RootsResponse>, RelayerError> {
+    if bucket_id as usize >= state.config.num_buckets() {
         return Err(RelayerError::InvalidBucket(bucket_id as u64));
     }
 
-    let amount = BUCKET_AMOUNTS[bucket_id as usize];
-    let tree_size = state.merkle_service.size(bucket_id).await?;
-    let merkle_root = state.merkle_service.root(bucket_id).await?;
+    let current_root = state.merkle_service.root(bucket_id).await?;
+    let (pool_pda, _) = solana_sdk::pubkey::Pubkey::find_program_address(
+        &[b"pool", &[bucket_id]],
+        &state.config.program_id,
+    );
+    let on_chain_root = match DepositPoolView::fetch(&state.rpc_client, &pool_pda).await {
+        Ok(pool) => Some(hex::encode(pool.merkle_root())),
+        Err(e) => {
+            tracing::warn!(bucket_id, "Failed to fetch on-chain root: {}", e);
+            None
+        }
+    };
+    let historical_roots = state
+        .withdrawal_service
+        .historical_roots(bucket_id)
+        .await
+        .into_iter()
+        .map(|(root, recorded_at)| HistoricalRootInfo {
+            root: hex::encode(root),
+            recorded_at,
+        })
+        .collect();
 
-    Ok(Json(PoolStatus {
+    Ok(Json(RootsResponse {
         bucket_id,
-        amount_lamports: amount,
-        amount_sol: amount as f64 / 1_000_000_000.0,
-        tree_size,
-        merkle_root: hex::encode(merkle_root),
+        current_root: hex::encode(current_root),
+        on_chain_root,
+        historical_roots,
     }))
 }
 
-async fn get_proof(
+/// Proofs for several leaves of one bucket, so note recovery costs one Tor round trip
+async fn get_proof_batch(
     State(state): State<Arc<RelayerState>>,
-    axum::extract::Path((bucket_id, leaf_index)): axum::extract::Path<(u8, u64)>,
-) -> std::result::Result<Json<ProofResponse>, RelayerError> {
-    if bucket_id as usize >= BUCKET_AMOUNTS.len() {
-        return Err(RelayerError::InvalidBucket(bucket_id as u64));
+    Json(req): Json<BatchProofRequest>,
+) -> std::result::Result<Json<BatchProofResponse>, RelayerError> {
+    if req.bucket_id as usize >= state.config.num_buckets() {
+        return Err(RelayerError::InvalidBucket(req.bucket_id as u64));
+    }
+    if req.leaf_indices.is_empty() || req.leaf_indices.len() > MAX_BATCH_PROOFS {
+        return Err(RelayerError::InvalidRequest(format!(
+            "leaf_indices must hold 1 to {} entries, got {}",
+            MAX_BATCH_PROOFS,
+            req.leaf_indices.len()
+        )));
     }
 
-    let proof = state.merkle_service.proof(bucket_id, leaf_index).await?;
+    let mut proofs = Vec::with_capacity(req.leaf_indices.len());
+    for leaf_index in req.leaf_indices {
+        proofs.push(
+            match state.merkle_service.proof(req.bucket_id, leaf_index).await {
+                Ok(proof) => proof.into(),
+                Err(e) => ProofResponse {
+                    success: false,
+                    siblings: None,
+                    path_indices: None,
+                    leaf_index: Some(leaf_index),
+                    error: Some(e.to_string()),
+                },
+            },
+        );
+    }
 
-    Ok(Json(ProofResponse {
-        success: true,
-        siblings: Some(proof.siblings.iter().map(hex::encode).collect()),
-        path_indices: Some(proof.path_indices.clone()),
-        leaf_index: Some(proof.leaf_index),
-        error: None,
-    }))
+    Ok(Json(BatchProofResponse { proofs }))
 }
 
 #[derive(Serialize)]
@@ -631,6 +1300,20 @@ struct CommitmentResponse {
     error: Option<String>,
 }
 
+#[derive(Serialize)]
+struct NullifierResponse {
+    spent: bool,
+}
+
+#[derive(Serialize)]
+struct NoteResponse {
+    success: bool,
+    ciphertext_hex: Option<String>,
+    ephemeral_pubkey_hex: Option<String>,
+    created_at: Option<i64>,
+    error: Option<String>,
+}
+
 #[derive(Serialize)]
 struct PendingWithdrawalInfo {
     pda: String,
@@ -642,6 +1325,7 @@ struct PendingWithdrawalInfo {
     amount: u64,
     fee: u64,
     executed: bool,
+    cancelled: bool,
 }
 
 #[derive(Serialize)]
@@ -649,11 +1333,31 @@ struct PendingWithdrawalsResponse {
     pending: Vec<PendingWithdrawalInfo>,
 }
 
+#[derive(Serialize)]
+struct TreasuryBalance {
+    /// Account address (base58)
+    address: String,
+    lamports: u64,
+}
+
+#[derive(Serialize)]
+struct TreasuryResponse {
+    /// Program treasury PDA that receives withdrawal fees
+    fee_treasury: TreasuryBalance,
+    /// Wallet that receives credit payments
+    credit_treasury: TreasuryBalance,
+    total_lamports: u64,
+    /// Withdrawals executed by this relayer
+    executed_withdrawals: u64,
+    /// Fees from those withdrawals, in lamports
+    fees_collected: u64,
+}
+
 async fn get_commitment(
     State(state): State<Arc<RelayerState>>,
     axum::extract::Path((bucket_id, leaf_index)): axum::extract::Path<(u8, u64)>,
 ) -> std::result::Result<Json<CommitmentResponse>, RelayerError> {
-    if bucket_id as usize >= BUCKET_AMOUNTS.len() {
+    if bucket_id as usize >= state.config.num_buckets() {
         return Err(RelayerError::InvalidBucket(bucket_id as u64));
     }
 
@@ -677,3 +1381,142 @@ async fn get_commitment(
         error: None,
     }))
 }
+
+/// Encrypted note of the deposit at `leaf_index`, read straight from its on-chain account
+async fn get_note(
+    State(state): State<Arc<RelayerState>>,
+    axum::extract::Path((bucket_id, leaf_index)): axum::extract::Path<(u8, u64)>,
+) -> std::result::Result<Json<NoteResponse>, RelayerError> {
+    if bucket_id as usize >= state.config.num_buckets() {
+        return Err(RelayerError::InvalidBucket(bucket_id as u64));
+    }
+
+    let (pool_pda, _) = solana_sdk::pubkey::Pubkey::find_program_address(
+        &[b"pool", &[bucket_id]],
+        &state.config.program_id,
+    );
+    let note_pda = EncryptedNoteView::pda(&state.config.program_id, &pool_pda, leaf_index);
+    let Some(note) = EncryptedNoteView::fetch(&state.rpc_client, &note_pda).await? else {
+        return Ok(Json(NoteResponse {
+            success: false,
+            ciphertext_hex: None,
+            ephemeral_pubkey_hex: None,
+            created_at: None,
+            error: Some(format!("No note at leaf index {}", leaf_index)),
+        }));
+    };
+
+    Ok(Json(NoteResponse {
+        success: true,
+        ciphertext_hex: Some(hex::encode(note.ciphertext())),
+        ephemeral_pubkey_hex: Some(hex::encode(note.ephemeral_pubkey())),
+        created_at: Some(note.created_at()),
+        error: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::MockRpc;
+    use solana_client::rpc_request::RpcRequest;
+
+    /// State backed by `rpc`, with trees initialized for every bucket except `uninitialized`
+    async fn test_state(
+        rpc: MockRpc,
+        uninitialized: Option<u8>,
+        dir: &std::path::Path,
+    ) -> Arc<RelayerState> {
+        let config = RelayerConfig::for_tests();
+        let rpc_client = Arc::new(rpc.into_client());
+        let blind_signer = Arc::new(
+            BlindSignerService::with_key_path(
+                config.rsa_key_bits,
+                config.rsa_rotation_grace_secs,
+                dir.join("signing_key.der"),
+            )
+            .unwrap(),
+        );
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(dir.join("merkle")));
+        for bucket_id in config.bucket_ids() {
+            if Some(bucket_id) != uninitialized {
+                merkle_service.init_tree(bucket_id).await.unwrap();
+            }
+        }
+        let deposit_service = Arc::new(DepositService::with_token_store_path(
+            config.clone(),
+            rpc_client.clone(),
+            blind_signer.clone(),
+            merkle_service.clone(),
+            dir.join("tokens.dat"),
+        ));
+        let withdrawal_service = Arc::new(WithdrawalService::new(
+            config.clone(),
+            rpc_client.clone(),
+            merkle_service.clone(),
+        ));
+        let ecdh_secret = StaticSecret::random_from_rng(OsRng);
+        let ecdh_pubkey = X25519PublicKey::from(&ecdh_secret);
+        Arc::new(RelayerState {
+            config,
+            rpc_client,
+            blind_signer,
+            merkle_service,
+            deposit_service,
+            withdrawal_service,
+            ecdh_secret,
+            ecdh_pubkey,
+        })
+    }
+
+    async fn check(state: Arc<RelayerState>) -> (StatusCode, serde_json::Value) {
+        let (status, Json(body)) = deep_health(State(state)).await;
+        (status, serde_json::to_value(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_deep_health() {
+        let dir = tempfile::tempdir().unwrap();
+        let ok = serde_json::json!({ "healthy": true });
+
+        let (status, body) =
+            check(test_state(MockRpc::new().blockhash(), None, dir.path()).await).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["rpc"], ok);
+        assert_eq!(body["merkle_trees"], ok);
+        assert_eq!(body["blind_signer"], ok);
+
+        // A bucket whose tree never loaded
+        let dir = tempfile::tempdir().unwrap();
+        let (status, body) =
+            check(test_state(MockRpc::new().blockhash(), Some(3), dir.path()).await).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["rpc"], ok);
+        assert_eq!(
+            body["merkle_trees"],
+            serde_json::json!({ "healthy": false, "detail": "Uninitialized buckets: 3" })
+        );
+        assert_eq!(body["blind_signer"], ok);
+
+        // The RPC node is unreachable
+        let dir = tempfile::tempdir().unwrap();
+        let unreachable = MockRpc::new().on(RpcRequest::GetLatestBlockhash, |_| {
+            Err(
+                std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "connection refused")
+                    .into(),
+            )
+        });
+        let (status, body) = check(test_state(unreachable, None, dir.path()).await).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["rpc"]["healthy"], false);
+        assert!(body["rpc"]["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("RPC error: "));
+        assert_eq!(body["merkle_trees"], ok);
+        assert_eq!(body["blind_signer"], ok);
+    }
+}
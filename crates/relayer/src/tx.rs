@@ -0,0 +1,176 @@
+/// Transaction submission that survives blockhash expiry
+/// A transaction whose blockhash has expired can never land, so once `send_and_confirm`
+/// gives up on it, rebuilding with a fresh blockhash can't double-execute the instructions
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcError;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use tracing::warn;
+
+/// Why a submission failed, with the signature of the last transaction sent (if any)
+/// so callers can still look it up on-chain
+#[derive(Debug)]
+pub struct SubmitError {
+    pub signature: Option<Signature>,
+    pub error: ClientError,
+}
+
+/// The transaction's blockhash expired before it was confirmed
+fn is_blockhash_expired(error: &ClientError) -> bool {
+    if error.get_transaction_error() == Some(TransactionError::BlockhashNotFound) {
+        return true;
+    }
+    // What `send_and_confirm_transaction` returns once the blockhash is no longer valid
+    matches!(
+        error.kind(),
+        solana_client::client_error::ClientErrorKind::RpcError(RpcError::ForUser(message))
+            if message.starts_with("unable to confirm transaction")
+    )
+}
+
+/// Sign `instructions` with the latest blockhash and send, re-signing with a fresh one up
+/// to `max_attempts` times in total while the blockhash keeps expiring first
+pub async fn submit_with_blockhash_refresh(
+    rpc_client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+    max_attempts: u32,
+) -> std::result::Result<Signature, SubmitError> {
+    let mut attempt = 1;
+    loop {
+        let recent_blockhash =
+            rpc_client
+                .get_latest_blockhash()
+                .await
+                .map_err(|error| SubmitError {
+                    signature: None,
+                    error,
+                })?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        let signature = transaction.signatures[0];
+
+        let error = match rpc_client.send_and_confirm_transaction(&transaction).await {
+            Ok(signature) => return Ok(signature),
+            Err(error) => error,
+        };
+        if attempt >= max_attempts || !is_blockhash_expired(&error) {
+            return Err(SubmitError {
+                signature: Some(signature),
+                error,
+            });
+        }
+
+        // It may have landed between the last status check and the expiry check
+        match rpc_client.get_signature_status(&signature).await {
+            Ok(Some(Ok(()))) => return Ok(signature),
+            Ok(Some(Err(e))) => {
+                return Err(SubmitError {
+                    signature: Some(signature),
+                    error: e.into(),
+                })
+            }
+            Ok(None) => {}
+            Err(error) => {
+                return Err(SubmitError {
+                    signature: Some(signature),
+                    error,
+                })
+            }
+        }
+
+        warn!(
+            tx_signature = %signature,
+            attempt,
+            max_attempts,
+            "Blockhash expired before confirmation, re-signing with a fresh one"
+        );
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{self, with_context, MockRpc};
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// RPC double where the first `expiring` transactions sent never confirm and their
+    /// blockhash then reads as expired, later ones confirm right away
+    fn client(expiring: usize) -> (RpcClient, Arc<Mutex<Vec<String>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let blockhashes = AtomicU8::new(0);
+        let sending = sent.clone();
+        let statuses = sent.clone();
+        let rpc_client = MockRpc::new()
+            .on(RpcRequest::GetLatestBlockhash, move |_| {
+                let count = blockhashes.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(with_context(json!({
+                    "blockhash": solana_sdk::hash::Hash::new_from_array([count; 32]).to_string(),
+                    "lastValidBlockHeight": 100,
+                })))
+            })
+            .on(RpcRequest::SendTransaction, move |params| {
+                let signature = mock_rpc::sent_transaction(&params).signatures[0].to_string();
+                sending.lock().unwrap().push(signature.clone());
+                Ok(json!(signature))
+            })
+            .on(RpcRequest::GetSignatureStatuses, move |params| {
+                let signature = params[0][0].as_str().unwrap();
+                let position = statuses.lock().unwrap().iter().position(|s| s == signature);
+                let status = (position.unwrap() >= expiring).then(mock_rpc::finalized_status);
+                Ok(with_context(json!([status])))
+            })
+            .on(RpcRequest::IsBlockhashValid, |_| {
+                Ok(with_context(json!(false)))
+            })
+            .into_client();
+        (rpc_client, sent)
+    }
+
+    #[tokio::test]
+    async fn test_expired_blockhash_is_refreshed() {
+        let payer = Keypair::new();
+        let instructions = [solana_sdk::system_instruction::transfer(
+            &payer.pubkey(),
+            &solana_sdk::pubkey::Pubkey::new_unique(),
+            1,
+        )];
+
+        // Two expiries, the third transaction lands
+        let (rpc_client, sent) = client(2);
+        let signature = submit_with_blockhash_refresh(&rpc_client, &instructions, &payer, 3)
+            .await
+            .unwrap();
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[2], signature.to_string());
+        // Every attempt was signed over its own blockhash
+        assert_ne!(sent[0], sent[1]);
+        assert_ne!(sent[1], sent[2]);
+
+        // Out of attempts: the last signature comes back with the expiry error
+        let (rpc_client, sent) = client(2);
+        let err = submit_with_blockhash_refresh(&rpc_client, &instructions, &payer, 2)
+            .await
+            .unwrap_err();
+        assert!(is_blockhash_expired(&err.error));
+        assert_eq!(
+            err.signature.map(|s| s.to_string()).as_ref(),
+            sent.lock().unwrap().last()
+        );
+        assert_eq!(sent.lock().unwrap().len(), 2);
+    }
+}
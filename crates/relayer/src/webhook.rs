@@ -0,0 +1,185 @@
+/// Push notifications to an operator-configured URL when withdrawals execute
+/// Callbacks go through Tor like every other outbound request, and are signed with the
+/// relayer keypair so receivers can check they came from this relayer: the signature covers
+/// the exact body bytes and travels in headers, receivers verify the body before parsing it
+use serde::Serialize;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use std::sync::Arc;
+use std::time::Duration;
+use tracezero::{Config as TorConfig, Method, TorHttpClient};
+use tracing::{info, warn};
+
+/// Delivery attempts per notification
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled after each failure
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Relayer pubkey that produced the signature (base58)
+const SIGNER_HEADER: &str = "X-Relayer-Signer";
+
+/// ed25519 signature over the request body (base58)
+const SIGNATURE_HEADER: &str = "X-Relayer-Signature";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct WithdrawalExecutedEvent {
+    /// Amount paid out in lamports (after fee)
+    pub amount: u64,
+    /// Nullifier hash (hex)
+    pub nullifier_hash: String,
+    /// Recipient stealth address (base58)
+    pub recipient: String,
+    pub tx_signature: String,
+}
+
+/// Webhook request: the event's JSON, sent as is, and the signature over those bytes
+struct SignedWebhook {
+    body: Vec<u8>,
+    signature: String,
+}
+
+pub struct WebhookNotifier {
+    url: String,
+    client: TorHttpClient,
+    keypair: Arc<Keypair>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, socks_addr: &str, keypair: Arc<Keypair>) -> anyhow::Result<Self> {
+        let client = TorHttpClient::new(TorConfig::default().with_socks_addr(socks_addr))?;
+        Ok(Self {
+            url,
+            client,
+            keypair,
+        })
+    }
+
+    fn sign(&self, event: &WithdrawalExecutedEvent) -> anyhow::Result<SignedWebhook> {
+        let body = serde_json::to_vec(event)?;
+        let signature = self.keypair.sign_message(&body).to_string();
+        Ok(SignedWebhook { body, signature })
+    }
+
+    /// POST the event, retrying with backoff, failures are logged and dropped
+    pub async fn notify_withdrawal_executed(&self, event: WithdrawalExecutedEvent) {
+        let signed = match self.sign(&event) {
+            Ok(signed) => signed,
+            Err(e) => {
+                warn!("Failed to sign webhook payload: {}", e);
+                return;
+            }
+        };
+
+        let signer = self.keypair.pubkey().to_string();
+        let mut delay = WEBHOOK_RETRY_DELAY;
+        for attempt in 1..=WEBHOOK_ATTEMPTS {
+            let request = self
+                .client
+                .request(Method::POST, &self.url)
+                .header("Content-Type", "application/json")
+                .header(SIGNER_HEADER, &signer)
+                .header(SIGNATURE_HEADER, &signed.signature)
+                .body(signed.body.clone());
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {
+                    info!("✓ Webhook delivered for tx {}", event.tx_signature);
+                    return;
+                }
+                Ok(response) => warn!(
+                    "⚠ Webhook attempt {}/{} rejected: HTTP {}",
+                    attempt,
+                    WEBHOOK_ATTEMPTS,
+                    response.status()
+                ),
+                Err(e) => warn!(
+                    "⚠ Webhook attempt {}/{} failed: {}",
+                    attempt, WEBHOOK_ATTEMPTS, e
+                ),
+            }
+            if attempt < WEBHOOK_ATTEMPTS {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        warn!(
+            "✗ Giving up on webhook for tx {} after {} attempts",
+            event.tx_signature, WEBHOOK_ATTEMPTS
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signature;
+    use std::str::FromStr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_webhook_signature_covers_sent_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            // Headers and body arrive before the client waits on the response
+            loop {
+                let n = stream.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .map_or(0, |len| len.parse::<usize>().unwrap());
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let keypair = Arc::new(Keypair::new());
+        let notifier = WebhookNotifier {
+            url,
+            client: TorHttpClient::new_direct().unwrap(),
+            keypair: keypair.clone(),
+        };
+        notifier
+            .notify_withdrawal_executed(WithdrawalExecutedEvent {
+                amount: 995_000_000,
+                nullifier_hash: hex::encode([9u8; 32]),
+                recipient: Pubkey::new_unique().to_string(),
+                tx_signature: "5sig".into(),
+            })
+            .await;
+
+        let request = server.await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let header = |name: &str| {
+            head.lines()
+                .find_map(|line| line.strip_prefix(&format!("{}: ", name.to_lowercase())))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(header(SIGNER_HEADER), keypair.pubkey().to_string());
+
+        // Receivers verify the bytes they got, no re-encoding involved
+        let signature = Signature::from_str(&header(SIGNATURE_HEADER)).unwrap();
+        assert!(signature.verify(keypair.pubkey().as_ref(), body.as_bytes()));
+        let event: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(event["amount"], 995_000_000);
+
+        let tampered = body.replace("995000000", "1");
+        assert!(!signature.verify(keypair.pubkey().as_ref(), tampered.as_bytes()));
+    }
+}
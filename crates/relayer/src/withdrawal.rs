@@ -1,4 +1,8 @@
-use privacy_proxy_sdk::withdrawal::{WithdrawalRequest, WithdrawalResponse};
+use futures::stream::{FuturesUnordered, StreamExt};
+use privacy_proxy_sdk::crypto::is_field_element;
+use privacy_proxy_sdk::withdrawal::{
+    compute_withdrawal_fee, OwnershipProofRequest, WithdrawalRequest, WithdrawalResponse, ZkProof,
+};
 use sha2::{Digest, Sha256};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
@@ -8,33 +12,99 @@ use solana_sdk::{
     system_program::ID as SYSTEM_PROGRAM_ID,
     transaction::Transaction,
 };
-use std::collections::HashMap;
+use solana_transaction_status::UiTransactionEncoding;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{OnceCell, RwLock, Semaphore};
 use tracing::{error, info, warn};
 
 use crate::config::RelayerConfig;
 use crate::error::{RelayerError, Result};
 use crate::merkle_service::MerkleService;
+use crate::on_chain::{
+    historical_roots_account_index, DepositPoolView, GlobalConfigView, HistoricalRootsView,
+    MAX_CHAINED_ACCOUNTS,
+};
+use crate::rpc::rpc_read;
+use crate::tx::{submit_with_blockhash_refresh, SubmitError};
+use crate::webhook::{WebhookNotifier, WithdrawalExecutedEvent};
+
+/// BN254 base field modulus q, every proof point coordinate must be below it
+const BN254_BASE_FIELD_MODULUS: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x47,
+];
 
 /// Minimum time to keep historical roots (48 hours)
 /// This ensures roots are available for delayed withdrawals (max 24 hours)
-#[allow(dead_code)]
 const MIN_ROOT_RETENTION_HOURS: u64 = 48;
 
 /// Maximum number of historical roots to keep per bucket (as a safety limit)
-#[allow(dead_code)]
 const MAX_HISTORICAL_ROOTS: usize = 1000;
 
 /// Historical root with timestamp for time-based pruning
 #[derive(Clone)]
-#[allow(dead_code)]
 struct TimestampedRoot {
     root: [u8; 32],
     added_at: Instant,
+    /// Unix timestamp of `added_at`, reported by `GET /roots`
+    recorded_at: u64,
+}
+
+/// WithdrawalRequested event emitted by request_withdrawal
+#[derive(Debug, PartialEq, Eq)]
+struct WithdrawalRequestedEvent {
+    pool: Pubkey,
+    /// `tx_id` the program gave the pending withdrawal, bound into cancel proofs
+    pending_id: u64,
+    execute_after: i64,
+}
+
+/// Result of walking the chained HistoricalRoots accounts for a root
+#[derive(Debug, PartialEq, Eq)]
+enum RootLookup {
+    /// Pool's current root or one of the two it replaced last, accepted regardless of age
+    Recent,
+    /// Root is stored in the account at this chain index, one of the newest `cap`,
+    /// and was replaced at `added_at`
+    Found { index: u8, added_at: i64 },
+    /// No chained account holds the root
+    NotFound,
+    /// Root is only stored in an account older than the newest `cap`
+    BeyondCap,
+}
+
+/// Accounts the program reads to validate a withdrawal root, fetched in one call
+struct RootChain {
+    config: GlobalConfigView,
+    pool: DepositPoolView,
+    /// HistoricalRoots accounts by chain index, None where one was never created
+    accounts: Vec<Option<HistoricalRootsView>>,
+}
+
+impl RootChain {
+    /// Chained account to point request_withdrawal at for `root`, None if no account holds it
+    /// Refuses what the program would: roots older than the `cap` newest accounts (a relayer
+    /// limit) and roots replaced more than max_root_age_hours before `now`
+    fn account_for_root(&self, root: &[u8; 32], cap: usize, now: i64) -> Result<Option<u8>> {
+        match find_root_in_chain(&self.pool, &self.accounts, root, cap) {
+            RootLookup::Recent => Ok(Some(0)),
+            RootLookup::Found { index, added_at } => {
+                if !self.config.root_is_fresh(added_at, now) {
+                    return Err(RelayerError::RootExpired(self.config.max_root_age_hours()));
+                }
+                Ok(Some(index))
+            }
+            RootLookup::BeyondCap => Err(RelayerError::RootTooOld(cap)),
+            RootLookup::NotFound => Ok(None),
+        }
+    }
 }
 
+/// Per-bucket map of historical roots
+type HistoricalRootsByBucket = Vec<HashMap<[u8; 32], TimestampedRoot>>;
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct PendingWithdrawalRecord {
     /// The on-chain PDA address of the PendingWithdrawal account
@@ -55,16 +125,35 @@ pub struct PendingWithdrawalRecord {
     pub fee: u64,
     /// Whether we've already executed this
     pub executed: bool,
+    /// Whether the owner cancelled this withdrawal on-chain
+    #[serde(default)]
+    pub cancelled: bool,
+    /// `tx_id` of the on-chain PendingWithdrawal, cancel proofs are bound to it
+    #[serde(default)]
+    pub pending_id: u64,
+    /// Execution transaction, set once executed
+    #[serde(default)]
+    pub tx_signature: Option<String>,
 }
 
 pub struct WithdrawalService {
     config: RelayerConfig,
     rpc_client: Arc<RpcClient>,
+    /// Blockhashes, simulations and sends (defaults to `rpc_client`)
+    send_client: Arc<RpcClient>,
     merkle_service: Arc<MerkleService>,
     /// Historical roots per bucket with timestamps for time-based pruning
-    historical_roots: Arc<RwLock<Vec<HashMap<[u8; 32], TimestampedRoot>>>>,
+    historical_roots: Arc<RwLock<HistoricalRootsByBucket>>,
     /// Pending withdrawals we need to execute after timelock
     pending_withdrawals: Arc<RwLock<Vec<PendingWithdrawalRecord>>>,
+    /// Bounds simultaneous executions so bursts can't starve the RPC
+    execution_permits: Arc<Semaphore>,
+    /// PDAs of withdrawals currently being executed, so no record is sent twice at once
+    executing: Arc<std::sync::Mutex<HashSet<Pubkey>>>,
+    /// Set once the fee treasury PDA is known to be rent-exempt
+    treasury_ready: Arc<OnceCell<()>>,
+    /// Notified after each executed withdrawal (None when WEBHOOK_URL is unset)
+    webhook: Option<Arc<WebhookNotifier>>,
 }
 
 impl WithdrawalService {
@@ -73,20 +162,46 @@ impl WithdrawalService {
         rpc_client: Arc<RpcClient>,
         merkle_service: Arc<MerkleService>,
     ) -> Self {
-        let num_buckets = crate::config::BUCKET_AMOUNTS.len();
+        let num_buckets = config.bucket_amounts.len();
         let historical_roots = (0..num_buckets).map(|_| HashMap::new()).collect();
+        let execution_permits = Arc::new(Semaphore::new(config.max_concurrent_executions));
+        let webhook = config.webhook_url.clone().and_then(|url| {
+            match WebhookNotifier::new(url, &config.tor_socks_addr, config.keypair.clone()) {
+                Ok(notifier) => {
+                    info!("Webhook notifications enabled");
+                    Some(Arc::new(notifier))
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to set up webhook client, notifications disabled: {}",
+                        e
+                    );
+                    None
+                }
+            }
+        });
         Self {
             config,
+            send_client: rpc_client.clone(),
             rpc_client,
             merkle_service,
             historical_roots: Arc::new(RwLock::new(historical_roots)),
             pending_withdrawals: Arc::new(RwLock::new(Vec::new())),
+            execution_permits,
+            executing: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            treasury_ready: Arc::new(OnceCell::new()),
+            webhook,
         }
     }
 
+    /// Send transactions through a separate endpoint from the one used for reads
+    pub fn with_send_client(mut self, send_client: Arc<RpcClient>) -> Self {
+        self.send_client = send_client;
+        self
+    }
+
     /// Record current root as historical (call after each deposit)
     /// Uses time-based pruning to ensure roots are available for delayed withdrawals
-    #[allow(dead_code)]
     pub async fn record_historical_root(&self, bucket_id: u8) -> Result<()> {
         let root = self.merkle_service.root(bucket_id).await?;
         let mut roots = self.historical_roots.write().await;
@@ -124,6 +239,7 @@ impl WithdrawalService {
                 TimestampedRoot {
                     root,
                     added_at: now,
+                    recorded_at: crate::blind_signer::unix_now(),
                 },
             );
         }
@@ -131,126 +247,88 @@ impl WithdrawalService {
         Ok(())
     }
 
+    /// Locally tracked roots for a bucket with their unix timestamps, newest first
+    pub async fn historical_roots(&self, bucket_id: u8) -> Vec<([u8; 32], u64)> {
+        let roots = self.historical_roots.read().await;
+        let mut entries: Vec<_> = roots
+            .get(bucket_id as usize)
+            .map(|bucket_roots| bucket_roots.values().collect())
+            .unwrap_or_default();
+        entries.sort_by_key(|timestamped| std::cmp::Reverse(timestamped.added_at));
+        entries
+            .into_iter()
+            .map(|timestamped| (timestamped.root, timestamped.recorded_at))
+            .collect()
+    }
+
     pub async fn handle_withdrawal(
         &self,
         request: WithdrawalRequest,
         delay_hours: u8,
+        requested_bucket: Option<u8>,
     ) -> Result<WithdrawalResponse> {
         info!("=== Withdrawal Request Debug ===");
         info!(
             "nullifier_hash: {:?}",
-            hex::encode(&request.public_inputs.nullifier_hash)
+            hex::encode(request.public_inputs.nullifier_hash)
         );
         info!(
             "recipient: {:?}",
-            hex::encode(&request.public_inputs.recipient)
+            hex::encode(request.public_inputs.recipient)
         );
-        info!("relayer: {:?}", hex::encode(&request.public_inputs.relayer));
+        info!("relayer: {:?}", hex::encode(request.public_inputs.relayer));
         info!("amount: {}", request.public_inputs.amount);
         info!("fee: {}", request.public_inputs.fee);
         info!(
             "binding_hash: {:?}",
-            hex::encode(&request.public_inputs.binding_hash)
+            hex::encode(request.public_inputs.binding_hash)
         );
-        info!("root: {:?}", hex::encode(&request.public_inputs.root));
-        info!("proof_a: {:?}", hex::encode(&request.proof.a));
-        info!("proof_b: {:?}", hex::encode(&request.proof.b));
-        info!("proof_c: {:?}", hex::encode(&request.proof.c));
+        info!("root: {:?}", hex::encode(request.public_inputs.root));
+        info!("proof_a: {:?}", hex::encode(request.proof.a));
+        info!("proof_b: {:?}", hex::encode(request.proof.b));
+        info!("proof_c: {:?}", hex::encode(request.proof.c));
         info!("=== End Debug ===");
 
         // 1. Validate the request
         request
             .validate()
             .map_err(|e| RelayerError::InvalidRequest(e.to_string()))?;
+        validate_withdrawal_inputs(&request)?;
+        self.ensure_not_pending(&request.public_inputs.nullifier_hash)
+            .await?;
 
-        // 2. Verify merkle root is valid (current or historical)
-        let bucket_id = crate::config::get_bucket_id(request.public_inputs.amount)
-            .ok_or(RelayerError::InvalidBucket(request.public_inputs.amount))?;
-        self.verify_merkle_root(&request.public_inputs.root, bucket_id)
+        // 2. Check the proof's public amount is the bucket's denomination, then verify
+        // the merkle root is valid (current or historical)
+        let bucket_id = resolve_withdrawal_bucket(
+            &self.config.bucket_amounts,
+            request.public_inputs.amount,
+            requested_bucket,
+            self.config.reject_amount_mismatch,
+        )?;
+        let root_account_index = self
+            .verify_merkle_root(&request.public_inputs.root, bucket_id)
             .await?;
 
         // 3. Submit withdrawal request on-chain
-        let tx_signature = self
-            .submit_withdrawal_request(&request, delay_hours)
+        let (tx_signature, record) = self
+            .submit_withdrawal_request(&request, bucket_id, delay_hours, root_account_index)
             .await?;
 
         // 4. Track this pending withdrawal for automatic execution
-        {
-            let inputs = &request.public_inputs;
-            let (pool_pda, _) =
-                Pubkey::find_program_address(&[b"pool", &[bucket_id]], &self.config.program_id);
-
-            // Fetch total_deposits to derive the pending PDA (same as submit_withdrawal_request)
-            let pool_data = self
-                .rpc_client
-                .get_account_data(&pool_pda)
-                .await
-                .unwrap_or_default();
-            // total_deposits was incremented by the request, but we used the pre-increment value
-            // The PDA was derived with the pre-increment total_deposits, which is now total_deposits - 1
-            // Actually, request_withdrawal uses pool.total_deposits at the time of the call,
-            // and doesn't increment it. So we need the value BEFORE the tx
-            // But the tx already executed. Let's parse current total_deposits and subtract 0
-            // (request_withdrawal doesn't change total_deposits, only deposit does)
-            // Actually looking at request_withdrawal.rs, it uses pool.total_deposits as-is
-            // So we need the current value. But we already computed it in submit_withdrawal_request
-            // Let's just re-derive it
-            let total_deposits = if pool_data.len() >= 65 {
-                u64::from_le_bytes(pool_data[57..65].try_into().unwrap_or([0u8; 8]))
-            } else {
-                0u64
-            };
-            // The pending PDA was created with total_deposits value at time of request.
-            // Since request_withdrawal doesn't increment total_deposits, the current value
-            // minus 0 is correct. But we need the value BEFORE the tx executed
-            // Actually, the tx already ran, and request_withdrawal doesn't change total_deposits.
-            // So current total_deposits is the same value used for the PDA seed
-            // WAIT - but the PDA was created with the value at tx time. If no other deposits
-            // happened between our fetch and the tx, it's the same. For safety, let's
-            // compute it from the tx_id we know: it's total_deposits at time of request
-            // Since we fetched it right before submitting, and the tx just confirmed,
-            // the value we used is total_deposits (current) - but request_withdrawal
-            // doesn't modify it. So current value = value used for PDA
-            let (pending_pda, _) = Pubkey::find_program_address(
-                &[b"pending", pool_pda.as_ref(), &total_deposits.to_le_bytes()],
-                &self.config.program_id,
-            );
-
-            let recipient = Pubkey::new_from_array(inputs.recipient);
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs() as i64;
-            let execute_after = now + (delay_hours as i64) * 3600;
-
-            // Compute fee same as on-chain
-            let amount_lamports = crate::config::BUCKET_AMOUNTS[bucket_id as usize];
-            let fee = amount_lamports * self.config.fee_bps as u64 / 10000;
-            let withdrawal_amount = amount_lamports - fee;
-            let record = PendingWithdrawalRecord {
-                pda: pending_pda,
-                pool_pda,
-                bucket_id,
-                nullifier_hash: inputs.nullifier_hash,
-                recipient,
-                execute_after,
-                amount: withdrawal_amount,
-                fee,
-                executed: false,
-            };
-
-            let mut pending = self.pending_withdrawals.write().await;
-            pending.push(record);
-            info!(
-                "Tracked pending withdrawal: execute_after={}, recipient={}",
-                execute_after, recipient
-            );
-        }
+        info!(
+            bucket_id = record.bucket_id,
+            nullifier_hash = %hex::encode(record.nullifier_hash),
+            execute_after = record.execute_after,
+            recipient = %record.recipient,
+            "Tracked pending withdrawal"
+        );
+        self.pending_withdrawals.write().await.push(record);
 
         info!(
-            "Withdrawal request submitted: recipient={:?}, tx={}",
-            &request.public_inputs.recipient[..8],
-            tx_signature
+            bucket_id,
+            nullifier_hash = %hex::encode(request.public_inputs.nullifier_hash),
+            tx_signature = %tx_signature,
+            "Withdrawal request submitted"
         );
         Ok(WithdrawalResponse {
             success: true,
@@ -260,17 +338,32 @@ impl WithdrawalService {
     }
 
     /// Verify the merkle root is valid (current or historical)
-    /// Time-based pruning ensures roots are available for at least MIN_ROOT_RETENTION_HOURS
-    async fn verify_merkle_root(&self, root: &[u8; 32], bucket_id: u8) -> Result<()> {
+    /// Returns the index of the chained HistoricalRoots account holding the root, which the
+    /// program is pointed at. Roots older than the `max_root_accounts` newest accounts are rejected
+    async fn verify_merkle_root(&self, root: &[u8; 32], bucket_id: u8) -> Result<u8> {
         let current_root = self.merkle_service.root(bucket_id).await?;
         if root == &current_root {
-            return Ok(());
+            return Ok(0);
+        }
+
+        // Refuse roots the program would reject before paying for the transaction
+        if let Some(chain) = self.fetch_root_chain(bucket_id).await? {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            let index = chain
+                .account_for_root(root, self.config.max_root_accounts, now)
+                .inspect_err(|e| warn!(bucket_id, "Refusing withdrawal root: {}", e))?;
+            if let Some(index) = index {
+                return Ok(index);
+            }
         }
 
         let roots = self.historical_roots.read().await;
         if let Some(bucket_roots) = roots.get(bucket_id as usize) {
             if bucket_roots.contains_key(root) {
-                return Ok(());
+                return Ok(0);
             }
         }
 
@@ -278,68 +371,99 @@ impl WithdrawalService {
 
         // Allow it through - on-chain will do final validation
         // This is safe because the smart contract validates against its own historical roots
+        Ok(0)
+    }
+
+    /// Fetch the config, the pool and its chained HistoricalRoots accounts in one call
+    /// None while the program or the pool isn't initialized
+    async fn fetch_root_chain(&self, bucket_id: u8) -> Result<Option<RootChain>> {
+        let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &self.config.program_id);
+        let (pool_pda, _) =
+            Pubkey::find_program_address(&[b"pool", &[bucket_id]], &self.config.program_id);
+        let pdas: Vec<Pubkey> = [config_pda, pool_pda]
+            .into_iter()
+            .chain((0..MAX_CHAINED_ACCOUNTS).map(|i| self.historical_roots_pda(&pool_pda, i as u8)))
+            .collect();
+
+        let mut accounts = rpc_read(|| self.rpc_client.get_multiple_accounts(&pdas))
+            .await?
+            .into_iter()
+            .map(|account| account.map(|a| a.data));
+        let (Some(config), Some(pool)) = (accounts.next().flatten(), accounts.next().flatten())
+        else {
+            return Ok(None);
+        };
+        Ok(Some(RootChain {
+            config: GlobalConfigView::from_account_data(&config)?,
+            pool: DepositPoolView::from_account_data(&pool)?,
+            accounts: accounts
+                .map(|data| {
+                    data.map(|data| HistoricalRootsView::from_account_data(&data))
+                        .transpose()
+                })
+                .collect::<Result<_>>()?,
+        }))
+    }
+
+    fn historical_roots_pda(&self, pool_pda: &Pubkey, index: u8) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"historical_roots", pool_pda.as_ref(), &[index]],
+            &self.config.program_id,
+        )
+        .0
+    }
+
+    /// PendingWithdrawal PDA, one per nullifier while the withdrawal is open
+    fn pending_withdrawal_pda(&self, pool_pda: &Pubkey, nullifier_hash: &[u8; 32]) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"pending", pool_pda.as_ref(), nullifier_hash],
+            &self.config.program_id,
+        )
+        .0
+    }
+
+    /// Refuse a second withdrawal for a nullifier we're still tracking as pending
+    /// The program would reject it too (its pending PDA already exists), this saves the fee
+    async fn ensure_not_pending(&self, nullifier_hash: &[u8; 32]) -> Result<()> {
+        let pending = self.pending_withdrawals.read().await;
+        if pending
+            .iter()
+            .any(|r| &r.nullifier_hash == nullifier_hash && !r.executed && !r.cancelled)
+        {
+            return Err(RelayerError::WithdrawalAlreadyPending);
+        }
         Ok(())
     }
 
+    /// Submit request_withdrawal and build the tracking record from the program's event
     async fn submit_withdrawal_request(
         &self,
         request: &WithdrawalRequest,
+        bucket_id: u8,
         delay_hours: u8,
-    ) -> Result<String> {
+        root_account_index: u8,
+    ) -> Result<(String, PendingWithdrawalRecord)> {
         let relayer = &self.config.keypair;
         let inputs = &request.public_inputs;
 
-        // Get bucket ID from amount
-        let bucket_id = crate::config::get_bucket_id(inputs.amount)
-            .ok_or(RelayerError::InvalidBucket(inputs.amount))?;
-
         // Derive PDAs
         let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &self.config.program_id);
 
         let (pool_pda, _) =
             Pubkey::find_program_address(&[b"pool", &[bucket_id]], &self.config.program_id);
 
-        let (historical_roots_pda, _) = Pubkey::find_program_address(
-            &[b"historical_roots", pool_pda.as_ref(), &[0u8]],
-            &self.config.program_id,
-        );
+        let historical_roots_pda = self.historical_roots_pda(&pool_pda, root_account_index);
 
         let (nullifier_pda, _) = Pubkey::find_program_address(
             &[b"nullifier", &inputs.nullifier_hash],
             &self.config.program_id,
         );
 
-        // Fetch pool account to get total_deposits for pending withdrawal PDA
-        let pool_data = self
-            .rpc_client
-            .get_account_data(&pool_pda)
-            .await
-            .map_err(|e| RelayerError::TransactionFailed(format!("Failed to fetch pool: {}", e)))?;
-
-        // Parse total_deposits from pool account data
-        // DepositPool layout:
-        // - discriminator: 8 bytes (offset 0)
-        // - bucket_id: 1 byte (offset 8)
-        // - amount_lamports: 8 bytes (offset 9)
-        // - merkle_root: 32 bytes (offset 17)
-        // - next_index: 8 bytes (offset 49)
-        // - total_deposits: 8 bytes (offset 57)
-        let total_deposits = if pool_data.len() >= 65 {
-            u64::from_le_bytes(pool_data[57..65].try_into().unwrap_or([0u8; 8]))
-        } else {
-            0u64
-        };
-
-        let (pending_pda, _) = Pubkey::find_program_address(
-            &[b"pending", pool_pda.as_ref(), &total_deposits.to_le_bytes()],
-            &self.config.program_id,
-        );
-
         // Build instruction data matching the program's expected format:
         // bucket_id: u8, nullifier_hash: [u8; 32], recipient: [u8; 32],
         // proof_a: [u8; 64], proof_b: [u8; 128], proof_c: [u8; 64],
         // merkle_root: [u8; 32], delay_hours: u8, binding_hash: [u8; 32],
-        // relayer_field: [u8; 32]
+        // relayer_field: [u8; 32], root_account_index: u8
         let mut data = vec![0u8; 8];
         let discriminator = anchor_discriminator("request_withdrawal");
         data[..8].copy_from_slice(&discriminator);
@@ -355,43 +479,104 @@ impl WithdrawalService {
         data.push(delay_hours);
         data.extend_from_slice(&inputs.binding_hash);
         data.extend_from_slice(&inputs.relayer); // Field element from circuit
+        data.push(root_account_index);
+
+        // Refuse paused pools before paying for a transaction the program would reject
+        DepositPoolView::fetch(&self.rpc_client, &pool_pda)
+            .await?
+            .ensure_active()?;
+        let pending_pda = self.pending_withdrawal_pda(&pool_pda, &inputs.nullifier_hash);
+
+        let accounts = vec![
+            AccountMeta::new(relayer.pubkey(), true), // payer (signer, mut)
+            AccountMeta::new_readonly(config_pda, false), // config
+            AccountMeta::new(pool_pda, false),        // pool (mut)
+            AccountMeta::new_readonly(historical_roots_pda, false), // historical_roots
+            AccountMeta::new_readonly(nullifier_pda, false), // nullifier_check (not init here)
+            AccountMeta::new(pending_pda, false),     // pending_withdrawal (init)
+            AccountMeta::new_readonly(self.config.zk_verifier_id, false), // zk_verifier program
+            AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+        ];
 
         let instruction = Instruction {
             program_id: self.config.program_id,
-            accounts: vec![
-                AccountMeta::new(relayer.pubkey(), true), // payer (signer, mut)
-                AccountMeta::new_readonly(config_pda, false), // config
-                AccountMeta::new(pool_pda, false),        // pool (mut)
-                AccountMeta::new_readonly(historical_roots_pda, false), // historical_roots
-                AccountMeta::new_readonly(nullifier_pda, false), // nullifier_check (not init here)
-                AccountMeta::new(pending_pda, false),     // pending_withdrawal (init)
-                AccountMeta::new_readonly(self.config.zk_verifier_id, false), // zk_verifier program
-                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
-            ],
+            accounts,
             data,
         };
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
-        let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&relayer.pubkey()),
-            &[relayer.as_ref()],
-            recent_blockhash,
-        );
+        let signature = submit_with_blockhash_refresh(
+            &self.send_client,
+            &self.config.with_compute_budget(vec![instruction]),
+            relayer,
+            self.config.tx_submit_attempts,
+        )
+        .await
+        .map_err(|e| RelayerError::TransactionFailed(e.error.to_string()))?;
+
+        // The pending id the program assigned is only known from its event
+        let event = self.fetch_withdrawal_requested(&signature).await?;
+        if event.pool != pool_pda {
+            return Err(RelayerError::Internal(format!(
+                "WithdrawalRequested event for pool {} does not match {}",
+                event.pool, pool_pda
+            )));
+        }
 
-        let signature = self
-            .rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .await
-            .map_err(|e| RelayerError::TransactionFailed(e.to_string()))?;
+        // Compute fee same as on-chain
+        let amount_lamports = self
+            .config
+            .bucket_amount(bucket_id)
+            .ok_or(RelayerError::InvalidBucket(bucket_id as u64))?;
+        let fee = compute_withdrawal_fee(amount_lamports, self.config.fee_bps);
+        let record = PendingWithdrawalRecord {
+            pda: pending_pda,
+            pool_pda,
+            bucket_id,
+            nullifier_hash: inputs.nullifier_hash,
+            recipient: Pubkey::new_from_array(inputs.recipient),
+            execute_after: event.execute_after,
+            amount: amount_lamports - fee,
+            fee,
+            executed: false,
+            cancelled: false,
+            pending_id: event.pending_id,
+            tx_signature: None,
+        };
 
-        Ok(signature.to_string())
+        Ok((signature.to_string(), record))
+    }
+
+    /// Read the WithdrawalRequested event from a confirmed request_withdrawal transaction
+    async fn fetch_withdrawal_requested(
+        &self,
+        signature: &solana_sdk::signature::Signature,
+    ) -> Result<WithdrawalRequestedEvent> {
+        let tx = rpc_read(|| {
+            self.rpc_client
+                .get_transaction(signature, UiTransactionEncoding::Json)
+        })
+        .await?;
+        let logs: Option<Vec<String>> = tx
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages.into());
+        parse_withdrawal_requested(&logs.unwrap_or_default()).ok_or_else(|| {
+            RelayerError::TransactionFailed(format!(
+                "No WithdrawalRequested event in transaction {}",
+                signature
+            ))
+        })
     }
 
     pub async fn execute_withdrawal_by_record(
         &self,
         record: &PendingWithdrawalRecord,
     ) -> Result<String> {
+        let _permit = self
+            .execution_permits
+            .acquire()
+            .await
+            .map_err(|_| RelayerError::Internal("Execution limiter closed".into()))?;
         let relayer = &self.config.keypair;
 
         // Derive all required PDAs
@@ -407,15 +592,18 @@ impl WithdrawalService {
 
         info!(
             "Execute withdrawal: nullifier={}, recipient={}, pool={}, relayer_treasury={}",
-            hex::encode(&record.nullifier_hash),
+            hex::encode(record.nullifier_hash),
             record.recipient,
             record.pool_pda,
             relayer_treasury
         );
 
+        // Nullifier and recipient in one round trip
+        let keys = [nullifier_pda, record.recipient];
+        let accounts = rpc_read(|| self.rpc_client.get_multiple_accounts(&keys)).await?;
+
         // Check if nullifier already exists (from previous attempt)
-        let nullifier_exists = self.rpc_client.get_account(&nullifier_pda).await.is_ok();
-        if nullifier_exists {
+        if accounts[0].is_some() {
             info!("Nullifier account already exists, withdrawal may have already executed");
             return Ok("Already executed".to_string());
         }
@@ -425,119 +613,26 @@ impl WithdrawalService {
         // but the runtime enforces rent-exemption post-transaction. If the credited
         // amount is below rent-exempt minimum for a 0-byte account (890,880 lamports),
         // the transaction fails. Pre-funding with rent-exempt minimum avoids this.
-        let rent_exempt_minimum: u64 = 890_880; // 0-byte account rent-exempt minimum
-        let mut prefunded = false;
-
-        let recipient_exists = self.rpc_client.get_account(&record.recipient).await.is_ok();
-        if !recipient_exists {
-            info!(
-                "Recipient {} doesn't exist, pre-funding with {} lamports",
-                record.recipient, rent_exempt_minimum
-            );
-            let prefund_tx = Transaction::new_signed_with_payer(
-                &[solana_sdk::system_instruction::transfer(
-                    &relayer.pubkey(),
-                    &record.recipient,
-                    rent_exempt_minimum,
-                )],
-                Some(&relayer.pubkey()),
-                &[relayer.as_ref()],
-                self.rpc_client.get_latest_blockhash().await?,
-            );
-            self.rpc_client
-                .send_and_confirm_transaction(&prefund_tx)
-                .await
-                .map_err(|e| {
-                    RelayerError::TransactionFailed(format!("Failed to pre-fund recipient: {}", e))
-                })?;
-            info!("✓ Recipient pre-funded");
-            prefunded = true;
-        } else {
-            // Check if existing account has enough balance for rent
-            let account = self.rpc_client.get_account(&record.recipient).await?;
-            if account.lamports < rent_exempt_minimum {
-                let needed = rent_exempt_minimum - account.lamports;
-                info!(
-                    "Recipient {} exists but needs {} more lamports for rent exemption",
-                    record.recipient, needed
-                );
-                let topup_tx = Transaction::new_signed_with_payer(
-                    &[solana_sdk::system_instruction::transfer(
-                        &relayer.pubkey(),
-                        &record.recipient,
-                        needed,
-                    )],
-                    Some(&relayer.pubkey()),
-                    &[relayer.as_ref()],
-                    self.rpc_client.get_latest_blockhash().await?,
-                );
-                self.rpc_client
-                    .send_and_confirm_transaction(&topup_tx)
-                    .await
-                    .map_err(|e| {
-                        RelayerError::TransactionFailed(format!(
-                            "Failed to top up recipient: {}",
-                            e
-                        ))
-                    })?;
-                info!("✓ Recipient topped up");
-                prefunded = true;
-            }
-        }
+        let recipient_lamports = accounts[1].as_ref().map(|a| a.lamports);
+        let mut prefunded = self
+            .ensure_rent_exempt(&record.recipient, recipient_lamports, "Recipient")
+            .await?;
 
-        let treasury_exists = self.rpc_client.get_account(&relayer_treasury).await.is_ok();
-        if !treasury_exists {
-            info!(
-                "Treasury {} doesn't exist, pre-funding with {} lamports",
-                relayer_treasury, rent_exempt_minimum
-            );
-            let prefund_tx = Transaction::new_signed_with_payer(
-                &[solana_sdk::system_instruction::transfer(
-                    &relayer.pubkey(),
-                    &relayer_treasury,
-                    rent_exempt_minimum,
-                )],
-                Some(&relayer.pubkey()),
-                &[relayer.as_ref()],
-                self.rpc_client.get_latest_blockhash().await?,
-            );
-            self.rpc_client
-                .send_and_confirm_transaction(&prefund_tx)
-                .await
-                .map_err(|e| {
-                    RelayerError::TransactionFailed(format!("Failed to pre-fund treasury: {}", e))
-                })?;
-            info!("✓ Treasury pre-funded");
-            prefunded = true;
-        } else {
-            // Check if existing treasury has enough balance for rent
-            let account = self.rpc_client.get_account(&relayer_treasury).await?;
-            if account.lamports < rent_exempt_minimum {
-                let needed = rent_exempt_minimum - account.lamports;
-                info!(
-                    "Treasury {} exists but needs {} more lamports for rent exemption",
-                    relayer_treasury, needed
-                );
-                let topup_tx = Transaction::new_signed_with_payer(
-                    &[solana_sdk::system_instruction::transfer(
-                        &relayer.pubkey(),
-                        &relayer_treasury,
-                        needed,
-                    )],
-                    Some(&relayer.pubkey()),
-                    &[relayer.as_ref()],
-                    self.rpc_client.get_latest_blockhash().await?,
-                );
-                self.rpc_client
-                    .send_and_confirm_transaction(&topup_tx)
+        // The treasury only ever gains lamports, so it stays funded once it has been
+        let mut treasury_funded = false;
+        self.treasury_ready
+            .get_or_try_init(|| async {
+                let lamports = rpc_read(|| self.rpc_client.get_account(&relayer_treasury))
                     .await
-                    .map_err(|e| {
-                        RelayerError::TransactionFailed(format!("Failed to top up treasury: {}", e))
-                    })?;
-                info!("✓ Treasury topped up");
-                prefunded = true;
-            }
-        }
+                    .ok()
+                    .map(|a| a.lamports);
+                treasury_funded = self
+                    .ensure_rent_exempt(&relayer_treasury, lamports, "Treasury")
+                    .await?;
+                Ok::<_, RelayerError>(())
+            })
+            .await?;
+        prefunded |= treasury_funded;
 
         // Small delay to ensure pre-funded accounts are fully settled
         // This prevents race conditions on devnet/localhost
@@ -560,17 +655,25 @@ impl WithdrawalService {
             ],
             data: discriminator.to_vec(),
         };
+        // Once executed the pending account is dead weight, reclaim its rent in the same transaction
+        let close = close_withdrawal_instruction(
+            &self.config.program_id,
+            &relayer.pubkey(),
+            &config_pda,
+            &record.pda,
+        );
 
-        let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let instructions = self.config.with_compute_budget(vec![instruction, close]);
+        let recent_blockhash = self.send_client.get_latest_blockhash().await?;
         let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
+            &instructions,
             Some(&relayer.pubkey()),
             &[relayer.as_ref()],
             recent_blockhash,
         );
 
         // First simulate to get detailed error
-        match self.rpc_client.simulate_transaction(&transaction).await {
+        match self.send_client.simulate_transaction(&transaction).await {
             Ok(sim_result) => {
                 if let Some(err) = sim_result.value.err {
                     error!("Simulation failed: {:?}", err);
@@ -592,32 +695,26 @@ impl WithdrawalService {
             }
         }
 
-        // Send transaction with better error handling
-        let signature = match self
-            .rpc_client
-            .send_and_confirm_transaction_with_spinner_and_config(
-                &transaction,
-                self.rpc_client.commitment(),
-                solana_client::rpc_config::RpcSendTransactionConfig {
-                    skip_preflight: false, // Enable preflight for better error messages
-                    ..Default::default()
-                },
-            )
-            .await
-        {
-            Ok(sig) => sig,
-            Err(e) => {
-                let error_msg = e.to_string();
-                return Err(RelayerError::TransactionFailed(format!(
-                    "Withdrawal execution failed: {}",
-                    error_msg
-                )));
-            }
-        };
+        // Preflight stays on for better error messages
+        let signature = submit_with_blockhash_refresh(
+            &self.send_client,
+            &instructions,
+            relayer,
+            self.config.tx_submit_attempts,
+        )
+        .await
+        .map_err(|SubmitError { error, .. }| {
+            RelayerError::TransactionFailed(format!("Withdrawal execution failed: {}", error))
+        })?;
 
         info!(
-            "Withdrawal executed: recipient={}, amount={}, fee={}, tx={}",
-            record.recipient, record.amount, record.fee, signature
+            bucket_id = record.bucket_id,
+            nullifier_hash = %hex::encode(record.nullifier_hash),
+            recipient = %record.recipient,
+            amount = record.amount,
+            fee = record.fee,
+            tx_signature = %signature,
+            "Withdrawal executed"
         );
         Ok(signature.to_string())
     }
@@ -629,7 +726,7 @@ impl WithdrawalService {
         let pending = self.pending_withdrawals.read().await;
         let record = pending
             .iter()
-            .find(|r| r.nullifier_hash == nullifier_hash && !r.executed)
+            .find(|r| r.nullifier_hash == nullifier_hash && !r.executed && !r.cancelled)
             .cloned();
 
         drop(pending);
@@ -639,19 +736,94 @@ impl WithdrawalService {
                 "No pending withdrawal found for this nullifier hash".into(),
             )
         })?;
+        let _claim = self.claim_execution(record.pda).await.ok_or_else(|| {
+            RelayerError::InvalidRequest("Withdrawal is already being executed".into())
+        })?;
 
         let tx = self.execute_withdrawal_by_record(&record).await?;
+        self.mark_executed(&record.pda, &tx).await;
+
+        Ok(tx)
+    }
+
+    /// Top `address` up to the rent-exempt minimum for a 0-byte account
+    /// `lamports` is its current balance, None if it doesn't exist. Returns whether a transfer was sent
+    async fn ensure_rent_exempt(
+        &self,
+        address: &Pubkey,
+        lamports: Option<u64>,
+        label: &str,
+    ) -> Result<bool> {
+        let rent_exempt_minimum: u64 = 890_880; // 0-byte account rent-exempt minimum
+        let current = lamports.unwrap_or(0);
+        if current >= rent_exempt_minimum {
+            return Ok(false);
+        }
+        let needed = rent_exempt_minimum - current;
+        if lamports.is_none() {
+            info!(
+                "{} {} doesn't exist, pre-funding with {} lamports",
+                label, address, needed
+            );
+        } else {
+            info!(
+                "{} {} exists but needs {} more lamports for rent exemption",
+                label, address, needed
+            );
+        }
+
+        let relayer = &self.config.keypair;
+        submit_with_blockhash_refresh(
+            &self.send_client,
+            &self
+                .config
+                .with_compute_budget(vec![solana_sdk::system_instruction::transfer(
+                    &relayer.pubkey(),
+                    address,
+                    needed,
+                )]),
+            relayer,
+            self.config.tx_submit_attempts,
+        )
+        .await
+        .map_err(|e| {
+            RelayerError::TransactionFailed(format!(
+                "Failed to fund {}: {}",
+                label.to_lowercase(),
+                e.error
+            ))
+        })?;
+        info!("✓ {} funded", label);
+        Ok(true)
+    }
 
-        // Mark as executed
+    /// Reserve `pda` for execution
+    /// None if another task is executing it, or it was executed or cancelled since it was read
+    async fn claim_execution(&self, pda: Pubkey) -> Option<ExecutionClaim> {
+        if !self.executing.lock().unwrap().insert(pda) {
+            return None;
+        }
+        let claim = ExecutionClaim {
+            executing: self.executing.clone(),
+            pda,
+        };
+        let pending = self.pending_withdrawals.read().await;
+        pending
+            .iter()
+            .any(|r| r.pda == pda && !r.executed && !r.cancelled)
+            .then_some(claim)
+    }
+
+    async fn mark_executed(&self, pda: &Pubkey, tx_signature: &str) {
         let mut pending = self.pending_withdrawals.write().await;
+        // A nullifier's PDA is reused once an earlier request was cancelled and closed
         if let Some(r) = pending
             .iter_mut()
-            .find(|r| r.nullifier_hash == nullifier_hash)
+            .find(|r| r.pda == *pda && !r.executed && !r.cancelled)
         {
             r.executed = true;
+            r.tx_signature = Some(tx_signature.to_string());
         }
-
-        Ok(tx)
     }
 
     pub async fn poll_and_execute(&self) -> Vec<(Pubkey, std::result::Result<String, String>)> {
@@ -664,47 +836,371 @@ impl WithdrawalService {
             let pending = self.pending_withdrawals.read().await;
             pending
                 .iter()
-                .filter(|r| !r.executed && now >= r.execute_after)
+                .filter(|r| !r.executed && !r.cancelled && now >= r.execute_after)
                 .cloned()
                 .collect()
         };
+        info!(eligible = eligible.len(), "Withdrawal poll tick");
         if eligible.is_empty() {
             return vec![];
         }
 
-        info!(
-            "Found {} pending withdrawals ready for execution",
-            eligible.len()
-        );
+        // Records are independent, so start them all, execution_permits bounds how many
+        // actually run at once (shared with the HTTP execute path)
+        eligible
+            .into_iter()
+            .map(|record| async move {
+                // Skip records the HTTP endpoint or an overlapping poll is already executing
+                let _claim = self.claim_execution(record.pda).await?;
+                Some(self.execute_and_record(record).await)
+            })
+            .collect::<FuturesUnordered<_>>()
+            .filter_map(|result| async move { result })
+            .collect()
+            .await
+    }
 
-        let mut results = Vec::new();
-        for record in &eligible {
-            match self.execute_withdrawal_by_record(record).await {
-                Ok(tx) => {
-                    info!("✓ Executed withdrawal to {}: tx={}", record.recipient, tx);
-                    // Mark as executed
-                    let mut pending = self.pending_withdrawals.write().await;
-                    if let Some(r) = pending.iter_mut().find(|r| r.pda == record.pda) {
-                        r.executed = true;
-                    }
-                    results.push((record.recipient, Ok(tx)));
-                }
-                Err(e) => {
-                    error!(
-                        "✗ Failed to execute withdrawal to {}: {}",
-                        record.recipient, e
-                    );
-                    results.push((record.recipient, Err(e.to_string())));
+    async fn execute_and_record(
+        &self,
+        record: PendingWithdrawalRecord,
+    ) -> (Pubkey, std::result::Result<String, String>) {
+        match self.execute_withdrawal_by_record(&record).await {
+            Ok(tx) => {
+                info!("✓ Executed withdrawal to {}: tx={}", record.recipient, tx);
+                self.mark_executed(&record.pda, &tx).await;
+                // Delivered in the background so retries don't hold up the poll tick
+                if let Some(webhook) = &self.webhook {
+                    let webhook = webhook.clone();
+                    let event = WithdrawalExecutedEvent {
+                        amount: record.amount,
+                        nullifier_hash: hex::encode(record.nullifier_hash),
+                        recipient: record.recipient.to_string(),
+                        tx_signature: tx.clone(),
+                    };
+                    tokio::spawn(async move {
+                        webhook.notify_withdrawal_executed(event).await;
+                    });
                 }
+                (record.recipient, Ok(tx))
             }
+            Err(e) => {
+                error!(
+                    "✗ Failed to execute withdrawal to {}: {}",
+                    record.recipient, e
+                );
+                (record.recipient, Err(e.to_string()))
+            }
+        }
+    }
+
+    /// Cancel a pending withdrawal with the owner's ownership proof
+    /// The proof is bound to the pending withdrawal id, which must match the tracked PDA
+    pub async fn cancel_withdrawal(&self, request: &OwnershipProofRequest) -> Result<String> {
+        validate_proof_points(&request.proof)?;
+
+        let record = {
+            let pending = self.pending_withdrawals.read().await;
+            pending
+                .iter()
+                .find(|r| r.nullifier_hash == request.nullifier_hash && !r.executed && !r.cancelled)
+                .cloned()
+        }
+        .ok_or_else(|| {
+            RelayerError::InvalidRequest(
+                "No pending withdrawal found for this nullifier hash".into(),
+            )
+        })?;
+
+        if request.pending_withdrawal_id != record.pending_id {
+            return Err(RelayerError::InvalidRequest(
+                "Pending withdrawal id does not match this withdrawal".into(),
+            ));
+        }
+
+        let relayer = &self.config.keypair;
+        let (config_pda, _) = Pubkey::find_program_address(&[b"config"], &self.config.program_id);
+        let instruction = Instruction {
+            program_id: self.config.program_id,
+            accounts: vec![
+                AccountMeta::new(relayer.pubkey(), true), // relayer (signer, mut, receives rent)
+                AccountMeta::new_readonly(config_pda, false), // config
+                AccountMeta::new(record.pda, false),      // pending_withdrawal (mut, closed)
+                AccountMeta::new_readonly(self.config.zk_verifier_id, false), // zk_verifier program
+                AccountMeta::new_readonly(SYSTEM_PROGRAM_ID, false),
+            ],
+            data: cancel_withdrawal_data(request),
+        };
+
+        let signature = submit_with_blockhash_refresh(
+            &self.send_client,
+            &self.config.with_compute_budget(vec![instruction]),
+            relayer,
+            self.config.tx_submit_attempts,
+        )
+        .await
+        .map_err(|e| RelayerError::TransactionFailed(e.error.to_string()))?;
+
+        let mut pending = self.pending_withdrawals.write().await;
+        if let Some(r) = pending
+            .iter_mut()
+            .find(|r| r.pda == record.pda && !r.executed && !r.cancelled)
+        {
+            r.cancelled = true;
         }
 
-        results
+        info!(
+            bucket_id = record.bucket_id,
+            nullifier_hash = %hex::encode(record.nullifier_hash),
+            pda = %record.pda,
+            tx_signature = %signature,
+            "Withdrawal cancelled"
+        );
+        Ok(signature.to_string())
     }
 
     pub async fn get_pending_withdrawals(&self) -> Vec<PendingWithdrawalRecord> {
         self.pending_withdrawals.read().await.clone()
     }
+
+    /// Latest withdrawal tracked for `nullifier_hash`
+    /// A cancelled request can be followed by a new one for the same nullifier
+    pub async fn withdrawal_status(
+        &self,
+        nullifier_hash: &[u8; 32],
+    ) -> Option<PendingWithdrawalRecord> {
+        self.pending_withdrawals
+            .read()
+            .await
+            .iter()
+            .rev()
+            .find(|r| &r.nullifier_hash == nullifier_hash)
+            .cloned()
+    }
+
+    /// Whether `nullifier_hash` was spent on-chain: its nullifier record only exists once
+    /// a withdrawal has executed, pending or cancelled requests don't create it
+    pub async fn is_nullifier_spent(&self, nullifier_hash: &[u8; 32]) -> Result<bool> {
+        let (nullifier_pda, _) =
+            Pubkey::find_program_address(&[b"nullifier", nullifier_hash], &self.config.program_id);
+        let account = rpc_read(|| {
+            self.rpc_client
+                .get_account_with_commitment(&nullifier_pda, self.rpc_client.commitment())
+        })
+        .await?
+        .value;
+        Ok(account.is_some())
+    }
+
+    pub async fn fee_summary(&self) -> FeeSummary {
+        FeeSummary::from_records(&self.pending_withdrawals.read().await)
+    }
+}
+
+/// Releases a withdrawal's execution slot when dropped
+struct ExecutionClaim {
+    executing: Arc<std::sync::Mutex<HashSet<Pubkey>>>,
+    pda: Pubkey,
+}
+
+impl Drop for ExecutionClaim {
+    fn drop(&mut self) {
+        self.executing.lock().unwrap().remove(&self.pda);
+    }
+}
+
+/// Fees earned from withdrawals this relayer has executed
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FeeSummary {
+    pub executed_withdrawals: u64,
+    /// Sum of `fee` over executed records, in lamports
+    pub fees_collected: u64,
+}
+
+impl FeeSummary {
+    fn from_records(records: &[PendingWithdrawalRecord]) -> Self {
+        records
+            .iter()
+            .filter(|r| r.executed)
+            .fold(Self::default(), |summary, r| Self {
+                executed_withdrawals: summary.executed_withdrawals + 1,
+                fees_collected: summary.fees_collected.saturating_add(r.fee),
+            })
+    }
+}
+
+/// Search chained HistoricalRoots accounts, indexed by chain position, for `root`
+/// Newest account first: the one the last deposit (leaf `next_index - 1`) saved into, then
+/// the ones before it, wrapping past account 0 like the program does. Only the `cap` newest
+/// accounts count, a root found further back is `BeyondCap`
+fn find_root_in_chain(
+    pool: &DepositPoolView,
+    accounts: &[Option<HistoricalRootsView>],
+    root: &[u8; 32],
+    cap: usize,
+) -> RootLookup {
+    if pool.is_valid_root(root) {
+        return RootLookup::Recent;
+    }
+    let Some(last_leaf) = pool.next_index().checked_sub(1) else {
+        return RootLookup::NotFound;
+    };
+    let chain_len = MAX_CHAINED_ACCOUNTS as usize;
+    let newest = historical_roots_account_index(last_leaf) as usize;
+    for age in 0..chain_len {
+        let index = (newest + chain_len - age) % chain_len;
+        // Accounts before the first one never existed, the chain hasn't wrapped
+        let Some(account) = accounts.get(index).and_then(Option::as_ref) else {
+            break;
+        };
+        if let Some(added_at) = account.root_added_at(root) {
+            return if age < cap {
+                RootLookup::Found {
+                    index: index as u8,
+                    added_at,
+                }
+            } else {
+                RootLookup::BeyondCap
+            };
+        }
+    }
+    RootLookup::NotFound
+}
+
+/// Pick the bucket a withdrawal is relayed to
+/// The program verifies the proof against `bucket_amounts[bucket_id]`, so a proof generated
+/// for any other amount would fail verification on-chain with an opaque error.
+/// Without an explicit bucket the amount must itself be a bucket denomination
+fn resolve_withdrawal_bucket(
+    bucket_amounts: &[u64],
+    amount: u64,
+    requested_bucket: Option<u8>,
+    reject_mismatch: bool,
+) -> Result<u8> {
+    let Some(bucket_id) = requested_bucket else {
+        return crate::config::get_bucket_id(bucket_amounts, amount)
+            .ok_or(RelayerError::InvalidBucket(amount));
+    };
+    let expected = *bucket_amounts
+        .get(bucket_id as usize)
+        .ok_or_else(|| RelayerError::InvalidRequest(format!("Unknown bucket {}", bucket_id)))?;
+
+    if amount != expected {
+        if reject_mismatch {
+            return Err(RelayerError::AmountMismatch {
+                bucket_id,
+                amount,
+                expected,
+            });
+        }
+        warn!(
+            "Proof amount {} does not match bucket {} ({}), relaying anyway",
+            amount, bucket_id, expected
+        );
+    }
+    Ok(bucket_id)
+}
+
+/// Reject proofs and public inputs the on-chain verifier can never accept, before paying
+/// for a transaction. Serde already pins the array sizes; this checks the values inside them
+fn validate_withdrawal_inputs(request: &WithdrawalRequest) -> Result<()> {
+    validate_proof_points(&request.proof)?;
+
+    let inputs = &request.public_inputs;
+    if inputs.root.iter().all(|&b| b == 0) {
+        return Err(RelayerError::InvalidRequest(
+            "Merkle root must be non-zero".into(),
+        ));
+    }
+    for (name, value) in [
+        ("root", &inputs.root),
+        ("nullifier_hash", &inputs.nullifier_hash),
+        ("recipient", &inputs.recipient),
+        ("relayer", &inputs.relayer),
+        ("binding_hash", &inputs.binding_hash),
+    ] {
+        if !is_field_element(value) {
+            return Err(RelayerError::InvalidRequest(format!(
+                "Public input {} is not a BN254 field element",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Each proof point must be non-identity with coordinates in the base field
+/// (32-byte big-endian coordinates: A and C are G1 = x,y; B is G2 = x0,x1,y0,y1)
+fn validate_proof_points(proof: &ZkProof) -> Result<()> {
+    for (name, point) in [
+        ("proof_a", &proof.a[..]),
+        ("proof_b", &proof.b[..]),
+        ("proof_c", &proof.c[..]),
+    ] {
+        if point.iter().all(|&b| b == 0) {
+            return Err(RelayerError::InvalidRequest(format!(
+                "{} must be non-zero",
+                name
+            )));
+        }
+        if let Some(i) = point
+            .chunks(32)
+            .position(|coord| coord >= &BN254_BASE_FIELD_MODULUS[..])
+        {
+            return Err(RelayerError::InvalidRequest(format!(
+                "{} coordinate {} is not in the base field",
+                name, i
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Decode the WithdrawalRequested event from `Program data: <base64>` logs
+/// Layout: sha256("event:WithdrawalRequested")[..8] + pool (32) + pending_id (u64 LE)
+/// + execute_after (i64 LE)
+fn parse_withdrawal_requested(logs: &[String]) -> Option<WithdrawalRequestedEvent> {
+    use base64::Engine;
+
+    let discriminator = &Sha256::digest(b"event:WithdrawalRequested")[..8];
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        .find(|bytes| bytes.len() >= 56 && &bytes[..8] == discriminator)
+        .map(|bytes| WithdrawalRequestedEvent {
+            pool: Pubkey::new_from_array(bytes[8..40].try_into().unwrap()),
+            pending_id: u64::from_le_bytes(bytes[40..48].try_into().unwrap()),
+            execute_after: i64::from_le_bytes(bytes[48..56].try_into().unwrap()),
+        })
+}
+
+/// Instruction data for cancel_withdrawal(proof_a, proof_b, proof_c, binding_hash, pending_withdrawal_id)
+fn cancel_withdrawal_data(request: &OwnershipProofRequest) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + 64 + 128 + 64 + 32 + 8);
+    data.extend_from_slice(&anchor_discriminator("cancel_withdrawal"));
+    data.extend_from_slice(&request.proof.a);
+    data.extend_from_slice(&request.proof.b);
+    data.extend_from_slice(&request.proof.c);
+    data.extend_from_slice(&request.binding_hash);
+    data.extend_from_slice(&request.pending_withdrawal_id.to_le_bytes());
+    data
+}
+
+/// close_withdrawal: refunds an executed (or long-expired) pending withdrawal's rent to the relayer
+fn close_withdrawal_instruction(
+    program_id: &Pubkey,
+    relayer: &Pubkey,
+    config_pda: &Pubkey,
+    pending_pda: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*relayer, true), // relayer (signer, mut, receives rent)
+            AccountMeta::new_readonly(*config_pda, false), // config
+            AccountMeta::new(*pending_pda, false), // pending_withdrawal (mut, closed)
+        ],
+        data: anchor_discriminator("close_withdrawal").to_vec(),
+    }
 }
 
 fn anchor_discriminator(name: &str) -> [u8; 8] {
@@ -714,3 +1210,649 @@ fn anchor_discriminator(name: &str) -> [u8; 8] {
     discriminator.copy_from_slice(&hash[..8]);
     discriminator
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_rpc::{self, with_context, Calls, MockRpc};
+    use solana_client::rpc_request::RpcRequest;
+
+    fn roots_account(roots: &[[u8; 32]]) -> Option<HistoricalRootsView> {
+        Some(HistoricalRootsView::with_roots(roots, 1_000))
+    }
+
+    fn found(index: u8) -> RootLookup {
+        RootLookup::Found {
+            index,
+            added_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_root_found_within_cap() {
+        let chain = vec![
+            roots_account(&[[1u8; 32], [2u8; 32]]),
+            roots_account(&[[3u8; 32]]),
+            roots_account(&[[4u8; 32]]),
+        ];
+        // 17 deposits: the last one saved its root into account 2
+        let pool = DepositPoolView::with_indices(17, 17);
+
+        assert_eq!(find_root_in_chain(&pool, &chain, &[4u8; 32], 1), found(2));
+        assert_eq!(find_root_in_chain(&pool, &chain, &[1u8; 32], 3), found(0));
+        // Chain ends before the cap
+        assert_eq!(
+            find_root_in_chain(&pool, &chain, &[9u8; 32], 8),
+            RootLookup::NotFound
+        );
+        // The pool's current root needs no chained account
+        assert_eq!(
+            find_root_in_chain(&pool, &chain, &pool.merkle_root(), 8),
+            RootLookup::Recent
+        );
+        assert_eq!(
+            find_root_in_chain(&DepositPoolView::default(), &[], &[1u8; 32], 8),
+            RootLookup::NotFound
+        );
+    }
+
+    #[test]
+    fn test_root_in_late_chained_account() {
+        // 40 deposits filled accounts 0-3 and moved on to account 4
+        let mut chain: Vec<_> = (1..=5u8).map(|i| roots_account(&[[i; 32]])).collect();
+        let pool = DepositPoolView::with_indices(40, 40);
+        assert_eq!(find_root_in_chain(&pool, &chain, &[5u8; 32], 4), found(4));
+        assert_eq!(find_root_in_chain(&pool, &chain, &[2u8; 32], 4), found(1));
+
+        // Past the last account the chain wraps to account 0, which is now the newest
+        chain.resize(MAX_CHAINED_ACCOUNTS as usize, roots_account(&[]));
+        chain[31] = roots_account(&[[31u8; 32]]);
+        chain[0] = roots_account(&[[32u8; 32]]);
+        let next_index = MAX_CHAINED_ACCOUNTS * crate::on_chain::ROOTS_PER_ACCOUNT as u64 + 1;
+        let pool = DepositPoolView::with_indices(next_index, next_index);
+        assert_eq!(find_root_in_chain(&pool, &chain, &[31u8; 32], 2), found(31));
+    }
+
+    #[test]
+    fn test_root_beyond_cap_rejected() {
+        let chain = vec![
+            roots_account(&[[1u8; 32]]),
+            roots_account(&[[2u8; 32]]),
+            roots_account(&[[3u8; 32]]),
+        ];
+        let pool = DepositPoolView::with_indices(17, 17);
+
+        assert_eq!(
+            find_root_in_chain(&pool, &chain, &[1u8; 32], 2),
+            RootLookup::BeyondCap
+        );
+        assert_eq!(find_root_in_chain(&pool, &chain, &[1u8; 32], 3), found(0));
+    }
+
+    #[test]
+    fn test_stale_root_refused_before_submitting() {
+        let chain = RootChain {
+            config: GlobalConfigView::with_max_root_age(1),
+            pool: DepositPoolView::with_indices(9, 9),
+            accounts: vec![roots_account(&[[1u8; 32]]), roots_account(&[[2u8; 32]])],
+        };
+
+        assert_eq!(
+            chain
+                .account_for_root(&[2u8; 32], 4, 1_000 + 3_600)
+                .unwrap(),
+            Some(1)
+        );
+        assert!(matches!(
+            chain.account_for_root(&[2u8; 32], 4, 1_000 + 3_601),
+            Err(RelayerError::RootExpired(1))
+        ));
+        // The current root never expires
+        assert_eq!(
+            chain
+                .account_for_root(&chain.pool.merkle_root(), 4, i64::MAX)
+                .unwrap(),
+            Some(0)
+        );
+        assert_eq!(chain.account_for_root(&[9u8; 32], 4, 1_000).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cancel_withdrawal_data_layout() {
+        let request = OwnershipProofRequest {
+            proof: privacy_proxy_sdk::withdrawal::ZkProof {
+                a: [1u8; 64],
+                b: [2u8; 128],
+                c: [3u8; 64],
+            },
+            nullifier_hash: [4u8; 32],
+            pending_withdrawal_id: 7,
+            binding_hash: [5u8; 32],
+        };
+
+        let data = cancel_withdrawal_data(&request);
+        assert_eq!(data.len(), 8 + 64 + 128 + 64 + 32 + 8);
+        assert_eq!(data[..8], anchor_discriminator("cancel_withdrawal"));
+        assert_eq!(data[8..72], [1u8; 64]);
+        assert_eq!(data[72..200], [2u8; 128]);
+        assert_eq!(data[200..264], [3u8; 64]);
+        assert_eq!(data[264..296], [5u8; 32]);
+        assert_eq!(data[296..], 7u64.to_le_bytes());
+    }
+
+    /// RPC double for one pool that creates pending withdrawals the way the program does:
+    /// one PDA per nullifier, `init` failing while it exists, ids from a counter
+    fn pending_requests(program_id: Pubkey, pool_pda: Pubkey) -> RpcClient {
+        use base64::Engine;
+        use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+
+        let open = std::sync::Mutex::new(std::collections::HashSet::new());
+        let pending_counter = std::sync::Mutex::new(0u64);
+        let event = Arc::new(std::sync::Mutex::new(None::<String>));
+        let logged = event.clone();
+        MockRpc::new()
+            .accounts(move |_| {
+                let data = DepositPoolView::with_indices(5, 5).to_account_data();
+                Some(mock_rpc::account(&data, &program_id))
+            })
+            .blockhash()
+            .on(RpcRequest::SendTransaction, move |params| {
+                let tx = mock_rpc::sent_transaction(&params);
+                let keys = tx.message.static_account_keys();
+                let ix = tx
+                    .message
+                    .instructions()
+                    .iter()
+                    .find(|ix| keys[ix.program_id_index as usize] == program_id)
+                    .unwrap();
+                let pending_pda = keys[ix.accounts[5] as usize];
+                // discriminator (8) + bucket_id (1), then the nullifier hash
+                let nullifier_hash = &ix.data[9..41];
+
+                let (expected, _) = Pubkey::find_program_address(
+                    &[b"pending", pool_pda.as_ref(), nullifier_hash],
+                    &program_id,
+                );
+                assert_eq!(
+                    pending_pda, expected,
+                    "pending PDA must be seeded by nullifier"
+                );
+                if !open.lock().unwrap().insert(pending_pda) {
+                    return Err(RpcError::RpcResponseError {
+                        code: -32002,
+                        message: format!(
+                            "Transaction simulation failed: Allocate: account Address {{ address: {}, base: None }} already in use",
+                            pending_pda
+                        ),
+                        data: RpcResponseErrorData::Empty,
+                    }
+                    .into());
+                }
+
+                let mut counter = pending_counter.lock().unwrap();
+                let mut data = Sha256::digest(b"event:WithdrawalRequested")[..8].to_vec();
+                data.extend_from_slice(pool_pda.as_ref());
+                data.extend_from_slice(&counter.to_le_bytes());
+                data.extend_from_slice(&1_234i64.to_le_bytes());
+                *counter += 1;
+                *event.lock().unwrap() =
+                    Some(base64::engine::general_purpose::STANDARD.encode(data));
+                Ok(serde_json::json!(tx.signatures[0].to_string()))
+            })
+            .on(RpcRequest::GetSignatureStatuses, |_| {
+                Ok(with_context(serde_json::json!([mock_rpc::finalized_status()])))
+            })
+            .on(RpcRequest::GetTransaction, move |_| {
+                let event = logged.lock().unwrap().clone().unwrap();
+                Ok(serde_json::json!({
+                    "slot": 1,
+                    "blockTime": null,
+                    "transaction": "",
+                    "meta": {
+                        "err": null,
+                        "status": { "Ok": null },
+                        "fee": 5000,
+                        "preBalances": [],
+                        "postBalances": [],
+                        "logMessages": [
+                            "Program log: Instruction: RequestWithdrawal",
+                            "Program log: Withdrawal requested",
+                            format!("Program data: {}", event),
+                        ],
+                    },
+                }))
+            })
+            .into_client()
+    }
+
+    #[tokio::test]
+    async fn test_pending_withdrawals_are_unique_per_nullifier() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RelayerConfig::for_tests();
+        let bucket_id = 2u8;
+        let amount = config.bucket_amount(bucket_id).unwrap();
+        let program_id = config.program_id;
+        let (pool_pda, _) = Pubkey::find_program_address(&[b"pool", &[bucket_id]], &program_id);
+
+        let rpc_client = Arc::new(pending_requests(program_id, pool_pda));
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().to_path_buf(),
+        ));
+        let service = WithdrawalService::new(config, rpc_client, merkle_service);
+
+        let request = |nullifier_hash: [u8; 32]| WithdrawalRequest {
+            proof: privacy_proxy_sdk::withdrawal::ZkProof {
+                a: [0u8; 64],
+                b: [0u8; 128],
+                c: [0u8; 64],
+            },
+            public_inputs: privacy_proxy_sdk::withdrawal::WithdrawalPublicInputs {
+                root: [1u8; 32],
+                nullifier_hash,
+                recipient: [3u8; 32],
+                amount,
+                relayer: [4u8; 32],
+                fee: 0,
+                binding_hash: [5u8; 32],
+            },
+        };
+        let pending_pda = |nullifier_hash: &[u8; 32]| {
+            Pubkey::find_program_address(
+                &[b"pending", pool_pda.as_ref(), nullifier_hash],
+                &program_id,
+            )
+            .0
+        };
+
+        // Two requests against the same pool state get distinct accounts and ids
+        let (_, first) = service
+            .submit_withdrawal_request(&request([2u8; 32]), bucket_id, 1, 0)
+            .await
+            .unwrap();
+        let (_, second) = service
+            .submit_withdrawal_request(&request([6u8; 32]), bucket_id, 1, 0)
+            .await
+            .unwrap();
+        assert_eq!(first.pda, pending_pda(&[2u8; 32]));
+        assert_eq!(second.pda, pending_pda(&[6u8; 32]));
+        assert_ne!(first.pda, second.pda);
+        assert_eq!((first.pending_id, second.pending_id), (0, 1));
+        assert_eq!(first.execute_after, 1_234);
+        assert_eq!(first.pool_pda, pool_pda);
+
+        // The program refuses a second pending withdrawal for an open nullifier...
+        assert!(service
+            .submit_withdrawal_request(&request([2u8; 32]), bucket_id, 1, 0)
+            .await
+            .is_err());
+
+        // ...and the relayer refuses it before sending anything
+        service.pending_withdrawals.write().await.push(first);
+        assert!(matches!(
+            service.ensure_not_pending(&[2u8; 32]).await,
+            Err(RelayerError::WithdrawalAlreadyPending)
+        ));
+        assert!(service.ensure_not_pending(&[6u8; 32]).await.is_ok());
+        service.pending_withdrawals.write().await[0].cancelled = true;
+        assert!(service.ensure_not_pending(&[2u8; 32]).await.is_ok());
+
+        // Status reports the latest request for a nullifier
+        assert!(
+            service
+                .withdrawal_status(&[2u8; 32])
+                .await
+                .unwrap()
+                .cancelled
+        );
+        let mut retry = service.pending_withdrawals.read().await[0].clone();
+        retry.cancelled = false;
+        retry.pending_id = 2;
+        service.pending_withdrawals.write().await.push(retry);
+        let status = service.withdrawal_status(&[2u8; 32]).await.unwrap();
+        assert_eq!((status.pending_id, status.cancelled), (2, false));
+        assert!(service.withdrawal_status(&[6u8; 32]).await.is_none());
+
+        // Unrelated program data is ignored
+        assert_eq!(
+            parse_withdrawal_requested(&["Program data: AAAA".to_string()]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_withdrawal_bucket() {
+        let buckets = crate::config::DEFAULT_BUCKET_AMOUNTS;
+
+        // Amount matches the requested bucket
+        assert_eq!(
+            resolve_withdrawal_bucket(&buckets, 1_000_000_000, Some(2), true).unwrap(),
+            2
+        );
+        // No bucket given, derived from the amount
+        assert_eq!(
+            resolve_withdrawal_bucket(&buckets, 5_000_000_000, None, true).unwrap(),
+            3
+        );
+
+        // Proof generated for 1 SOL but sent to the 0.5 SOL bucket
+        match resolve_withdrawal_bucket(&buckets, 1_000_000_000, Some(1), true) {
+            Err(RelayerError::AmountMismatch {
+                bucket_id,
+                amount,
+                expected,
+            }) => {
+                assert_eq!(bucket_id, 1);
+                assert_eq!(amount, 1_000_000_000);
+                assert_eq!(expected, 500_000_000);
+            }
+            other => panic!("expected AmountMismatch, got {:?}", other.map(|_| ())),
+        }
+        assert!(matches!(
+            resolve_withdrawal_bucket(&buckets, 123, None, true),
+            Err(RelayerError::InvalidBucket(123))
+        ));
+
+        // Check disabled: relayed as requested, on-chain verification decides
+        assert_eq!(
+            resolve_withdrawal_bucket(&buckets, 1_000_000_000, Some(1), false).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_validate_withdrawal_inputs() {
+        use privacy_proxy_sdk::withdrawal::WithdrawalPublicInputs;
+
+        let valid = || WithdrawalRequest {
+            proof: ZkProof {
+                a: [1u8; 64],
+                b: [2u8; 128],
+                c: [3u8; 64],
+            },
+            public_inputs: WithdrawalPublicInputs {
+                root: [4u8; 32],
+                nullifier_hash: [5u8; 32],
+                recipient: [6u8; 32],
+                amount: 1_000_000_000,
+                relayer: [7u8; 32],
+                fee: 0,
+                binding_hash: [8u8; 32],
+            },
+        };
+        assert!(validate_withdrawal_inputs(&valid()).is_ok());
+
+        let rejected = |request: WithdrawalRequest| match validate_withdrawal_inputs(&request) {
+            Err(RelayerError::InvalidRequest(message)) => message,
+            other => panic!("expected InvalidRequest, got {:?}", other),
+        };
+
+        let mut request = valid();
+        request.proof.c = [0u8; 64];
+        assert_eq!(rejected(request), "proof_c must be non-zero");
+
+        // Third G2 coordinate at the modulus
+        let mut request = valid();
+        request.proof.b[64..96].copy_from_slice(&BN254_BASE_FIELD_MODULUS);
+        assert_eq!(
+            rejected(request),
+            "proof_b coordinate 2 is not in the base field"
+        );
+
+        let mut request = valid();
+        request.public_inputs.root = [0u8; 32];
+        assert_eq!(rejected(request), "Merkle root must be non-zero");
+
+        let mut request = valid();
+        request.public_inputs.recipient = [0xff; 32];
+        assert_eq!(
+            rejected(request),
+            "Public input recipient is not a BN254 field element"
+        );
+    }
+
+    #[test]
+    fn test_fee_summary_counts_executed_only() {
+        let record = |fee, executed, cancelled| PendingWithdrawalRecord {
+            pda: Pubkey::new_unique(),
+            pool_pda: Pubkey::new_unique(),
+            bucket_id: 2,
+            nullifier_hash: [0u8; 32],
+            recipient: Pubkey::new_unique(),
+            execute_after: 0,
+            amount: 1_000_000_000 - fee,
+            fee,
+            executed,
+            cancelled,
+            pending_id: 0,
+            tx_signature: None,
+        };
+        let records = vec![
+            record(5_000_000, true, false),
+            record(5_000_000, true, false),
+            record(5_000_000, false, false),
+            record(5_000_000, false, true),
+        ];
+        assert_eq!(
+            FeeSummary::from_records(&records),
+            FeeSummary {
+                executed_withdrawals: 2,
+                fees_collected: 10_000_000,
+            }
+        );
+    }
+
+    /// Execute transactions as the program would see them
+    #[derive(Default)]
+    struct SendStats {
+        /// Sent transactions that also close the pending withdrawal
+        closes: std::sync::atomic::AtomicUsize,
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+    }
+
+    /// RPC double for withdrawal execution: recipients and treasury already exist, nullifiers
+    /// don't, and each execute transaction takes a while to send so overlap can be observed
+    fn slow_execution(nullifiers: Vec<Pubkey>) -> (Arc<RpcClient>, Calls, Arc<SendStats>) {
+        let stats = Arc::new(SendStats::default());
+        let sending = stats.clone();
+        let rpc = MockRpc::new()
+            .accounts(move |address| {
+                (!nullifiers.contains(address)).then(|| mock_rpc::account(&[], &SYSTEM_PROGRAM_ID))
+            })
+            .blockhash()
+            .on(RpcRequest::SimulateTransaction, |_| {
+                Ok(with_context(serde_json::json!({
+                    "err": null,
+                    "logs": [],
+                    "accounts": null,
+                    "unitsConsumed": 0,
+                })))
+            })
+            .on_async(RpcRequest::SendTransaction, move |params| {
+                use std::sync::atomic::Ordering;
+
+                let tx = mock_rpc::sent_transaction(&params);
+                let stats = sending.clone();
+                async move {
+                    if tx.message.instructions().iter().any(|ix| {
+                        ix.data
+                            .starts_with(&anchor_discriminator("close_withdrawal"))
+                    }) {
+                        stats.closes.fetch_add(1, Ordering::SeqCst);
+                    }
+                    let now = stats.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    stats.max_in_flight.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    stats.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(serde_json::json!(tx.signatures[0].to_string()))
+                }
+            })
+            .on(RpcRequest::GetSignatureStatuses, |_| {
+                Ok(with_context(serde_json::json!([
+                    mock_rpc::finalized_status()
+                ])))
+            });
+        let calls = rpc.calls();
+        (Arc::new(rpc.into_client()), calls, stats)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_poll_executes_in_parallel_once_each() {
+        const WITHDRAWALS: u8 = 8;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RelayerConfig::for_tests();
+        let limit = config.max_concurrent_executions;
+        let program_id = config.program_id;
+
+        let records: Vec<PendingWithdrawalRecord> = (0..WITHDRAWALS)
+            .map(|i| PendingWithdrawalRecord {
+                pda: Pubkey::new_unique(),
+                pool_pda: Pubkey::new_unique(),
+                bucket_id: 2,
+                nullifier_hash: [i; 32],
+                recipient: Pubkey::new_unique(),
+                execute_after: 0,
+                amount: 995_000_000,
+                fee: 5_000_000,
+                executed: false,
+                cancelled: false,
+                pending_id: 0,
+                tx_signature: None,
+            })
+            .collect();
+        let nullifiers = records
+            .iter()
+            .map(|r| {
+                Pubkey::find_program_address(&[b"nullifier", &r.nullifier_hash], &program_id).0
+            })
+            .collect();
+
+        let (rpc_client, calls, stats) = slow_execution(nullifiers);
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().to_path_buf(),
+        ));
+        let service = WithdrawalService::new(config, rpc_client, merkle_service);
+        service.pending_withdrawals.write().await.extend(records);
+
+        // Two overlapping polls see the same eligible records
+        let (first, second) = tokio::join!(service.poll_and_execute(), service.poll_and_execute());
+
+        use std::sync::atomic::Ordering;
+        assert_eq!(first.len() + second.len(), WITHDRAWALS as usize);
+        assert!(first
+            .iter()
+            .chain(&second)
+            .all(|(_, result)| result.is_ok()));
+        assert_eq!(calls.get(RpcRequest::SendTransaction), WITHDRAWALS as usize);
+        // Treasury looked up once, nullifier and recipient checks are batched
+        assert_eq!(calls.get(RpcRequest::GetAccountInfo), 1);
+        let max_in_flight = stats.max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight > 1, "executions did not overlap");
+        assert!(
+            max_in_flight <= limit,
+            "{} executions exceeded the limit",
+            max_in_flight
+        );
+        assert!(service
+            .get_pending_withdrawals()
+            .await
+            .iter()
+            .all(|r| r.executed && r.tx_signature.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_sends_go_to_write_client() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RelayerConfig::for_tests();
+        let record = PendingWithdrawalRecord {
+            pda: Pubkey::new_unique(),
+            pool_pda: Pubkey::new_unique(),
+            bucket_id: 2,
+            nullifier_hash: [1; 32],
+            recipient: Pubkey::new_unique(),
+            execute_after: 0,
+            amount: 995_000_000,
+            fee: 5_000_000,
+            executed: false,
+            cancelled: false,
+            pending_id: 0,
+            tx_signature: None,
+        };
+        let nullifier = Pubkey::find_program_address(
+            &[b"nullifier", &record.nullifier_hash],
+            &config.program_id,
+        )
+        .0;
+
+        let (read_client, read_calls, _) = slow_execution(vec![nullifier]);
+        let (write_client, write_calls, write_stats) = slow_execution(vec![nullifier]);
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().to_path_buf(),
+        ));
+        let service = WithdrawalService::new(config, read_client, merkle_service)
+            .with_send_client(write_client);
+
+        service.execute_withdrawal_by_record(&record).await.unwrap();
+
+        use std::sync::atomic::Ordering;
+        assert_eq!(read_calls.get(RpcRequest::SendTransaction), 0);
+        assert_eq!(read_calls.get(RpcRequest::GetAccountInfo), 1);
+        assert_eq!(write_calls.get(RpcRequest::SendTransaction), 1);
+        assert_eq!(write_stats.closes.load(Ordering::SeqCst), 1);
+        assert_eq!(write_calls.get(RpcRequest::GetAccountInfo), 0);
+    }
+
+    #[tokio::test]
+    async fn test_historical_roots_newest_first() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().to_path_buf(),
+        ));
+        merkle_service.init_tree(2).await.unwrap();
+        let rpc_client = Arc::new(RpcClient::new("http://127.0.0.1:0".to_string()));
+        let service = WithdrawalService::new(
+            RelayerConfig::for_tests(),
+            rpc_client,
+            merkle_service.clone(),
+        );
+
+        service.record_historical_root(2).await.unwrap();
+        let empty_root = merkle_service.root(2).await.unwrap();
+        merkle_service.insert(2, [1u8; 32]).await.unwrap();
+        service.record_historical_root(2).await.unwrap();
+        let latest_root = merkle_service.root(2).await.unwrap();
+
+        let roots: Vec<_> = service
+            .historical_roots(2)
+            .await
+            .into_iter()
+            .map(|(root, _)| root)
+            .collect();
+        assert_eq!(roots, vec![latest_root, empty_root]);
+        assert!(service.historical_roots(0).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_is_nullifier_spent() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RelayerConfig::for_tests();
+        let spent_hash = [3u8; 32];
+        let (spent, _) =
+            Pubkey::find_program_address(&[b"nullifier", &spent_hash], &config.program_id);
+        // Only the nullifier record of `spent` exists
+        let rpc_client = Arc::new(
+            MockRpc::new()
+                .accounts(move |address| {
+                    (*address == spent).then(|| mock_rpc::account(&[], &Pubkey::default()))
+                })
+                .into_client(),
+        );
+        let merkle_service = Arc::new(MerkleService::with_persistence_path(
+            temp_dir.path().to_path_buf(),
+        ));
+        let service = WithdrawalService::new(config, rpc_client, merkle_service);
+
+        assert!(service.is_nullifier_spent(&spent_hash).await.unwrap());
+        assert!(!service.is_nullifier_spent(&[4u8; 32]).await.unwrap());
+    }
+}
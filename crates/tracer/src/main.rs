@@ -1,19 +1,37 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use clap::Parser;
-use solana_client::rpc_client::RpcClient;
+use futures::stream::{self, StreamExt};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
 use solana_sdk::pubkey::Pubkey;
-use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding, UiTransactionStatusMeta,
+    UiTransactionTokenBalance,
+};
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Transactions fetched at once per address
+const RPC_CONCURRENCY: usize = 8;
+
+/// Max signatures per getSignaturesForAddress call (RPC limit)
+const SIGNATURE_PAGE_SIZE: usize = 1000;
 
 #[derive(Debug, Clone)]
 struct TransactionInfo {
     signature: String,
     timestamp: Option<DateTime<Utc>>,
+    /// Lamports for SOL, base units for tokens
     amount: i64,
     sender: String,
     tx_type: TransactionType,
+    /// SPL mint of a token inflow (None = native SOL)
+    token_mint: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,32 +69,55 @@ struct PrivacyTraceResult {
     deposit_wallets: Vec<String>,
     user_deposited_directly: bool,
     user_funded_deposit_wallet: bool,
+    /// Transfer from the user wallet that funded a deposit wallet
+    funding_tx: Option<TransactionInfo>,
     trace_path: Vec<String>,
 }
 
+/// How far back each address's history is scanned (0 = everything)
+#[derive(Debug, Clone, Copy)]
+struct ScanLimits {
+    /// Wallet histories: withdrawal receiver and deposit wallet funding
+    max_sigs: usize,
+    /// Pool PDA histories
+    max_program_sigs: usize,
+}
+
 struct TransactionTracer {
     client: RpcClient,
     max_depth: usize,
     program_id: Pubkey,
+    limits: ScanLimits,
+    /// Set when some history had more signatures than its limit allowed scanning
+    truncated: AtomicBool,
+    /// Transactions by signature, shared by every branch of the trace
+    tx_cache: Mutex<HashMap<String, Arc<EncodedConfirmedTransactionWithStatusMeta>>>,
+    /// Balances by address
+    balance_cache: Mutex<HashMap<String, u64>>,
 }
 
 impl TransactionTracer {
-    fn new(rpc_url: &str, max_depth: usize, program_id: Pubkey) -> Self {
+    fn new(rpc_url: &str, max_depth: usize, program_id: Pubkey, limits: ScanLimits) -> Self {
         Self {
             client: RpcClient::new(rpc_url.to_string()),
             max_depth,
             program_id,
+            limits,
+            truncated: AtomicBool::new(false),
+            tx_cache: Mutex::new(HashMap::new()),
+            balance_cache: Mutex::new(HashMap::new()),
         }
     }
 
     async fn trace_privacy(&self, receiver: &str, user_wallet: &str) -> Result<PrivacyTraceResult> {
         let mut tree = TraceNode::new(receiver.to_string(), 0);
         tree.label = Some("withdrawal receiver".to_string());
-        self.fill_balance(&mut tree)?;
+        self.fill_balance(&mut tree).await?;
 
         let mut deposit_wallets: Vec<String> = Vec::new();
         let mut user_deposited_directly = false;
         let mut user_funded_deposit_wallet = false;
+        let mut funding_tx = None;
         let mut trace_path: Vec<String> = Vec::new();
 
         println!(
@@ -84,7 +125,7 @@ impl TransactionTracer {
             &receiver[..8]
         );
 
-        let receiver_txs = self.get_incoming_transactions(receiver)?;
+        let receiver_txs = self.get_incoming_transactions(receiver).await?;
         println!("  Found {} incoming transaction(s)", receiver_txs.len());
         tree.transactions = receiver_txs.clone();
 
@@ -100,14 +141,14 @@ impl TransactionTracer {
 
             let mut pool_node = TraceNode::new(pool_addr.clone(), 1);
             pool_node.label = Some("pool PDA".to_string());
-            self.fill_balance(&mut pool_node)?;
+            self.fill_balance(&mut pool_node).await?;
 
             println!(
                 "Fetching transactions for {}... (depth 1, pool PDA)",
                 &pool_addr[..8]
             );
 
-            let pool_txs = self.get_program_deposits(pool_addr)?;
+            let pool_txs = self.get_program_deposits(pool_addr).await?;
             println!("  Found {} program deposit(s)", pool_txs.len());
             pool_node.transactions = pool_txs.clone();
 
@@ -131,7 +172,7 @@ impl TransactionTracer {
 
                 let mut dep_node = TraceNode::new(dep_addr.clone(), 2);
                 dep_node.label = Some("deposit wallet".to_string());
-                self.fill_balance(&mut dep_node)?;
+                self.fill_balance(&mut dep_node).await?;
 
                 if self.max_depth > 2 {
                     println!(
@@ -139,10 +180,11 @@ impl TransactionTracer {
                         &dep_addr[..8]
                     );
 
-                    let funded = self.check_direct_funding(dep_addr, user_wallet)?;
+                    let funding = self.check_direct_funding(dep_addr, user_wallet).await?;
 
-                    if funded {
+                    if let Some(funding) = funding {
                         user_funded_deposit_wallet = true;
+                        funding_tx.get_or_insert(funding);
                         trace_path = vec![
                             receiver.to_string(),
                             pool_addr.clone(),
@@ -152,7 +194,7 @@ impl TransactionTracer {
 
                         let mut user_node = TraceNode::new(user_wallet.to_string(), 3);
                         user_node.label = Some("YOUR WALLET".to_string());
-                        self.fill_balance(&mut user_node)?;
+                        self.fill_balance(&mut user_node).await?;
                         dep_node
                             .senders
                             .insert(user_wallet.to_string(), Box::new(user_node));
@@ -172,116 +214,149 @@ impl TransactionTracer {
             deposit_wallets,
             user_deposited_directly,
             user_funded_deposit_wallet,
+            funding_tx,
             trace_path,
         })
     }
 
-    fn get_incoming_transactions(&self, address: &str) -> Result<Vec<TransactionInfo>> {
-        let pubkey = Pubkey::from_str(address)?;
-        let signatures = self
-            .client
-            .get_signatures_for_address(&pubkey)
-            .map_err(|e| anyhow!("Failed to fetch signatures for {}: {}", &address[..8], e))?;
-
+    async fn get_incoming_transactions(&self, address: &str) -> Result<Vec<TransactionInfo>> {
         let mut results = Vec::new();
 
-        for sig_info in signatures.iter().take(20) {
-            if sig_info.err.is_some() {
-                continue;
-            }
-            let signature = sig_info.signature.parse()?;
-            let tx = self
-                .client
-                .get_transaction(&signature, UiTransactionEncoding::JsonParsed)
-                .ok();
-
-            if let Some(tx) = tx {
-                if let Some(info) =
-                    self.extract_incoming_transfer(&tx, address, &sig_info.signature)
-                {
-                    results.push(info);
-                }
-            }
+        for (signature, tx) in self
+            .fetch_transactions(address, self.limits.max_sigs)
+            .await?
+        {
+            results.extend(self.extract_incoming_transfers(&tx, address, &signature));
         }
 
         Ok(results)
     }
 
-    fn get_program_deposits(&self, pool_address: &str) -> Result<Vec<TransactionInfo>> {
-        let pubkey = Pubkey::from_str(pool_address)?;
-        let signatures = self
-            .client
-            .get_signatures_for_address(&pubkey)
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to fetch signatures for {}: {}",
-                    &pool_address[..8],
-                    e
-                )
-            })?;
-
+    async fn get_program_deposits(&self, pool_address: &str) -> Result<Vec<TransactionInfo>> {
         let mut results = Vec::new();
 
-        for sig_info in signatures.iter().take(50) {
-            if sig_info.err.is_some() {
+        for (signature, tx) in self
+            .fetch_transactions(pool_address, self.limits.max_program_sigs)
+            .await?
+        {
+            if !self.tx_involves_program(&tx) {
                 continue;
             }
-            let signature = sig_info.signature.parse()?;
-            let tx = self
-                .client
-                .get_transaction(&signature, UiTransactionEncoding::JsonParsed)
-                .ok();
+            results.extend(self.extract_incoming_transfers(&tx, pool_address, &signature));
+        }
 
-            if let Some(tx) = tx {
-                if !self.tx_involves_program(&tx) {
-                    continue;
-                }
-                if let Some(info) =
-                    self.extract_incoming_transfer(&tx, pool_address, &sig_info.signature)
-                {
-                    results.push(info);
-                }
+        Ok(results)
+    }
+
+    /// First SOL or token transfer from `user_wallet` into `target_address`, if any
+    async fn check_direct_funding(
+        &self,
+        target_address: &str,
+        user_wallet: &str,
+    ) -> Result<Option<TransactionInfo>> {
+        for (signature, tx) in self
+            .fetch_transactions(target_address, self.limits.max_sigs)
+            .await?
+        {
+            if let Some(info) = self
+                .extract_incoming_transfers(&tx, target_address, &signature)
+                .into_iter()
+                .find(|info| info.sender == user_wallet)
+            {
+                return Ok(Some(info));
             }
         }
 
-        Ok(results)
+        Ok(None)
     }
 
-    fn check_direct_funding(&self, target_address: &str, user_wallet: &str) -> Result<bool> {
-        let pubkey = Pubkey::from_str(target_address)?;
+    /// Successful transactions among the latest `limit` signatures of `address` (0 = all),
+    /// newest first
+    /// Fetched `RPC_CONCURRENCY` at a time; `buffered` keeps signature order so traversal
+    /// sees them exactly as a sequential loop would. Transactions that fail to load are skipped
+    async fn fetch_transactions(
+        &self,
+        address: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, Arc<EncodedConfirmedTransactionWithStatusMeta>)>> {
+        let pubkey = Pubkey::from_str(address)?;
         let signatures = self
-            .client
-            .get_signatures_for_address(&pubkey)
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to fetch signatures for {}: {}",
-                    &target_address[..8],
-                    e
-                )
-            })?;
+            .fetch_signatures(&pubkey, limit)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch signatures for {}: {}", &address[..8], e))?;
 
-        for sig_info in signatures.iter().take(50) {
+        let mut pending = Vec::new();
+        for sig_info in signatures {
             if sig_info.err.is_some() {
                 continue;
             }
-            let signature = sig_info.signature.parse()?;
-            let tx = self
-                .client
-                .get_transaction(&signature, UiTransactionEncoding::JsonParsed)
-                .ok();
-
-            if let Some(tx) = tx {
-                if let Some(info) =
-                    self.extract_incoming_transfer(&tx, target_address, &sig_info.signature)
-                {
-                    if info.sender == user_wallet {
-                        return Ok(true);
-                    }
+            let signature: Signature = sig_info.signature.parse()?;
+            pending.push((sig_info.signature, signature));
+        }
+
+        let fetched: Vec<_> = stream::iter(pending)
+            .map(|(signature_str, signature)| async move {
+                let cached = self.tx_cache.lock().unwrap().get(&signature_str).cloned();
+                if let Some(tx) = cached {
+                    return Some((signature_str, tx));
                 }
+                let tx = self
+                    .client
+                    .get_transaction(&signature, UiTransactionEncoding::JsonParsed)
+                    .await
+                    .ok()
+                    .map(Arc::new)?;
+                self.tx_cache
+                    .lock()
+                    .unwrap()
+                    .insert(signature_str.clone(), tx.clone());
+                Some((signature_str, tx))
+            })
+            .buffered(RPC_CONCURRENCY)
+            .collect()
+            .await;
+
+        Ok(fetched.into_iter().flatten().collect())
+    }
+
+    /// Latest `limit` signatures of `address` (0 = all), paging back with a `before` cursor
+    async fn fetch_signatures(
+        &self,
+        address: &Pubkey,
+        limit: usize,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        let mut signatures = Vec::new();
+        let mut before = None;
+
+        while signatures.len() < limit {
+            let page_size = SIGNATURE_PAGE_SIZE.min(limit - signatures.len());
+            let page = self
+                .client
+                .get_signatures_for_address_with_config(
+                    address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(page_size),
+                        commitment: None,
+                    },
+                )
+                .await?;
+            let exhausted = page.len() < page_size;
+            before = match page.last() {
+                Some(last) => Some(last.signature.parse()?),
+                None => None,
+            };
+            signatures.extend(page);
+            if exhausted || before.is_none() {
+                return Ok(signatures);
             }
         }
 
-        Ok(false)
+        // Stopped at the limit with history possibly remaining
+        self.truncated.store(true, Ordering::Relaxed);
+        Ok(signatures)
     }
 
     fn tx_involves_program(&self, tx: &EncodedConfirmedTransactionWithStatusMeta) -> bool {
@@ -311,79 +386,66 @@ impl TransactionTracer {
         false
     }
 
-    fn extract_incoming_transfer(
+    /// Inflows to `receiver_address` in one transaction: native SOL from lamport deltas,
+    /// plus one entry per SPL mint from token balance deltas of accounts it owns
+    fn extract_incoming_transfers(
         &self,
         tx: &EncodedConfirmedTransactionWithStatusMeta,
         receiver_address: &str,
         signature: &str,
-    ) -> Option<TransactionInfo> {
-        let receiver = Pubkey::from_str(receiver_address).ok()?;
-        let meta = tx.transaction.meta.as_ref()?;
-
+    ) -> Vec<TransactionInfo> {
+        let Some(meta) = tx.transaction.meta.as_ref() else {
+            return Vec::new();
+        };
         let account_keys = match &tx.transaction.transaction {
             solana_transaction_status::EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
                 solana_transaction_status::UiMessage::Parsed(parsed_msg) => {
                     &parsed_msg.account_keys
                 }
-                _ => return None,
+                _ => return Vec::new(),
             },
-            _ => return None,
+            _ => return Vec::new(),
         };
 
-        let pre_balances = &meta.pre_balances;
-        let post_balances = &meta.post_balances;
-
-        let receiver_index = account_keys
-            .iter()
-            .position(|key| Pubkey::from_str(&key.pubkey).ok() == Some(receiver))?;
-
-        let pre = *pre_balances.get(receiver_index)?;
-        let post = *post_balances.get(receiver_index)?;
-        let change = post as i64 - pre as i64;
-
-        if change <= 0 {
-            return None;
-        }
-
-        let mut sender_address = "unknown".to_string();
-        let mut best_match = 0i64;
-
-        for (i, key) in account_keys.iter().enumerate() {
-            if i == receiver_index {
-                continue;
-            }
-            if let (Some(&pre_b), Some(&post_b)) = (pre_balances.get(i), post_balances.get(i)) {
-                let delta = post_b as i64 - pre_b as i64;
-                if delta < best_match {
-                    best_match = delta;
-                    sender_address = key.pubkey.clone();
-                }
-            }
-        }
-
         let tx_type = if self.tx_involves_program(tx) {
             TransactionType::Program
         } else {
             TransactionType::Transfer
         };
-
         let timestamp = tx
             .block_time
-            .map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_else(|| Utc::now()));
-
-        Some(TransactionInfo {
+            .map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now));
+        let info = |amount: i64, sender: String, token_mint: Option<String>| TransactionInfo {
             signature: signature.to_string(),
             timestamp,
-            amount: change,
-            sender: sender_address,
-            tx_type,
-        })
+            amount,
+            sender,
+            tx_type: tx_type.clone(),
+            token_mint,
+        };
+
+        let mut transfers = Vec::new();
+        if let Some((change, sender)) = sol_inflow(meta, account_keys, receiver_address) {
+            transfers.push(info(change, sender, None));
+        }
+        for (mint, change, sender) in token_inflows(meta, account_keys, receiver_address) {
+            transfers.push(info(change, sender, Some(mint)));
+        }
+        transfers
     }
 
-    fn fill_balance(&self, node: &mut TraceNode) -> Result<()> {
+    async fn fill_balance(&self, node: &mut TraceNode) -> Result<()> {
+        if let Some(&balance) = self.balance_cache.lock().unwrap().get(&node.address) {
+            node.balance = Some(balance);
+            return Ok(());
+        }
         if let Ok(pubkey) = Pubkey::from_str(&node.address) {
-            if let Ok(balance) = self.client.get_balance(&pubkey) {
+            if let Ok(balance) = self.client.get_balance(&pubkey).await {
                 node.balance = Some(balance);
+                self.balance_cache
+                    .lock()
+                    .unwrap()
+                    .insert(node.address.clone(), balance);
             }
         }
         Ok(())
@@ -428,13 +490,16 @@ impl TransactionTracer {
                     .timestamp
                     .map(|ts| ts.format("%Y-%m-%d %H:%M").to_string())
                     .unwrap_or_else(|| "unknown".to_string());
-                let amount = tx.amount as f64 / 1e9;
+                let amount = match &tx.token_mint {
+                    None => format!("{:.4} SOL", tx.amount as f64 / 1e9),
+                    Some(mint) => format!("{} units of {}...", tx.amount, &mint[..8]),
+                };
                 let type_str = match tx.tx_type {
                     TransactionType::Transfer => "TRANSFER",
                     TransactionType::Program => "PROGRAM ",
                 };
                 println!(
-                    "{}  {} | {:.4} SOL | {} | {}...",
+                    "{}  {} | {} | {} | {}...",
                     tx_prefix,
                     type_str,
                     amount,
@@ -464,6 +529,97 @@ impl TransactionTracer {
     }
 }
 
+type ParsedAccountKeys = [solana_transaction_status::parse_accounts::ParsedAccount];
+
+/// Lamports gained by `receiver_address`, attributed to the account that lost the most
+fn sol_inflow(
+    meta: &UiTransactionStatusMeta,
+    account_keys: &ParsedAccountKeys,
+    receiver_address: &str,
+) -> Option<(i64, String)> {
+    let receiver = Pubkey::from_str(receiver_address).ok()?;
+    let pre_balances = &meta.pre_balances;
+    let post_balances = &meta.post_balances;
+
+    let receiver_index = account_keys
+        .iter()
+        .position(|key| Pubkey::from_str(&key.pubkey).ok() == Some(receiver))?;
+
+    let pre = *pre_balances.get(receiver_index)?;
+    let post = *post_balances.get(receiver_index)?;
+    let change = post as i64 - pre as i64;
+
+    if change <= 0 {
+        return None;
+    }
+
+    let mut sender_address = "unknown".to_string();
+    let mut best_match = 0i64;
+
+    for (i, key) in account_keys.iter().enumerate() {
+        if i == receiver_index {
+            continue;
+        }
+        if let (Some(&pre_b), Some(&post_b)) = (pre_balances.get(i), post_balances.get(i)) {
+            let delta = post_b as i64 - pre_b as i64;
+            if delta < best_match {
+                best_match = delta;
+                sender_address = key.pubkey.clone();
+            }
+        }
+    }
+
+    Some((change, sender_address))
+}
+
+/// Per-mint token amounts gained by token accounts owned by `receiver_address`, each
+/// attributed to the owner whose accounts lost the most of that mint. Balances without an
+/// owner are keyed by the token account address instead
+fn token_inflows(
+    meta: &UiTransactionStatusMeta,
+    account_keys: &ParsedAccountKeys,
+    receiver_address: &str,
+) -> Vec<(String, i64, String)> {
+    let pre: Option<Vec<UiTransactionTokenBalance>> = meta.pre_token_balances.clone().into();
+    let post: Option<Vec<UiTransactionTokenBalance>> = meta.post_token_balances.clone().into();
+
+    // (mint, owner) -> post - pre; accounts missing on one side count as zero there
+    let mut deltas: HashMap<(String, String), i128> = HashMap::new();
+    for (balances, sign) in [
+        (pre.unwrap_or_default(), -1i128),
+        (post.unwrap_or_default(), 1),
+    ] {
+        for balance in balances {
+            let amount: i128 = balance.ui_token_amount.amount.parse().unwrap_or(0);
+            let owner: Option<String> = balance.owner.into();
+            let Some(owner) = owner.or_else(|| {
+                account_keys
+                    .get(balance.account_index as usize)
+                    .map(|key| key.pubkey.clone())
+            }) else {
+                continue;
+            };
+            *deltas.entry((balance.mint, owner)).or_default() += sign * amount;
+        }
+    }
+
+    let mut inflows: Vec<(String, i64, String)> = Vec::new();
+    for ((mint, owner), delta) in &deltas {
+        if owner != receiver_address || *delta <= 0 {
+            continue;
+        }
+        let sender = deltas
+            .iter()
+            .filter(|((m, o), d)| m == mint && o != owner && **d < 0)
+            .min_by_key(|(_, d)| **d)
+            .map(|((_, o), _)| o.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        inflows.push((mint.clone(), (*delta).min(i64::MAX as i128) as i64, sender));
+    }
+    inflows.sort();
+    inflows
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "test-privacy")]
 #[command(about = "Test privacy of a withdrawal by tracing the transaction chain")]
@@ -482,6 +638,24 @@ struct Args {
 
     #[arg(short, long, default_value = "10")]
     depth: usize,
+
+    /// Signatures scanned per wallet (withdrawal receiver, deposit wallets), 0 = full history.
+    /// Funding older than the limit is not seen, so a low limit can give a false NOT TRACEABLE
+    #[arg(long, default_value = "50")]
+    max_sigs: usize,
+
+    /// Signatures scanned per pool PDA, 0 = full history.
+    /// Deposits older than the limit are not seen, so a low limit can miss deposit wallets
+    #[arg(long, default_value = "50")]
+    max_program_sigs: usize,
+}
+
+fn limit_label(limit: usize) -> String {
+    if limit == 0 {
+        "full history".to_string()
+    } else {
+        limit.to_string()
+    }
 }
 
 #[tokio::main]
@@ -493,11 +667,20 @@ async fn main() -> Result<()> {
     println!("  Program ID:          {}", args.program);
     println!("  RPC:                 {}", args.rpc);
     println!("  Max Depth:           {}", args.depth);
+    println!("  Max Signatures:      {}", limit_label(args.max_sigs));
+    println!(
+        "  Max Pool Signatures: {}",
+        limit_label(args.max_program_sigs)
+    );
     println!();
 
     let program_id =
         Pubkey::from_str(&args.program).map_err(|e| anyhow!("Invalid program ID: {}", e))?;
-    let tracer = TransactionTracer::new(&args.rpc, args.depth, program_id);
+    let limits = ScanLimits {
+        max_sigs: args.max_sigs,
+        max_program_sigs: args.max_program_sigs,
+    };
+    let tracer = TransactionTracer::new(&args.rpc, args.depth, program_id, limits);
     let result = tracer
         .trace_privacy(&args.withdrawal_receiver, &args.original_depositor)
         .await?;
@@ -520,7 +703,17 @@ async fn main() -> Result<()> {
     } else if result.user_funded_deposit_wallet {
         println!("VERDICT: CORRELATABLE");
         println!("Your wallet did NOT deposit to the pool directly (good)");
-        println!("But your wallet sent SOL directly to the deposit wallet");
+        match result
+            .funding_tx
+            .as_ref()
+            .and_then(|tx| tx.token_mint.as_ref())
+        {
+            Some(mint) => {
+                println!("But your wallet sent SPL tokens (mint {})", mint);
+                println!("directly to the deposit wallet");
+            }
+            None => println!("But your wallet sent SOL directly to the deposit wallet"),
+        }
         println!("(the relayer). An observer can link:");
         println!("  withdrawal -> pool -> deposit wallet <- your wallet");
         println!();
@@ -546,6 +739,12 @@ async fn main() -> Result<()> {
         println!("Your wallet does not appear in the transaction chain");
         println!("from the withdrawal receiver through the pool to the");
         println!("deposit wallet");
+        if tracer.truncated.load(Ordering::Relaxed) {
+            println!();
+            println!("Note: some histories were longer than the scan limit,");
+            println!("older transactions were not checked. Rerun with higher");
+            println!("--max-sigs / --max-program-sigs (0 = full history)");
+        }
     }
 
     Ok(())
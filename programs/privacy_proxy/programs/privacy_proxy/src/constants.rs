@@ -10,6 +10,9 @@ pub const NULLIFIER_SEED: &[u8] = b"nullifier";
 /// Seed for used token PDA (prevents double-redemption)
 pub const USED_TOKEN_SEED: &[u8] = b"used_token";
 
+/// Seed for used commitment PDA (prevents duplicate leaves)
+pub const USED_COMMITMENT_SEED: &[u8] = b"used_commitment";
+
 /// Seed for pending withdrawal PDA
 pub const PENDING_SEED: &[u8] = b"pending";
 
@@ -34,15 +37,33 @@ pub const NUM_BUCKETS: usize = 7;
 /// Merkle tree depth (supports 2^20 = ~1M deposits per pool)
 pub const MERKLE_TREE_DEPTH: usize = 20;
 
+/// Leaves in a tree of MERKLE_TREE_DEPTH, the most deposits a pool can ever take
+pub const MAX_TREE_LEAVES: u64 = 1 << MERKLE_TREE_DEPTH;
+
 /// Minimum withdrawal delay in hours
 pub const MIN_DELAY_HOURS: u8 = 0;
 
 /// Maximum withdrawal delay in hours
 pub const MAX_DELAY_HOURS: u8 = 24;
 
+/// Default age limit for withdrawal roots (one week)
+pub const DEFAULT_MAX_ROOT_AGE_HOURS: u16 = 7 * 24;
+
+/// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
 /// Default fee in basis points (0.5%)
 pub const DEFAULT_FEE_BPS: u16 = 50;
 
+/// Maximum number of HistoricalRoots accounts searched when validating a withdrawal root:
+/// the one at the instruction's `root_account_index` plus the chained accounts after it,
+/// passed as remaining accounts. Bounding them bounds the cost of root validation
+pub const MAX_ROOT_LOOKUP_ACCOUNTS: usize = 4;
+
+/// How long past its timelock an unexecuted pending withdrawal is kept before
+/// close_withdrawal may reclaim its rent (30 days)
+pub const PENDING_WITHDRAWAL_EXPIRY_SECS: i64 = 30 * 24 * 60 * 60;
+
 /// Maximum encrypted note size
 /// REDUCED to 128 bytes to fit within BPF stack limits
 pub const MAX_ENCRYPTED_NOTE_SIZE: usize = 128;
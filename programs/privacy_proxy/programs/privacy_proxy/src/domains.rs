@@ -0,0 +1,19 @@
+//! Domain tags shared with the circuits and zk_verifier, derived from their ASCII tags
+
+/// Big-endian u32 of a 4-char tag
+pub const fn from_tag(tag: &[u8; 4]) -> u64 {
+    u32::from_be_bytes(*tag) as u64
+}
+
+/// Withdrawal binding hash, MUST match circuits/withdrawal.circom
+pub const DOMAIN_BIND: u64 = from_tag(b"bild");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domains_match_circuits() {
+        assert_eq!(DOMAIN_BIND, 1651076196);
+    }
+}
@@ -14,6 +14,9 @@ pub enum PrivacyProxyError {
     #[msg("Protocol is paused")]
     ProtocolPaused,
 
+    #[msg("Pool is paused")]
+    PoolPaused,
+
     #[msg("Invalid ZK proof")]
     InvalidProof,
 
@@ -23,6 +26,9 @@ pub enum PrivacyProxyError {
     #[msg("Invalid Merkle root")]
     InvalidMerkleRoot,
 
+    #[msg("Merkle root is older than the allowed age")]
+    MerkleRootExpired,
+
     #[msg("Withdrawal delay out of range")]
     InvalidDelayHours,
 
@@ -52,4 +58,22 @@ pub enum PrivacyProxyError {
 
     #[msg("Invalid binding hash - proof not bound to these parameters")]
     InvalidBindingHash,
+
+    #[msg("Root lookup exceeds the maximum number of historical roots accounts")]
+    RootLookupTooDeep,
+
+    #[msg("Invalid historical roots account")]
+    InvalidHistoricalRootsAccount,
+
+    #[msg("Withdrawal is neither executed nor expired")]
+    WithdrawalNotClosable,
+
+    #[msg("Signer is not the proposed admin")]
+    NotPendingAdmin,
+
+    #[msg("Only the recipient or the authorized relayer can emergency-execute")]
+    UnauthorizedExecutor,
+
+    #[msg("Pool still holds unwithdrawn deposits")]
+    PoolNotEmpty,
 }
@@ -0,0 +1,51 @@
+/// Events emitted for off-chain indexers and the relayer
+use anchor_lang::prelude::*;
+
+/// Emitted by request_withdrawal with the id the new pending withdrawal was given
+/// Owners need it for the ownership proof that cancels the withdrawal
+#[event]
+pub struct WithdrawalRequested {
+    /// Pool the withdrawal is from
+    pub pool: Pubkey,
+    /// `tx_id` of the new pending withdrawal, taken from `pool.pending_counter`
+    pub pending_id: u64,
+    /// Timestamp after which the withdrawal can be executed
+    pub execute_after: i64,
+}
+
+/// Emitted by deposit once the commitment's leaf is assigned
+/// The relayer rebuilds its tree from these instead of scraping log strings
+#[event]
+pub struct DepositEvent {
+    /// Pool the deposit went into
+    pub pool: Pubkey,
+    pub bucket_id: u8,
+    /// Position of the commitment in the pool's tree
+    pub leaf_index: u64,
+    pub commitment: [u8; 32],
+    /// Root after inserting the commitment
+    pub merkle_root: [u8; 32],
+}
+
+/// Emitted by execute_withdrawal after the funds have moved
+#[event]
+pub struct WithdrawalExecuted {
+    pub pool: Pubkey,
+    /// `tx_id` of the pending withdrawal that was executed
+    pub pending_id: u64,
+    /// Nullifier now marked as spent
+    pub nullifier_hash: [u8; 32],
+    pub recipient: Pubkey,
+    /// Lamports sent to the recipient
+    pub amount: u64,
+    /// Lamports sent to the relayer treasury
+    pub fee: u64,
+}
+
+/// Emitted by cancel_withdrawal, the nullifier stays unspent
+#[event]
+pub struct WithdrawalCancelled {
+    pub pool: Pubkey,
+    /// `tx_id` of the pending withdrawal that was cancelled
+    pub pending_id: u64,
+}
@@ -0,0 +1,31 @@
+/// Second step of an admin transfer - ONLY callable by the proposed admin
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::PrivacyProxyError;
+use crate::state::GlobalConfig;
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.pending_admin != Pubkey::default() @ PrivacyProxyError::NotPendingAdmin,
+        constraint = config.pending_admin == new_admin.key() @ PrivacyProxyError::NotPendingAdmin,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+}
+
+pub fn handler(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+
+    let previous = config.admin;
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+
+    msg!("Admin transferred from {} to {}", previous, config.admin);
+    Ok(())
+}
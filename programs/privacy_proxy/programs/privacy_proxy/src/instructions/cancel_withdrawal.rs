@@ -5,6 +5,7 @@ use anchor_lang::prelude::*;
 
 use crate::constants::*;
 use crate::errors::PrivacyProxyError;
+use crate::events::WithdrawalCancelled;
 use crate::state::{GlobalConfig, PendingWithdrawal, WithdrawalStatus};
 
 pub mod zk_verifier {
@@ -42,7 +43,8 @@ pub fn handler(
     proof_a: [u8; 64],
     proof_b: [u8; 128],
     proof_c: [u8; 64],
-    binding_hash: [u8; 32], // Circuit output - binding hash
+    binding_hash: [u8; 32],     // Circuit output - binding hash
+    pending_withdrawal_id: u64, // Id the proof was generated for
 ) -> Result<()> {
     let config = &ctx.accounts.config;
     let pending = &mut ctx.accounts.pending_withdrawal;
@@ -57,12 +59,18 @@ pub fn handler(
         &proof_b,
         &proof_c,
         &pending.nullifier_hash,
-        pending.tx_id,
+        pending_withdrawal_id,
         &binding_hash,
+        pending.tx_id, // The verifier rejects proofs bound to any other pending withdrawal
     )?;
 
     pending.status = WithdrawalStatus::Cancelled;
 
+    emit!(WithdrawalCancelled {
+        pool: pending.pool,
+        pending_id: pending.tx_id,
+    });
+
     msg!("Withdrawal cancelled");
     msg!("TX ID: {}", pending.tx_id);
     msg!("Binding hash verified: {:?}", &binding_hash[..8]);
@@ -81,13 +89,14 @@ fn verify_ownership_proof_cpi<'info>(
     nullifier_hash: &[u8; 32],
     pending_withdrawal_id: u64,
     binding_hash: &[u8; 32],
+    expected_pending_withdrawal_id: u64,
 ) -> Result<()> {
     use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
     use anchor_lang::solana_program::program::invoke;
 
     let discriminator: [u8; 8] = compute_verify_ownership_discriminator();
 
-    let mut data = Vec::with_capacity(8 + 64 + 128 + 64 + 32 + 8 + 32);
+    let mut data = Vec::with_capacity(8 + 64 + 128 + 64 + 32 + 8 + 32 + 8);
     data.extend_from_slice(&discriminator);
 
     // Groth16Proof struct
@@ -102,6 +111,9 @@ fn verify_ownership_proof_cpi<'info>(
     // Binding hash (circuit output)
     data.extend_from_slice(binding_hash);
 
+    // Pending withdrawal being cancelled
+    data.extend_from_slice(&expected_pending_withdrawal_id.to_le_bytes());
+
     let accounts = vec![AccountMeta::new_readonly(caller.key(), true)];
     let ix = Instruction {
         program_id: zk_verifier_program.key(),
@@ -0,0 +1,97 @@
+/// Close an empty pool and refund the rent of its accounts - ONLY callable by admin
+/// Retires a denomination: the DepositPool, its first HistoricalRoots account and any chained
+/// HistoricalRoots accounts passed as remaining accounts are closed to the admin.
+/// Only allowed once every deposit has been withdrawn. Lamports sent to the pool on top of
+/// its rent go to the admin as well
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::constants::*;
+use crate::errors::PrivacyProxyError;
+use crate::state::{
+    derive_historical_roots_pda, DepositPool, GlobalConfig, HistoricalRoots, HISTORICAL_ROOTS_SEED,
+};
+
+#[derive(Accounts)]
+#[instruction(bucket_id: u8)]
+pub struct ClosePool<'info> {
+    /// Admin closing the pool, receives the rent
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ ProgramError::InvalidArgument,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [POOL_SEED, &[bucket_id]],
+        bump = pool.bump,
+        close = admin,
+    )]
+    pub pool: Account<'info, DepositPool>,
+
+    /// First historical roots account, created by init_pool
+    #[account(
+        mut,
+        seeds = [HISTORICAL_ROOTS_SEED, pool.key().as_ref(), &[0u8]],
+        bump = historical_roots.bump,
+        close = admin,
+    )]
+    pub historical_roots: Account<'info, HistoricalRoots>,
+}
+
+pub fn handler(ctx: Context<ClosePool>, bucket_id: u8) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+
+    // Every deposit must have been withdrawn, which also rules out pending withdrawals.
+    // The balance isn't checked: anyone can send lamports to the pool, and closing it
+    // sweeps them to the admin together with the rent
+    require!(
+        pool.anonymity_set_size == 0,
+        PrivacyProxyError::PoolNotEmpty
+    );
+    let pool_info = pool.to_account_info();
+    let excess = pool_info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(pool_info.data_len()));
+
+    // Chained accounts 1..N, any subset; each must be this pool's account at its stored index
+    let admin_info = ctx.accounts.admin.to_account_info();
+    for account in ctx.remaining_accounts {
+        require_keys_eq!(
+            *account.owner,
+            crate::ID,
+            PrivacyProxyError::InvalidHistoricalRootsAccount
+        );
+        let roots = {
+            let data = account.try_borrow_data()?;
+            HistoricalRoots::try_deserialize(&mut &data[..])?
+        };
+        let (expected, _) =
+            derive_historical_roots_pda(&pool.key(), roots.account_index, &crate::ID);
+        require!(
+            roots.account_index != 0 && roots.pool == pool.key() && account.key() == expected,
+            PrivacyProxyError::InvalidHistoricalRootsAccount
+        );
+
+        **admin_info.try_borrow_mut_lamports()? = admin_info
+            .lamports()
+            .checked_add(account.lamports())
+            .ok_or(PrivacyProxyError::Overflow)?;
+        **account.try_borrow_mut_lamports()? = 0;
+        account.assign(&system_program::ID);
+        account.resize(0)?;
+    }
+
+    msg!("Pool {} closed", bucket_id);
+    msg!("Excess lamports swept: {}", excess);
+    msg!(
+        "Chained historical roots accounts closed: {}",
+        ctx.remaining_accounts.len()
+    );
+    Ok(())
+}
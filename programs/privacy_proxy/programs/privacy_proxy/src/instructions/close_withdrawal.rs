@@ -0,0 +1,59 @@
+/// Close a finished pending withdrawal and refund its rent to the relayer
+/// Executed withdrawals are only kept around for their status, the nullifier record is what
+/// prevents double-spends. A pending withdrawal nobody executed for PENDING_WITHDRAWAL_EXPIRY_SECS
+/// past its timelock can be closed too; its nullifier was never spent, so the owner can request again
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::PrivacyProxyError;
+use crate::state::{GlobalConfig, PendingWithdrawal, WithdrawalStatus};
+
+#[derive(Accounts)]
+pub struct CloseWithdrawal<'info> {
+    /// Relayer that paid for the pending withdrawal, receives the rent
+    #[account(
+        mut,
+        constraint = relayer.key() == config.authorized_relayer @ PrivacyProxyError::UnauthorizedRelayer
+    )]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            PENDING_SEED,
+            pending_withdrawal.pool.as_ref(),
+            &pending_withdrawal.nullifier_hash,
+        ],
+        bump = pending_withdrawal.bump,
+        close = relayer,
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+}
+
+pub fn handler(ctx: Context<CloseWithdrawal>) -> Result<()> {
+    let pending = &ctx.accounts.pending_withdrawal;
+
+    let closable = match pending.status {
+        WithdrawalStatus::Executed => true,
+        WithdrawalStatus::Pending => {
+            let expires_at = pending
+                .execute_after
+                .checked_add(PENDING_WITHDRAWAL_EXPIRY_SECS)
+                .ok_or(PrivacyProxyError::Overflow)?;
+            Clock::get()?.unix_timestamp >= expires_at
+        }
+        WithdrawalStatus::Cancelled => false,
+    };
+    require!(closable, PrivacyProxyError::WithdrawalNotClosable);
+
+    msg!("Withdrawal closed");
+    msg!("TX ID: {}", pending.tx_id);
+
+    Ok(())
+}
@@ -7,8 +7,10 @@ use anchor_lang::system_program;
 
 use crate::constants::*;
 use crate::errors::PrivacyProxyError;
+use crate::events::DepositEvent;
 use crate::state::{
-    DepositPool, EncryptedNote, GlobalConfig, HistoricalRoots, UsedToken, HISTORICAL_ROOTS_SEED,
+    DepositPool, EncryptedNote, GlobalConfig, HistoricalRoots, UsedCommitment, UsedToken,
+    HISTORICAL_ROOTS_SEED,
 };
 
 #[derive(Accounts)]
@@ -36,11 +38,18 @@ pub struct Deposit<'info> {
     )]
     pub pool: Account<'info, DepositPool>,
 
-    /// Historical roots account for this pool
+    /// Historical roots account that receives the current root
+    /// Created here when the previous account in the chain fills up
     #[account(
-        mut,
-        seeds = [HISTORICAL_ROOTS_SEED, pool.key().as_ref(), &[0u8]],
-        bump = historical_roots.bump,
+        init_if_needed,
+        payer = relayer,
+        space = HistoricalRoots::SIZE,
+        seeds = [
+            HISTORICAL_ROOTS_SEED,
+            pool.key().as_ref(),
+            &[HistoricalRoots::account_index_for(pool.next_index)],
+        ],
+        bump,
     )]
     pub historical_roots: Account<'info, HistoricalRoots>,
 
@@ -64,16 +73,28 @@ pub struct Deposit<'info> {
     )]
     pub encrypted_note: Account<'info, EncryptedNote>,
 
+    /// Used commitment record - `init` fails if this commitment was already deposited
+    /// Its rent is paid by the relayer on top of the token and note records
+    #[account(
+        init,
+        payer = relayer,
+        space = UsedCommitment::SIZE,
+        seeds = [USED_COMMITMENT_SEED, &commitment],
+        bump,
+    )]
+    pub used_commitment: Account<'info, UsedCommitment>,
+
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(
     ctx: Context<Deposit>,
     bucket_id: u8,
-    _commitment: [u8; 32],
+    commitment: [u8; 32],
     token_hash: [u8; 32],
     encrypted_note_data: Vec<u8>,
-    merkle_root: [u8; 32], // Actual Merkle root from relayer
+    merkle_root: [u8; 32],           // Actual Merkle root from relayer
+    note_ephemeral_pubkey: [u8; 32], // ECDH key the note was encrypted with, zero if no note
 ) -> Result<()> {
     let config = &ctx.accounts.config;
     let pool = &mut ctx.accounts.pool;
@@ -83,6 +104,7 @@ pub fn handler(
 
     // Check protocol not paused
     require!(!config.paused, PrivacyProxyError::ProtocolPaused);
+    require!(!pool.paused, PrivacyProxyError::PoolPaused);
 
     // Validate bucket
     require!(
@@ -90,6 +112,12 @@ pub fn handler(
         PrivacyProxyError::InvalidBucketId
     );
 
+    // Respect the admin's cap on this pool
+    require!(
+        pool.total_deposits < pool.deposit_cap(),
+        PrivacyProxyError::PoolFull
+    );
+
     // Validate encrypted note size
     require!(
         encrypted_note_data.len() <= MAX_ENCRYPTED_NOTE_SIZE,
@@ -110,8 +138,20 @@ pub fn handler(
         amount,
     )?;
 
+    // First deposit into a new chained account
+    if historical_roots.pool == Pubkey::default() {
+        historical_roots.pool = pool.key();
+        historical_roots.bucket_id = bucket_id;
+        historical_roots.account_index = HistoricalRoots::account_index_for(pool.next_index);
+        historical_roots.bump = ctx.bumps.historical_roots;
+        msg!(
+            "Historical roots account {} started",
+            historical_roots.account_index
+        );
+    }
+
     // Save current root to history before updating
-    historical_roots.add_root(pool.merkle_root);
+    historical_roots.add_root(pool.merkle_root, Clock::get()?.unix_timestamp);
     pool.add_root_to_history();
 
     // The relayer maintains the authoritative Poseidon-based Merkle tree, that matches the ZK circuit
@@ -141,9 +181,25 @@ pub fn handler(
     note.leaf_index = leaf_index;
     note.ciphertext[..encrypted_note_data.len()].copy_from_slice(&encrypted_note_data);
     note.ciphertext_len = encrypted_note_data.len() as u16;
+    note.ephemeral_pubkey = note_ephemeral_pubkey;
     note.created_at = Clock::get()?.unix_timestamp;
     note.bump = ctx.bumps.encrypted_note;
 
+    // Record the commitment so it can never be inserted again
+    let used_commitment = &mut ctx.accounts.used_commitment;
+    used_commitment.commitment = commitment;
+    used_commitment.pool = pool.key();
+    used_commitment.leaf_index = leaf_index;
+    used_commitment.bump = ctx.bumps.used_commitment;
+
+    emit!(DepositEvent {
+        pool: pool.key(),
+        bucket_id,
+        leaf_index,
+        commitment,
+        merkle_root,
+    });
+
     msg!("Deposit successful");
     msg!("Pool: bucket {}", bucket_id);
     msg!("Amount: {} lamports", amount);
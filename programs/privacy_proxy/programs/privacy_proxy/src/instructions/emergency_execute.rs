@@ -0,0 +1,22 @@
+/// Execute a matured withdrawal while the protocol or its pool is paused
+/// A pause stops new deposits and withdrawal requests, but it must not trap funds whose proof
+/// was already verified and whose timelock already ran out. Same accounts and checks as
+/// `execute_withdrawal` (timelock, status, nullifier, fee split) minus the pause flags, and only
+/// the recipient or the authorized relayer may call it
+use anchor_lang::prelude::*;
+
+use crate::errors::PrivacyProxyError;
+use crate::instructions::execute_withdrawal::{settle, ExecuteWithdrawal};
+
+pub fn handler(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+    require!(
+        ctx.accounts.pending_withdrawal.may_emergency_execute(
+            &ctx.accounts.executor.key(),
+            &ctx.accounts.config.authorized_relayer
+        ),
+        PrivacyProxyError::UnauthorizedExecutor
+    );
+
+    msg!("Emergency execution, pause checks skipped");
+    settle(ctx)
+}
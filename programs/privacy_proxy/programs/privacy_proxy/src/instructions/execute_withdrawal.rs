@@ -4,6 +4,7 @@ use anchor_lang::prelude::*;
 
 use crate::constants::*;
 use crate::errors::PrivacyProxyError;
+use crate::events::WithdrawalExecuted;
 use crate::state::{
     DepositPool, GlobalConfig, NullifierRecord, PendingWithdrawal, WithdrawalStatus,
 };
@@ -65,21 +66,42 @@ pub struct ExecuteWithdrawal<'info> {
 }
 
 pub fn handler(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+    // Check protocol not paused
+    require!(
+        !ctx.accounts.config.paused,
+        PrivacyProxyError::ProtocolPaused
+    );
+    require!(!ctx.accounts.pool.paused, PrivacyProxyError::PoolPaused);
+
+    settle(ctx)
+}
+
+/// Pay out a matured pending withdrawal and spend its nullifier
+/// Everything but the pause checks, shared with `emergency_execute`
+pub(crate) fn settle(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
     let config = &ctx.accounts.config;
     let pool = &mut ctx.accounts.pool;
     let pending = &mut ctx.accounts.pending_withdrawal;
     let nullifier = &mut ctx.accounts.nullifier;
 
-    // Check protocol not paused
-    require!(!config.paused, PrivacyProxyError::ProtocolPaused);
-
     // Check timelock has expired
     let clock = Clock::get()?;
     require!(
-        clock.unix_timestamp >= pending.execute_after,
+        pending.is_matured(clock.unix_timestamp),
         PrivacyProxyError::TimelockNotExpired
     );
 
+    // Re-derive the fee split instead of trusting what was stored at request time:
+    // the pending amounts must add up to this pool's denomination, with the fee taken
+    // at the current fee_bps. Changing fee_bps therefore strands pending withdrawals
+    // until their owners cancel and request again
+    let expected_fee = config.withdrawal_fee(pool.amount_lamports)?;
+    require!(
+        pending.fee == expected_fee
+            && pending.amount.checked_add(pending.fee) == Some(pool.amount_lamports),
+        PrivacyProxyError::InvalidDepositAmount
+    );
+
     // Transfer funds from pool to recipient
     let pool_lamports = pool.to_account_info().lamports();
     require!(
@@ -130,6 +152,15 @@ pub fn handler(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
     // Mark withdrawal as executed
     pending.status = WithdrawalStatus::Executed;
 
+    emit!(WithdrawalExecuted {
+        pool: pool.key(),
+        pending_id: pending.tx_id,
+        nullifier_hash: pending.nullifier_hash,
+        recipient: pending.recipient,
+        amount: pending.amount,
+        fee: pending.fee,
+    });
+
     msg!("Withdrawal executed");
     msg!("Amount: {} lamports", pending.amount);
     msg!("Fee: {} lamports", pending.fee);
@@ -47,7 +47,7 @@ pub struct InitPool<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<InitPool>, bucket_id: u8) -> Result<()> {
+pub fn handler(ctx: Context<InitPool>, bucket_id: u8, max_deposits: u64) -> Result<()> {
     // Validate bucket ID
     require!(
         (bucket_id as usize) < NUM_BUCKETS,
@@ -65,6 +65,9 @@ pub fn handler(ctx: Context<InitPool>, bucket_id: u8) -> Result<()> {
     pool.total_deposits = 0;
     pool.anonymity_set_size = 0;
     pool.historical_roots_index = 0;
+    pool.paused = false;
+    pool.pending_counter = 0;
+    pool.max_deposits = max_deposits;
     pool.bump = ctx.bumps.pool;
 
     // Initialize historical roots
@@ -78,6 +81,7 @@ pub fn handler(ctx: Context<InitPool>, bucket_id: u8) -> Result<()> {
     msg!("Pool initialized");
     msg!("Bucket ID: {}", bucket_id);
     msg!("Amount: {} lamports", pool.amount_lamports);
+    msg!("Deposit cap: {}", pool.deposit_cap());
 
     Ok(())
 }
@@ -41,6 +41,8 @@ pub fn handler(ctx: Context<Initialize>, params: InitializeParams) -> Result<()>
     config.min_delay_hours = MIN_DELAY_HOURS;
     config.max_delay_hours = MAX_DELAY_HOURS;
     config.paused = false;
+    config.pending_admin = Pubkey::default();
+    config.max_root_age_hours = DEFAULT_MAX_ROOT_AGE_HOURS;
     config.bump = ctx.bumps.config;
 
     msg!("Privacy-Proxy initialized");
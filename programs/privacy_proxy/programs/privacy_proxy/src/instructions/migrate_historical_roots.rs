@@ -0,0 +1,100 @@
+/// Grow a HistoricalRoots account created before `added_at` existed - ONLY callable by admin
+/// Such accounts are too short to deserialize, so deposits into them and withdrawals against
+/// their roots fail until this reallocates them to the current size. The admin pays the extra rent.
+/// Roots already stored get the migration time, so the age limit counts from the upgrade
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::constants::*;
+use crate::errors::PrivacyProxyError;
+use crate::state::{DepositPool, GlobalConfig, HistoricalRoots, HISTORICAL_ROOTS_SEED};
+
+#[derive(Accounts)]
+#[instruction(bucket_id: u8, account_index: u8)]
+pub struct MigrateHistoricalRoots<'info> {
+    /// Admin running the migration, pays the rent for the added bytes
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ ProgramError::InvalidArgument,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        seeds = [POOL_SEED, &[bucket_id]],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, DepositPool>,
+
+    /// CHECK: Old layout can't be deserialized as HistoricalRoots, seeds and owner are checked
+    #[account(
+        mut,
+        seeds = [HISTORICAL_ROOTS_SEED, pool.key().as_ref(), &[account_index]],
+        bump,
+        owner = crate::ID @ PrivacyProxyError::InvalidHistoricalRootsAccount,
+    )]
+    pub historical_roots: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<MigrateHistoricalRoots>,
+    bucket_id: u8,
+    account_index: u8,
+) -> Result<()> {
+    let account = ctx.accounts.historical_roots.to_account_info();
+
+    // Already at the current layout, nothing to do
+    if account.data_len() >= HistoricalRoots::SIZE {
+        msg!(
+            "Historical roots account {} of pool {} is already migrated",
+            account_index,
+            bucket_id
+        );
+        return Ok(());
+    }
+    require!(
+        account.data_len() == HistoricalRoots::LEGACY_SIZE,
+        PrivacyProxyError::InvalidHistoricalRootsAccount
+    );
+
+    // Top up rent for the larger account before growing it
+    let rent = Rent::get()?.minimum_balance(HistoricalRoots::SIZE);
+    let shortfall = rent.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.resize(HistoricalRoots::SIZE)?;
+
+    // The new bytes are zeroed, so `added_at` reads as all zero until stamped
+    let mut roots = {
+        let data = account.try_borrow_data()?;
+        HistoricalRoots::try_deserialize(&mut &data[..])?
+    };
+    let now = Clock::get()?.unix_timestamp;
+    for added_at in roots.added_at.iter_mut().take(roots.count as usize) {
+        *added_at = now;
+    }
+    let mut data = account.try_borrow_mut_data()?;
+    roots.try_serialize(&mut &mut data[..])?;
+
+    msg!(
+        "Historical roots account {} of pool {} migrated",
+        account_index,
+        bucket_id
+    );
+    Ok(())
+}
@@ -1,8 +1,15 @@
+pub mod accept_admin;
 pub mod cancel_withdrawal;
+pub mod close_pool;
+pub mod close_withdrawal;
 pub mod deposit;
+pub mod emergency_execute;
 pub mod execute_withdrawal;
 pub mod init_pool;
 pub mod initialize;
+pub mod migrate_historical_roots;
+pub mod propose_admin;
 pub mod purchase_credits;
 pub mod request_withdrawal;
+pub mod set_pool_paused;
 pub mod update_config;
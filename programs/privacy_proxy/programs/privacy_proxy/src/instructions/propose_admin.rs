@@ -0,0 +1,28 @@
+/// First step of an admin transfer - ONLY callable by the current admin
+/// The new admin only takes over once it signs accept_admin, so a mistyped or
+/// unrecoverable key can never end up holding the protocol
+/// Proposing the default pubkey withdraws a pending proposal
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::state::GlobalConfig;
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ ProgramError::InvalidArgument,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+}
+
+pub fn handler(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.config.pending_admin = new_admin;
+
+    msg!("Proposed admin {}", new_admin);
+    Ok(())
+}
@@ -5,6 +5,7 @@ use anchor_lang::system_program;
 
 use crate::constants::*;
 use crate::errors::PrivacyProxyError;
+use crate::math::amount_with_fee;
 use crate::state::GlobalConfig;
 
 #[derive(Accounts)]
@@ -74,24 +75,37 @@ pub fn handler(
 
 /// Find the bucket amount from total payment (amount + fee)
 fn find_bucket_amount(total_payment: u64, fee_bps: u16) -> Result<u64> {
-    // total = base + (base * fee_bps / 10000)
-    // total = base * (1 + fee_bps / 10000)
-    // total = base * (10000 + fee_bps) / 10000
-    // base = total * 10000 / (10000 + fee_bps)
-    let fee_multiplier = 10000u64 + fee_bps as u64;
-
+    // total = base + (base * fee_bps / 10000), same fee math as withdrawals
     for &bucket_amount in BUCKET_AMOUNTS.iter() {
-        let expected_total = bucket_amount
-            .checked_mul(fee_multiplier)
-            .ok_or(PrivacyProxyError::Overflow)?
-            .checked_div(10000)
-            .ok_or(PrivacyProxyError::Overflow)?;
+        let expected_total = amount_with_fee(bucket_amount, fee_bps)?;
 
         // Allow small rounding tolerance
-        if total_payment >= expected_total && total_payment <= expected_total + 1000 {
+        if total_payment >= expected_total && total_payment <= expected_total.saturating_add(1000) {
             return Ok(bucket_amount);
         }
     }
 
     Err(PrivacyProxyError::InvalidDepositAmount.into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_bucket_amount() {
+        for &bucket_amount in BUCKET_AMOUNTS.iter() {
+            let total = amount_with_fee(bucket_amount, DEFAULT_FEE_BPS).unwrap();
+            assert_eq!(
+                find_bucket_amount(total, DEFAULT_FEE_BPS).unwrap(),
+                bucket_amount
+            );
+            assert_eq!(
+                find_bucket_amount(total + 1000, DEFAULT_FEE_BPS).unwrap(),
+                bucket_amount
+            );
+            assert!(find_bucket_amount(total - 1, DEFAULT_FEE_BPS).is_err());
+        }
+        assert!(find_bucket_amount(u64::MAX, u16::MAX).is_err());
+    }
+}
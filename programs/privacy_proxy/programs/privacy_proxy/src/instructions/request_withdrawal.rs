@@ -2,17 +2,18 @@
 /// User generates ZK proof off-chain proving they know a valid deposit, without revealing which one. Withdrawal is timelocked for privacy
 /// Now verifies binding hash to ensure proof is bound to specific recipient/relayer/fee values. The binding hash is computed off-chain and verified by the ZK proof
 use anchor_lang::prelude::*;
+use solana_curve25519::edwards::{validate_edwards, PodEdwardsPoint};
 
 use crate::constants::*;
 use crate::errors::PrivacyProxyError;
+use crate::events::WithdrawalRequested;
+use crate::math::amount_after_fee;
 use crate::state::{
-    DepositPool, GlobalConfig, HistoricalRoots, PendingWithdrawal, WithdrawalStatus,
-    HISTORICAL_ROOTS_SEED,
+    derive_historical_roots_pda, DepositPool, GlobalConfig, HistoricalRoots, PendingWithdrawal,
+    WithdrawalStatus, HISTORICAL_ROOTS_SEED, MAX_CHAINED_ACCOUNTS,
 };
 
-/// Domain tag for withdrawal binding hash: "bind" as u32
-/// MUST match: circuits/withdrawal.circom
-pub const DOMAIN_BIND: u64 = 1651076196;
+pub use crate::domains::DOMAIN_BIND;
 
 /// ZK Verifier program for CPI
 pub mod zk_verifier {
@@ -32,6 +33,7 @@ pub mod zk_verifier {
     _delay_hours: u8,
     _binding_hash: [u8; 32],
     relayer_field: [u8; 32],  // Field element from circuit (potentially reduced mod BN254)
+    root_account_index: u8,
 )]
 pub struct RequestWithdrawal<'info> {
     /// Relayer submitting the withdrawal request (pays fees)
@@ -53,9 +55,9 @@ pub struct RequestWithdrawal<'info> {
     )]
     pub pool: Account<'info, DepositPool>,
 
-    /// Historical roots account for extended root validation
+    /// Chained historical roots account holding the proven root (any index, not just 0)
     #[account(
-        seeds = [HISTORICAL_ROOTS_SEED, pool.key().as_ref(), &[0u8]],
+        seeds = [HISTORICAL_ROOTS_SEED, pool.key().as_ref(), &[root_account_index]],
         bump = historical_roots.bump,
     )]
     pub historical_roots: Account<'info, HistoricalRoots>,
@@ -68,12 +70,13 @@ pub struct RequestWithdrawal<'info> {
     )]
     pub nullifier_check: AccountInfo<'info>,
 
-    /// Pending withdrawal account
+    /// Pending withdrawal account, one per nullifier: `init` fails while a request for the
+    /// same nullifier is still open, so a nullifier can't tie up more than one pending account
     #[account(
         init,
         payer = relayer,
         space = PendingWithdrawal::SIZE,
-        seeds = [PENDING_SEED, pool.key().as_ref(), &pool.total_deposits.to_le_bytes()],
+        seeds = [PENDING_SEED, pool.key().as_ref(), &nullifier_hash],
         bump,
     )]
     pub pending_withdrawal: Account<'info, PendingWithdrawal>,
@@ -98,6 +101,7 @@ pub fn handler(
     delay_hours: u8,
     binding_hash: [u8; 32],  // Computed off-chain, verified by ZK proof
     relayer_field: [u8; 32], // Field element from circuit (potentially reduced mod BN254)
+    root_account_index: u8,  // Chained HistoricalRoots account the relayer found the root in
 ) -> Result<()> {
     let config = &ctx.accounts.config;
     let pool = &mut ctx.accounts.pool;
@@ -105,6 +109,7 @@ pub fn handler(
 
     // Check protocol not paused
     require!(!config.paused, PrivacyProxyError::ProtocolPaused);
+    require!(!pool.paused, PrivacyProxyError::PoolPaused);
 
     // Validate bucket
     require!(
@@ -118,24 +123,45 @@ pub fn handler(
         PrivacyProxyError::InvalidDelayHours
     );
 
+    // Recipient must be a real ed25519 point, otherwise the proof would bind funds to
+    // bytes no keypair can ever sign for (e.g. a value reduced mod BN254 in the circuit)
+    require!(
+        validate_edwards(&PodEdwardsPoint(recipient)),
+        PrivacyProxyError::InvalidProof
+    );
+
     // Verify nullifier hasn't been used (account should not exist)
     require!(
         ctx.accounts.nullifier_check.data_is_empty(),
         PrivacyProxyError::NullifierAlreadyUsed
     );
 
-    // Verify Merkle root is valid (current, in pool history, or in extended history)
-    let root_valid = pool.is_valid_root(&merkle_root)
-        || ctx.accounts.historical_roots.contains_root(&merkle_root);
-    require!(root_valid, PrivacyProxyError::InvalidMerkleRoot);
+    // Verify Merkle root is valid (current, in pool history, or in extended history).
+    // The historical roots account at `root_account_index` is searched first, then the
+    // accounts after it in the chain, passed as remaining accounts and capped so that
+    // validating an old root has a bounded cost
+    require!(
+        root_account_index < MAX_CHAINED_ACCOUNTS,
+        PrivacyProxyError::InvalidHistoricalRootsAccount
+    );
+    require!(
+        ctx.remaining_accounts.len() < MAX_ROOT_LOOKUP_ACCOUNTS,
+        PrivacyProxyError::RootLookupTooDeep
+    );
+    let clock = Clock::get()?;
+    validate_merkle_root(
+        config,
+        pool,
+        &pool.key(),
+        &ctx.accounts.historical_roots,
+        ctx.remaining_accounts,
+        &merkle_root,
+        clock.unix_timestamp,
+    )?;
 
     // Calculate amounts for proof verification
     let amount = BUCKET_AMOUNTS[bucket_id as usize];
-    let fee = amount
-        .checked_mul(config.fee_bps as u64)
-        .ok_or(PrivacyProxyError::Overflow)?
-        .checked_div(10000)
-        .ok_or(PrivacyProxyError::Overflow)?;
+    let fee = config.withdrawal_fee(amount)?;
 
     // The binding_hash is provided by the relayer (computed off-chain)
     // The ZK proof verification will fail if the binding_hash doesn't match:
@@ -157,12 +183,12 @@ pub fn handler(
         &relayer_field, // Use field element from circuit
         fee,
         &binding_hash,
+        amount, // Expected bucket denomination, the verifier requires the proven amount to match
     )?;
 
-    let withdrawal_amount = amount.checked_sub(fee).ok_or(PrivacyProxyError::Overflow)?;
+    let withdrawal_amount = amount_after_fee(amount, config.fee_bps)?;
 
     // Calculate execute_after timestamp
-    let clock = Clock::get()?;
     let delay_seconds = (delay_hours as i64) * 3600;
     let execute_after = clock
         .unix_timestamp
@@ -171,11 +197,10 @@ pub fn handler(
 
     // Create pending withdrawal
     // Convert recipient field element back to Pubkey for storage
-    // Note: If the recipient was reduced mod BN254, this may not be a valid Pubkey
-    // In practice, stealth addresses should be chosen to be valid field elements
+    // Already checked to be a valid ed25519 point above
     let recipient_pubkey = Pubkey::new_from_array(recipient);
 
-    pending.tx_id = pool.total_deposits; // Use as unique ID
+    pending.tx_id = pool.next_pending_id().ok_or(PrivacyProxyError::Overflow)?;
     pending.pool = pool.key();
     pending.recipient = recipient_pubkey;
     pending.amount = withdrawal_amount;
@@ -185,6 +210,12 @@ pub fn handler(
     pending.status = WithdrawalStatus::Pending;
     pending.bump = ctx.bumps.pending_withdrawal;
 
+    emit!(WithdrawalRequested {
+        pool: pending.pool,
+        pending_id: pending.tx_id,
+        execute_after,
+    });
+
     msg!("Withdrawal requested");
     msg!("Amount: {} lamports (fee: {})", withdrawal_amount, fee);
     msg!("Recipient: {}", recipient_pubkey);
@@ -194,6 +225,72 @@ pub fn handler(
     Ok(())
 }
 
+/// Check that a withdrawal may prove against `merkle_root`: the pool's current root, one of
+/// the two it replaced last, or a root saved in `historical_roots` or the chained accounts
+/// after it that is within max_root_age_hours
+/// The pool's own buffer carries no timestamps, it holds the newest history there is and is
+/// always treated as fresh
+fn validate_merkle_root(
+    config: &GlobalConfig,
+    pool: &DepositPool,
+    pool_key: &Pubkey,
+    historical_roots: &HistoricalRoots,
+    chained: &[AccountInfo],
+    merkle_root: &[u8; 32],
+    now: i64,
+) -> Result<()> {
+    if pool.is_valid_root(merkle_root) {
+        return Ok(());
+    }
+    let added_at = match historical_roots.root_added_at(merkle_root) {
+        Some(added_at) => added_at,
+        None => chained_root_added_at(
+            chained,
+            pool_key,
+            historical_roots.account_index,
+            merkle_root,
+        )?
+        .ok_or(PrivacyProxyError::InvalidMerkleRoot)?,
+    };
+    // Old roots only count while they're recent enough
+    require!(
+        config.root_is_fresh(added_at, now),
+        PrivacyProxyError::MerkleRootExpired
+    );
+    Ok(())
+}
+
+/// Search the HistoricalRoots accounts following `start_index` in the chain (wrapping past the
+/// last one) for a root, returning when it was saved
+/// Each account must be the PDA for its position in the chain and owned by this program
+fn chained_root_added_at(
+    accounts: &[AccountInfo],
+    pool: &Pubkey,
+    start_index: u8,
+    root: &[u8; 32],
+) -> Result<Option<i64>> {
+    for (i, account) in accounts.iter().enumerate() {
+        let index = (start_index as usize + 1 + i) % MAX_CHAINED_ACCOUNTS as usize;
+        let (expected, _) = derive_historical_roots_pda(pool, index as u8, &crate::ID);
+        require_keys_eq!(
+            account.key(),
+            expected,
+            PrivacyProxyError::InvalidHistoricalRootsAccount
+        );
+        require_keys_eq!(
+            *account.owner,
+            crate::ID,
+            PrivacyProxyError::InvalidHistoricalRootsAccount
+        );
+        let data = account.try_borrow_data()?;
+        let roots = HistoricalRoots::try_deserialize(&mut &data[..])?;
+        if let Some(added_at) = roots.root_added_at(root) {
+            return Ok(Some(added_at));
+        }
+    }
+    Ok(None)
+}
+
 /// Compute Anchor instruction discriminator
 /// discriminator = sha256("global:<instruction_name>")[0..8]
 fn compute_discriminator(name: &str) -> [u8; 8] {
@@ -219,6 +316,7 @@ fn verify_withdrawal_proof_cpi<'info>(
     relayer: &[u8; 32], // Field element from circuit
     fee: u64,
     binding_hash: &[u8; 32],
+    expected_amount: u64,
 ) -> Result<()> {
     use anchor_lang::solana_program::instruction::Instruction;
     use anchor_lang::solana_program::program::invoke;
@@ -227,7 +325,7 @@ fn verify_withdrawal_proof_cpi<'info>(
     // discriminator = sha256("global:verify_withdrawal")[0..8]
     let discriminator = compute_discriminator("verify_withdrawal");
 
-    let mut data = Vec::with_capacity(8 + 256 + 32 * 4 + 8 * 2 + 32 * 3);
+    let mut data = Vec::with_capacity(8 + 256 + 32 * 4 + 8 * 2 + 32 * 3 + 8);
     data.extend_from_slice(&discriminator);
 
     // Groth16Proof
@@ -246,6 +344,9 @@ fn verify_withdrawal_proof_cpi<'info>(
     // Binding hash (circuit output)
     data.extend_from_slice(binding_hash);
 
+    // Denomination of this pool
+    data.extend_from_slice(&expected_amount.to_le_bytes());
+
     let accounts = vec![
         anchor_lang::solana_program::instruction::AccountMeta::new_readonly(caller.key(), true),
     ];
@@ -264,3 +365,118 @@ fn verify_withdrawal_proof_cpi<'info>(
     msg!("✓ ZK proof verified via CPI");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// HistoricalRoots account `index` of `pool` holding `roots`, saved at `added_at`
+    fn roots_account(
+        pool: &Pubkey,
+        index: u8,
+        roots: &[[u8; 32]],
+        added_at: i64,
+    ) -> HistoricalRoots {
+        let mut account = HistoricalRoots {
+            pool: *pool,
+            account_index: index,
+            ..Default::default()
+        };
+        for root in roots {
+            account.add_root(*root, added_at);
+        }
+        account
+    }
+
+    /// Serialized account data and its PDA, as passed in remaining accounts
+    fn chained_data(roots: &HistoricalRoots) -> (Pubkey, Vec<u8>) {
+        let mut data = Vec::new();
+        roots.try_serialize(&mut data).unwrap();
+        let (key, _) = derive_historical_roots_pda(&roots.pool, roots.account_index, &crate::ID);
+        (key, data)
+    }
+
+    #[test]
+    fn test_root_in_any_chained_account() {
+        let config = GlobalConfig::default();
+        let pool_key = Pubkey::new_unique();
+        let pool = DepositPool {
+            merkle_root: [0xaa; 32],
+            ..Default::default()
+        };
+        let check =
+            |historical_roots: &HistoricalRoots, chained: &[AccountInfo], root: [u8; 32]| {
+                validate_merkle_root(
+                    &config,
+                    &pool,
+                    &pool_key,
+                    historical_roots,
+                    chained,
+                    &root,
+                    0,
+                )
+            };
+
+        // Past 32 deposits the recent roots live in accounts 4..31
+        let fifth = roots_account(&pool_key, 4, &[[4; 32]], 0);
+        assert!(check(&fifth, &[], [4; 32]).is_ok());
+        assert!(check(&fifth, &[], [0xaa; 32]).is_ok());
+        assert_eq!(
+            check(&fifth, &[], [5; 32]).unwrap_err(),
+            PrivacyProxyError::InvalidMerkleRoot.into()
+        );
+
+        // The chain continues after the last account at account 0
+        let last = roots_account(&pool_key, MAX_CHAINED_ACCOUNTS - 1, &[[31; 32]], 0);
+        let (first_key, mut first_data) =
+            chained_data(&roots_account(&pool_key, 0, &[[0x10; 32]], 0));
+        let mut lamports = 0;
+        let first = AccountInfo::new(
+            &first_key,
+            false,
+            false,
+            &mut lamports,
+            &mut first_data,
+            &crate::ID,
+            false,
+            0,
+        );
+        assert!(check(&last, std::slice::from_ref(&first), [0x10; 32]).is_ok());
+
+        // ...and nowhere else
+        assert_eq!(
+            check(&fifth, &[first], [0x10; 32]).unwrap_err(),
+            PrivacyProxyError::InvalidHistoricalRootsAccount.into()
+        );
+    }
+
+    #[test]
+    fn test_stale_root_rejected() {
+        let config = GlobalConfig {
+            max_root_age_hours: 1,
+            ..Default::default()
+        };
+        let pool_key = Pubkey::new_unique();
+        let mut pool = DepositPool {
+            merkle_root: [0xaa; 32],
+            ..Default::default()
+        };
+        let historical_roots = roots_account(&pool_key, 0, &[[1; 32]], 1_000);
+        let check = |pool: &DepositPool, root: [u8; 32], now: i64| {
+            validate_merkle_root(&config, pool, &pool_key, &historical_roots, &[], &root, now)
+        };
+
+        assert!(check(&pool, [1; 32], 1_000 + 3_600).is_ok());
+        assert_eq!(
+            check(&pool, [1; 32], 1_000 + 3_601).unwrap_err(),
+            PrivacyProxyError::MerkleRootExpired.into()
+        );
+        // The current root never expires
+        assert!(check(&pool, [0xaa; 32], i64::MAX).is_ok());
+
+        // Neither does a root the pool just replaced, which is also in its own buffer
+        pool.merkle_root = [2; 32];
+        pool.historical_roots[0] = [1; 32];
+        assert!(check(&pool, [1; 32], 1_000 + 3_601).is_ok());
+    }
+}
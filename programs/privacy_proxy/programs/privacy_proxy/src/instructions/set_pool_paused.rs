@@ -0,0 +1,40 @@
+/// Pause or resume a single pool - ONLY callable by admin
+/// Halts deposits, withdrawal requests and executions for one bucket while the others stay live
+/// The global `paused` flag in GlobalConfig still applies on top of this
+use anchor_lang::prelude::*;
+
+use crate::constants::*;
+use crate::errors::PrivacyProxyError;
+use crate::state::{DepositPool, GlobalConfig};
+
+#[derive(Accounts)]
+#[instruction(bucket_id: u8)]
+pub struct SetPoolPaused<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        constraint = config.admin == admin.key() @ ProgramError::InvalidArgument,
+    )]
+    pub config: Account<'info, GlobalConfig>,
+
+    #[account(
+        mut,
+        seeds = [POOL_SEED, &[bucket_id]],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, DepositPool>,
+}
+
+pub fn handler(ctx: Context<SetPoolPaused>, bucket_id: u8, paused: bool) -> Result<()> {
+    require!(
+        (bucket_id as usize) < NUM_BUCKETS,
+        PrivacyProxyError::InvalidBucketId
+    );
+
+    ctx.accounts.pool.paused = paused;
+
+    msg!("Updated pool {} paused to {}", bucket_id, paused);
+    Ok(())
+}
@@ -7,8 +7,13 @@ use crate::state::GlobalConfig;
 pub struct UpdateConfigParams {
     pub relayer_treasury: Option<Pubkey>,
     pub authorized_relayer: Option<Pubkey>,
+    /// execute_withdrawal re-checks the fee at the current value, so withdrawals
+    /// requested before a change can't execute until cancelled and re-requested
     pub fee_bps: Option<u16>,
+    /// Protocol-wide pause, single pools are paused with set_pool_paused
     pub paused: Option<bool>,
+    /// 0 lifts the age limit on withdrawal roots
+    pub max_root_age_hours: Option<u16>,
 }
 
 #[derive(Accounts)]
@@ -48,6 +53,11 @@ pub fn handler(ctx: Context<UpdateConfig>, params: UpdateConfigParams) -> Result
         msg!("Updated paused to {}", paused);
     }
 
+    if let Some(hours) = params.max_root_age_hours {
+        config.max_root_age_hours = hours;
+        msg!("Updated max_root_age_hours to {}", hours);
+    }
+
     msg!("Config updated");
     Ok(())
 }
@@ -1,17 +1,26 @@
 use anchor_lang::prelude::*;
 
 pub mod constants;
+pub mod domains;
 pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod math;
 pub mod state;
 
+use instructions::accept_admin::*;
 use instructions::cancel_withdrawal::*;
+use instructions::close_pool::*;
+use instructions::close_withdrawal::*;
 use instructions::deposit::*;
 use instructions::execute_withdrawal::*;
 use instructions::init_pool::*;
 use instructions::initialize::*;
+use instructions::migrate_historical_roots::*;
+use instructions::propose_admin::*;
 use instructions::purchase_credits::*;
 use instructions::request_withdrawal::*;
+use instructions::set_pool_paused::*;
 use instructions::update_config::*;
 
 declare_id!("Dzpj74oeEhpyXwaiLUFKgzVz1Dcj4ZobsoczYdHiMaB3");
@@ -24,8 +33,8 @@ pub mod privacy_proxy {
         instructions::initialize::handler(ctx, params)
     }
 
-    pub fn init_pool(ctx: Context<InitPool>, bucket_id: u8) -> Result<()> {
-        instructions::init_pool::handler(ctx, bucket_id)
+    pub fn init_pool(ctx: Context<InitPool>, bucket_id: u8, max_deposits: u64) -> Result<()> {
+        instructions::init_pool::handler(ctx, bucket_id, max_deposits)
     }
 
     pub fn purchase_credits(
@@ -43,6 +52,7 @@ pub mod privacy_proxy {
         token_hash: [u8; 32],
         encrypted_note: Vec<u8>,
         merkle_root: [u8; 32],
+        note_ephemeral_pubkey: [u8; 32],
     ) -> Result<()> {
         instructions::deposit::handler(
             ctx,
@@ -51,6 +61,7 @@ pub mod privacy_proxy {
             token_hash,
             encrypted_note,
             merkle_root,
+            note_ephemeral_pubkey,
         )
     }
 
@@ -66,6 +77,7 @@ pub mod privacy_proxy {
         delay_hours: u8,
         binding_hash: [u8; 32],
         relayer_field: [u8; 32], // Field element from circuit (potentially reduced mod BN254)
+        root_account_index: u8,
     ) -> Result<()> {
         instructions::request_withdrawal::handler(
             ctx,
@@ -79,6 +91,7 @@ pub mod privacy_proxy {
             delay_hours,
             binding_hash,
             relayer_field,
+            root_account_index,
         )
     }
 
@@ -86,17 +99,57 @@ pub mod privacy_proxy {
         instructions::execute_withdrawal::handler(ctx)
     }
 
+    pub fn emergency_execute(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+        instructions::emergency_execute::handler(ctx)
+    }
+
     pub fn cancel_withdrawal(
         ctx: Context<CancelWithdrawal>,
         proof_a: [u8; 64],
         proof_b: [u8; 128],
         proof_c: [u8; 64],
         binding_hash: [u8; 32],
+        pending_withdrawal_id: u64,
     ) -> Result<()> {
-        instructions::cancel_withdrawal::handler(ctx, proof_a, proof_b, proof_c, binding_hash)
+        instructions::cancel_withdrawal::handler(
+            ctx,
+            proof_a,
+            proof_b,
+            proof_c,
+            binding_hash,
+            pending_withdrawal_id,
+        )
+    }
+
+    pub fn close_withdrawal(ctx: Context<CloseWithdrawal>) -> Result<()> {
+        instructions::close_withdrawal::handler(ctx)
     }
 
     pub fn update_config(ctx: Context<UpdateConfig>, params: UpdateConfigParams) -> Result<()> {
         instructions::update_config::handler(ctx, params)
     }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin::handler(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin::handler(ctx)
+    }
+
+    pub fn set_pool_paused(ctx: Context<SetPoolPaused>, bucket_id: u8, paused: bool) -> Result<()> {
+        instructions::set_pool_paused::handler(ctx, bucket_id, paused)
+    }
+
+    pub fn close_pool(ctx: Context<ClosePool>, bucket_id: u8) -> Result<()> {
+        instructions::close_pool::handler(ctx, bucket_id)
+    }
+
+    pub fn migrate_historical_roots(
+        ctx: Context<MigrateHistoricalRoots>,
+        bucket_id: u8,
+        account_index: u8,
+    ) -> Result<()> {
+        instructions::migrate_historical_roots::handler(ctx, bucket_id, account_index)
+    }
 }
@@ -0,0 +1,71 @@
+/// Fee arithmetic shared by the instructions
+/// Every helper is checked and fails with `Overflow` instead of wrapping
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::errors::PrivacyProxyError;
+
+/// Fee on `amount` at `fee_bps`, rounded down
+pub fn compute_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = amount
+        .checked_mul(fee_bps as u64)
+        .ok_or(PrivacyProxyError::Overflow)?
+        / BPS_DENOMINATOR;
+    Ok(fee)
+}
+
+/// What is left of `amount` once the fee is taken out
+pub fn amount_after_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = compute_fee(amount, fee_bps)?;
+    Ok(amount.checked_sub(fee).ok_or(PrivacyProxyError::Overflow)?)
+}
+
+/// What a buyer pays for `amount`: the amount plus its fee
+pub fn amount_with_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = compute_fee(amount, fee_bps)?;
+    Ok(amount.checked_add(fee).ok_or(PrivacyProxyError::Overflow)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_fee() {
+        assert_eq!(compute_fee(1_000_000_000, 50).unwrap(), 5_000_000);
+        assert_eq!(compute_fee(1_000_000_000, 0).unwrap(), 0);
+        assert_eq!(compute_fee(1_000_000_000, 10_000).unwrap(), 1_000_000_000);
+        // Rounds down
+        assert_eq!(compute_fee(199, 50).unwrap(), 0);
+        assert_eq!(compute_fee(200, 50).unwrap(), 1);
+        assert_eq!(compute_fee(0, u16::MAX).unwrap(), 0);
+        assert!(compute_fee(u64::MAX, 2).is_err());
+        assert_eq!(compute_fee(u64::MAX, 1).unwrap(), u64::MAX / 10_000);
+    }
+
+    #[test]
+    fn test_amount_after_fee() {
+        assert_eq!(amount_after_fee(1_000_000_000, 50).unwrap(), 995_000_000);
+        assert_eq!(amount_after_fee(u64::MAX, 0).unwrap(), u64::MAX);
+        assert_eq!(amount_after_fee(1_000, 10_000).unwrap(), 0);
+        // A fee above 100% would take more than the amount
+        assert!(amount_after_fee(10_000, 10_001).is_err());
+    }
+
+    #[test]
+    fn test_amount_with_fee() {
+        assert_eq!(amount_with_fee(1_000_000_000, 50).unwrap(), 1_005_000_000);
+        assert_eq!(amount_with_fee(u64::MAX, 0).unwrap(), u64::MAX);
+        assert!(amount_with_fee(u64::MAX - 1, 1).is_err());
+        // Fee and remainder always add back up to the amount
+        for amount in [0, 1, 199, 200, 100_000_000, 10_000_000_000] {
+            for fee_bps in [0, 1, 50, 9_999, 10_000] {
+                assert_eq!(
+                    amount_after_fee(amount, fee_bps).unwrap()
+                        + compute_fee(amount, fee_bps).unwrap(),
+                    amount
+                );
+            }
+        }
+    }
+}
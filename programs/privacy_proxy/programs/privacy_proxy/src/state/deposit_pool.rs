@@ -2,6 +2,8 @@
 /// The separate HistoricalRoots account provides additional capacity
 use anchor_lang::prelude::*;
 
+use crate::constants::MAX_TREE_LEAVES;
+
 /// Number of historical roots to keep in the pool itself
 /// REDUCED to 2 to fit within BPF stack limits (was 8)
 /// Additional roots are stored in the separate HistoricalRoots account
@@ -36,6 +38,18 @@ pub struct DepositPool {
 
     /// PDA bump
     pub bump: u8,
+
+    /// Whether this pool alone is paused (the global flag lives in GlobalConfig)
+    /// Taken from the former padding, so existing pools read as unpaused
+    pub paused: bool,
+
+    /// Withdrawal requests ever made, numbers each pending withdrawal (its `tx_id`)
+    /// Ownership proofs for cancel bind to that id, so it must never repeat
+    pub pending_counter: u64,
+
+    /// Most deposits this pool accepts, set by the admin at init_pool
+    /// 0 (including pools created before the cap existed) means the full tree
+    pub max_deposits: u64,
 }
 
 impl Default for DepositPool {
@@ -50,6 +64,9 @@ impl Default for DepositPool {
             historical_roots: [[0u8; 32]; HISTORICAL_ROOTS_COUNT],
             historical_roots_index: 0,
             bump: 0,
+            paused: false,
+            pending_counter: 0,
+            max_deposits: 0,
         }
     }
 }
@@ -65,7 +82,10 @@ impl DepositPool {
         (32 * HISTORICAL_ROOTS_COUNT) + // historical_roots
         1 + // historical_roots_index
         1 + // bump
-        64; // padding
+        1 + // paused
+        8 + // pending_counter
+        8 + // max_deposits
+        47; // padding
 
     /// Check if a Merkle root is valid (current or recent historical)
     pub fn is_valid_root(&self, root: &[u8; 32]) -> bool {
@@ -84,6 +104,21 @@ impl DepositPool {
         false
     }
 
+    /// Deposits this pool accepts in total, never more than the tree holds
+    pub fn deposit_cap(&self) -> u64 {
+        match self.max_deposits {
+            0 => MAX_TREE_LEAVES,
+            cap => cap.min(MAX_TREE_LEAVES),
+        }
+    }
+
+    /// Id for the next pending withdrawal, advancing the counter
+    pub fn next_pending_id(&mut self) -> Option<u64> {
+        let id = self.pending_counter;
+        self.pending_counter = id.checked_add(1)?;
+        Some(id)
+    }
+
     /// Add a new root to history
     pub fn add_root_to_history(&mut self) {
         self.historical_roots[self.historical_roots_index as usize] = self.merkle_root;
@@ -96,3 +131,31 @@ impl DepositPool {
 impl anchor_lang::Space for DepositPool {
     const INIT_SPACE: usize = Self::SIZE;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_ids_never_repeat() {
+        let mut pool = DepositPool::default();
+        assert_eq!(pool.next_pending_id(), Some(0));
+        assert_eq!(pool.next_pending_id(), Some(1));
+        // Deposits don't move the counter, unlike total_deposits
+        pool.total_deposits += 1;
+        assert_eq!(pool.next_pending_id(), Some(2));
+
+        pool.pending_counter = u64::MAX;
+        assert_eq!(pool.next_pending_id(), None);
+    }
+
+    #[test]
+    fn test_deposit_cap() {
+        let mut pool = DepositPool::default();
+        assert_eq!(pool.deposit_cap(), MAX_TREE_LEAVES);
+        pool.max_deposits = 100;
+        assert_eq!(pool.deposit_cap(), 100);
+        pool.max_deposits = u64::MAX;
+        assert_eq!(pool.deposit_cap(), MAX_TREE_LEAVES);
+    }
+}
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::math::compute_fee;
+
 #[account]
 pub struct GlobalConfig {
     /// Admin who can update config
@@ -31,6 +33,14 @@ pub struct GlobalConfig {
 
     /// PDA bump
     pub bump: u8,
+
+    /// Admin proposed by propose_admin, takes over once it calls accept_admin
+    /// (default = no transfer in progress). Taken from the former padding
+    pub pending_admin: Pubkey,
+
+    /// Oldest historical root request_withdrawal accepts, in hours since it was
+    /// replaced (0 = no limit). The current root is always accepted
+    pub max_root_age_hours: u16,
 }
 
 impl Default for GlobalConfig {
@@ -46,6 +56,8 @@ impl Default for GlobalConfig {
             max_delay_hours: 0,
             paused: false,
             bump: 0,
+            pending_admin: Pubkey::default(),
+            max_root_age_hours: 0,
         }
     }
 }
@@ -62,5 +74,18 @@ impl GlobalConfig {
         1 + // max_delay_hours
         1 + // paused
         1 + // bump
-        64; // padding for future use
+        32 + // pending_admin
+        2 + // max_root_age_hours
+        30; // padding for future use
+
+    /// Relayer fee on a withdrawal of `amount` lamports at the current fee_bps
+    pub fn withdrawal_fee(&self, amount: u64) -> Result<u64> {
+        compute_fee(amount, self.fee_bps)
+    }
+
+    /// Whether a root replaced at `added_at` is still within max_root_age_hours at `now`
+    pub fn root_is_fresh(&self, added_at: i64, now: i64) -> bool {
+        self.max_root_age_hours == 0
+            || now.saturating_sub(added_at) <= self.max_root_age_hours as i64 * 3600
+    }
 }
@@ -20,7 +20,7 @@ pub struct HistoricalRoots {
     pub bucket_id: u8,
 
     /// Account index (for chaining multiple accounts)
-    /// Account 0 stores roots 0-31, Account 1 stores 32-63, etc.
+    /// Account 0 stores roots 0-7, Account 1 stores 8-15, etc.
     pub account_index: u8,
 
     /// Current write index in the circular buffer
@@ -34,6 +34,11 @@ pub struct HistoricalRoots {
 
     /// PDA bump
     pub bump: u8,
+
+    /// When each root in `roots` was replaced by a newer one (unix timestamp)
+    /// Appended after the original fields, accounts created before it must go through
+    /// migrate_historical_roots to grow into it
+    pub added_at: [i64; ROOTS_PER_ACCOUNT],
 }
 
 impl Default for HistoricalRoots {
@@ -46,6 +51,7 @@ impl Default for HistoricalRoots {
             count: 0,
             roots: [[0u8; 32]; ROOTS_PER_ACCOUNT],
             bump: 0,
+            added_at: [0; ROOTS_PER_ACCOUNT],
         }
     }
 }
@@ -57,12 +63,17 @@ impl HistoricalRoots {
         1 + // account_index
         1 + // write_index
         1 + // count
-        (32 * ROOTS_PER_ACCOUNT) + // roots (32 * 32 = 1024 bytes)
+        (32 * ROOTS_PER_ACCOUNT) + // roots (32 * 8 = 256 bytes)
         1 + // bump
+        (8 * ROOTS_PER_ACCOUNT) + // added_at
         8; // padding
 
-    pub fn add_root(&mut self, root: [u8; 32]) {
+    /// Size of accounts created before `added_at` existed
+    pub const LEGACY_SIZE: usize = Self::SIZE - 8 * ROOTS_PER_ACCOUNT;
+
+    pub fn add_root(&mut self, root: [u8; 32], now: i64) {
         self.roots[self.write_index as usize] = root;
+        self.added_at[self.write_index as usize] = now;
         self.write_index = ((self.write_index as usize + 1) % ROOTS_PER_ACCOUNT) as u8;
         if (self.count as usize) < ROOTS_PER_ACCOUNT {
             self.count += 1;
@@ -79,6 +90,14 @@ impl HistoricalRoots {
         false
     }
 
+    /// When `root` was saved to this account, None if it isn't here
+    pub fn root_added_at(&self, root: &[u8; 32]) -> Option<i64> {
+        let count = self.count as usize;
+        (0..count)
+            .find(|&i| &self.roots[i] == root)
+            .map(|i| self.added_at[i])
+    }
+
     pub fn get_latest_root(&self) -> Option<[u8; 32]> {
         if self.count == 0 {
             return None;
@@ -95,6 +114,12 @@ impl HistoricalRoots {
         self.count as usize >= ROOTS_PER_ACCOUNT
     }
 
+    /// Chained account that receives the root saved by the deposit at `leaf_index`
+    /// Accounts fill up in turn; past the last one the chain wraps to account 0
+    pub fn account_index_for(leaf_index: u64) -> u8 {
+        ((leaf_index / ROOTS_PER_ACCOUNT as u64) % MAX_CHAINED_ACCOUNTS as u64) as u8
+    }
+
     pub fn next_account_index(&self) -> Option<u8> {
         if self.account_index < MAX_CHAINED_ACCOUNTS - 1 {
             Some(self.account_index + 1)
@@ -116,3 +141,37 @@ pub fn derive_historical_roots_pda(
         program_id,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::GlobalConfig;
+
+    #[test]
+    fn test_stale_root_rejected() {
+        let config = GlobalConfig {
+            max_root_age_hours: 1,
+            ..Default::default()
+        };
+        let mut roots = HistoricalRoots::default();
+        roots.add_root([1u8; 32], 1_000);
+        roots.add_root([2u8; 32], 5_000);
+
+        let added_at = roots.root_added_at(&[1u8; 32]).unwrap();
+        assert!(config.root_is_fresh(added_at, 1_000 + 3_600));
+        assert!(!config.root_is_fresh(added_at, 1_000 + 3_601));
+        assert_eq!(roots.root_added_at(&[2u8; 32]), Some(5_000));
+        assert_eq!(roots.root_added_at(&[3u8; 32]), None);
+
+        // Overwriting a slot replaces its timestamp too
+        for i in 0..ROOTS_PER_ACCOUNT as u8 {
+            roots.add_root([10 + i; 32], 9_000);
+        }
+        assert_eq!(roots.root_added_at(&[1u8; 32]), None);
+        assert_eq!(roots.root_added_at(&[10u8; 32]), Some(9_000));
+
+        // Zero disables the age limit
+        let unlimited = GlobalConfig::default();
+        assert!(unlimited.root_is_fresh(0, i64::MAX));
+    }
+}
@@ -4,6 +4,7 @@ pub mod global_config;
 pub mod historical_roots;
 pub mod nullifier;
 pub mod pending_withdrawal;
+pub mod used_commitment;
 pub mod used_token;
 
 pub use deposit_pool::*;
@@ -12,4 +13,5 @@ pub use global_config::*;
 pub use historical_roots::*;
 pub use nullifier::*;
 pub use pending_withdrawal::*;
+pub use used_commitment::*;
 pub use used_token::*;
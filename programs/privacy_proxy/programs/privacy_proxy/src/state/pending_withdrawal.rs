@@ -51,4 +51,46 @@ impl PendingWithdrawal {
         1 + // status
         1 + // bump
         32; // padding
+
+    /// Whether the timelock has run out at `now`
+    pub fn is_matured(&self, now: i64) -> bool {
+        now >= self.execute_after
+    }
+
+    /// Whether `executor` may settle this withdrawal while paused: only the recipient or the
+    /// authorized relayer, not the admin who paused the protocol
+    pub fn may_emergency_execute(&self, executor: &Pubkey, authorized_relayer: &Pubkey) -> bool {
+        executor == &self.recipient || executor == authorized_relayer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timelock() {
+        let pending = PendingWithdrawal {
+            execute_after: 1_000,
+            ..Default::default()
+        };
+        assert!(!pending.is_matured(999));
+        assert!(pending.is_matured(1_000));
+        assert!(pending.is_matured(5_000));
+    }
+
+    #[test]
+    fn test_emergency_executors() {
+        let relayer = Pubkey::new_unique();
+        let pending = PendingWithdrawal {
+            recipient: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        assert!(pending.may_emergency_execute(&pending.recipient, &relayer));
+        assert!(pending.may_emergency_execute(&relayer, &relayer));
+
+        // Anyone else, the admin included, waits for the unpause
+        let admin = Pubkey::new_unique();
+        assert!(!pending.may_emergency_execute(&admin, &relayer));
+    }
 }
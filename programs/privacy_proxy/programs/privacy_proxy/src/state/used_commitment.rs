@@ -0,0 +1,28 @@
+/// Prevents the same commitment from being inserted into the tree twice
+/// The relayer owns the tree, so this is the only on-chain guard against it repeating a leaf
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(Default)]
+pub struct UsedCommitment {
+    /// The deposited commitment
+    pub commitment: [u8; 32],
+
+    /// Pool the commitment was deposited into
+    pub pool: Pubkey,
+
+    /// Leaf index the commitment was inserted at
+    pub leaf_index: u64,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl UsedCommitment {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // commitment
+        32 + // pool
+        8 + // leaf_index
+        1 + // bump
+        16; // padding
+}
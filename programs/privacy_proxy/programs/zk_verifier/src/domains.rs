@@ -0,0 +1,25 @@
+//! Domain tags of the binding hashes this program checks
+//! Derived from their ASCII tags so a typo can't drift from circuits/*.circom unnoticed
+//! Note the tags really are "bild" and "orsb", those are the values the circuits use
+
+/// Big-endian u32 of a 4-char tag, the form the circuits hardcode
+pub const fn from_tag(tag: &[u8; 4]) -> u64 {
+    u32::from_be_bytes(*tag) as u64
+}
+
+/// Withdrawal binding hash, MUST match circuits/withdrawal.circom
+pub const DOMAIN_BIND: u64 = from_tag(b"bild");
+
+/// Ownership binding hash, MUST match circuits/ownership.circom
+pub const DOMAIN_OWNER_BIND: u64 = from_tag(b"orsb");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domains_match_circuits() {
+        assert_eq!(DOMAIN_BIND, 1651076196);
+        assert_eq!(DOMAIN_OWNER_BIND, 1869771618);
+    }
+}
@@ -42,13 +42,16 @@ pub fn verify_withdrawal_proof(
         );
     }
 
-    msg!("Public inputs (first 8 bytes each):");
-    for (i, input) in public_inputs.iter().enumerate() {
-        msg!("  [{}]: {:?}", i, &input[..8]);
+    #[cfg(feature = "verbose-logs")]
+    {
+        msg!("Public inputs (first 8 bytes each):");
+        for (i, input) in public_inputs.iter().enumerate() {
+            msg!("  [{}]: {:?}", i, &input[..8]);
+        }
+        msg!("Proof A (first 8 bytes): {:?}", &proof_a[..8]);
+        msg!("Proof B (first 8 bytes): {:?}", &proof_b[..8]);
+        msg!("Proof C (first 8 bytes): {:?}", &proof_c[..8]);
     }
-    msg!("Proof A (first 8 bytes): {:?}", &proof_a[..8]);
-    msg!("Proof B (first 8 bytes): {:?}", &proof_b[..8]);
-    msg!("Proof C (first 8 bytes): {:?}", &proof_c[..8]);
 
     let vk = Groth16Verifyingkey {
         nr_pubinputs: 7,
@@ -73,6 +76,10 @@ pub fn verify_withdrawal_proof(
     Ok(())
 }
 
+/// IC points of an ownership VK that binds the pending withdrawal id:
+/// 2 public inputs + 1 output + the constant term
+const OWNERSHIP_IC_POINTS: usize = 4;
+
 /// Verify a Groth16 proof for ownership (2 public inputs + 1 output)
 ///
 /// Public inputs (in order):
@@ -82,9 +89,9 @@ pub fn verify_withdrawal_proof(
 /// Public output:
 /// 3. bindingHash - Poseidon(DOMAIN_OWNER_BIND, nullifier, pendingWithdrawalId)
 ///
-/// NOTE: Current VK was compiled with only 1 public input (nullifierHash)
-/// The circuit needs to be recompiled with pendingWithdrawalId as public input
-/// For now, we verify with the available VK structure
+/// A VK compiled before pendingWithdrawalId became public (2 IC points) proves nothing about
+/// which withdrawal is cancelled, so a proof for one could cancel any other; it is rejected
+/// until the circuit is recompiled (`circuits/scripts/recompile_ownership.sh`)
 pub fn verify_ownership_proof(
     proof_a: &[u8; 64],
     proof_b: &[u8; 128],
@@ -93,72 +100,37 @@ pub fn verify_ownership_proof(
     binding_hash: &[u8; 32],
 ) -> Result<()> {
     let ic_points = get_ownership_ic_points();
+    if ic_points.len() != OWNERSHIP_IC_POINTS {
+        msg!(
+            "Ownership VK has {} IC points, expected {}",
+            ic_points.len(),
+            OWNERSHIP_IC_POINTS
+        );
+        return Err(ZkVerifierError::OutdatedVerifyingKey.into());
+    }
 
-    // Current VK has 2 IC points (for 1 public input)
-    // Expected: 4 IC points (for 2 inputs + 1 binding hash output)
-    // We need to handle this mismatch gracefully
-    if ic_points.len() == 2 {
-        // Old VK with only nullifierHash as public input
-        // Verify with just nullifierHash for now
-        msg!("Warning: Using legacy ownership VK with 1 public input");
-        msg!("Circuit should be recompiled with pendingWithdrawalId as public input");
-
-        let legacy_inputs: [[u8; 32]; 1] = [public_inputs[0]];
-
-        let vk = Groth16Verifyingkey {
-            nr_pubinputs: 1,
-            vk_alpha_g1: OWNERSHIP_ALPHA_G1,
-            vk_beta_g2: OWNERSHIP_BETA_G2,
-            vk_gamme_g2: OWNERSHIP_GAMMA_G2,
-            vk_delta_g2: OWNERSHIP_DELTA_G2,
-            vk_ic: ic_points,
-        };
-
-        let mut verifier =
-            Groth16Verifier::<1>::new(proof_a, proof_b, proof_c, &legacy_inputs, &vk).map_err(
-                |e| {
-                    msg!("Failed to create ownership verifier: {:?}", e);
-                    ZkVerifierError::VerificationFailed
-                },
-            )?;
-
-        verifier.verify().map_err(|e| {
-            msg!("Ownership verification failed: {:?}", e);
-            ZkVerifierError::InvalidProof
-        })?;
-
-        // Additional check: verify binding hash is provided (even if not in proof)
-        // This provides some protection until circuit is recompiled
-        if binding_hash.iter().all(|&b| b == 0) {
-            msg!("Error: Binding hash cannot be zero");
-            return Err(ZkVerifierError::InvalidBindingHash.into());
-        }
-    } else {
-        // New VK with full public inputs
-        let mut all_inputs: [[u8; 32]; 3] = [[0u8; 32]; 3];
-        all_inputs[..2].copy_from_slice(public_inputs);
-        all_inputs[2] = *binding_hash;
+    // Outputs come first in snarkjs public signals: [bindingHash, nullifierHash, pendingWithdrawalId]
+    let all_inputs: [[u8; 32]; 3] = [*binding_hash, public_inputs[0], public_inputs[1]];
 
-        let vk = Groth16Verifyingkey {
-            nr_pubinputs: 3, // 2 inputs + 1 binding hash output
-            vk_alpha_g1: OWNERSHIP_ALPHA_G1,
-            vk_beta_g2: OWNERSHIP_BETA_G2,
-            vk_gamme_g2: OWNERSHIP_GAMMA_G2,
-            vk_delta_g2: OWNERSHIP_DELTA_G2,
-            vk_ic: ic_points,
-        };
+    let vk = Groth16Verifyingkey {
+        nr_pubinputs: 3, // 2 inputs + 1 binding hash output
+        vk_alpha_g1: OWNERSHIP_ALPHA_G1,
+        vk_beta_g2: OWNERSHIP_BETA_G2,
+        vk_gamme_g2: OWNERSHIP_GAMMA_G2,
+        vk_delta_g2: OWNERSHIP_DELTA_G2,
+        vk_ic: ic_points,
+    };
 
-        let mut verifier = Groth16Verifier::<3>::new(proof_a, proof_b, proof_c, &all_inputs, &vk)
-            .map_err(|e| {
+    let mut verifier = Groth16Verifier::<3>::new(proof_a, proof_b, proof_c, &all_inputs, &vk)
+        .map_err(|e| {
             msg!("Failed to create ownership verifier: {:?}", e);
             ZkVerifierError::VerificationFailed
         })?;
 
-        verifier.verify().map_err(|e| {
-            msg!("Ownership verification failed: {:?}", e);
-            ZkVerifierError::InvalidProof
-        })?;
-    }
+    verifier.verify().map_err(|e| {
+        msg!("Ownership verification failed: {:?}", e);
+        ZkVerifierError::InvalidProof
+    })?;
 
     Ok(())
 }
@@ -365,4 +337,19 @@ mod tests {
             Err(e) => println!("✓ Non-negated: FAILED as expected {:?}", e),
         }
     }
+
+    #[test]
+    fn test_legacy_ownership_vk_rejected() {
+        // The checked-in VK still has the 2 IC points of the pre-binding circuit
+        assert_eq!(get_ownership_ic_points().len(), 2);
+        let err = verify_ownership_proof(
+            &[0u8; 64],
+            &[0u8; 128],
+            &[0u8; 64],
+            &[[3u8; 32], [0u8; 32]],
+            &[4u8; 32],
+        )
+        .unwrap_err();
+        assert_eq!(err, ZkVerifierError::OutdatedVerifyingKey.into());
+    }
 }
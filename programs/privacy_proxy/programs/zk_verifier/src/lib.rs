@@ -4,11 +4,22 @@
 /// - Ownership proof now outputs bindingHash that MUST be verified
 /// - Domain separation is enforced (circuit-side)
 /// - Fee < amount is enforced (circuit-side)
+/// - Amount must equal the denomination of the pool the caller withdraws from
 ///
 /// Poseidon binding hash is computed by the circuit and included in the proof's public inputs
 /// On-chain verification trusts the circuit output since full Poseidon is too heavy for Solana BPF
 use anchor_lang::prelude::*;
 
+/// `msg!` that is only compiled in with the `verbose-logs` feature
+/// Default builds log the verification outcome alone, every extra line costs compute units
+macro_rules! verbose_msg {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "verbose-logs")]
+        msg!($($arg)*);
+    };
+}
+
+pub mod domains;
 pub mod groth16;
 pub mod poseidon;
 pub mod verifying_key;
@@ -18,13 +29,7 @@ use poseidon::verify_binding_inputs;
 
 declare_id!("2ntZ79MomBLsLyaExjGW6F7kkYtmprhdzZzQaMXSMZRu");
 
-/// Domain tag for withdrawal binding hash: "bind" as u32
-/// MUST match: circuits/withdrawal.circom
-pub const DOMAIN_BIND: u64 = 1651076196;
-
-/// Domain tag for ownership binding hash: "ownb" as u32
-/// MUST match: circuits/ownership.circom
-pub const DOMAIN_OWNER_BIND: u64 = 1869771618;
+pub use domains::{DOMAIN_BIND, DOMAIN_OWNER_BIND};
 
 /// Public inputs for withdrawal proof verification
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
@@ -80,19 +85,27 @@ pub mod zk_verifier {
     /// 5. Domain-separated hashes
     /// 6. Valid Merkle proof
     /// 7. Binding hash computation
+    ///
+    /// `expected_amount` is the denomination of the pool the caller withdraws from;
+    /// a proof for any other amount is rejected before pairing checks run
     pub fn verify_withdrawal(
         _ctx: Context<VerifyWithdrawal>,
         proof: Groth16Proof,
         public_inputs: WithdrawalPublicInputs,
         binding_hash: [u8; 32], // Circuit output - included in proof verification
+        expected_amount: u64,
     ) -> Result<()> {
-        msg!("Verifying withdrawal proof...");
+        verbose_msg!("Verifying withdrawal proof...");
 
         // Additional on-chain validation
         require!(
             public_inputs.amount > 0,
             ZkVerifierError::InvalidPublicInputs
         );
+        require!(
+            public_inputs.amount == expected_amount,
+            ZkVerifierError::AmountMismatch
+        );
         require!(
             public_inputs.fee < public_inputs.amount,
             ZkVerifierError::InvalidPublicInputs
@@ -117,14 +130,14 @@ pub mod zk_verifier {
         verify_withdrawal_proof(&proof.a, &proof.b, &proof.c, &inputs)?;
 
         msg!("✓ Withdrawal proof verified successfully");
-        msg!("  Nullifier hash: {:?}", &public_inputs.nullifier_hash[..8]);
-        msg!("  Recipient: {:?}", &public_inputs.recipient[..8]);
-        msg!(
+        verbose_msg!("  Nullifier hash: {:?}", &public_inputs.nullifier_hash[..8]);
+        verbose_msg!("  Recipient: {:?}", &public_inputs.recipient[..8]);
+        verbose_msg!(
             "  Amount: {} (fee: {})",
             public_inputs.amount,
             public_inputs.fee
         );
-        msg!("  Binding hash verified: {:?}", &binding_hash[..8]);
+        verbose_msg!("  Binding hash verified: {:?}", &binding_hash[..8]);
 
         Ok(())
     }
@@ -135,27 +148,35 @@ pub mod zk_verifier {
     /// 1. nullifier != 0
     /// 2. Domain-separated nullifier hash
     /// 3. Binding hash = Poseidon(DOMAIN_OWNER_BIND, nullifier, pendingWithdrawalId)
+    ///
+    /// `expected_pending_withdrawal_id` is the `tx_id` of the withdrawal the caller cancels;
+    /// a proof made for any other pending withdrawal is rejected before pairing checks run
     pub fn verify_ownership(
         _ctx: Context<VerifyOwnership>,
         proof: Groth16Proof,
         public_inputs: OwnershipPublicInputs,
         binding_hash: [u8; 32], // Circuit output - MUST be verified
+        expected_pending_withdrawal_id: u64,
     ) -> Result<()> {
-        msg!("Verifying ownership proof...");
-        msg!(
+        verbose_msg!("Verifying ownership proof...");
+        verbose_msg!(
             "  Pending withdrawal ID: {}",
             public_inputs.pending_withdrawal_id
         );
 
         // We cannot verify the binding hash directly because it uses the private nullifier
-        // The circuit guarantees the binding is correct
-        // The smart contract should verify that the pendingWithdrawalId matches the actual pending withdrawal being cancelled
+        // The circuit guarantees the binding is correct, as long as the id it was bound to
+        // is the pending withdrawal actually being cancelled
+        require!(
+            public_inputs.pending_withdrawal_id == expected_pending_withdrawal_id,
+            ZkVerifierError::PendingWithdrawalIdMismatch
+        );
         let inputs = prepare_ownership_inputs(&public_inputs);
         verify_ownership_proof(&proof.a, &proof.b, &proof.c, &inputs, &binding_hash)?;
 
         msg!("✓ Ownership proof verified");
-        msg!("  Nullifier hash: {:?}", &public_inputs.nullifier_hash[..8]);
-        msg!("  Binding hash: {:?}", &binding_hash[..8]);
+        verbose_msg!("  Nullifier hash: {:?}", &public_inputs.nullifier_hash[..8]);
+        verbose_msg!("  Binding hash: {:?}", &binding_hash[..8]);
 
         Ok(())
     }
@@ -187,6 +208,15 @@ pub enum ZkVerifierError {
 
     #[msg("Invalid binding hash - proof not bound to these parameters")]
     InvalidBindingHash,
+
+    #[msg("Proven amount does not match the pool denomination")]
+    AmountMismatch,
+
+    #[msg("Ownership proof is bound to a different pending withdrawal")]
+    PendingWithdrawalIdMismatch,
+
+    #[msg("Ownership verifying key predates pending withdrawal binding, recompile the circuit")]
+    OutdatedVerifyingKey,
 }
 
 /// Prepare public inputs for withdrawal verification
@@ -200,27 +230,66 @@ fn prepare_withdrawal_inputs(
     inputs: &WithdrawalPublicInputs,
     binding_hash: &[u8; 32],
 ) -> [[u8; 32]; 7] {
-    let mut amount_bytes = [0u8; 32];
-    amount_bytes[24..32].copy_from_slice(&inputs.amount.to_be_bytes());
-
-    let mut fee_bytes = [0u8; 32];
-    fee_bytes[24..32].copy_from_slice(&inputs.fee.to_be_bytes());
-
     [
         *binding_hash,         // Circuit output (comes first in snarkjs)
         inputs.merkle_root,    // Already in correct format from circuit
         inputs.nullifier_hash, // Already in correct format from circuit
         inputs.recipient,      // Field element from circuit (already reduced if needed)
-        amount_bytes,
+        u64_to_field(inputs.amount),
         inputs.relayer, // Field element from circuit (already reduced if needed)
-        fee_bytes,
+        u64_to_field(inputs.fee),
     ]
 }
 
 /// Prepare public inputs for ownership verification
 fn prepare_ownership_inputs(inputs: &OwnershipPublicInputs) -> [[u8; 32]; 2] {
-    let mut withdrawal_id_bytes = [0u8; 32];
-    withdrawal_id_bytes[24..32].copy_from_slice(&inputs.pending_withdrawal_id.to_be_bytes());
+    [
+        inputs.nullifier_hash,
+        u64_to_field(inputs.pending_withdrawal_id),
+    ]
+}
 
-    [inputs.nullifier_hash, withdrawal_id_bytes]
+/// `value` as a big-endian field element, the encoding of the SDK's `crypto::u64_to_field`
+fn u64_to_field(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_withdrawal_inputs_match_signal_order() {
+        let inputs = WithdrawalPublicInputs {
+            merkle_root: [2; 32],
+            nullifier_hash: [3; 32],
+            recipient: [4; 32],
+            amount: 5,
+            relayer: [6; 32],
+            fee: 7,
+        };
+        let binding_hash = [1; 32];
+        let prepared = prepare_withdrawal_inputs(&inputs, &binding_hash);
+
+        // The SDK builds its requests against the same fixture
+        let fixture =
+            include_str!("../../../../../circuits/fixtures/withdrawal_public_signals.txt");
+        let names: Vec<_> = fixture.lines().collect();
+        assert_eq!(names.len(), prepared.len());
+        for (name, value) in names.into_iter().zip(prepared) {
+            let expected = match name {
+                "bindingHash" => binding_hash,
+                "root" => inputs.merkle_root,
+                "nullifierHash" => inputs.nullifier_hash,
+                "recipient" => inputs.recipient,
+                "amount" => u64_to_field(inputs.amount),
+                "relayer" => inputs.relayer,
+                "fee" => u64_to_field(inputs.fee),
+                other => panic!("unknown public signal {}", other),
+            };
+            assert_eq!(value, expected, "{} is out of place", name);
+        }
+    }
 }
@@ -128,7 +128,7 @@ async function main() {
 
     try {
       const poolTx = await program.methods
-        .initPool(bucketId)
+        .initPool(bucketId, new anchor.BN(0))
         .accounts({
           admin: wallet.publicKey,
           pool: poolPda,
@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { PrivacyProxy } from "../target/types/privacy_proxy";
 import { ZkVerifier } from "../target/types/zk_verifier";
-import { Keypair, PublicKey } from "@solana/web3.js";
+import { Keypair, PublicKey, SystemProgram, Transaction } from "@solana/web3.js";
 import { expect } from "chai";
 
 describe("privacy_proxy", () => {
@@ -79,6 +79,313 @@ describe("privacy_proxy", () => {
     console.log("Purchase credits tx:", tx);
     console.log("✓ Credits purchased");
   });
+
+  it("Rolls deposits over into chained historical roots accounts", async () => {
+    const bucketId = 0;
+    const rootsPerAccount = 8;
+    // Enough to fill accounts 0-3 and move on to account 4
+    const deposits = 40;
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), Buffer.from([bucketId])],
+      program.programId
+    );
+    const rootsPda = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("historical_roots"), poolPda.toBuffer(), Buffer.from([index])],
+        program.programId
+      )[0];
+    // Root the relayer reports after deposit i (distinct, never the initial root)
+    const rootAfter = (i: number) => new Array(32).fill(0).map((_, j) => (j === 0 ? i + 1 : 7));
+
+    await program.methods
+      .initPool(bucketId, new anchor.BN(0))
+      .accounts({ admin: admin.publicKey })
+      .rpc();
+
+    for (let i = 0; i < deposits; i++) {
+      await program.methods
+        .deposit(
+          bucketId,
+          new Array(32).fill(i + 1),
+          new Array(32).fill(0).map((_, j) => (j === 0 ? i + 100 : 0)),
+          Buffer.from([]),
+          rootAfter(i),
+          new Array(32).fill(0)
+        )
+        .accountsPartial({
+          relayer: relayer.publicKey,
+          pool: poolPda,
+          historicalRoots: rootsPda(Math.floor(i / rootsPerAccount)),
+        })
+        .signers([relayer])
+        .rpc();
+    }
+
+    // Deposits 0-7 filled account 0, deposits 8-15 account 1, and so on
+    const first = await program.account.historicalRoots.fetch(rootsPda(0));
+    const fifth = await program.account.historicalRoots.fetch(rootsPda(4));
+    expect(first.count).to.equal(rootsPerAccount);
+    expect(fifth.accountIndex).to.equal(4);
+    expect(fifth.count).to.equal(deposits - 4 * rootsPerAccount);
+
+    // Request a withdrawal against a root. The dummy proof always fails verification,
+    // so getting past root validation is what shows the root was accepted
+    const requestAgainst = async (root: number[], accountIndex: number, chained: number[] = []) => {
+      try {
+        await program.methods
+          .requestWithdrawal(
+            bucketId,
+            new Array(32).fill(9),
+            Array.from(Keypair.generate().publicKey.toBytes()),
+            new Array(64).fill(0),
+            new Array(128).fill(0),
+            new Array(64).fill(0),
+            root,
+            1,
+            new Array(32).fill(3),
+            Array.from(relayer.publicKey.toBytes()),
+            accountIndex
+          )
+          .accountsPartial({
+            relayer: relayer.publicKey,
+            pool: poolPda,
+            historicalRoots: rootsPda(accountIndex),
+          })
+          .remainingAccounts(
+            chained.map((index) => ({ pubkey: rootsPda(index), isWritable: false, isSigner: false }))
+          )
+          .signers([relayer])
+          .rpc();
+      } catch (err: unknown) {
+        return (err as Error).toString();
+      }
+      expect.fail("Expected the dummy proof to be rejected");
+    };
+
+    // Early root, only left in account 0
+    expect(await requestAgainst(rootAfter(0), 0)).to.not.include("InvalidMerkleRoot");
+    // Root saved after the rollover, reachable through the account after the named one
+    expect(await requestAgainst(rootAfter(8), 0, [1])).to.not.include("InvalidMerkleRoot");
+    // Root saved by deposit 36, past the accounts a lookup from account 0 may reach
+    expect(await requestAgainst(rootAfter(35), 4)).to.not.include("InvalidMerkleRoot");
+    // Never a root of this pool
+    expect(await requestAgainst(new Array(32).fill(0xee), 4)).to.include("InvalidMerkleRoot");
+
+    console.log("✓ Roots stay valid across chained accounts");
+  });
+
+  it("Rejects a commitment that was already deposited", async () => {
+    const bucketId = 0;
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), Buffer.from([bucketId])],
+      program.programId
+    );
+    const pool = await program.account.depositPool.fetch(poolPda);
+    const [rootsPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("historical_roots"),
+        poolPda.toBuffer(),
+        Buffer.from([Math.floor(pool.nextIndex.toNumber() / 8) % 32]),
+      ],
+      program.programId
+    );
+
+    // Same commitment as the first rollover deposit, with an unused token
+    try {
+      await program.methods
+        .deposit(
+          bucketId,
+          new Array(32).fill(1),
+          new Array(32).fill(0).map((_, j) => (j === 0 ? 250 : 1)),
+          Buffer.from([]),
+          new Array(32).fill(0x55),
+          new Array(32).fill(0)
+        )
+        .accountsPartial({
+          relayer: relayer.publicKey,
+          pool: poolPda,
+          historicalRoots: rootsPda,
+        })
+        .signers([relayer])
+        .rpc();
+      expect.fail("Expected the repeated commitment to be rejected");
+    } catch (err: unknown) {
+      expect((err as Error).toString()).to.include("already in use");
+    }
+
+    // Nothing was inserted
+    const after = await program.account.depositPool.fetch(poolPda);
+    expect(after.nextIndex.toNumber()).to.equal(pool.nextIndex.toNumber());
+
+    console.log("✓ Repeated commitment rejected");
+  });
+
+  it("Closes only an empty pool", async () => {
+    const poolPda = (bucketId: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("pool"), Buffer.from([bucketId])],
+        program.programId
+      )[0];
+    const rootsPda = (bucketId: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("historical_roots"), poolPda(bucketId).toBuffer(), Buffer.from([0])],
+        program.programId
+      )[0];
+
+    // Bucket 0 still holds the deposits from the rollover test
+    try {
+      await program.methods
+        .closePool(0)
+        .accounts({ admin: admin.publicKey })
+        .rpc();
+      expect.fail("Expected a pool with deposits to stay open");
+    } catch (err: unknown) {
+      expect((err as Error).toString()).to.include("PoolNotEmpty");
+    }
+
+    // A fresh bucket can be retired, rent goes back to the admin
+    const bucketId = 1;
+    await program.methods
+      .initPool(bucketId, new anchor.BN(0))
+      .accounts({ admin: admin.publicKey })
+      .rpc();
+
+    // Lamports donated to the pool can't hold it open, they're swept with the rent
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: relayer.publicKey,
+          toPubkey: poolPda(bucketId),
+          lamports: 1,
+        })
+      ),
+      [relayer]
+    );
+    const balanceBefore = await provider.connection.getBalance(admin.publicKey);
+
+    await program.methods
+      .closePool(bucketId)
+      .accounts({ admin: admin.publicKey })
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(poolPda(bucketId))).to.equal(null);
+    expect(await provider.connection.getAccountInfo(rootsPda(bucketId))).to.equal(null);
+    expect(await provider.connection.getBalance(admin.publicKey)).to.be.greaterThan(balanceBefore);
+
+    console.log("✓ Empty pool closed despite a donation, non-empty pool kept");
+  });
+
+  it("Rejects deposits past the pool's cap", async () => {
+    const bucketId = 2;
+    const [poolPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), Buffer.from([bucketId])],
+      program.programId
+    );
+    const [rootsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("historical_roots"), poolPda.toBuffer(), Buffer.from([0])],
+      program.programId
+    );
+    const deposit = (i: number) =>
+      program.methods
+        .deposit(
+          bucketId,
+          new Array(32).fill(0).map((_, j) => (j === 0 ? 200 + i : 2)),
+          new Array(32).fill(0).map((_, j) => (j === 0 ? 200 + i : 3)),
+          Buffer.from([]),
+          new Array(32).fill(0).map((_, j) => (j === 0 ? 200 + i : 4)),
+          new Array(32).fill(0)
+        )
+        .accountsPartial({
+          relayer: relayer.publicKey,
+          pool: poolPda,
+          historicalRoots: rootsPda,
+        })
+        .signers([relayer])
+        .rpc();
+
+    await program.methods
+      .initPool(bucketId, new anchor.BN(1))
+      .accounts({ admin: admin.publicKey })
+      .rpc();
+    await deposit(0);
+
+    try {
+      await deposit(1);
+      expect.fail("Expected a deposit past the cap to be rejected");
+    } catch (err: unknown) {
+      expect((err as Error).toString()).to.include("PoolFull");
+    }
+
+    const pool = await program.account.depositPool.fetch(poolPda);
+    expect(pool.maxDeposits.toNumber()).to.equal(1);
+    expect(pool.totalDeposits.toNumber()).to.equal(1);
+
+    console.log("✓ Pool cap enforced");
+  });
+
+  it("Transfers admin in two steps", async () => {
+    const newAdmin = Keypair.generate();
+    const stranger = Keypair.generate();
+
+    await program.methods
+      .proposeAdmin(newAdmin.publicKey)
+      .accountsPartial({ admin: admin.publicKey, config: configPda })
+      .rpc();
+
+    // Proposing alone hands nothing over
+    let config = await program.account.globalConfig.fetch(configPda);
+    expect(config.admin.toString()).to.equal(admin.publicKey.toString());
+    expect(config.pendingAdmin.toString()).to.equal(newAdmin.publicKey.toString());
+
+    // Only the proposed key can accept
+    try {
+      await program.methods
+        .acceptAdmin()
+        .accountsPartial({ newAdmin: stranger.publicKey, config: configPda })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Expected a stranger's accept to be rejected");
+    } catch (err: unknown) {
+      expect((err as Error).toString()).to.include("NotPendingAdmin");
+    }
+
+    await program.methods
+      .acceptAdmin()
+      .accountsPartial({ newAdmin: newAdmin.publicKey, config: configPda })
+      .signers([newAdmin])
+      .rpc();
+
+    config = await program.account.globalConfig.fetch(configPda);
+    expect(config.admin.toString()).to.equal(newAdmin.publicKey.toString());
+    expect(config.pendingAdmin.toString()).to.equal(PublicKey.default.toString());
+
+    // The old admin lost its rights
+    try {
+      await program.methods
+        .proposeAdmin(admin.publicKey)
+        .accountsPartial({ admin: admin.publicKey, config: configPda })
+        .rpc();
+      expect.fail("Expected the previous admin to be rejected");
+    } catch (err: unknown) {
+      expect((err as Error).toString()).to.include("Error");
+    }
+
+    // Hand it back so the provider wallet stays admin
+    await program.methods
+      .proposeAdmin(admin.publicKey)
+      .accountsPartial({ admin: newAdmin.publicKey, config: configPda })
+      .signers([newAdmin])
+      .rpc();
+    await program.methods
+      .acceptAdmin()
+      .accountsPartial({ newAdmin: admin.publicKey, config: configPda })
+      .rpc();
+
+    config = await program.account.globalConfig.fetch(configPda);
+    expect(config.admin.toString()).to.equal(admin.publicKey.toString());
+
+    console.log("✓ Admin transferred and returned");
+  });
 });
 
 describe("zk_verifier (security hardened v2)", () => {
@@ -109,7 +416,7 @@ describe("zk_verifier (security hardened v2)", () => {
 
     try {
       await program.methods
-        .verifyWithdrawal(proof, publicInputs, bindingHash)
+        .verifyWithdrawal(proof, publicInputs, bindingHash, publicInputs.amount)
         .accounts({
           caller: caller.publicKey,
         })
@@ -142,7 +449,7 @@ describe("zk_verifier (security hardened v2)", () => {
 
     try {
       await program.methods
-        .verifyWithdrawal(proof, publicInputs, bindingHash)
+        .verifyWithdrawal(proof, publicInputs, bindingHash, publicInputs.amount)
         .accounts({
           caller: caller.publicKey,
         })
@@ -155,6 +462,44 @@ describe("zk_verifier (security hardened v2)", () => {
     }
   });
 
+  it("Rejects an amount that isn't the pool denomination", async () => {
+    const proof = {
+      a: new Array(64).fill(0),
+      b: new Array(128).fill(0),
+      c: new Array(64).fill(0),
+    };
+
+    const publicInputs = {
+      merkleRoot: new Array(32).fill(1),
+      nullifierHash: new Array(32).fill(2),
+      recipient: caller.publicKey,
+      amount: new anchor.BN(1_000_000_000), // 1 SOL proof...
+      relayer: PublicKey.default,
+      fee: new anchor.BN(5_000_000),
+    };
+
+    const bindingHash = new Array(32).fill(3);
+
+    try {
+      await program.methods
+        .verifyWithdrawal(
+          proof,
+          publicInputs,
+          bindingHash,
+          new anchor.BN(100_000_000) // ...against the 0.1 SOL pool
+        )
+        .accounts({
+          caller: caller.publicKey,
+        })
+        .rpc();
+
+      expect.fail("Expected amount mismatch to fail");
+    } catch (err: unknown) {
+      console.log("✓ Amount outside the pool denomination rejected");
+      expect((err as Error).toString()).to.include("AmountMismatch");
+    }
+  });
+
   it("Rejects zero amount", async () => {
     const proof = {
       a: new Array(64).fill(0),
@@ -175,7 +520,7 @@ describe("zk_verifier (security hardened v2)", () => {
 
     try {
       await program.methods
-        .verifyWithdrawal(proof, publicInputs, bindingHash)
+        .verifyWithdrawal(proof, publicInputs, bindingHash, publicInputs.amount)
         .accounts({
           caller: caller.publicKey,
         })
@@ -206,7 +551,7 @@ describe("zk_verifier (security hardened v2)", () => {
 
     try {
       await program.methods
-        .verifyOwnership(proof, publicInputs, bindingHash)
+        .verifyOwnership(proof, publicInputs, bindingHash, publicInputs.pendingWithdrawalId)
         .accounts({
           caller: caller.publicKey,
         })
@@ -218,4 +563,33 @@ describe("zk_verifier (security hardened v2)", () => {
       expect((err as Error).toString()).to.include("Error");
     }
   });
+
+  it("Rejects an ownership proof bound to a different pending withdrawal", async () => {
+    const proof = {
+      a: new Array(64).fill(0),
+      b: new Array(128).fill(0),
+      c: new Array(64).fill(0),
+    };
+
+    // Proof made for pending withdrawal 1...
+    const publicInputs = {
+      nullifierHash: new Array(32).fill(3),
+      pendingWithdrawalId: new anchor.BN(1),
+    };
+    const bindingHash = new Array(32).fill(4);
+
+    try {
+      await program.methods
+        .verifyOwnership(proof, publicInputs, bindingHash, new anchor.BN(2)) // ...cancelling 2
+        .accounts({
+          caller: caller.publicKey,
+        })
+        .rpc();
+
+      expect.fail("Expected a proof for another withdrawal to be rejected");
+    } catch (err: unknown) {
+      console.log("✓ Ownership proof for another pending withdrawal rejected");
+      expect((err as Error).toString()).to.include("PendingWithdrawalIdMismatch");
+    }
+  });
 });